@@ -0,0 +1,380 @@
+use crate::models::{
+    Connection as SshConnConfig, ConnectionGroup, ConnectionImportSummary, JumpHop, SshKey,
+};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+/// Bumped whenever the export layout changes incompatibly. Readers accept
+/// any version up to this one; unknown fields are ignored.
+pub const CONNECTION_EXPORT_VERSION: u32 = 1;
+
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Iteration counts accepted on import; a crafted file could otherwise make
+/// key derivation run for hours.
+const PBKDF2_ITERATIONS_RANGE: std::ops::RangeInclusive<u32> = 10_000..=10_000_000;
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionExport {
+    version: u32,
+    exported_at: i64,
+    /// Set when secrets were encrypted with a passphrase; without it secrets
+    /// were left out of the export.
+    #[serde(default)]
+    encryption: Option<ExportEncryption>,
+    #[serde(default)]
+    groups: Vec<ConnectionGroup>,
+    #[serde(default)]
+    keys: Vec<ExportedKey>,
+    #[serde(default)]
+    connections: Vec<SshConnConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportEncryption {
+    kdf: String,
+    iterations: u32,
+    salt: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedKey {
+    id: i64,
+    name: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    Aes256Gcm::new(&key.into())
+}
+
+/// Encrypt to base64(nonce || ciphertext).
+fn encrypt_secret(cipher: &Aes256Gcm, value: &str) -> Result<String, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, value.as_bytes())
+        .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(general_purpose::STANDARD.encode(out))
+}
+
+fn decrypt_secret(cipher: &Aes256Gcm, value: &str) -> Result<String, String> {
+    let data = general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| format!("Invalid encrypted value: {}", e))?;
+    if data.len() < NONCE_LEN {
+        return Err("Invalid encrypted value".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong passphrase or corrupted export".to_string())?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+/// Encrypts with the passphrase when one is given, otherwise drops the secret.
+fn seal(cipher: Option<&Aes256Gcm>, value: Option<String>) -> Result<Option<String>, String> {
+    match (cipher, value) {
+        (Some(cipher), Some(value)) => encrypt_secret(cipher, &value).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn unseal(cipher: Option<&Aes256Gcm>, value: Option<String>) -> Result<Option<String>, String> {
+    match (cipher, value) {
+        (Some(cipher), Some(value)) => decrypt_secret(cipher, &value).map(Some),
+        (None, value) => Ok(value),
+        (_, None) => Ok(None),
+    }
+}
+
+/// Seal or unseal the passwords of a jump chain, remapping key ids on import
+/// through `key_ids`. Loaded key material is always dropped.
+fn map_jump_hosts(
+    hops: Vec<JumpHop>,
+    mut secret: impl FnMut(Option<String>) -> Result<Option<String>, String>,
+    key_ids: Option<&HashMap<i64, i64>>,
+) -> Result<Vec<JumpHop>, String> {
+    hops.into_iter()
+        .map(|hop| {
+            Ok(JumpHop {
+                password: secret(hop.password)?,
+                ssh_key_id: match key_ids {
+                    Some(key_ids) => hop.ssh_key_id.and_then(|id| key_ids.get(&id).copied()),
+                    None => hop.ssh_key_id,
+                },
+                key_content: None,
+                key_passphrase: None,
+                ..hop
+            })
+        })
+        .collect()
+}
+
+/// "name (2)", "name (3)", ... until the name is free.
+fn unique_name(name: &str, taken: &[String]) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range")
+}
+
+/// Write all connections, groups and the keys they use to a JSON file.
+/// Passwords and key material are encrypted with `passphrase`, or left out
+/// when no passphrase is given. Returns the number of connections written.
+#[tauri::command]
+pub fn export_connections(
+    app_handle: AppHandle,
+    path: String,
+    passphrase: Option<String>,
+) -> Result<usize, String> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let (cipher, encryption) = match passphrase {
+        Some(passphrase) => {
+            let mut salt = [0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            (
+                Some(derive_cipher(&passphrase, &salt, PBKDF2_ITERATIONS)),
+                Some(ExportEncryption {
+                    kdf: "pbkdf2-sha256".to_string(),
+                    iterations: PBKDF2_ITERATIONS,
+                    salt: general_purpose::STANDARD.encode(salt),
+                }),
+            )
+        }
+        None => (None, None),
+    };
+    let cipher = cipher.as_ref();
+
+    let mut connections = Vec::new();
+    for conn in crate::db::get_connections(app_handle.clone())? {
+        connections.push(SshConnConfig {
+            password: seal(cipher, conn.password)?,
+            jump_password: seal(cipher, conn.jump_password)?,
+            jump_hosts: map_jump_hosts(conn.jump_hosts, |v| seal(cipher, v), None)?,
+            key_content: None,
+            key_passphrase: None,
+            ..conn
+        });
+    }
+
+    let mut keys = Vec::new();
+    for key in crate::db::get_ssh_keys(app_handle.clone())? {
+        let Some(id) = key.id else { continue };
+        if !connections.iter().any(|conn| {
+            conn.ssh_key_id == Some(id)
+                || conn.jump_key_id == Some(id)
+                || conn.jump_hosts.iter().any(|hop| hop.ssh_key_id == Some(id))
+        }) {
+            continue;
+        }
+        keys.push(ExportedKey {
+            id,
+            name: key.name,
+            content: seal(cipher, Some(key.content))?,
+            passphrase: seal(cipher, key.passphrase)?,
+        });
+    }
+
+    let export = ConnectionExport {
+        version: CONNECTION_EXPORT_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        encryption,
+        groups: crate::db::get_groups(app_handle.clone())?,
+        keys,
+        connections,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    Ok(export.connections.len())
+}
+
+/// Import a file written by `export_connections`. Groups are recreated (or
+/// matched by name under the same parent) with fresh ids. Name clashes are
+/// resolved per `on_duplicate`: `"suffix"` (default) renames, `"skip"` skips.
+#[tauri::command]
+pub fn import_connections(
+    app_handle: AppHandle,
+    path: String,
+    passphrase: Option<String>,
+    on_duplicate: Option<String>,
+) -> Result<ConnectionImportSummary, String> {
+    let skip_duplicates = match on_duplicate.as_deref().unwrap_or("suffix") {
+        "suffix" => false,
+        "skip" => true,
+        other => return Err(format!("Unsupported duplicate handling: {}", other)),
+    };
+
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let export: ConnectionExport =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid export file: {}", e))?;
+    if export.version > CONNECTION_EXPORT_VERSION {
+        return Err(format!(
+            "Export version {} is newer than supported version {}",
+            export.version, CONNECTION_EXPORT_VERSION
+        ));
+    }
+
+    let cipher = match &export.encryption {
+        Some(encryption) => {
+            if encryption.kdf != "pbkdf2-sha256" {
+                return Err(format!("Unsupported export encryption: {}", encryption.kdf));
+            }
+            if !PBKDF2_ITERATIONS_RANGE.contains(&encryption.iterations) {
+                return Err(format!(
+                    "Unsupported export key derivation iteration count: {}",
+                    encryption.iterations
+                ));
+            }
+            let passphrase = passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or("This export is encrypted; a passphrase is required")?;
+            let salt = general_purpose::STANDARD
+                .decode(&encryption.salt)
+                .map_err(|e| format!("Invalid export salt: {}", e))?;
+            Some(derive_cipher(&passphrase, &salt, encryption.iterations))
+        }
+        None => None,
+    };
+    let cipher = cipher.as_ref();
+
+    // Parents must exist before their children, so insert in passes.
+    let mut existing_groups = crate::db::get_groups(app_handle.clone())?;
+    let mut group_ids: HashMap<i64, i64> = HashMap::new();
+    let mut pending: Vec<&ConnectionGroup> = export.groups.iter().collect();
+    while !pending.is_empty() {
+        let before = pending.len();
+        let mut deferred = Vec::new();
+        for group in pending {
+            let old_parent = group.parent_id.filter(|parent| {
+                export
+                    .groups
+                    .iter()
+                    .any(|candidate| candidate.id == Some(*parent))
+            });
+            let parent_id = match old_parent {
+                Some(old) => match group_ids.get(&old) {
+                    Some(new) => Some(*new),
+                    None => {
+                        deferred.push(group);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            let new_id = match existing_groups
+                .iter()
+                .find(|g| g.name == group.name && g.parent_id == parent_id)
+                .and_then(|g| g.id)
+            {
+                Some(id) => id,
+                None => {
+                    let new_group = ConnectionGroup {
+                        id: None,
+                        name: group.name.clone(),
+                        parent_id,
+                        color: group.color.clone(),
+                        icon: group.icon.clone(),
+                    };
+                    let id = crate::db::insert_group(&app_handle, &new_group)?;
+                    existing_groups.push(ConnectionGroup {
+                        id: Some(id),
+                        ..new_group
+                    });
+                    id
+                }
+            };
+            if let Some(old_id) = group.id {
+                group_ids.insert(old_id, new_id);
+            }
+        }
+        if deferred.len() == before {
+            return Err("Export contains a group parent cycle".to_string());
+        }
+        pending = deferred;
+    }
+
+    let mut known_keys = crate::db::get_ssh_keys(app_handle.clone())?;
+    let mut key_ids: HashMap<i64, i64> = HashMap::new();
+    for key in export.keys {
+        let Some(content) = unseal(cipher, key.content)? else {
+            continue;
+        };
+        let new_id = match known_keys.iter().find(|k| k.content == content) {
+            Some(existing) => existing.id,
+            None => {
+                let new_key = SshKey {
+                    id: None,
+                    name: key.name,
+                    content,
+                    passphrase: unseal(cipher, key.passphrase)?,
+                    created_at: chrono::Utc::now().timestamp(),
+                };
+                let id = crate::db::insert_ssh_key(&app_handle, &new_key)?;
+                known_keys.push(SshKey {
+                    id: Some(id),
+                    ..new_key
+                });
+                Some(id)
+            }
+        };
+        if let Some(new_id) = new_id {
+            key_ids.insert(key.id, new_id);
+        }
+    }
+
+    let mut taken_names: Vec<String> = crate::db::get_connections(app_handle.clone())?
+        .into_iter()
+        .map(|conn| conn.name)
+        .collect();
+    let mut summary = ConnectionImportSummary::default();
+
+    for conn in export.connections {
+        let name = if taken_names.contains(&conn.name) {
+            if skip_duplicates {
+                summary.skipped += 1;
+                summary.skipped_hosts.push(conn.name);
+                continue;
+            }
+            unique_name(&conn.name, &taken_names)
+        } else {
+            conn.name.clone()
+        };
+
+        let imported = SshConnConfig {
+            id: None,
+            name: name.clone(),
+            password: unseal(cipher, conn.password)?,
+            jump_password: unseal(cipher, conn.jump_password)?,
+            group_id: conn.group_id.and_then(|id| group_ids.get(&id).copied()),
+            ssh_key_id: conn.ssh_key_id.and_then(|id| key_ids.get(&id).copied()),
+            jump_key_id: conn.jump_key_id.and_then(|id| key_ids.get(&id).copied()),
+            jump_hosts: map_jump_hosts(conn.jump_hosts, |v| unseal(cipher, v), Some(&key_ids))?,
+            key_content: None,
+            key_passphrase: None,
+            ..conn
+        };
+        crate::db::create_connection(app_handle.clone(), imported)?;
+        taken_names.push(name);
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
@@ -1,10 +1,15 @@
 use crate::models::{
-    AccountProfile, AIConfig, AIEndpointConfig, AISubscriptionConfig, AppSettings,
-    Connection as SshConnection, ConnectionGroup, ConnectionTimeoutSettings,
-    FileManagerSettings, HeartbeatSettings, LocalWorkspaceSnapshot, NetworkAdaptiveSettings,
-    PoolHealthSettings, PendingCheckoutSession, ReconnectSettings, SshKey, SshPoolSettings,
-    SyncPreferences, TerminalAppearanceSettings, Tunnel,
+    AIConfig, AIEndpointConfig, AISubscriptionConfig, AccountProfile, AppSettings,
+    CommandSafetySettings, CommandSnippet, Connection as SshConnection, ConnectionGroup,
+    ConnectionTimeoutSettings, FileManagerSettings, GroupDefaults, HeartbeatSettings, JumpHop,
+    KeyPublicExport, LocalWorkspaceSnapshot, NetworkAdaptiveSettings, PathBookmark,
+    PendingCheckoutSession, PoolHealthSettings, ReconnectSettings, SshKey, SshPoolSettings,
+    SyncPreferences, TerminalAppearanceSettings, Tunnel, CONNECTION_ICONS,
+    DEFAULT_MAX_CONCURRENT_TRANSFERS,
 };
+use crate::ssh::known_hosts::{validate_host_key_policy, DEFAULT_HOST_KEY_POLICY};
+use crate::ssh::utils::validate_sftp_buffer_size;
+use crate::ssh::{validate_forward_bind_address, DEFAULT_FORWARD_BIND_ADDRESS};
 use rusqlite::{params, Connection, OptionalExtension, Result, Row};
 use tauri::{AppHandle, Manager};
 
@@ -60,6 +65,18 @@ pub fn init_db(app_handle: &AppHandle) -> Result<()> {
         [],
     );
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS command_snippets (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            command TEXT NOT NULL,
+            description TEXT,
+            tags TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
     conn.execute(
         r#"CREATE TABLE IF NOT EXISTS settings (
             id INTEGER PRIMARY KEY CHECK (id = 1),
@@ -357,6 +374,191 @@ pub fn init_db(app_handle: &AppHandle) -> Result<()> {
     );
     let _ = conn.execute("ALTER TABLE connections ADD COLUMN ssh_key_id INTEGER REFERENCES ssh_keys(id) ON DELETE SET NULL", []);
 
+    // Migration: Add comma-separated tags to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN tags TEXT NOT NULL DEFAULT ''",
+        [],
+    );
+
+    // Migration: Add last measured latency to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN last_connected INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN last_latency_ms INTEGER",
+        [],
+    );
+
+    // Migration: Add force_scp to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN force_scp INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add max_background_sessions to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN max_background_sessions INTEGER",
+        [],
+    );
+
+    // Migration: Add jump_hosts to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN jump_hosts TEXT NOT NULL DEFAULT '[]'",
+        [],
+    );
+
+    // Migration: Add jump_auth_type to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN jump_auth_type TEXT",
+        [],
+    );
+
+    // Migration: Add jump_key_id to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN jump_key_id INTEGER",
+        [],
+    );
+
+    // Migration: Add color to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN color TEXT",
+        [],
+    );
+
+    // Migration: Add icon to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN icon TEXT",
+        [],
+    );
+
+    // Migration: Add color and icon to connection groups
+    let _ = conn.execute(
+        "ALTER TABLE connection_groups ADD COLUMN color TEXT",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE connection_groups ADD COLUMN icon TEXT",
+        [],
+    );
+
+    // Migration: Add defaults inherited by new connections to connection groups
+    for column in [
+        "default_username TEXT",
+        "default_auth_type TEXT",
+        "default_ssh_key_id INTEGER",
+        "default_jump_hosts TEXT",
+        "default_host_key_policy TEXT",
+        "default_proxy_command TEXT",
+    ] {
+        let _ = conn.execute(
+            &format!("ALTER TABLE connection_groups ADD COLUMN {}", column),
+            [],
+        );
+    }
+
+    // Migration: Add sort_order to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add is_favorite to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add agent_forwarding to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN agent_forwarding INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add low_latency to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN low_latency INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add idle_timeout_minutes to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN idle_timeout_minutes INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add kex_algorithms to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN kex_algorithms TEXT",
+        [],
+    );
+
+    // Migration: Add ciphers to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN ciphers TEXT",
+        [],
+    );
+
+    // Migration: Add mac_algorithms to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN mac_algorithms TEXT",
+        [],
+    );
+
+    // Migration: Add legacy_mode to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN legacy_mode INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add init_command to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN init_command TEXT",
+        [],
+    );
+
+    // Migration: Add remote_rc to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN remote_rc TEXT",
+        [],
+    );
+
+    // Migration: Add host_key_policy to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN host_key_policy TEXT",
+        [],
+    );
+
+    // Migration: Add socket_path to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN socket_path TEXT",
+        [],
+    );
+
+    // Migration: Add proxy_command to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN proxy_command TEXT",
+        [],
+    );
+
+    // Migration: Add sftp_handle_cache to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN sftp_handle_cache INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add notes to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN notes TEXT",
+        [],
+    );
+
+    // Migration: Add x11_forwarding to connections
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN x11_forwarding INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
     // Migration: Add reconnect settings
     let _ = conn.execute(
         r#"ALTER TABLE settings ADD COLUMN reconnect_max_attempts INTEGER NOT NULL DEFAULT 5"#,
@@ -437,6 +639,78 @@ pub fn init_db(app_handle: &AppHandle) -> Result<()> {
         [],
     );
 
+    // Migration: Add external editor command
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_default_editor TEXT"#,
+        [],
+    );
+
+    // Migration: Add transfer bandwidth limit
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_max_transfer_rate INTEGER NOT NULL DEFAULT 0"#,
+        [],
+    );
+
+    // Migration: Add idle background session timeout
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN ssh_idle_session_timeout_minutes INTEGER NOT NULL DEFAULT 10"#,
+        [],
+    );
+
+    // Migration: Add user-editable dangerous command patterns for AI-run commands
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN command_safety_dangerous_patterns TEXT"#,
+        [],
+    );
+
+    // Migration: Add local bind address for port forwards
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN forward_bind_address TEXT NOT NULL DEFAULT '127.0.0.1'"#,
+        [],
+    );
+
+    // Migration: Add separate SFTP read buffer size
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_sftp_read_buffer_size INTEGER NOT NULL DEFAULT 512"#,
+        [],
+    );
+
+    // Migration: Add soft delete toggle
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_soft_delete INTEGER NOT NULL DEFAULT 1"#,
+        [],
+    );
+
+    // Migration: Add delete confirmation threshold
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_require_confirm_threshold INTEGER NOT NULL DEFAULT 1000"#,
+        [],
+    );
+
+    // Migration: Add max concurrent transfers to settings
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN file_manager_max_concurrent_transfers INTEGER NOT NULL DEFAULT 3"#,
+        [],
+    );
+
+    // Migration: Add OSC 7 working directory tracking to settings
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN terminal_track_cwd INTEGER NOT NULL DEFAULT 0"#,
+        [],
+    );
+
+    // Migration: Add host_key_policy to settings
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN host_key_policy TEXT NOT NULL DEFAULT 'ask'"#,
+        [],
+    );
+
+    // Migration: Add keepalive interval for open shells
+    let _ = conn.execute(
+        r#"ALTER TABLE settings ADD COLUMN heartbeat_shell_keepalive_interval_secs INTEGER NOT NULL DEFAULT 30"#,
+        [],
+    );
+
     // --- Transfer Records Support ---
 
     // Create transfer_records table
@@ -472,6 +746,22 @@ pub fn init_db(app_handle: &AppHandle) -> Result<()> {
         [],
     );
 
+    // Bookmarked and recently visited remote directories, per connection.
+    // kind is 'bookmark' or 'recent'.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS path_bookmarks (
+            id INTEGER PRIMARY KEY,
+            connection_id INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'bookmark',
+            path TEXT NOT NULL,
+            name TEXT,
+            created_at INTEGER NOT NULL,
+            UNIQUE(connection_id, kind, path),
+            FOREIGN KEY(connection_id) REFERENCES connections(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -517,35 +807,131 @@ pub fn save_local_workspace_snapshot(
     Ok(())
 }
 
+/// Trim, drop empties and dedupe (case-insensitively) before storing.
+fn join_tags(tags: &[String]) -> String {
+    let mut normalized: Vec<&str> = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag);
+        }
+    }
+    normalized.join(",")
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Accept `#rgb` / `#rrggbb` (stored lowercase); blank means no colour.
+fn validate_color(color: Option<&str>) -> Result<Option<String>, String> {
+    let Some(color) = color.map(str::trim).filter(|c| !c.is_empty()) else {
+        return Ok(None);
+    };
+    let digits = color.strip_prefix('#').unwrap_or("");
+    if !matches!(digits.len(), 3 | 6) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid color '{}': expected a hex value like #d33 or #dd3333",
+            color
+        ));
+    }
+    Ok(Some(color.to_ascii_lowercase()))
+}
+
+fn validate_icon(icon: Option<&str>) -> Result<Option<String>, String> {
+    let Some(icon) = icon.map(str::trim).filter(|i| !i.is_empty()) else {
+        return Ok(None);
+    };
+    if !CONNECTION_ICONS.contains(&icon) {
+        return Err(format!(
+            "Unknown icon '{}': expected one of {}",
+            icon,
+            CONNECTION_ICONS.join(", ")
+        ));
+    }
+    Ok(Some(icon.to_string()))
+}
+
+/// Hops are stored as JSON; loaded key material never is.
+fn jump_hosts_json(hops: &[JumpHop]) -> String {
+    let hops: Vec<JumpHop> = hops
+        .iter()
+        .map(|hop| JumpHop {
+            key_content: None,
+            key_passphrase: None,
+            ..hop.clone()
+        })
+        .collect();
+    serde_json::to_string(&hops).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn parse_jump_hosts(raw: Option<String>) -> Vec<JumpHop> {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn map_connection_row(row: &Row<'_>) -> Result<SshConnection> {
+    let tags: Option<String> = row.get(14)?;
+    Ok(SshConnection {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        host: row.get(2)?,
+        port: row.get(3)?,
+        username: row.get(4)?,
+        password: row.get(5)?,
+        jump_host: row.get(6)?,
+        jump_port: row.get(7)?,
+        jump_username: row.get(8)?,
+        jump_password: row.get(9)?,
+        group_id: row.get(10)?,
+        os_type: row.get(11)?,
+        auth_type: row.get(12)?,
+        ssh_key_id: row.get(13)?,
+        key_content: None,
+        key_passphrase: None,
+        tags: split_tags(tags.as_deref().unwrap_or_default()),
+        last_connected: row.get(15)?,
+        last_latency_ms: row.get(16)?,
+        force_scp: row.get(17)?,
+        max_background_sessions: row.get(18)?,
+        jump_hosts: parse_jump_hosts(row.get(19)?),
+        jump_auth_type: row.get(20)?,
+        jump_key_id: row.get(21)?,
+        color: row.get(22)?,
+        icon: row.get(23)?,
+        sort_order: row.get(24)?,
+        is_favorite: row.get(25)?,
+        agent_forwarding: row.get(26)?,
+        low_latency: row.get(27)?,
+        idle_timeout_minutes: row.get(28)?,
+        kex_algorithms: row.get(29)?,
+        ciphers: row.get(30)?,
+        mac_algorithms: row.get(31)?,
+        legacy_mode: row.get(32)?,
+        init_command: row.get(33)?,
+        remote_rc: row.get(34)?,
+        host_key_policy: row.get(35)?,
+        socket_path: row.get(36)?,
+        proxy_command: row.get(37)?,
+        sftp_handle_cache: row.get(38)?,
+        notes: row.get(39)?,
+        x11_forwarding: row.get(40)?,
+    })
+}
+
 #[tauri::command]
 pub fn get_connections(app_handle: AppHandle) -> Result<Vec<SshConnection>, String> {
     let db_path = get_db_path(&app_handle);
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
-    let mut stmt = conn.prepare("SELECT id, name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id FROM connections")
+    let mut stmt = conn.prepare("SELECT id, name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id, tags, last_connected, last_latency_ms, force_scp, max_background_sessions, jump_hosts, jump_auth_type, jump_key_id, color, icon, sort_order, is_favorite, agent_forwarding, low_latency, idle_timeout_minutes, kex_algorithms, ciphers, mac_algorithms, legacy_mode, init_command, remote_rc, host_key_policy, socket_path, proxy_command, sftp_handle_cache, notes, x11_forwarding FROM connections ORDER BY group_id, sort_order, id")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
-        .query_map([], |row| {
-            Ok(SshConnection {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                host: row.get(2)?,
-                port: row.get(3)?,
-                username: row.get(4)?,
-                password: row.get(5)?,
-                jump_host: row.get(6)?,
-                jump_port: row.get(7)?,
-                jump_username: row.get(8)?,
-                jump_password: row.get(9)?,
-                group_id: row.get(10)?,
-                os_type: row.get(11)?,
-                auth_type: row.get(12)?,
-                ssh_key_id: row.get(13)?,
-                key_content: None,
-                key_passphrase: None,
-            })
-        })
+        .query_map([], map_connection_row)
         .map_err(|e| e.to_string())?;
 
     let mut connections = Vec::new();
@@ -563,30 +949,11 @@ pub fn get_connection_by_id(
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id FROM connections WHERE id = ?1")
+        .prepare("SELECT id, name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id, tags, last_connected, last_latency_ms, force_scp, max_background_sessions, jump_hosts, jump_auth_type, jump_key_id, color, icon, sort_order, is_favorite, agent_forwarding, low_latency, idle_timeout_minutes, kex_algorithms, ciphers, mac_algorithms, legacy_mode, init_command, remote_rc, host_key_policy, socket_path, proxy_command, sftp_handle_cache, notes, x11_forwarding FROM connections WHERE id = ?1")
         .map_err(|e| e.to_string())?;
 
     let mut rows = stmt
-        .query_map(params![id], |row| {
-            Ok(SshConnection {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                host: row.get(2)?,
-                port: row.get(3)?,
-                username: row.get(4)?,
-                password: row.get(5)?,
-                jump_host: row.get(6)?,
-                jump_port: row.get(7)?,
-                jump_username: row.get(8)?,
-                jump_password: row.get(9)?,
-                group_id: row.get(10)?,
-                os_type: row.get(11)?,
-                auth_type: row.get(12)?,
-                ssh_key_id: row.get(13)?,
-                key_content: None,
-                key_passphrase: None,
-            })
-        })
+        .query_map(params![id], map_connection_row)
         .map_err(|e| e.to_string())?;
 
     if let Some(row) = rows.next() {
@@ -602,7 +969,7 @@ pub fn get_groups(app_handle: AppHandle) -> Result<Vec<ConnectionGroup>, String>
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, parent_id FROM connection_groups")
+        .prepare("SELECT id, name, parent_id, color, icon FROM connection_groups")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
@@ -611,6 +978,8 @@ pub fn get_groups(app_handle: AppHandle) -> Result<Vec<ConnectionGroup>, String>
                 id: row.get(0)?,
                 name: row.get(1)?,
                 parent_id: row.get(2)?,
+                color: row.get(3)?,
+                icon: row.get(4)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -622,35 +991,229 @@ pub fn get_groups(app_handle: AppHandle) -> Result<Vec<ConnectionGroup>, String>
     Ok(groups)
 }
 
+/// Fill the fields `conn` leaves unset from `defaults`.
+fn apply_group_defaults(conn: &mut SshConnection, defaults: &GroupDefaults) {
+    fn fill<T: Clone>(field: &mut Option<T>, default: &Option<T>) {
+        if field.is_none() {
+            field.clone_from(default);
+        }
+    }
+
+    if conn.username.trim().is_empty() {
+        if let Some(username) = &defaults.username {
+            conn.username = username.clone();
+        }
+    }
+    fill(&mut conn.auth_type, &defaults.auth_type);
+    fill(&mut conn.ssh_key_id, &defaults.ssh_key_id);
+    if conn.jump_chain().is_empty() {
+        conn.jump_hosts = defaults.jump_hosts.clone();
+    }
+    fill(&mut conn.host_key_policy, &defaults.host_key_policy);
+    if conn.proxy_command.as_deref().is_none_or(|c| c.trim().is_empty()) {
+        conn.proxy_command.clone_from(&defaults.proxy_command);
+    }
+}
+
+fn get_group_defaults_with_conn(db_conn: &Connection, group_id: i64) -> Result<GroupDefaults> {
+    db_conn.query_row(
+        "SELECT default_username, default_auth_type, default_ssh_key_id, default_jump_hosts, default_host_key_policy, default_proxy_command FROM connection_groups WHERE id = ?1",
+        params![group_id],
+        |row| {
+            Ok(GroupDefaults {
+                username: row.get(0)?,
+                auth_type: row.get(1)?,
+                ssh_key_id: row.get(2)?,
+                jump_hosts: parse_jump_hosts(row.get(3)?),
+                host_key_policy: row.get(4)?,
+                proxy_command: row.get(5)?,
+            })
+        },
+    )
+}
+
+/// Apply the defaults of `group_id` and then of each parent group, so the
+/// nearest group wins for every field it sets.
+fn inherit_group_defaults(db_conn: &Connection, conn: &mut SshConnection) -> Result<(), String> {
+    let mut group_id = conn.group_id;
+    let mut visited = Vec::new();
+    while let Some(id) = group_id {
+        // Guard against a parent_id cycle
+        if visited.contains(&id) {
+            break;
+        }
+        visited.push(id);
+        let defaults = match get_group_defaults_with_conn(db_conn, id) {
+            Ok(defaults) => defaults,
+            Err(rusqlite::Error::QueryReturnedNoRows) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        apply_group_defaults(conn, &defaults);
+        group_id = db_conn
+            .query_row(
+                "SELECT parent_id FROM connection_groups WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub fn create_connection(app_handle: AppHandle, conn: SshConnection) -> Result<(), String> {
+pub fn create_connection(app_handle: AppHandle, mut conn: SshConnection) -> Result<i64, String> {
     println!("Creating connection: {:?}", conn);
     let db_path = get_db_path(&app_handle);
     let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    inherit_group_defaults(&db_conn, &mut conn)?;
+    let color = validate_color(conn.color.as_deref())?;
+    let icon = validate_icon(conn.icon.as_deref())?;
+    if let Some(policy) = conn.host_key_policy.as_deref() {
+        validate_host_key_policy(policy)?;
+    }
+    // New connections go to the end of their group
+    let sort_order: i64 = db_conn
+        .query_row(
+            "SELECT COALESCE(MAX(sort_order) + 1, 0) FROM connections WHERE group_id IS ?1",
+            params![conn.group_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
 
     db_conn.execute(
-        "INSERT INTO connections (name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-        params![conn.name, conn.host, conn.port, conn.username, conn.password, conn.jump_host, conn.jump_port, conn.jump_username, conn.jump_password, conn.group_id, conn.os_type, conn.auth_type.unwrap_or("password".to_string()), conn.ssh_key_id],
+        "INSERT INTO connections (name, host, port, username, password, jump_host, jump_port, jump_username, jump_password, group_id, os_type, auth_type, ssh_key_id, tags, force_scp, max_background_sessions, jump_hosts, jump_auth_type, jump_key_id, color, icon, sort_order, is_favorite, agent_forwarding, low_latency, idle_timeout_minutes, kex_algorithms, ciphers, mac_algorithms, legacy_mode, init_command, remote_rc, host_key_policy, socket_path, proxy_command, sftp_handle_cache, notes, x11_forwarding) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38)",
+        params![conn.name, conn.host, conn.port, conn.username, conn.password, conn.jump_host, conn.jump_port, conn.jump_username, conn.jump_password, conn.group_id, conn.os_type, conn.auth_type.unwrap_or("password".to_string()), conn.ssh_key_id, join_tags(&conn.tags), conn.force_scp, conn.max_background_sessions, jump_hosts_json(&conn.jump_hosts), conn.jump_auth_type, conn.jump_key_id, color, icon, sort_order, conn.is_favorite, conn.agent_forwarding, conn.low_latency, conn.idle_timeout_minutes, conn.kex_algorithms, conn.ciphers, conn.mac_algorithms, conn.legacy_mode, conn.init_command, conn.remote_rc, conn.host_key_policy, conn.socket_path, conn.proxy_command, conn.sftp_handle_cache, conn.notes, conn.x11_forwarding],
     ).map_err(|e| {
         println!("Error inserting connection: {}", e);
         e.to_string()
     })?;
     println!("Connection created successfully");
+    Ok(db_conn.last_insert_rowid())
+}
+
+pub fn record_connection_latency(
+    app_handle: &AppHandle,
+    id: i64,
+    latency_ms: i64,
+) -> Result<(), String> {
+    let db_path = get_db_path(app_handle);
+    let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    db_conn
+        .execute(
+            "UPDATE connections SET last_connected = ?1, last_latency_ms = ?2 WHERE id = ?3",
+            params![chrono::Utc::now().timestamp(), latency_ms, id],
+        )
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Connections carrying `tag` (case-insensitive), across all groups.
+#[tauri::command]
+pub fn get_connections_by_tag(
+    app_handle: AppHandle,
+    tag: String,
+) -> Result<Vec<SshConnection>, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(get_connections(app_handle)?
+        .into_iter()
+        .filter(|conn| conn.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .collect())
+}
+
+/// Pinned connections across all groups.
+#[tauri::command]
+pub fn get_favorites(app_handle: AppHandle) -> Result<Vec<SshConnection>, String> {
+    Ok(get_connections(app_handle)?
+        .into_iter()
+        .filter(|conn| conn.is_favorite)
+        .collect())
+}
+
+/// Pin or unpin a connection without sending the whole connection back.
+#[tauri::command]
+pub fn set_favorite(app_handle: AppHandle, id: i64, is_favorite: bool) -> Result<(), String> {
+    let db_path = get_db_path(&app_handle);
+    let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let updated = db_conn
+        .execute(
+            "UPDATE connections SET is_favorite = ?1 WHERE id = ?2",
+            params![is_favorite, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Connection {} not found", id));
+    }
+    Ok(())
+}
+
+/// Duplicate a connection as "Copy of <name>", keeping its group and jump
+/// host settings. Returns the new connection id.
+#[tauri::command]
+pub fn clone_connection(app_handle: AppHandle, id: i64) -> Result<i64, String> {
+    let source = get_connection_by_id(&app_handle, id)?.ok_or("Connection not found")?;
+    let copy = SshConnection {
+        id: None,
+        name: format!("Copy of {}", source.name),
+        ..source
+    };
+    create_connection(app_handle, copy)
+}
+
 #[tauri::command]
 pub fn update_connection(app_handle: AppHandle, conn: SshConnection) -> Result<(), String> {
+    let color = validate_color(conn.color.as_deref())?;
+    let icon = validate_icon(conn.icon.as_deref())?;
+    if let Some(policy) = conn.host_key_policy.as_deref() {
+        validate_host_key_policy(policy)?;
+    }
     let db_path = get_db_path(&app_handle);
     let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     db_conn.execute(
-        "UPDATE connections SET name=?1, host=?2, port=?3, username=?4, password=?5, jump_host=?6, jump_port=?7, jump_username=?8, jump_password=?9, group_id=?10, os_type=?11, auth_type=?12, ssh_key_id=?13 WHERE id=?14",
-        params![conn.name, conn.host, conn.port, conn.username, conn.password, conn.jump_host, conn.jump_port, conn.jump_username, conn.jump_password, conn.group_id, conn.os_type, conn.auth_type.unwrap_or("password".to_string()), conn.ssh_key_id, conn.id],
+        "UPDATE connections SET name=?1, host=?2, port=?3, username=?4, password=?5, jump_host=?6, jump_port=?7, jump_username=?8, jump_password=?9, group_id=?10, os_type=?11, auth_type=?12, ssh_key_id=?13, tags=?14, force_scp=?15, max_background_sessions=?16, jump_hosts=?17, jump_auth_type=?18, jump_key_id=?19, color=?20, icon=?21, is_favorite=?22, agent_forwarding=?23, low_latency=?24, idle_timeout_minutes=?25, kex_algorithms=?26, ciphers=?27, mac_algorithms=?28, legacy_mode=?29, init_command=?30, remote_rc=?31, host_key_policy=?32, socket_path=?33, proxy_command=?34, sftp_handle_cache=?35, notes=?36, x11_forwarding=?37 WHERE id=?38",
+        params![conn.name, conn.host, conn.port, conn.username, conn.password, conn.jump_host, conn.jump_port, conn.jump_username, conn.jump_password, conn.group_id, conn.os_type, conn.auth_type.unwrap_or("password".to_string()), conn.ssh_key_id, join_tags(&conn.tags), conn.force_scp, conn.max_background_sessions, jump_hosts_json(&conn.jump_hosts), conn.jump_auth_type, conn.jump_key_id, color, icon, conn.is_favorite, conn.agent_forwarding, conn.low_latency, conn.idle_timeout_minutes, conn.kex_algorithms, conn.ciphers, conn.mac_algorithms, conn.legacy_mode, conn.init_command, conn.remote_rc, conn.host_key_policy, conn.socket_path, conn.proxy_command, conn.sftp_handle_cache, conn.notes, conn.x11_forwarding, conn.id],
     ).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Move `ids` into `group_id` (None for ungrouped) in the given order, in one
+/// transaction. Connections of the group not listed keep their place after them.
+#[tauri::command]
+pub fn reorder_connections(
+    app_handle: AppHandle,
+    group_id: Option<i64>,
+    ids: Vec<i64>,
+) -> Result<(), String> {
+    let db_path = get_db_path(&app_handle);
+    let mut db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = db_conn.transaction().map_err(|e| e.to_string())?;
+
+    let listed = ids.len() as i64;
+    tx.execute(
+        "UPDATE connections SET sort_order = sort_order + ?1 WHERE group_id IS ?2",
+        params![listed, group_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (index, id) in ids.iter().enumerate() {
+        let updated = tx
+            .execute(
+                "UPDATE connections SET group_id = ?1, sort_order = ?2 WHERE id = ?3",
+                params![group_id, index as i64, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Connection {} not found", id));
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn delete_connection(app_handle: AppHandle, id: i64) -> Result<(), String> {
     let db_path = get_db_path(&app_handle);
@@ -659,6 +1222,9 @@ pub fn delete_connection(app_handle: AppHandle, id: i64) -> Result<(), String> {
     db_conn
         .execute("DELETE FROM connections WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    db_conn
+        .execute("DELETE FROM path_bookmarks WHERE connection_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -789,6 +1355,260 @@ pub fn delete_tunnel(app_handle: AppHandle, id: i64) -> Result<(), String> {
     Ok(())
 }
 
+fn map_snippet_row(row: &Row<'_>) -> Result<CommandSnippet> {
+    let tags: Option<String> = row.get(4)?;
+    Ok(CommandSnippet {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        command: row.get(2)?,
+        description: row.get(3)?,
+        tags: split_tags(tags.as_deref().unwrap_or_default()),
+        created_at: row.get(5)?,
+    })
+}
+
+#[tauri::command]
+pub fn get_snippets(app_handle: AppHandle) -> Result<Vec<CommandSnippet>, String> {
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, command, description, tags, created_at FROM command_snippets ORDER BY name COLLATE NOCASE")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], map_snippet_row)
+        .map_err(|e| e.to_string())?;
+
+    let mut snippets = Vec::new();
+    for row in rows {
+        snippets.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(snippets)
+}
+
+pub fn get_snippet_by_id(app_handle: &AppHandle, id: i64) -> Result<Option<CommandSnippet>, String> {
+    let db_path = get_db_path(app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, name, command, description, tags, created_at FROM command_snippets WHERE id = ?1",
+        params![id],
+        map_snippet_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn validate_snippet(snippet: &CommandSnippet) -> Result<(), String> {
+    if snippet.name.trim().is_empty() {
+        return Err("Snippet name is required".to_string());
+    }
+    if snippet.command.trim().is_empty() {
+        return Err("Snippet command is required".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn create_snippet(app_handle: AppHandle, snippet: CommandSnippet) -> Result<i64, String> {
+    validate_snippet(&snippet)?;
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let created_at = snippet.created_at.unwrap_or_else(unix_now);
+
+    conn.execute(
+        "INSERT INTO command_snippets (name, command, description, tags, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            snippet.name.trim(),
+            snippet.command,
+            snippet.description,
+            join_tags(&snippet.tags),
+            created_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+pub fn update_snippet(app_handle: AppHandle, snippet: CommandSnippet) -> Result<(), String> {
+    validate_snippet(&snippet)?;
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let id = snippet
+        .id
+        .ok_or_else(|| "Snippet ID is required for update".to_string())?;
+
+    conn.execute(
+        "UPDATE command_snippets SET name=?1, command=?2, description=?3, tags=?4 WHERE id=?5",
+        params![
+            snippet.name.trim(),
+            snippet.command,
+            snippet.description,
+            join_tags(&snippet.tags),
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_snippet(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM command_snippets WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Recently visited directories kept per connection.
+const MAX_RECENT_PATHS: i64 = 20;
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn map_path_bookmark_row(row: &Row<'_>) -> Result<PathBookmark> {
+    Ok(PathBookmark {
+        id: row.get(0)?,
+        connection_id: row.get(1)?,
+        path: row.get(2)?,
+        name: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+fn query_path_bookmarks(
+    app_handle: &AppHandle,
+    connection_id: i64,
+    kind: &str,
+    order: &str,
+) -> Result<Vec<PathBookmark>, String> {
+    let db_path = get_db_path(app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, connection_id, path, name, created_at FROM path_bookmarks WHERE connection_id = ?1 AND kind = ?2 ORDER BY {}",
+            order
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![connection_id, kind], map_path_bookmark_row)
+        .map_err(|e| e.to_string())?;
+
+    let mut bookmarks = Vec::new();
+    for row in rows {
+        bookmarks.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(bookmarks)
+}
+
+#[tauri::command]
+pub fn get_bookmarks(app_handle: AppHandle, connection_id: i64) -> Result<Vec<PathBookmark>, String> {
+    query_path_bookmarks(
+        &app_handle,
+        connection_id,
+        "bookmark",
+        "COALESCE(name, path) COLLATE NOCASE",
+    )
+}
+
+/// Most recently visited directories first, at most `MAX_RECENT_PATHS`.
+#[tauri::command]
+pub fn get_recent_paths(app_handle: AppHandle, connection_id: i64) -> Result<Vec<PathBookmark>, String> {
+    query_path_bookmarks(&app_handle, connection_id, "recent", "created_at DESC, id DESC")
+}
+
+/// Bookmark a directory; bookmarking the same path again updates its name.
+#[tauri::command]
+pub fn add_bookmark(app_handle: AppHandle, bookmark: PathBookmark) -> Result<i64, String> {
+    if bookmark.path.trim().is_empty() {
+        return Err("Bookmark path is required".to_string());
+    }
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO path_bookmarks (connection_id, kind, path, name, created_at) VALUES (?1, 'bookmark', ?2, ?3, ?4)
+         ON CONFLICT(connection_id, kind, path) DO UPDATE SET name = excluded.name",
+        params![
+            bookmark.connection_id,
+            bookmark.path.trim(),
+            bookmark.name,
+            bookmark.created_at.unwrap_or_else(unix_now)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id FROM path_bookmarks WHERE connection_id = ?1 AND kind = 'bookmark' AND path = ?2",
+        params![bookmark.connection_id, bookmark.path.trim()],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_bookmark(app_handle: AppHandle, bookmark: PathBookmark) -> Result<(), String> {
+    let id = bookmark
+        .id
+        .ok_or_else(|| "Bookmark ID is required for update".to_string())?;
+    if bookmark.path.trim().is_empty() {
+        return Err("Bookmark path is required".to_string());
+    }
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE path_bookmarks SET path=?1, name=?2 WHERE id=?3 AND kind = 'bookmark'",
+        params![bookmark.path.trim(), bookmark.name, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_bookmark(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let db_path = get_db_path(&app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM path_bookmarks WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Move `path` to the top of the connection's recent list, dropping the
+/// oldest entries beyond `MAX_RECENT_PATHS`.
+pub fn record_recent_path(app_handle: &AppHandle, connection_id: i64, path: &str) -> Result<(), String> {
+    let db_path = get_db_path(app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO path_bookmarks (connection_id, kind, path, created_at) VALUES (?1, 'recent', ?2, ?3)
+         ON CONFLICT(connection_id, kind, path) DO UPDATE SET created_at = excluded.created_at",
+        params![connection_id, path, unix_now()],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM path_bookmarks WHERE connection_id = ?1 AND kind = 'recent' AND id NOT IN (
+            SELECT id FROM path_bookmarks WHERE connection_id = ?1 AND kind = 'recent'
+            ORDER BY created_at DESC, id DESC LIMIT ?2
+        )",
+        params![connection_id, MAX_RECENT_PATHS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 pub fn get_tunnel_by_id(app_handle: &AppHandle, id: i64) -> Result<Option<Tunnel>, String> {
     let db_path = get_db_path(app_handle);
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
@@ -826,29 +1646,96 @@ pub fn get_tunnel_by_id(app_handle: &AppHandle, id: i64) -> Result<Option<Tunnel
 
 #[tauri::command]
 pub fn create_group(app_handle: AppHandle, group: ConnectionGroup) -> Result<(), String> {
-    let db_path = get_db_path(&app_handle);
+    insert_group(&app_handle, &group)?;
+    Ok(())
+}
+
+/// Insert a group and return its row id.
+pub fn insert_group(app_handle: &AppHandle, group: &ConnectionGroup) -> Result<i64, String> {
+    let color = validate_color(group.color.as_deref())?;
+    let icon = validate_icon(group.icon.as_deref())?;
+    let db_path = get_db_path(app_handle);
     let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     db_conn
         .execute(
-            "INSERT INTO connection_groups (name, parent_id) VALUES (?1, ?2)",
-            params![group.name, group.parent_id],
+            "INSERT INTO connection_groups (name, parent_id, color, icon) VALUES (?1, ?2, ?3, ?4)",
+            params![group.name, group.parent_id, color, icon],
         )
         .map_err(|e| e.to_string())?;
-    Ok(())
+    Ok(db_conn.last_insert_rowid())
 }
 
 #[tauri::command]
 pub fn update_group(app_handle: AppHandle, group: ConnectionGroup) -> Result<(), String> {
+    let color = validate_color(group.color.as_deref())?;
+    let icon = validate_icon(group.icon.as_deref())?;
     let db_path = get_db_path(&app_handle);
     let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     db_conn
         .execute(
-            "UPDATE connection_groups SET name=?1, parent_id=?2 WHERE id=?3",
-            params![group.name, group.parent_id, group.id],
+            "UPDATE connection_groups SET name=?1, parent_id=?2, color=?3, icon=?4 WHERE id=?5",
+            params![group.name, group.parent_id, color, icon, group.id],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Defaults new connections in group `id` inherit.
+#[tauri::command]
+pub fn get_group_defaults(app_handle: AppHandle, id: i64) -> Result<GroupDefaults, String> {
+    let db_path = get_db_path(&app_handle);
+    let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    get_group_defaults_with_conn(&db_conn, id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "Group not found".to_string(),
+        e => e.to_string(),
+    })
+}
+
+/// Replace the defaults of group `id`. Existing connections are unaffected;
+/// only connections created in the group afterwards inherit them.
+#[tauri::command]
+pub fn set_group_defaults(
+    app_handle: AppHandle,
+    id: i64,
+    defaults: GroupDefaults,
+) -> Result<(), String> {
+    if let Some(policy) = defaults.host_key_policy.as_deref() {
+        validate_host_key_policy(policy)?;
+    }
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let jump_hosts = if defaults.jump_hosts.is_empty() {
+        None
+    } else {
+        Some(jump_hosts_json(&defaults.jump_hosts))
+    };
+    let db_path = get_db_path(&app_handle);
+    let db_conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let updated = db_conn
+        .execute(
+            "UPDATE connection_groups SET default_username=?1, default_auth_type=?2, default_ssh_key_id=?3, default_jump_hosts=?4, default_host_key_policy=?5, default_proxy_command=?6 WHERE id=?7",
+            params![
+                non_empty(&defaults.username),
+                non_empty(&defaults.auth_type),
+                defaults.ssh_key_id,
+                jump_hosts,
+                non_empty(&defaults.host_key_policy),
+                non_empty(&defaults.proxy_command),
+                id
+            ],
         )
         .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Group not found".to_string());
+    }
     Ok(())
 }
 
@@ -866,7 +1753,7 @@ pub fn delete_group(app_handle: AppHandle, id: i64) -> Result<(), String> {
 }
 
 pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
-    let mut stmt = conn.prepare("SELECT theme, language, account_mode, account_user_id, account_display_name, account_email, account_enterprise_id, account_enterprise_name, account_sub_account_id, account_access_token, account_refresh_token, account_expires_at, account_refresh_expires_at, sync_enabled, sync_endpoint_url, sync_organization_scope, sync_assets, sync_settings, sync_last_cloud_sync_at, ai_api_url, ai_api_key, ai_model_name, ai_provider_type, ai_subscription_plan, ai_subscription_status, ai_subscription_seats, ai_subscription_billing_scope, ai_subscription_price_per_seat, ai_subscription_currency, ai_subscription_plan_display_name, ai_subscription_started_at, ai_subscription_renewal_at, ai_subscription_allow_custom_endpoint, ai_subscription_use_custom_endpoint, ai_subscription_sync_to_cloud, ai_custom_endpoint_name, ai_custom_endpoint_url, ai_custom_endpoint_key, ai_custom_endpoint_model_name, ai_custom_endpoint_provider_type, ai_pending_checkout_invoice_id, ai_pending_checkout_provider_key, ai_pending_checkout_url, ai_pending_checkout_external_reference, ai_pending_checkout_created_at, ai_pending_checkout_expires_at, terminal_font_size, terminal_font_family, terminal_cursor_style, terminal_line_height, file_manager_view_mode, file_manager_layout, ssh_max_background_sessions, ssh_enable_auto_cleanup, ssh_cleanup_interval_minutes, file_manager_sftp_buffer_size, connection_timeout_secs, jump_host_timeout_secs, local_forward_timeout_secs, command_timeout_secs, sftp_operation_timeout_secs, reconnect_max_attempts, reconnect_initial_delay_ms, reconnect_max_delay_ms, reconnect_backoff_multiplier, reconnect_enabled, heartbeat_tcp_keepalive_interval_secs, heartbeat_ssh_keepalive_interval_secs, heartbeat_app_heartbeat_interval_secs, heartbeat_timeout_secs, heartbeat_failed_heartbeats_before_action, pool_health_check_interval_secs, pool_session_warmup_count, pool_max_session_age_minutes, pool_unhealthy_threshold, network_adaptive_enabled, network_latency_check_interval_secs, network_high_latency_threshold_ms, network_low_bandwidth_threshold_kbps FROM settings WHERE id = 1")
+    let mut stmt = conn.prepare("SELECT theme, language, account_mode, account_user_id, account_display_name, account_email, account_enterprise_id, account_enterprise_name, account_sub_account_id, account_access_token, account_refresh_token, account_expires_at, account_refresh_expires_at, sync_enabled, sync_endpoint_url, sync_organization_scope, sync_assets, sync_settings, sync_last_cloud_sync_at, ai_api_url, ai_api_key, ai_model_name, ai_provider_type, ai_subscription_plan, ai_subscription_status, ai_subscription_seats, ai_subscription_billing_scope, ai_subscription_price_per_seat, ai_subscription_currency, ai_subscription_plan_display_name, ai_subscription_started_at, ai_subscription_renewal_at, ai_subscription_allow_custom_endpoint, ai_subscription_use_custom_endpoint, ai_subscription_sync_to_cloud, ai_custom_endpoint_name, ai_custom_endpoint_url, ai_custom_endpoint_key, ai_custom_endpoint_model_name, ai_custom_endpoint_provider_type, ai_pending_checkout_invoice_id, ai_pending_checkout_provider_key, ai_pending_checkout_url, ai_pending_checkout_external_reference, ai_pending_checkout_created_at, ai_pending_checkout_expires_at, terminal_font_size, terminal_font_family, terminal_cursor_style, terminal_line_height, file_manager_view_mode, file_manager_layout, ssh_max_background_sessions, ssh_enable_auto_cleanup, ssh_cleanup_interval_minutes, file_manager_sftp_buffer_size, connection_timeout_secs, jump_host_timeout_secs, local_forward_timeout_secs, command_timeout_secs, sftp_operation_timeout_secs, reconnect_max_attempts, reconnect_initial_delay_ms, reconnect_max_delay_ms, reconnect_backoff_multiplier, reconnect_enabled, heartbeat_tcp_keepalive_interval_secs, heartbeat_ssh_keepalive_interval_secs, heartbeat_app_heartbeat_interval_secs, heartbeat_timeout_secs, heartbeat_failed_heartbeats_before_action, pool_health_check_interval_secs, pool_session_warmup_count, pool_max_session_age_minutes, pool_unhealthy_threshold, network_adaptive_enabled, network_latency_check_interval_secs, network_high_latency_threshold_ms, network_low_bandwidth_threshold_kbps, file_manager_default_editor, file_manager_max_transfer_rate, ssh_idle_session_timeout_minutes, command_safety_dangerous_patterns, forward_bind_address, file_manager_sftp_read_buffer_size, file_manager_soft_delete, file_manager_require_confirm_threshold, file_manager_max_concurrent_transfers, terminal_track_cwd, host_key_policy, heartbeat_shell_keepalive_interval_secs FROM settings WHERE id = 1")
         ?;
 
     let mut rows = stmt
@@ -961,6 +1848,7 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
                         .get::<_, Option<String>>(48)?
                         .unwrap_or_else(|| "block".to_string()),
                     line_height: row.get::<_, Option<f32>>(49)?.unwrap_or(1.0),
+                    track_cwd: row.get::<_, Option<bool>>(88)?.unwrap_or(false),
                 },
                 file_manager: FileManagerSettings {
                     view_mode: row
@@ -970,11 +1858,27 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
                         .get::<_, Option<String>>(51)?
                         .unwrap_or_else(|| "bottom".to_string()),
                     sftp_buffer_size: row.get::<_, Option<i32>>(55)?.unwrap_or(512),
+                    sftp_read_buffer_size: row.get::<_, Option<i32>>(84)?.unwrap_or(512),
+                    default_editor: row.get::<_, Option<String>>(79)?,
+                    max_transfer_rate_bytes_per_sec: row
+                        .get::<_, Option<i64>>(80)?
+                        .unwrap_or(0)
+                        .max(0) as u64,
+                    soft_delete: row.get::<_, Option<bool>>(85)?.unwrap_or(true),
+                    require_confirm_threshold: row
+                        .get::<_, Option<i64>>(86)?
+                        .unwrap_or(1000)
+                        .max(0) as u64,
+                    max_concurrent_transfers: row
+                        .get::<_, Option<i64>>(87)?
+                        .unwrap_or(DEFAULT_MAX_CONCURRENT_TRANSFERS as i64)
+                        .clamp(1, u32::MAX as i64) as u32,
                 },
                 ssh_pool: SshPoolSettings {
                     max_background_sessions: row.get::<_, Option<i32>>(52)?.unwrap_or(10),
                     enable_auto_cleanup: row.get::<_, Option<bool>>(53)?.unwrap_or(true),
                     cleanup_interval_minutes: row.get::<_, Option<i32>>(54)?.unwrap_or(5),
+                    idle_session_timeout_minutes: row.get::<_, Option<i32>>(81)?.unwrap_or(10),
                 },
                 connection_timeout: ConnectionTimeoutSettings {
                     connection_timeout_secs: row.get::<_, Option<u32>>(56)?.unwrap_or(15),
@@ -982,6 +1886,12 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
                     local_forward_timeout_secs: row.get::<_, Option<u32>>(58)?.unwrap_or(10),
                     command_timeout_secs: row.get::<_, Option<u32>>(59)?.unwrap_or(30),
                     sftp_operation_timeout_secs: row.get::<_, Option<u32>>(60)?.unwrap_or(60),
+                    forward_bind_address: row
+                        .get::<_, Option<String>>(83)?
+                        .unwrap_or_else(|| DEFAULT_FORWARD_BIND_ADDRESS.to_string()),
+                    host_key_policy: row
+                        .get::<_, Option<String>>(89)?
+                        .unwrap_or_else(|| DEFAULT_HOST_KEY_POLICY.to_string()),
                 },
                 reconnect: ReconnectSettings {
                     max_reconnect_attempts: row.get::<_, Option<u32>>(61)?.unwrap_or(5),
@@ -996,6 +1906,7 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
                     app_heartbeat_interval_secs: row.get::<_, Option<u32>>(68)?.unwrap_or(30),
                     heartbeat_timeout_secs: row.get::<_, Option<u32>>(69)?.unwrap_or(5),
                     failed_heartbeats_before_action: row.get::<_, Option<u32>>(70)?.unwrap_or(3),
+                    shell_keepalive_interval_secs: row.get::<_, Option<u32>>(90)?.unwrap_or(30),
                 },
                 pool_health: PoolHealthSettings {
                     health_check_interval_secs: row.get::<_, Option<u32>>(71)?.unwrap_or(60),
@@ -1009,6 +1920,16 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
                     high_latency_threshold_ms: row.get::<_, Option<u32>>(77)?.unwrap_or(300),
                     low_bandwidth_threshold_kbps: row.get::<_, Option<u32>>(78)?.unwrap_or(100),
                 },
+                command_safety: match row.get::<_, Option<String>>(82)? {
+                    Some(patterns) => CommandSafetySettings {
+                        dangerous_command_patterns: patterns
+                            .lines()
+                            .map(|pattern| pattern.trim().to_string())
+                            .filter(|pattern| !pattern.is_empty())
+                            .collect(),
+                    },
+                    None => CommandSafetySettings::default(),
+                },
             })
         })
         ?;
@@ -1022,7 +1943,7 @@ pub fn get_settings_with_conn(conn: &Connection) -> Result<AppSettings> {
 
 pub fn save_settings_with_conn(conn: &Connection, settings: AppSettings) -> Result<()> {
     conn.execute(
-        "UPDATE settings SET theme=?1, language=?2, account_mode=?3, account_user_id=?4, account_display_name=?5, account_email=?6, account_enterprise_id=?7, account_enterprise_name=?8, account_sub_account_id=?9, account_access_token=?10, account_refresh_token=?11, account_expires_at=?12, account_refresh_expires_at=?13, sync_enabled=?14, sync_endpoint_url=?15, sync_organization_scope=?16, sync_assets=?17, sync_settings=?18, sync_last_cloud_sync_at=?19, ai_api_url=?20, ai_api_key=?21, ai_model_name=?22, ai_provider_type=?23, ai_subscription_plan=?24, ai_subscription_status=?25, ai_subscription_seats=?26, ai_subscription_billing_scope=?27, ai_subscription_price_per_seat=?28, ai_subscription_currency=?29, ai_subscription_plan_display_name=?30, ai_subscription_started_at=?31, ai_subscription_renewal_at=?32, ai_subscription_allow_custom_endpoint=?33, ai_subscription_use_custom_endpoint=?34, ai_subscription_sync_to_cloud=?35, ai_custom_endpoint_name=?36, ai_custom_endpoint_url=?37, ai_custom_endpoint_key=?38, ai_custom_endpoint_model_name=?39, ai_custom_endpoint_provider_type=?40, ai_pending_checkout_invoice_id=?41, ai_pending_checkout_provider_key=?42, ai_pending_checkout_url=?43, ai_pending_checkout_external_reference=?44, ai_pending_checkout_created_at=?45, ai_pending_checkout_expires_at=?46, terminal_font_size=?47, terminal_font_family=?48, terminal_cursor_style=?49, terminal_line_height=?50, file_manager_view_mode=?51, file_manager_layout=?52, ssh_max_background_sessions=?53, ssh_enable_auto_cleanup=?54, ssh_cleanup_interval_minutes=?55, file_manager_sftp_buffer_size=?56, connection_timeout_secs=?57, jump_host_timeout_secs=?58, local_forward_timeout_secs=?59, command_timeout_secs=?60, sftp_operation_timeout_secs=?61, reconnect_max_attempts=?62, reconnect_initial_delay_ms=?63, reconnect_max_delay_ms=?64, reconnect_backoff_multiplier=?65, reconnect_enabled=?66, heartbeat_tcp_keepalive_interval_secs=?67, heartbeat_ssh_keepalive_interval_secs=?68, heartbeat_app_heartbeat_interval_secs=?69, heartbeat_timeout_secs=?70, heartbeat_failed_heartbeats_before_action=?71, pool_health_check_interval_secs=?72, pool_session_warmup_count=?73, pool_max_session_age_minutes=?74, pool_unhealthy_threshold=?75, network_adaptive_enabled=?76, network_latency_check_interval_secs=?77, network_high_latency_threshold_ms=?78, network_low_bandwidth_threshold_kbps=?79 WHERE id = 1",
+        "UPDATE settings SET theme=?1, language=?2, account_mode=?3, account_user_id=?4, account_display_name=?5, account_email=?6, account_enterprise_id=?7, account_enterprise_name=?8, account_sub_account_id=?9, account_access_token=?10, account_refresh_token=?11, account_expires_at=?12, account_refresh_expires_at=?13, sync_enabled=?14, sync_endpoint_url=?15, sync_organization_scope=?16, sync_assets=?17, sync_settings=?18, sync_last_cloud_sync_at=?19, ai_api_url=?20, ai_api_key=?21, ai_model_name=?22, ai_provider_type=?23, ai_subscription_plan=?24, ai_subscription_status=?25, ai_subscription_seats=?26, ai_subscription_billing_scope=?27, ai_subscription_price_per_seat=?28, ai_subscription_currency=?29, ai_subscription_plan_display_name=?30, ai_subscription_started_at=?31, ai_subscription_renewal_at=?32, ai_subscription_allow_custom_endpoint=?33, ai_subscription_use_custom_endpoint=?34, ai_subscription_sync_to_cloud=?35, ai_custom_endpoint_name=?36, ai_custom_endpoint_url=?37, ai_custom_endpoint_key=?38, ai_custom_endpoint_model_name=?39, ai_custom_endpoint_provider_type=?40, ai_pending_checkout_invoice_id=?41, ai_pending_checkout_provider_key=?42, ai_pending_checkout_url=?43, ai_pending_checkout_external_reference=?44, ai_pending_checkout_created_at=?45, ai_pending_checkout_expires_at=?46, terminal_font_size=?47, terminal_font_family=?48, terminal_cursor_style=?49, terminal_line_height=?50, file_manager_view_mode=?51, file_manager_layout=?52, ssh_max_background_sessions=?53, ssh_enable_auto_cleanup=?54, ssh_cleanup_interval_minutes=?55, file_manager_sftp_buffer_size=?56, connection_timeout_secs=?57, jump_host_timeout_secs=?58, local_forward_timeout_secs=?59, command_timeout_secs=?60, sftp_operation_timeout_secs=?61, reconnect_max_attempts=?62, reconnect_initial_delay_ms=?63, reconnect_max_delay_ms=?64, reconnect_backoff_multiplier=?65, reconnect_enabled=?66, heartbeat_tcp_keepalive_interval_secs=?67, heartbeat_ssh_keepalive_interval_secs=?68, heartbeat_app_heartbeat_interval_secs=?69, heartbeat_timeout_secs=?70, heartbeat_failed_heartbeats_before_action=?71, pool_health_check_interval_secs=?72, pool_session_warmup_count=?73, pool_max_session_age_minutes=?74, pool_unhealthy_threshold=?75, network_adaptive_enabled=?76, network_latency_check_interval_secs=?77, network_high_latency_threshold_ms=?78, network_low_bandwidth_threshold_kbps=?79, file_manager_default_editor=?80, file_manager_max_transfer_rate=?81, ssh_idle_session_timeout_minutes=?82, command_safety_dangerous_patterns=?83, forward_bind_address=?84, file_manager_sftp_read_buffer_size=?85, file_manager_soft_delete=?86, file_manager_require_confirm_threshold=?87, file_manager_max_concurrent_transfers=?88, terminal_track_cwd=?89, host_key_policy=?90, heartbeat_shell_keepalive_interval_secs=?91 WHERE id = 1",
         params![
             settings.theme,
             settings.language,
@@ -1103,6 +2024,18 @@ pub fn save_settings_with_conn(conn: &Connection, settings: AppSettings) -> Resu
             settings.network_adaptive.latency_check_interval_secs,
             settings.network_adaptive.high_latency_threshold_ms,
             settings.network_adaptive.low_bandwidth_threshold_kbps,
+            settings.file_manager.default_editor,
+            settings.file_manager.max_transfer_rate_bytes_per_sec as i64,
+            settings.ssh_pool.idle_session_timeout_minutes,
+            settings.command_safety.dangerous_command_patterns.join("\n"),
+            settings.connection_timeout.forward_bind_address,
+            settings.file_manager.sftp_read_buffer_size,
+            settings.file_manager.soft_delete,
+            settings.file_manager.require_confirm_threshold as i64,
+            settings.file_manager.max_concurrent_transfers as i64,
+            settings.terminal_appearance.track_cwd,
+            settings.connection_timeout.host_key_policy,
+            settings.heartbeat.shell_keepalive_interval_secs,
         ],
     )?;
 
@@ -1117,10 +2050,27 @@ pub fn get_settings(app_handle: AppHandle) -> Result<AppSettings, String> {
 }
 
 #[tauri::command]
-pub fn save_settings(app_handle: AppHandle, settings: AppSettings) -> Result<(), String> {
+pub fn save_settings(
+    app_handle: AppHandle,
+    settings: AppSettings,
+) -> Result<Option<String>, String> {
+    // A wildcard bind is saved but reported back as a warning
+    let warning =
+        validate_forward_bind_address(&settings.connection_timeout.forward_bind_address)?;
+    validate_host_key_policy(&settings.connection_timeout.host_key_policy)?;
+    validate_sftp_buffer_size(settings.file_manager.sftp_buffer_size, "SFTP write buffer")?;
+    validate_sftp_buffer_size(
+        settings.file_manager.sftp_read_buffer_size,
+        "SFTP read buffer",
+    )?;
     let db_path = get_db_path(&app_handle);
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    save_settings_with_conn(&conn, settings).map_err(|e| e.to_string())
+    save_settings_with_conn(&conn, settings).map_err(|e| e.to_string())?;
+    app_handle
+        .state::<crate::ssh::client::AppState>()
+        .transfer_queue
+        .reload_limit();
+    Ok(warning)
 }
 
 // --- SSH Key Commands ---
@@ -1176,6 +2126,19 @@ pub fn delete_ssh_key(app_handle: AppHandle, id: i64) -> Result<(), String> {
     Ok(())
 }
 
+/// Insert a key and return its row id.
+pub fn insert_ssh_key(app_handle: &AppHandle, key: &SshKey) -> Result<i64, String> {
+    let db_path = get_db_path(app_handle);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO ssh_keys (name, content, passphrase, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![key.name, key.content, key.passphrase, key.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
 pub fn get_ssh_key_by_id(app_handle: &AppHandle, id: i64) -> Result<Option<SshKey>, String> {
     let db_path = get_db_path(app_handle);
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
@@ -1203,6 +2166,22 @@ pub fn get_ssh_key_by_id(app_handle: &AppHandle, id: i64) -> Result<Option<SshKe
     }
 }
 
+/// Public key of stored key `key_id` plus a ready-to-paste command that
+/// installs it, for servers where `install_ssh_key` cannot log in by password.
+#[tauri::command]
+pub fn get_key_public_openssh(app_handle: AppHandle, key_id: i64) -> Result<KeyPublicExport, String> {
+    let key = get_ssh_key_by_id(&app_handle, key_id)?.ok_or("SSH Key not found")?;
+    let public_key = crate::ssh::keys::public_key_openssh(&key.content)?;
+    let install_snippet = format!(
+        "mkdir -p ~/.ssh && chmod 700 ~/.ssh && echo '{}' >> ~/.ssh/authorized_keys && chmod 600 ~/.ssh/authorized_keys",
+        crate::ssh::file_ops::escape_shell_arg(&public_key)
+    );
+    Ok(KeyPublicExport {
+        public_key,
+        install_snippet,
+    })
+}
+
 #[tauri::command]
 pub fn generate_ssh_key(
     app_handle: AppHandle,
@@ -1,3 +1,5 @@
+mod ai;
+mod connection_export;
 mod db;
 mod models;
 mod ops;
@@ -25,32 +27,74 @@ pub fn run() {
             ops::init_ops_schema(app.handle())?;
             ssh::wsl::import_wsl_to_db(app.handle()).ok(); // Best effort import
             app.manage(ssh::AppState::new());
+            ssh::known_hosts::attach_app_handle(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             ssh::client::test_connection,
+            ssh::client::list_auth_methods,
+            ssh::connectivity::test_all_connections,
+            ssh::connectivity::measure_latency,
             ssh::client::connect,
             ssh::client::disconnect,
             ssh::client::cleanup_and_reconnect,
             ssh::client::cancel_transfer,
             ssh::client::cancel_command_execution,
             ssh::client::get_os_info,
+            ssh::client::get_session_diagnostics,
+            ssh::wsl::list_wsl_distros,
+            ssh::wsl::windows_to_wsl_path,
+            ssh::wsl::wsl_to_windows_path,
+            ssh::known_hosts::list_known_hosts,
+            ssh::authorized_keys::list_authorized_keys,
+            ssh::authorized_keys::remove_authorized_key,
+            ssh::benchmark::benchmark_transfer,
+            ssh::dir_watch::watch_remote_directory,
+            ssh::dir_watch::unwatch_remote_directory,
+            ssh::known_hosts::remove_known_host,
+            ssh::known_hosts::reset_known_host,
+            ssh::known_hosts::confirm_host_key,
             ssh::file_ops::list_files,
             ssh::file_ops::list_files_page,
             ssh::file_ops::read_remote_file,
+            ssh::file_ops::read_remote_file_range,
+            ssh::file_ops::read_remote_file_via_cat,
+            ssh::file_ops::write_remote_file_via_tee,
+            ssh::file_ops::get_file_preview,
+            ssh::file_ops::download_temp_and_open,
             ssh::file_ops::write_remote_file,
             ssh::file_ops::search_remote_files,
             ssh::file_ops::create_directory,
+            ssh::file_ops::create_directory_recursive,
             ssh::file_ops::create_file,
+            ssh::file_ops::check_writable,
+            ssh::file_ops::touch_file,
+            ssh::file_ops::duplicate_file,
             ssh::file_ops::delete_item,
+            ssh::file_ops::delete_item_confirmed,
+            ssh::file_ops::count_items,
+            ssh::trash::trash_item,
+            ssh::trash::list_trash,
+            ssh::trash::restore_from_trash,
+            ssh::trash::empty_trash,
             ssh::file_ops::rename_item,
+            ssh::file_ops::batch_delete,
+            ssh::file_ops::batch_move,
+            ssh::file_ops::batch_chmod,
+            ssh::file_ops::batch_chown,
             ssh::file_ops::change_file_permission,
             ssh::file_ops::download_file,
             ssh::file_ops::upload_file,
+            ssh::file_ops::upload_directory,
+            ssh::sync::sync_directory,
             ssh::file_ops::upload_file_with_progress,
             ssh::file_ops::download_file_with_progress,
             ssh::file_ops::get_transfers,
+            ssh::file_ops::get_aggregate_transfer_progress,
+            ssh::transfer_queue::get_transfer_queue,
+            ssh::transfer_queue::reorder_queue,
+            ssh::transfer_queue::prioritize_transfer,
             ssh::file_ops::remove_transfer,
             ssh::file_ops::start_transfer_with_manager,
             ssh::file_ops::pause_transfer,
@@ -60,11 +104,25 @@ pub fn run() {
             ssh::terminal::write_to_pty,
             ssh::terminal::write_binary_to_pty,
             ssh::terminal::resize_pty,
+            ssh::terminal::send_signal_to_shell,
+            ssh::terminal::open_shell,
+            ssh::terminal::close_shell,
             ssh::command::exec_command,
+            ssh::command::exec_command_sudo,
+            ssh::command::change_remote_password,
+            ssh::command::confirm_dangerous_command,
+            ssh::command::reject_dangerous_command,
+            ssh::command::run_snippet,
             ssh::command::get_working_directory,
+            ssh::command::sync_file_manager_to_cwd,
             db::get_connections,
             db::create_connection,
+            db::clone_connection,
+            db::get_connections_by_tag,
+            db::get_favorites,
+            db::set_favorite,
             db::update_connection,
+            db::reorder_connections,
             db::delete_connection,
             ops::asset_get_host_assets,
             ops::asset_search_host_assets,
@@ -130,6 +188,15 @@ pub fn run() {
             db::create_tunnel,
             db::update_tunnel,
             db::delete_tunnel,
+            db::get_snippets,
+            db::create_snippet,
+            db::update_snippet,
+            db::delete_snippet,
+            db::get_bookmarks,
+            db::get_recent_paths,
+            db::add_bookmark,
+            db::update_bookmark,
+            db::delete_bookmark,
             db::get_settings,
             db::save_settings,
             db::get_local_workspace_snapshot,
@@ -137,19 +204,42 @@ pub fn run() {
             db::get_groups,
             db::create_group,
             db::update_group,
+            db::get_group_defaults,
+            db::set_group_defaults,
             db::delete_group,
             db::get_ssh_keys,
             db::create_ssh_key,
             db::delete_ssh_key,
             db::generate_ssh_key,
+            db::get_key_public_openssh,
             ssh::connection::install_ssh_key,
             ssh::tunnel::get_active_tunnels,
             ssh::tunnel::start_tunnel,
             ssh::tunnel::stop_tunnel,
+            ssh::forwards::list_forwards,
+            ssh::forwards::start_local_forward,
+            ssh::forwards::stop_forward,
             system::get_file_icon,
             ssh::system::get_remote_system_status,
             ssh::system::get_server_status,
-            ssh::system::get_disk_usage
+            ssh::system::detect_remote_os,
+            ssh::system::get_disk_usage,
+            ssh::system::get_path_free_space,
+            ssh::system::list_processes,
+            ssh::system::kill_process,
+            ssh::service::list_services,
+            ssh::service::control_service,
+            ssh::docker::list_docker_containers,
+            ssh::docker::docker_container_action,
+            ssh::editor::open_remote_file_in_editor,
+            ssh::archive::create_remote_archive,
+            ssh::archive::extract_remote_archive,
+            ssh::checksum::remote_file_checksum,
+            ssh::ssh_config::import_ssh_config,
+            connection_export::export_connections,
+            connection_export::import_connections,
+            ai::suggest_command,
+            ai::explain_output
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -10,6 +10,16 @@ pub struct SshKey {
     pub created_at: i64,
 }
 
+/// A stored key's public half, for installing it on a server by hand.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPublicExport {
+    /// OpenSSH public key line
+    pub public_key: String,
+    /// Shell command that appends the key to `~/.ssh/authorized_keys`
+    pub install_snippet: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct HostAsset {
@@ -78,6 +88,176 @@ pub struct Connection {
     pub os_type: Option<String>,
     pub key_content: Option<String>,
     pub key_passphrase: Option<String>,
+    /// Free-form labels such as "prod" or "eu-west", independent of groups
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix time of the last successful `measure_latency` run
+    #[serde(default)]
+    pub last_connected: Option<i64>,
+    /// Round-trip time recorded by that run
+    #[serde(default)]
+    pub last_latency_ms: Option<i64>,
+    /// Skip SFTP entirely and transfer over SCP/exec, for servers without the subsystem
+    #[serde(default)]
+    pub force_scp: bool,
+    /// Background session pool size for this host; falls back to the global
+    /// `ssh_max_background_sessions` setting when unset
+    #[serde(default)]
+    pub max_background_sessions: Option<i32>,
+    /// Bastions to tunnel through, in order, each with its own auth. When empty the
+    /// legacy `jump_*` fields act as a one-hop chain (see `jump_chain`)
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpHop>,
+    /// Auth for the legacy single jump host: "password" (default) or "key"
+    #[serde(default)]
+    pub jump_auth_type: Option<String>,
+    /// Stored key used when `jump_auth_type` is "key"
+    #[serde(default)]
+    pub jump_key_id: Option<i64>,
+    /// Hex colour (`#rgb` or `#rrggbb`) for the sidebar
+    #[serde(default)]
+    pub color: Option<String>,
+    /// One of `CONNECTION_ICONS`
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Position within its group; lower sorts first
+    #[serde(default)]
+    pub sort_order: i64,
+    /// Pinned to the favorites list
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Request agent forwarding (`ssh -A`) on shell channels. While a shell is
+    /// open, anyone with root on the remote host can use the forwarded agent to
+    /// authenticate as you elsewhere, so only enable it for trusted hosts.
+    #[serde(default)]
+    pub agent_forwarding: bool,
+    /// Request X11 forwarding (`ssh -X`) on shell channels and relay the remote
+    /// X clients to the local display (`$DISPLAY`). Those clients can read the
+    /// screen and keystrokes of the local session, so only enable it for trusted
+    /// hosts.
+    #[serde(default)]
+    pub x11_forwarding: bool,
+    /// Serve file-browser SFTP from a dedicated blocking session instead of the
+    /// polling pool. Cuts per-request latency on fast LANs.
+    #[serde(default)]
+    pub low_latency: bool,
+    /// Close the session after this many minutes without shell I/O, commands or
+    /// transfers. 0 keeps it open indefinitely.
+    #[serde(default)]
+    pub idle_timeout_minutes: u32,
+    /// Comma-separated key exchange preference passed to libssh2, e.g. for legacy
+    /// appliances; unset keeps the library defaults
+    #[serde(default)]
+    pub kex_algorithms: Option<String>,
+    /// Comma-separated cipher preference, applied in both directions
+    #[serde(default)]
+    pub ciphers: Option<String>,
+    /// Comma-separated MAC preference, applied in both directions
+    #[serde(default)]
+    pub mac_algorithms: Option<String>,
+    /// Offer older kex/cipher/host-key algorithms (`LEGACY_*` bundles) for ancient
+    /// devices, and tolerate host keys that cannot be recorded in known_hosts.
+    /// Explicit algorithm fields above still take precedence.
+    #[serde(default)]
+    pub legacy_mode: bool,
+    /// Typed into each interactive shell once it opens, verbatim and followed by a
+    /// newline, e.g. `cd /srv/app && source .env`. May span several lines.
+    #[serde(default)]
+    pub init_command: Option<String>,
+    /// Shell startup file uploaded to a temp path on connect and used in place of
+    /// `~/.bashrc` (`bash --rcfile`, or `$ENV` for `sh`); removed on disconnect
+    #[serde(default)]
+    pub remote_rc: Option<String>,
+    /// Overrides the global host key policy (`strict`, `ask` or `accept-new`)
+    /// for this connection; `None` uses the setting
+    #[serde(default)]
+    pub host_key_policy: Option<String>,
+    /// Unix domain socket sshd listens on, e.g. a container or VM socket. When set,
+    /// the connection goes through it instead of TCP and jump hosts are not used;
+    /// `host`/`port` still name the host for known_hosts
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    /// Command whose stdin/stdout carry the SSH connection, like OpenSSH's
+    /// `ProxyCommand`; `%h`, `%p` and `%r` expand to host, port and user. Jump
+    /// hosts are not used when set; `socket_path` takes precedence
+    #[serde(default)]
+    pub proxy_command: Option<String>,
+    /// Keep files read through SFTP open on the server for a few seconds, so the
+    /// repeated small reads of editor previews skip reopening them.
+    #[serde(default)]
+    pub sftp_handle_cache: bool,
+    /// Free-form notes about the server, e.g. its purpose or quirks
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Icons the sidebar can render for a connection or group.
+pub const CONNECTION_ICONS: &[&str] = &[
+    "server",
+    "database",
+    "cloud",
+    "container",
+    "desktop",
+    "globe",
+    "shield",
+    "terminal",
+];
+
+/// One bastion in a jump chain. Each hop opens the TCP stream to the next
+/// hop, and the last one to the target.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct JumpHop {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub username: String,
+    /// "password" (default) or "key"
+    #[serde(default)]
+    pub auth_type: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub ssh_key_id: Option<i64>,
+    /// Loaded from the key store before connecting; never persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase: Option<String>,
+}
+
+impl Connection {
+    /// The hops to connect through, outermost first. Connections saved before
+    /// multi-hop support keep working: their single `jump_*` host becomes a
+    /// one-element chain.
+    pub fn jump_chain(&self) -> Vec<JumpHop> {
+        let hops: Vec<JumpHop> = self
+            .jump_hosts
+            .iter()
+            .filter(|hop| !hop.host.trim().is_empty())
+            .cloned()
+            .collect();
+        if !hops.is_empty() {
+            return hops;
+        }
+        match self.jump_host.as_deref().map(str::trim) {
+            Some(host) if !host.is_empty() => vec![JumpHop {
+                host: host.to_string(),
+                port: self.jump_port,
+                username: self.jump_username.clone().unwrap_or_default(),
+                auth_type: Some(
+                    self.jump_auth_type
+                        .clone()
+                        .unwrap_or_else(|| "password".to_string()),
+                ),
+                password: self.jump_password.clone(),
+                ssh_key_id: self.jump_key_id,
+                ..Default::default()
+            }],
+            _ => Vec::new(),
+        }
+    }
 }
 
 impl From<HostAsset> for Connection {
@@ -99,6 +279,33 @@ impl From<HostAsset> for Connection {
             os_type: Some(value.platform),
             key_content: None,
             key_passphrase: None,
+            tags: value.labels,
+            last_connected: None,
+            last_latency_ms: None,
+            force_scp: false,
+            max_background_sessions: None,
+            jump_hosts: Vec::new(),
+            jump_auth_type: None,
+            jump_key_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            is_favorite: value.is_favorite.unwrap_or(false),
+            agent_forwarding: false,
+            x11_forwarding: false,
+            low_latency: false,
+            idle_timeout_minutes: 0,
+            kex_algorithms: None,
+            ciphers: None,
+            mac_algorithms: None,
+            legacy_mode: false,
+            init_command: None,
+            remote_rc: None,
+            host_key_policy: None,
+            socket_path: None,
+            proxy_command: None,
+            sftp_handle_cache: false,
+            notes: None,
         }
     }
 }
@@ -114,7 +321,7 @@ impl From<Connection> for HostAsset {
             platform: value.os_type.clone().unwrap_or_else(|| "Linux".to_string()),
             folder_id: value.group_id,
             env_id: None,
-            labels: Vec::new(),
+            labels: value.tags,
             owner: None,
             criticality: "medium".to_string(),
             default_workspace_path: None,
@@ -143,6 +350,29 @@ pub struct ConnectionGroup {
     pub id: Option<i64>,
     pub name: String,
     pub parent_id: Option<i64>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// Values new connections in a group start from. Only fields the connection
+/// leaves unset are filled in; anything set on the connection wins.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupDefaults {
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub auth_type: Option<String>,
+    #[serde(default)]
+    pub ssh_key_id: Option<i64>,
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpHop>,
+    #[serde(default)]
+    pub host_key_policy: Option<String>,
+    #[serde(default)]
+    pub proxy_command: Option<String>,
 }
 
 impl From<AssetFolder> for ConnectionGroup {
@@ -151,6 +381,8 @@ impl From<AssetFolder> for ConnectionGroup {
             id: value.id,
             name: value.name,
             parent_id: value.parent_id,
+            color: value.color,
+            icon: None,
         }
     }
 }
@@ -161,7 +393,7 @@ impl From<ConnectionGroup> for AssetFolder {
             id: value.id,
             name: value.name,
             parent_id: value.parent_id,
-            color: None,
+            color: value.color,
         }
     }
 }
@@ -585,6 +817,34 @@ pub struct Tunnel {
     pub created_at: Option<i64>,
 }
 
+/// A saved command. `{{name}}` placeholders in `command` are filled in from
+/// the parameters passed to `run_snippet`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSnippet {
+    pub id: Option<i64>,
+    pub name: String,
+    pub command: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: Option<i64>,
+}
+
+/// A remote directory saved for quick navigation on one connection. Recent
+/// entries are recorded automatically as directories are listed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PathBookmark {
+    pub id: Option<i64>,
+    pub connection_id: i64,
+    pub path: String,
+    /// Display label; the path is shown when unset
+    pub name: Option<String>,
+    /// Creation time for bookmarks, last visit for recent paths
+    pub created_at: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TunnelStatus {
@@ -593,6 +853,72 @@ pub struct TunnelStatus {
     pub pid: Option<u32>,
 }
 
+/// One line of `~/.ssh/known_hosts`, as returned by `list_known_hosts`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostEntry {
+    /// 1-based line number in the file
+    pub line: usize,
+    /// Host patterns as written, e.g. `example.com`, `[example.com]:2222`, or
+    /// a `|1|...` hash that does not reveal the name
+    pub hosts: Vec<String>,
+    pub hashed: bool,
+    /// `@cert-authority` or `@revoked`, if present
+    pub marker: Option<String>,
+    pub key_type: String,
+    /// OpenSSH-style `SHA256:...` fingerprint
+    pub fingerprint: String,
+    pub comment: String,
+}
+
+/// One key in the login user's `~/.ssh/authorized_keys`, as returned by
+/// `list_authorized_keys`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizedKey {
+    /// 1-based line number in the file
+    pub line: usize,
+    pub key_type: String,
+    /// OpenSSH-style `SHA256:...` fingerprint
+    pub fingerprint: String,
+    pub comment: String,
+    /// Leading options such as `from="..."` or `command="..."`, if any
+    pub options: Option<String>,
+}
+
+/// A directory watch started by `watch_remote_directory`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteDirWatch {
+    pub watch_id: String,
+    /// "inotify" (recursive) or "poll" (the directory's own entries only)
+    pub method: String,
+}
+
+/// A forwarding thread run by the app itself, as returned by `list_forwards`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardInfo {
+    pub id: String,
+    /// "jump": a local forward carrying a session through a jump host;
+    /// "local": a port forward started with `start_local_forward`
+    pub kind: String,
+    pub local_port: u16,
+    /// `host:port` the forwarded channel is opened to
+    pub remote_target: String,
+    /// Host whose session opens the forwarded channel
+    pub via: String,
+    pub session_id: Option<String>,
+    /// Bytes received from the far side
+    pub rx_bytes: u64,
+    /// Bytes sent to the far side
+    pub tx_bytes: u64,
+    /// Moving averages over the last few seconds of polling
+    pub rx_bytes_per_second: u64,
+    pub tx_bytes_per_second: u64,
+    pub started_at: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FileEntry {
@@ -765,6 +1091,10 @@ pub struct TerminalAppearanceSettings {
     pub font_family: String,
     pub cursor_style: String,
     pub line_height: f32,
+    /// Parse OSC 7 sequences from shell output to follow the shell's
+    /// working directory (emits `cwd-changed:{id}`).
+    #[serde(default)]
+    pub track_cwd: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -772,7 +1102,49 @@ pub struct TerminalAppearanceSettings {
 pub struct FileManagerSettings {
     pub view_mode: String,
     pub layout: String,
+    /// Upload (write) buffer in KB, 4–4096.
     pub sftp_buffer_size: i32,
+    /// Download (read) buffer in KB, 4–4096.
+    #[serde(default = "default_sftp_read_buffer_size")]
+    pub sftp_read_buffer_size: i32,
+    /// Editor command used by `open_remote_file_in_editor`, e.g. `code --wait`.
+    /// Falls back to the system default application when unset.
+    #[serde(default)]
+    pub default_editor: Option<String>,
+    /// Combined cap for all running transfers, in bytes per second. 0 = unlimited.
+    #[serde(default)]
+    pub max_transfer_rate_bytes_per_sec: u64,
+    /// Delete in the file manager moves items to the remote trash instead of
+    /// removing them.
+    #[serde(default = "default_soft_delete")]
+    pub soft_delete: bool,
+    /// Deleting a directory with more files than this needs explicit
+    /// confirmation. 0 disables the check.
+    #[serde(default = "default_require_confirm_threshold")]
+    pub require_confirm_threshold: u64,
+    /// Transfers beyond this many wait in the queue as "pending".
+    #[serde(default = "default_max_concurrent_transfers")]
+    pub max_concurrent_transfers: u32,
+}
+
+pub const DEFAULT_MAX_CONCURRENT_TRANSFERS: u32 = 3;
+
+fn default_max_concurrent_transfers() -> u32 {
+    DEFAULT_MAX_CONCURRENT_TRANSFERS
+}
+
+pub const DEFAULT_REQUIRE_CONFIRM_THRESHOLD: u64 = 1000;
+
+fn default_require_confirm_threshold() -> u64 {
+    DEFAULT_REQUIRE_CONFIRM_THRESHOLD
+}
+
+fn default_soft_delete() -> bool {
+    true
+}
+
+fn default_sftp_read_buffer_size() -> i32 {
+    crate::ssh::utils::DEFAULT_SFTP_BUFFER_KB
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -781,6 +1153,14 @@ pub struct SshPoolSettings {
     pub max_background_sessions: i32,
     pub enable_auto_cleanup: bool,
     pub cleanup_interval_minutes: i32,
+    /// Idle background sessions beyond the first in each pool are closed after
+    /// this long, checked every `cleanup_interval_minutes`. 0 disables reaping.
+    #[serde(default = "default_idle_session_timeout_minutes")]
+    pub idle_session_timeout_minutes: i32,
+}
+
+fn default_idle_session_timeout_minutes() -> i32 {
+    10
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -791,6 +1171,20 @@ pub struct ConnectionTimeoutSettings {
     pub local_forward_timeout_secs: u32,
     pub command_timeout_secs: u32,
     pub sftp_operation_timeout_secs: u32,
+    /// Local address port-forward listeners bind to
+    #[serde(default = "default_forward_bind_address")]
+    pub forward_bind_address: String,
+    /// What to do with unknown host keys: `strict`, `ask` or `accept-new`
+    #[serde(default = "default_host_key_policy")]
+    pub host_key_policy: String,
+}
+
+fn default_forward_bind_address() -> String {
+    crate::ssh::DEFAULT_FORWARD_BIND_ADDRESS.to_string()
+}
+
+fn default_host_key_policy() -> String {
+    crate::ssh::known_hosts::DEFAULT_HOST_KEY_POLICY.to_string()
 }
 
 impl Default for ConnectionTimeoutSettings {
@@ -801,6 +1195,8 @@ impl Default for ConnectionTimeoutSettings {
             local_forward_timeout_secs: 10,
             command_timeout_secs: 30,
             sftp_operation_timeout_secs: 60,
+            forward_bind_address: default_forward_bind_address(),
+            host_key_policy: default_host_key_policy(),
         }
     }
 }
@@ -835,6 +1231,14 @@ pub struct HeartbeatSettings {
     pub app_heartbeat_interval_secs: u32, // 应用层心跳间隔，默认 30
     pub heartbeat_timeout_secs: u32,      // 心跳超时，默认 5
     pub failed_heartbeats_before_action: u32, // 触发动作前的心跳失败次数，默认 3
+    /// Keepalive interval while a terminal is open, when the regular
+    /// heartbeat is paused to keep the shell responsive; 0 turns it off
+    #[serde(default = "default_shell_keepalive_interval_secs")]
+    pub shell_keepalive_interval_secs: u32,
+}
+
+fn default_shell_keepalive_interval_secs() -> u32 {
+    30
 }
 
 impl Default for HeartbeatSettings {
@@ -845,6 +1249,7 @@ impl Default for HeartbeatSettings {
             app_heartbeat_interval_secs: 30,
             heartbeat_timeout_secs: 5,
             failed_heartbeats_before_action: 3,
+            shell_keepalive_interval_secs: default_shell_keepalive_interval_secs(),
         }
     }
 }
@@ -890,6 +1295,8 @@ pub struct AppSettings {
     pub pool_health: PoolHealthSettings,
     #[serde(default)]
     pub network_adaptive: NetworkAdaptiveSettings,
+    #[serde(default)]
+    pub command_safety: CommandSafetySettings,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -900,11 +1307,83 @@ pub struct Transfer {
     pub local_path: String,
     pub remote_path: String,
     pub transfer_type: String, // "upload" | "download"
-    pub status: String, // "pending" | "running" | "paused" | "completed" | "error" | "cancelled"
+    pub status: String, // "pending" | "running" | "paused" | "completed" | "error" | "cancelled" | "verify-failed"
     pub total_size: u64,
     pub transferred: u64,
     pub created_at: i64,
     pub error: Option<String>,
+    // Set when the transfer was verified
+    #[serde(default)]
+    pub local_hash: Option<String>,
+    #[serde(default)]
+    pub remote_hash: Option<String>,
+}
+
+/// Combined progress of every transfer that has not finished, for a single
+/// global indicator. Counts cover all transfers in the list.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateTransferProgress {
+    pub transferred: u64,
+    pub total: u64,
+    /// Combined rate over the last few seconds
+    pub bytes_per_second: u64,
+    /// `None` while the rate is zero
+    pub eta_seconds: Option<u64>,
+    pub running: usize,
+    pub queued: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// Modification time (Unix seconds) and size of a remote file, used to detect
+/// changes made on the server between reading and saving it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteFileStat {
+    pub mtime: i64,
+    pub size: u64,
+}
+
+/// Files, subdirectories and bytes under a remote path
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCount {
+    pub file_count: u64,
+    pub dir_count: u64,
+    pub total_size: u64,
+    /// Counting stopped at a file limit: there are more than `file_count`
+    /// files and the other totals are partial
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Outcome of `delete_item`: "deleted", or "confirmation-required" when the
+/// directory is above the confirm threshold and was left untouched.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteResult {
+    pub status: String,
+    #[serde(flatten)]
+    pub counts: ItemCount,
+}
+
+/// A remote item moved to the trash by `trash_item`. The same JSON is kept as
+/// the entry's manifest on the server.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    /// Name of the entry's directory under the trash root
+    pub id: String,
+    pub original_path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub deleted_at: i64,
+    /// The trash was unusable (e.g. another filesystem) and the item was
+    /// deleted outright
+    #[serde(default)]
+    pub permanent: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -917,7 +1396,8 @@ pub struct ConnectionStatusEvent {
     pub metrics: Option<ConnectionMetrics>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum ConnectionStatus {
     Connecting,
     Connected,
@@ -969,6 +1449,46 @@ impl Default for NetworkAdaptiveSettings {
     }
 }
 
+/// Patterns an AI-issued command is checked against before it runs.
+pub const DEFAULT_DANGEROUS_COMMAND_PATTERNS: &[&str] = &[
+    "rm -rf",
+    "rm -fr",
+    "rm -r -f",
+    "mkfs",
+    "dd if=",
+    ":(){ :|:& };:",
+    "> /dev/sd",
+    "wipefs",
+    "shred",
+    "chmod -R 777 /",
+    "chown -R",
+    "shutdown",
+    "reboot",
+    "halt",
+    "poweroff",
+    "init 0",
+    "kill -9 -1",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSafetySettings {
+    /// Plain substrings (whitespace-insensitive); a match makes `exec_command`
+    /// ask for confirmation instead of running an AI-issued command.
+    pub dangerous_command_patterns: Vec<String>,
+}
+
+impl Default for CommandSafetySettings {
+    fn default() -> Self {
+        Self {
+            dangerous_command_patterns: DEFAULT_DANGEROUS_COMMAND_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct NetworkStatus {
@@ -1115,3 +1635,31 @@ pub struct DiskUsage {
     pub available: u64,
     pub usage_percent: f32,
 }
+
+/// Free space of the filesystem backing a specific path
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PathFreeSpace {
+    pub path: String,
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub block_size: u64,
+}
+
+/// Result of `import_ssh_config` / `import_connections`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    pub skipped_hosts: Vec<String>,
+}
+
+/// Per-item failure collected by `batch_delete` / `batch_move`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemError {
+    pub path: String,
+    pub error: String,
+}
@@ -12,7 +12,7 @@ use crate::ssh::{client, client::AppState, command};
 use rusqlite::{params, Connection as SqliteConnection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
 
 fn now_ts() -> i64 {
@@ -1090,6 +1090,33 @@ pub fn map_connection_from_endpoint(
         os_type: Some(asset.platform.clone()),
         key_content: None,
         key_passphrase: None,
+        tags: asset.labels.clone(),
+        last_connected: None,
+        last_latency_ms: None,
+        force_scp: false,
+        max_background_sessions: None,
+        jump_hosts: Vec::new(),
+        jump_auth_type: None,
+        jump_key_id: None,
+        color: None,
+        icon: None,
+        sort_order: 0,
+        is_favorite: asset.is_favorite.unwrap_or(false),
+        agent_forwarding: false,
+        x11_forwarding: false,
+        low_latency: false,
+        idle_timeout_minutes: 0,
+        kex_algorithms: None,
+        ciphers: None,
+        mac_algorithms: None,
+        legacy_mode: false,
+        init_command: None,
+        remote_rc: None,
+        host_key_policy: None,
+        socket_path: None,
+        proxy_command: None,
+        sftp_handle_cache: false,
+        notes: None,
     }
 }
 
@@ -1985,6 +2012,10 @@ pub fn asset_restore_local_workspace_snapshot(
     let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
     restore_local_workspace_snapshot(&tx, snapshot)?;
     tx.commit().map_err(|e| e.to_string())?;
+    app_handle
+        .state::<AppState>()
+        .transfer_queue
+        .reload_limit();
     Ok(())
 }
 
@@ -3323,7 +3354,14 @@ pub async fn ops_execute_job(
     command_text: String,
     risk_level: Option<String>,
     source: Option<String>,
+    tool_call_id: Option<String>,
 ) -> Result<JobRun, String> {
+    // AI tool runs pass their tool call ID; a held-back command is not
+    // recorded as a job
+    if let Some(ref call_id) = tool_call_id {
+        command::hold_if_dangerous(&app_handle, &state, &session_id, &command_text, call_id)?;
+    }
+
     let db_path = get_db_path(&app_handle);
     let conn = SqliteConnection::open(db_path).map_err(|e| e.to_string())?;
     let created_at = now_ts();
@@ -3355,11 +3393,12 @@ pub async fn ops_execute_job(
         None,
     )?;
 
-    let output = command::exec_command(
+    let output = command::run_command(
         app_handle.clone(),
-        state,
+        &state,
         session_id.clone(),
         command_text.clone(),
+        tool_call_id,
         None,
     )
     .await;
@@ -3469,6 +3508,7 @@ pub async fn ops_execute_job_batch(
                 state.clone(),
                 ssh_config,
                 Some(format!("batch-{}-{}", target.asset_id, Uuid::new_v4())),
+                None,
             )
             .await?;
 
@@ -3495,6 +3535,7 @@ pub async fn ops_execute_job_batch(
             request.command_text.clone(),
             Some(normalized_risk.clone()),
             request.source.clone().or_else(|| Some("job-batch".to_string())),
+            None,
         )
         .await;
 
@@ -4321,6 +4362,7 @@ pub async fn session_connect_asset(
     access_endpoint_id: Option<i64>,
     existing_session_id: Option<String>,
     source: Option<String>,
+    shell: Option<crate::ssh::terminal::ShellOptions>,
 ) -> Result<AssetSessionConnectResult, String> {
     let db_path = get_db_path(&app_handle);
     let conn = SqliteConnection::open(db_path).map_err(|e| e.to_string())?;
@@ -4334,6 +4376,7 @@ pub async fn session_connect_asset(
         state.clone(),
         ssh_config,
         existing_session_id,
+        shell,
     )
     .await?;
 
@@ -4444,7 +4487,7 @@ mod tests {
     use crate::models::{
         AIConfig, AIEndpointConfig, AISubscriptionConfig, AccountProfile, AppSettings,
         ConnectionTimeoutSettings, FileManagerSettings, HeartbeatSettings,
-        NetworkAdaptiveSettings, PoolHealthSettings, ReconnectSettings, SshPoolSettings,
+        CommandSafetySettings, NetworkAdaptiveSettings, PoolHealthSettings, ReconnectSettings, SshPoolSettings,
         SyncPreferences, TerminalAppearanceSettings,
     };
 
@@ -4531,7 +4574,19 @@ mod tests {
                 network_adaptive_enabled INTEGER NOT NULL DEFAULT 1,
                 network_latency_check_interval_secs INTEGER NOT NULL DEFAULT 30,
                 network_high_latency_threshold_ms INTEGER NOT NULL DEFAULT 300,
-                network_low_bandwidth_threshold_kbps INTEGER NOT NULL DEFAULT 100
+                network_low_bandwidth_threshold_kbps INTEGER NOT NULL DEFAULT 100,
+                file_manager_default_editor TEXT,
+                file_manager_max_transfer_rate INTEGER NOT NULL DEFAULT 0,
+                ssh_idle_session_timeout_minutes INTEGER NOT NULL DEFAULT 10,
+                command_safety_dangerous_patterns TEXT,
+                forward_bind_address TEXT NOT NULL DEFAULT '127.0.0.1',
+                file_manager_sftp_read_buffer_size INTEGER NOT NULL DEFAULT 512,
+                file_manager_soft_delete INTEGER NOT NULL DEFAULT 1,
+                file_manager_require_confirm_threshold INTEGER NOT NULL DEFAULT 1000,
+                file_manager_max_concurrent_transfers INTEGER NOT NULL DEFAULT 3,
+                terminal_track_cwd INTEGER NOT NULL DEFAULT 0,
+                host_key_policy TEXT NOT NULL DEFAULT 'ask',
+                heartbeat_shell_keepalive_interval_secs INTEGER NOT NULL DEFAULT 30
             );
 
             CREATE TABLE IF NOT EXISTS connections (
@@ -4657,16 +4712,24 @@ mod tests {
                 font_family: "Fira Code".to_string(),
                 cursor_style: "bar".to_string(),
                 line_height: 1.1,
+                track_cwd: false,
             },
             file_manager: FileManagerSettings {
                 view_mode: if mode == "local" { "tree" } else { "flat" }.to_string(),
                 layout: if mode == "local" { "left" } else { "bottom" }.to_string(),
                 sftp_buffer_size: if mode == "local" { 768 } else { 512 },
+                sftp_read_buffer_size: if mode == "local" { 768 } else { 512 },
+                default_editor: None,
+                max_transfer_rate_bytes_per_sec: 0,
+                soft_delete: true,
+                require_confirm_threshold: 1000,
+                max_concurrent_transfers: 3,
             },
             ssh_pool: SshPoolSettings {
                 max_background_sessions: 6,
                 enable_auto_cleanup: true,
                 cleanup_interval_minutes: 5,
+                idle_session_timeout_minutes: 10,
             },
             connection_timeout: ConnectionTimeoutSettings {
                 connection_timeout_secs: 15,
@@ -4674,6 +4737,8 @@ mod tests {
                 local_forward_timeout_secs: 10,
                 command_timeout_secs: 30,
                 sftp_operation_timeout_secs: 60,
+                forward_bind_address: "127.0.0.1".to_string(),
+                host_key_policy: "ask".to_string(),
             },
             reconnect: ReconnectSettings {
                 max_reconnect_attempts: 5,
@@ -4688,6 +4753,7 @@ mod tests {
                 app_heartbeat_interval_secs: 30,
                 heartbeat_timeout_secs: 5,
                 failed_heartbeats_before_action: 3,
+                shell_keepalive_interval_secs: 30,
             },
             pool_health: PoolHealthSettings {
                 health_check_interval_secs: 60,
@@ -4701,6 +4767,7 @@ mod tests {
                 high_latency_threshold_ms: 300,
                 low_bandwidth_threshold_kbps: 100,
             },
+            command_safety: CommandSafetySettings::default(),
         }
     }
 
@@ -0,0 +1,200 @@
+use super::client::AppState;
+use super::command::{register_cancel_flag, unregister_cancel_flag};
+use super::file_ops::escape_shell_arg;
+use super::system::run_client_command_with_status;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+/// Prefix of the error returned when the archiver is missing on the host,
+/// followed by the tool name (e.g. `ARCHIVE_TOOL_MISSING:zip`).
+pub const ARCHIVE_TOOL_MISSING: &str = "ARCHIVE_TOOL_MISSING";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteArchive {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format.unwrap_or("tar.gz") {
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            "zip" => Ok(Self::Zip),
+            other => Err(format!("Unsupported archive format: {}", other)),
+        }
+    }
+
+    fn from_archive_path(path: &str) -> Result<Self, String> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if lower.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else {
+            Err(format!("Unrecognized archive type: {}", path))
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", escape_shell_arg(value))
+}
+
+/// Split `/a/b/c` into (`/a/b`, `c`), treating a bare name as relative to `.`.
+fn split_parent(path: &str) -> Result<(String, String), String> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return Err(format!("Invalid path: {}", path));
+    }
+    match trimmed.rsplit_once('/') {
+        Some((parent, name)) => {
+            let parent = if parent.is_empty() { "/" } else { parent };
+            Ok((parent.to_string(), name.to_string()))
+        }
+        None => Ok((".".to_string(), trimmed.to_string())),
+    }
+}
+
+/// Run `body` only when `tool` exists, otherwise print the missing-tool marker
+/// with status 127. `(exit 127)` keeps the shell alive for the exit-code trailer.
+fn with_tool(tool: &str, body: &str) -> String {
+    format!(
+        "export LC_ALL=C; if command -v {tool} >/dev/null 2>&1; then {body}; else echo {marker}:{tool}; (exit 127); fi",
+        tool = tool,
+        body = body,
+        marker = ARCHIVE_TOOL_MISSING
+    )
+}
+
+/// Map the script result to an error naming the tool that failed or is missing.
+fn check_tool_output(tool: &str, output: &str, exit_code: i32) -> Result<(), String> {
+    let missing = format!("{}:{}", ARCHIVE_TOOL_MISSING, tool);
+    if exit_code == 127 && output.trim() == missing {
+        return Err(missing);
+    }
+    if exit_code != 0 {
+        return Err(format!(
+            "{} exited with code {}: {}",
+            tool,
+            exit_code,
+            output.trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Pack a remote file or directory into `dest` (default `<path>.<ext>`
+/// next to the source). A `command_id` makes it cancellable through
+/// `cancel_command_execution`.
+#[command]
+pub async fn create_remote_archive(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    format: Option<String>,
+    dest: Option<String>,
+    command_id: Option<String>,
+) -> Result<RemoteArchive, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let format = ArchiveFormat::parse(format.as_deref())?;
+    let (parent, name) = split_parent(&path)?;
+    let dest =
+        dest.unwrap_or_else(|| format!("{}.{}", path.trim_end_matches('/'), format.extension()));
+
+    let (tool, body) = match format {
+        ArchiveFormat::TarGz => (
+            "tar",
+            format!(
+                "tar czf {} -C {} {} 2>&1",
+                quote(&dest),
+                quote(&parent),
+                quote(&name)
+            ),
+        ),
+        // zip has no -C, so resolve a relative destination before changing directory.
+        ArchiveFormat::Zip => (
+            "zip",
+            format!(
+                "dest={}; case \"$dest\" in /*) ;; *) dest=\"$PWD/$dest\" ;; esac; (cd {} && zip -r -q \"$dest\" {}) 2>&1",
+                quote(&dest),
+                quote(&parent),
+                quote(&name)
+            ),
+        ),
+    };
+    let script = with_tool(tool, &format!("{} && wc -c < {}", body, quote(&dest)));
+
+    let cancel_flag = register_cancel_flag(&state, command_id.as_deref())?;
+    let result = run_client_command_with_status(&client.client_type, script, cancel_flag).await;
+    unregister_cancel_flag(&state, command_id.as_deref());
+
+    let (output, exit_code) = result?;
+    check_tool_output(tool, &output, exit_code)?;
+    let size = output
+        .lines()
+        .last()
+        .and_then(|line| line.trim().parse::<u64>().ok())
+        .ok_or_else(|| format!("Unexpected {} output: {}", tool, output))?;
+
+    Ok(RemoteArchive { path: dest, size })
+}
+
+/// Unpack a `.tar.gz`/`.tgz` or `.zip` archive into `dest_dir` (default: the
+/// archive's directory). Returns the destination directory.
+#[command]
+pub async fn extract_remote_archive(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    dest_dir: Option<String>,
+    command_id: Option<String>,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let format = ArchiveFormat::from_archive_path(&path)?;
+    let dest_dir = match dest_dir {
+        Some(dir) => dir,
+        None => split_parent(&path)?.0,
+    };
+
+    let (tool, body) = match format {
+        ArchiveFormat::TarGz => (
+            "tar",
+            format!("tar xzf {} -C {} 2>&1", quote(&path), quote(&dest_dir)),
+        ),
+        ArchiveFormat::Zip => (
+            "unzip",
+            format!("unzip -o -q {} -d {} 2>&1", quote(&path), quote(&dest_dir)),
+        ),
+    };
+    let script = with_tool(tool, &format!("mkdir -p {} && {}", quote(&dest_dir), body));
+
+    let cancel_flag = register_cancel_flag(&state, command_id.as_deref())?;
+    let result = run_client_command_with_status(&client.client_type, script, cancel_flag).await;
+    unregister_cancel_flag(&state, command_id.as_deref());
+
+    let (output, exit_code) = result?;
+    check_tool_output(tool, &output, exit_code)?;
+    Ok(dest_dir)
+}
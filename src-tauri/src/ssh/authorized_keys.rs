@@ -0,0 +1,210 @@
+use super::client::{AppState, ClientType};
+use super::manager::SshCommand;
+use crate::models::AuthorizedKey;
+use crate::ssh::execute_ssh_operation;
+use crate::ssh::utils::SFTP_NO_SUCH_FILE;
+use ssh_key::authorized_keys::Entry;
+use ssh_key::{HashAlg, PublicKey};
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use tauri::State;
+
+/// Relative SFTP paths resolve against the login user's home directory.
+pub(crate) const AUTHORIZED_KEYS_PATH: &str = ".ssh/authorized_keys";
+
+/// OpenSSH-style `SHA256:...` fingerprint of an authorized_keys line or a
+/// bare public key line. `None` for blank lines, comments and anything that
+/// does not parse as a key.
+pub(crate) fn fingerprint(line: &str) -> Option<String> {
+    parse_entry(line).map(|entry| entry.public_key().fingerprint(HashAlg::Sha256).to_string())
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    line.parse::<Entry>().ok()
+}
+
+/// The keys in an authorized_keys file, in file order. Lines that are not
+/// keys are skipped.
+pub(crate) fn parse(content: &str) -> Vec<AuthorizedKey> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let entry = parse_entry(line)?;
+            let public_key: &PublicKey = entry.public_key();
+            let options = entry.config_opts().as_str();
+            Some(AuthorizedKey {
+                line: index + 1,
+                key_type: public_key.algorithm().as_str().to_string(),
+                fingerprint: public_key.fingerprint(HashAlg::Sha256).to_string(),
+                comment: public_key.comment().to_string(),
+                options: (!options.is_empty()).then(|| options.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// `content` without the lines whose key has `fingerprint`, and how many
+/// were dropped. Everything else, comments included, is kept verbatim.
+pub(crate) fn without_fingerprint(content: &str, fingerprint: &str) -> (String, usize) {
+    let mut removed = 0;
+    let mut kept = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        if self::fingerprint(line).as_deref() == Some(fingerprint) {
+            removed += 1;
+        } else {
+            kept.push_str(line);
+        }
+    }
+    (kept, removed)
+}
+
+/// Contents of the login user's authorized_keys, or an empty string when
+/// the file does not exist yet.
+pub(crate) fn read(sftp: &ssh2::Sftp) -> Result<String, String> {
+    let mut file = match crate::ssh::utils::ssh2_retry(|| {
+        sftp.open(Path::new(AUTHORIZED_KEYS_PATH))
+    }) {
+        Ok(file) => file,
+        Err(e) if e.code() == ssh2::ErrorCode::SFTP(SFTP_NO_SUCH_FILE) => return Ok(String::new()),
+        Err(e) => return Err(format!("Failed to open {}: {}", AUTHORIZED_KEYS_PATH, e)),
+    };
+
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", AUTHORIZED_KEYS_PATH, e)),
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Write `content` to `file`, waiting out non-blocking sessions.
+pub(crate) fn write_all(file: &mut ssh2::File, content: &[u8]) -> Result<(), String> {
+    let mut pos = 0;
+    while pos < content.len() {
+        match file.write(&content[pos..]) {
+            Ok(0) => return Err("Write returned 0 bytes".to_string()),
+            Ok(n) => pos += n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) => return Err(format!("Failed to write key: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+fn ssh_sender(
+    state: &State<'_, AppState>,
+    id: &str,
+) -> Result<std::sync::mpsc::Sender<SshCommand>, String> {
+    let clients = state.clients.lock().map_err(|e| e.to_string())?;
+    let client = clients.get(id).ok_or("Session not found")?;
+    match &client.client_type {
+        ClientType::Ssh(senders) => Ok(senders.ops.clone()),
+        ClientType::Wsl(_) => Err("authorized_keys is not available for WSL sessions".to_string()),
+    }
+}
+
+/// The keys in the login user's `~/.ssh/authorized_keys`. Empty when the
+/// file does not exist.
+#[tauri::command]
+pub async fn list_authorized_keys(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<AuthorizedKey>, String> {
+    let sender = ssh_sender(&state, &id)?;
+    execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::ListAuthorizedKeys { listener: tx })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await
+}
+
+/// Rewrite `~/.ssh/authorized_keys` without the key(s) with `fingerprint`.
+/// Returns the number of lines removed.
+#[tauri::command]
+pub async fn remove_authorized_key(
+    state: State<'_, AppState>,
+    id: String,
+    fingerprint: String,
+) -> Result<usize, String> {
+    let sender = ssh_sender(&state, &id)?;
+    execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::RemoveAuthorizedKey {
+                fingerprint,
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMPLW0CL1gejG8ZE8K+mIaXBLP3CWIAZy8jax8xg+kU+";
+
+    #[test]
+    fn parses_keys_with_options_and_skips_other_lines() {
+        let content = format!(
+            "# managed by hand\n\n{} alice@laptop\nnot a key\nfrom=\"10.0.0.0/8\",no-pty {} backup\n",
+            ED25519, ED25519
+        );
+        let keys = parse(&content);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].line, 3);
+        assert_eq!(keys[0].key_type, "ssh-ed25519");
+        assert_eq!(keys[0].comment, "alice@laptop");
+        assert_eq!(keys[0].options, None);
+        assert_eq!(
+            keys[0].fingerprint,
+            "SHA256:12l5lf+4ru5SM74LDpGcOmof+oUzXx+zT+qWFcv1Qzc"
+        );
+        assert_eq!(keys[1].line, 5);
+        assert_eq!(
+            keys[1].options.as_deref(),
+            Some("from=\"10.0.0.0/8\",no-pty")
+        );
+        assert_eq!(keys[1].fingerprint, keys[0].fingerprint);
+    }
+
+    #[test]
+    fn removes_only_matching_lines() {
+        let fp = fingerprint(ED25519).unwrap();
+        let content = format!("# keep me\n{} old\nnot a key\n", ED25519);
+        assert_eq!(
+            without_fingerprint(&content, &fp),
+            ("# keep me\nnot a key\n".to_string(), 1)
+        );
+        assert_eq!(
+            without_fingerprint("# nothing here\n", &fp),
+            ("# nothing here\n".to_string(), 0)
+        );
+    }
+}
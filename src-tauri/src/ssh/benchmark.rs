@@ -0,0 +1,220 @@
+use super::client::{AppState, ClientType};
+use super::command::{register_cancel_flag, unregister_cancel_flag};
+use super::manager::SshCommand;
+use crate::ssh::execute_ssh_operation;
+use serde::Serialize;
+use ssh_key::rand_core::{OsRng, RngCore};
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+/// Largest file `benchmark_transfer` accepts; transfers time out after five
+/// minutes, so anything bigger mostly measures that.
+pub const MAX_BENCHMARK_BYTES: u64 = 1024 * 1024 * 1024;
+
+const DEFAULT_BENCHMARK_DIR: &str = "/tmp";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferBenchmark {
+    pub bytes: u64,
+    pub upload_ms: u64,
+    pub download_ms: u64,
+    /// MiB per second
+    pub upload_mb_per_sec: f64,
+    pub download_mb_per_sec: f64,
+    /// Both directions over the combined time of the two phases
+    pub round_trip_mb_per_sec: f64,
+}
+
+/// Throughput in MiB per second; zero for an instant transfer rather than
+/// infinity, which JSON cannot carry.
+fn mb_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return 0.0;
+    }
+    bytes as f64 / (1024.0 * 1024.0) / secs
+}
+
+fn summarize(bytes: u64, upload: Duration, download: Duration) -> TransferBenchmark {
+    TransferBenchmark {
+        bytes,
+        upload_ms: upload.as_millis() as u64,
+        download_ms: download.as_millis() as u64,
+        upload_mb_per_sec: mb_per_sec(bytes, upload),
+        download_mb_per_sec: mb_per_sec(bytes, download),
+        round_trip_mb_per_sec: mb_per_sec(bytes * 2, upload + download),
+    }
+}
+
+/// A local temp file of `size` random bytes, so compression on the link
+/// cannot flatter the result.
+fn random_file(size: u64) -> Result<tempfile::TempPath, String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("ssh-assistant-benchmark-")
+        .tempfile()
+        .map_err(|e| format!("Failed to create benchmark file: {}", e))?;
+    let mut chunk = vec![0u8; 1024 * 1024];
+    let mut remaining = size;
+    while remaining > 0 {
+        let n = remaining.min(chunk.len() as u64) as usize;
+        OsRng.fill_bytes(&mut chunk[..n]);
+        file.write_all(&chunk[..n])
+            .map_err(|e| format!("Failed to write benchmark file: {}", e))?;
+        remaining -= n as u64;
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write benchmark file: {}", e))?;
+    Ok(file.into_temp_path())
+}
+
+/// Send a transfer command and time it until the manager replies.
+fn timed(
+    sender: &Sender<SshCommand>,
+    command: impl FnOnce(Sender<Result<(), String>>) -> SshCommand,
+) -> Result<Duration, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let started = Instant::now();
+    sender
+        .send(command(tx))
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+    rx.recv()
+        .map_err(|_| "Failed to receive response from SSH Manager".to_string())??;
+    Ok(started.elapsed())
+}
+
+fn delete_remote(sender: &Sender<SshCommand>, path: &str) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    if sender
+        .send(SshCommand::SftpDelete {
+            path: path.to_string(),
+            is_dir: false,
+            listener: tx,
+        })
+        .is_ok()
+    {
+        let _ = rx.recv();
+    }
+}
+
+fn run_benchmark(
+    app: AppHandle,
+    sender: Sender<SshCommand>,
+    bytes: u64,
+    remote_path: String,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<TransferBenchmark, String> {
+    let source = random_file(bytes)?;
+    let target = tempfile::Builder::new()
+        .prefix("ssh-assistant-benchmark-")
+        .tempfile()
+        .map_err(|e| format!("Failed to create benchmark file: {}", e))?
+        .into_temp_path();
+    let transfer_id = format!("benchmark-{}", uuid::Uuid::new_v4());
+    let pause_flag = Arc::new(AtomicBool::new(false));
+
+    let upload = timed(&sender, |listener| SshCommand::SftpUpload {
+        local_path: source.to_string_lossy().to_string(),
+        remote_path: remote_path.clone(),
+        transfer_id: transfer_id.clone(),
+        app_handle: app.clone(),
+        listener,
+        cancel_flag: cancel_flag.clone(),
+        pause_flag: pause_flag.clone(),
+        skip_space_check: false,
+        // A zero rate means unlimited, so the global bandwidth cap does
+        // not end up being what gets measured
+        rate_limit: Some(0),
+    });
+    let result = upload.and_then(|upload| {
+        let download = timed(&sender, |listener| SshCommand::SftpDownload {
+            remote_path: remote_path.clone(),
+            local_path: target.to_string_lossy().to_string(),
+            transfer_id: transfer_id.clone(),
+            app_handle: app.clone(),
+            listener,
+            cancel_flag: cancel_flag.clone(),
+            pause_flag: pause_flag.clone(),
+            rate_limit: Some(0),
+        })?;
+        Ok(summarize(bytes, upload, download))
+    });
+
+    // Also after a cancelled or failed upload, which may have left a partial file
+    delete_remote(&sender, &remote_path);
+    result
+}
+
+/// Upload `size_bytes` of random data to a unique file under `remote_dir`
+/// (default `/tmp`), download it again and delete it, timing both phases
+/// with the current buffer settings and no bandwidth cap. Best run while no
+/// other transfers are active. A `command_id` makes it cancellable through
+/// `cancel_command_execution`.
+#[tauri::command]
+pub async fn benchmark_transfer(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    size_bytes: u64,
+    remote_dir: Option<String>,
+    command_id: Option<String>,
+) -> Result<TransferBenchmark, String> {
+    if size_bytes == 0 || size_bytes > MAX_BENCHMARK_BYTES {
+        return Err(format!(
+            "Benchmark size must be between 1 byte and {}",
+            crate::ssh::utils::format_bytes(MAX_BENCHMARK_BYTES)
+        ));
+    }
+    let sender = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        let client = clients.get(&id).ok_or("Session not found")?;
+        match &client.client_type {
+            ClientType::Ssh(senders) => senders.ops.clone(),
+            ClientType::Wsl(_) => {
+                return Err("Transfer benchmarks are not available for WSL sessions".to_string())
+            }
+        }
+    };
+    let remote_dir = remote_dir.unwrap_or_else(|| DEFAULT_BENCHMARK_DIR.to_string());
+    let remote_path = format!(
+        "{}/.ssh-assistant-benchmark-{}",
+        remote_dir.trim_end_matches('/'),
+        uuid::Uuid::new_v4()
+    );
+    let cancel_flag = register_cancel_flag(&state, command_id.as_deref())?.unwrap_or_default();
+
+    let result = execute_ssh_operation(move || {
+        run_benchmark(app_handle, sender, size_bytes, remote_path, cancel_flag)
+    })
+    .await;
+
+    unregister_cancel_flag(&state, command_id.as_deref());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_mib_per_second_for_each_phase() {
+        let result = summarize(
+            8 * 1024 * 1024,
+            Duration::from_secs(2),
+            Duration::from_secs(1),
+        );
+        assert_eq!(result.upload_ms, 2000);
+        assert_eq!(result.upload_mb_per_sec, 4.0);
+        assert_eq!(result.download_mb_per_sec, 8.0);
+        assert!((result.round_trip_mb_per_sec - 16.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn instant_transfers_report_zero() {
+        assert_eq!(mb_per_sec(1024, Duration::ZERO), 0.0);
+    }
+}
@@ -0,0 +1,26 @@
+use super::client::AppState;
+use super::file_ops::remote_file_hash;
+use super::utils::HashAlgorithm;
+use tauri::{command, State};
+
+/// Hash a remote file with sha256 (default), md5 or sha1 and return the
+/// lowercase hex digest.
+#[command]
+pub async fn remote_file_checksum(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    algorithm: Option<String>,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+    tokio::task::spawn_blocking(move || {
+        remote_file_hash(&client.client_type, &path, Some(algorithm))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
@@ -1,15 +1,29 @@
 // use super::connection::SessionSshPool; // Keep for now if referenced elsewhere, but we will remove usage
-use super::manager::{SshCommand, SshManager};
-use super::terminal::start_shell_thread;
+use super::command::drop_pending_commands;
+use super::connection::PoolDiagnostics;
+use super::dir_watch::{stop_session_watches, DirWatchHandle};
+use super::events::ConnectionEventEmitter;
+use super::forwards::ForwardRegistry;
+use super::manager::{
+    init_command_input, SessionStatusMonitor, SshCommand, SshManager, StatusEmitter,
+};
+use super::system::RemoteOsInfo;
+use super::terminal::{start_shell_thread, ShellOptions};
+use super::transfer::RateLimiter;
+use super::transfer_queue::TransferQueue;
 use super::tunnel::TunnelRuntime;
-use crate::models::{Connection as SshConnConfig, ConnectionTimeoutSettings};
-use crate::ssh::{execute_ssh_operation, ShellMsg};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::models::{
+    AggregateTransferProgress, Connection as SshConnConfig, ConnectionTimeoutSettings,
+};
+use crate::ssh::{execute_ssh_operation, ProgressPayload, ShellMsg};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -28,13 +42,19 @@ pub enum ClientType {
 pub struct SshClient {
     pub client_type: ClientType,            // SSH Manager Channel or WSL
     pub shell_tx: Option<Sender<ShellMsg>>, // Terminal message channel (to Manager or WSL)
+    /// Extra shells opened with `open_shell`, keyed by shell id
+    pub shells: HashMap<String, Sender<ShellMsg>>,
     pub owner_cache: Arc<Mutex<HashMap<u32, String>>>, // UID cache (To be deprecated as Manager handles it internally, but keep for compatibility if needed)
     pub shutdown_signal: Arc<AtomicBool>,              // Shared signal
     pub os_info: Option<String>,                       // Remote OS information
+    /// Distro details probed by `detect_remote_os`, cached for the session
+    pub remote_os: Option<RemoteOsInfo>,
     pub asset_id: Option<i64>,
     pub access_endpoint_id: Option<i64>,
     pub credential_ref_id: Option<i64>,
     pub bastion_chain_id: Option<String>,
+    /// Saved connection this session was opened from, if any
+    pub connection_id: Option<i64>,
 }
 
 use crate::models::Transfer;
@@ -42,16 +62,190 @@ use crate::models::Transfer;
 pub struct TransferState {
     pub data: Mutex<Transfer>,
     pub cancel_flag: Arc<AtomicBool>,
+    /// Set by `pause_transfer`; transfer loops idle while it is true
+    pub pause_flag: Arc<AtomicBool>,
+    /// Bytes of earlier files already sent by a directory transfer; added to
+    /// the progress of the file currently being sent
+    pub progress_offset: AtomicU64,
+}
+
+impl TransferState {
+    /// Apply `update` to the transfer and emit `transfers-updated` if it
+    /// changed the status. All status changes should go through here.
+    pub fn update(&self, app: &AppHandle, update: impl FnOnce(&mut Transfer)) {
+        let changed = match self.data.lock() {
+            Ok(mut data) => {
+                let before = data.status.clone();
+                update(&mut data);
+                data.status != before
+            }
+            Err(_) => false,
+        };
+        if changed {
+            emit_transfers_updated(app);
+        }
+    }
+}
+
+/// Store a transfer's progress and emit `transfer-progress`. `transferred`
+/// and `total` describe the file being sent; for directory transfers the
+/// bytes of already finished files are added on top.
+pub fn record_transfer_progress(app: &AppHandle, transfer_id: &str, transferred: u64, total: u64) {
+    let state = app.state::<AppState>();
+    let transfer = state
+        .transfers
+        .lock()
+        .ok()
+        .and_then(|transfers| transfers.get(transfer_id).cloned());
+    let (transferred, total) = match transfer {
+        Some(transfer) => {
+            let offset = transfer.progress_offset.load(Ordering::Relaxed);
+            match transfer.data.lock() {
+                Ok(mut data) => {
+                    data.transferred = offset + transferred;
+                    data.total_size = data.total_size.max(offset + total);
+                    (data.transferred, data.total_size)
+                }
+                Err(_) => (offset + transferred, offset + total),
+            }
+        }
+        None => (transferred, total),
+    };
+    let _ = app.emit(
+        "transfer-progress",
+        ProgressPayload {
+            id: transfer_id.to_string(),
+            transferred,
+            total,
+        },
+    );
+}
+
+/// All tracked transfers, newest first.
+pub fn list_transfers(state: &AppState) -> Result<Vec<Transfer>, String> {
+    let transfers_map = state.transfers.lock().map_err(|e| e.to_string())?;
+    let mut transfers = Vec::new();
+    for state in transfers_map.values() {
+        let transfer = state.data.lock().map_err(|e| e.to_string())?;
+        transfers.push(transfer.clone());
+    }
+    // Sort by created_at DESC
+    transfers.sort_by_key(|transfer| Reverse(transfer.created_at));
+    Ok(transfers)
+}
+
+/// How far back `TransferRateWindow` looks when averaging the combined rate.
+const AGGREGATE_RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Samples of the bytes moved by all transfers so far, for a moving-average
+/// combined rate.
+#[derive(Default)]
+pub struct TransferRateWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl TransferRateWindow {
+    /// Record `moved` bytes at `now` and return the average bytes/second
+    /// over the last `AGGREGATE_RATE_WINDOW`.
+    pub(crate) fn sample(&mut self, now: Instant, moved: u64) -> u64 {
+        // Removing or restarting transfers makes the total go backwards
+        if self.samples.back().is_some_and(|(_, last)| moved < *last) {
+            self.samples.clear();
+        }
+        self.samples.push_back((now, moved));
+        // Keep one sample at or beyond the window edge as the baseline
+        while self.samples.len() > 2
+            && now.duration_since(self.samples[1].0) >= AGGREGATE_RATE_WINDOW
+        {
+            self.samples.pop_front();
+        }
+
+        let (start, first) = self.samples[0];
+        let elapsed = now.duration_since(start).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        ((moved - first) as f64 / elapsed) as u64
+    }
+}
+
+/// Sum progress across unfinished transfers and count transfers by status.
+pub fn aggregate_transfer_progress(state: &AppState) -> Result<AggregateTransferProgress, String> {
+    let mut progress = AggregateTransferProgress::default();
+    let mut moved = 0u64;
+    for transfer in list_transfers(state)? {
+        moved += transfer.transferred;
+        match transfer.status.as_str() {
+            "running" => progress.running += 1,
+            "pending" => progress.queued += 1,
+            "paused" => progress.paused += 1,
+            "completed" => progress.completed += 1,
+            "error" | "verify-failed" => progress.failed += 1,
+            _ => {}
+        }
+        if matches!(transfer.status.as_str(), "running" | "pending" | "paused") {
+            progress.transferred += transfer.transferred;
+            progress.total += transfer.total_size;
+        }
+    }
+
+    progress.bytes_per_second = state
+        .transfer_rate_window
+        .lock()
+        .map_err(|e| e.to_string())?
+        .sample(Instant::now(), moved);
+    if progress.bytes_per_second > 0 {
+        let remaining = progress.total.saturating_sub(progress.transferred);
+        progress.eta_seconds = Some(remaining.div_ceil(progress.bytes_per_second));
+    }
+    Ok(progress)
+}
+
+/// Push the full transfer list to the UI, followed by the combined progress
+/// as `aggregate-progress`, and let queued transfers re-check for a slot.
+/// Must not be called while holding the `transfers` map lock or a transfer's
+/// data lock.
+pub fn emit_transfers_updated(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    state.transfer_queue.wake();
+    if let Ok(transfers) = list_transfers(&state) {
+        let _ = app.emit("transfers-updated", transfers);
+    }
+    if let Ok(progress) = aggregate_transfer_progress(&state) {
+        let _ = app.emit("aggregate-progress", progress);
+    }
+}
+
+pub struct PendingCommand {
+    pub session_id: String,
+    pub command: String,
+    /// When the danger check held the command back; it expires after
+    /// `PENDING_COMMAND_TTL`
+    pub held_at: Instant,
 }
 
 pub struct AppState {
     pub clients: Mutex<HashMap<String, SshClient>>,
+    /// `<session id>:<remote path>` keys of files synced back from a local editor
+    pub editor_watches: Mutex<HashSet<String>>,
+    /// Global bandwidth cap shared by all transfers (see `max_transfer_rate_bytes_per_sec`)
+    pub transfer_rate_limiter: Arc<RateLimiter>,
+    /// AI-issued commands held back by the danger check, keyed by tool call ID
+    pub pending_dangerous_commands: Mutex<HashMap<String, PendingCommand>>,
     pub transfers: Mutex<HashMap<String, Arc<TransferState>>>, // ID -> TransferState
+    pub transfer_rate_window: Mutex<TransferRateWindow>,
+    pub transfer_queue: TransferQueue,
+    /// Last directory each shell reported via OSC 7, keyed by shell id
+    pub shell_cwds: Mutex<HashMap<String, String>>,
+    /// Forwarding threads run by the app, shared with the connection code
+    pub forwards: Arc<ForwardRegistry>,
     pub command_cancellations: Mutex<HashMap<String, Arc<AtomicBool>>>, // Command ID -> CancelFlag
-    pub tunnels: Mutex<HashMap<i64, TunnelRuntime>>,           // Tunnel ID -> runtime
-                                                               // Note: TransferManager is integrated but not stored in AppState
-                                                               // Each transfer operation can optionally use the new TransferManager
-                                                               // For backward compatibility, we maintain the existing transfer structure
+    /// Remote directory watches, keyed by watch ID
+    pub dir_watches: Mutex<HashMap<String, DirWatchHandle>>,
+    pub tunnels: Mutex<HashMap<i64, TunnelRuntime>>, // Tunnel ID -> runtime
+                                                     // Note: TransferManager is integrated but not stored in AppState
+                                                     // Each transfer operation can optionally use the new TransferManager
+                                                     // For backward compatibility, we maintain the existing transfer structure
 }
 
 #[derive(Clone, Debug, serde::Serialize)]
@@ -69,8 +263,16 @@ impl AppState {
     pub fn new() -> Self {
         Self {
             clients: Mutex::new(HashMap::new()),
+            editor_watches: Mutex::new(HashSet::new()),
+            transfer_rate_limiter: Arc::new(RateLimiter::new(0)),
+            pending_dangerous_commands: Mutex::new(HashMap::new()),
             transfers: Mutex::new(HashMap::new()),
+            transfer_rate_window: Mutex::new(TransferRateWindow::default()),
+            transfer_queue: TransferQueue::default(),
+            shell_cwds: Mutex::new(HashMap::new()),
+            forwards: ForwardRegistry::shared(),
             command_cancellations: Mutex::new(HashMap::new()),
+            dir_watches: Mutex::new(HashMap::new()),
             tunnels: Mutex::new(HashMap::new()),
         }
     }
@@ -95,6 +297,44 @@ pub fn get_client_session_context(
     })
 }
 
+/// Forwards the manager's liveness reports as `session-status:{id}` events.
+fn session_status_emitter(app: AppHandle, id: String) -> StatusEmitter {
+    let emitter = ConnectionEventEmitter::new(app);
+    Box::new(move |status, details| emitter.emit_session_status(&id, status, details))
+}
+
+/// Called from the manager thread when the idle timeout expires: tell the UI
+/// and drop the client. The shutdown signal identifies the connection, so a
+/// newer client that reused the session id is left alone.
+fn idle_disconnect_handler(
+    app: AppHandle,
+    id: String,
+    shutdown_signal: Arc<AtomicBool>,
+) -> Box<dyn FnOnce() + Send> {
+    Box::new(move || {
+        let client = app
+            .state::<AppState>()
+            .clients
+            .lock()
+            .ok()
+            .and_then(|mut clients| {
+                let is_same = clients
+                    .get(&id)
+                    .is_some_and(|c| Arc::ptr_eq(&c.shutdown_signal, &shutdown_signal));
+                if is_same {
+                    clients.remove(&id)
+                } else {
+                    None
+                }
+            });
+        if let Some(client) = client {
+            let _ = app.emit(&format!("session-idle-timeout:{}", id), ());
+            shutdown_client(client);
+            drop_pending_commands(&app.state::<AppState>(), &id);
+        }
+    })
+}
+
 fn shutdown_client(client: SshClient) {
     // 1. 发送停止信号
     client.shutdown_signal.store(true, Ordering::Relaxed);
@@ -103,6 +343,9 @@ fn shutdown_client(client: SshClient) {
     if let Some(tx) = client.shell_tx {
         let _ = tx.send(ShellMsg::Exit);
     }
+    for tx in client.shells.values() {
+        let _ = tx.send(ShellMsg::Exit);
+    }
 
     // 3. 关闭连接
     match &client.client_type {
@@ -114,9 +357,13 @@ fn shutdown_client(client: SshClient) {
     }
 }
 
-#[tauri::command]
-pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<String, String> {
-    let mut populated_config = config.clone();
+/// Fill in the secrets a stored connection refers to (jump host password from
+/// its asset endpoint, key material from the key store) before connecting.
+pub(crate) fn resolve_test_config(
+    app: &AppHandle,
+    config: SshConnConfig,
+) -> Result<SshConnConfig, String> {
+    let mut populated_config = config;
 
     if populated_config
         .jump_host
@@ -130,7 +377,7 @@ pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<St
             .unwrap_or(true)
     {
         if let Some(asset_id) = populated_config.id {
-            let conn = rusqlite::Connection::open(crate::db::get_db_path(&app))
+            let conn = rusqlite::Connection::open(crate::db::get_db_path(app))
                 .map_err(|e| format!("Failed to open database: {}", e))?;
             if let Ok((_, endpoint, _)) = crate::ops::resolve_asset_bundle(&conn, asset_id, None) {
                 populated_config.jump_password = endpoint.jump_password;
@@ -146,7 +393,7 @@ pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<St
 
     if populated_config.auth_type.as_deref() == Some("key") {
         if let Some(key_id) = populated_config.ssh_key_id {
-            match crate::db::get_ssh_key_by_id(&app, key_id) {
+            match crate::db::get_ssh_key_by_id(app, key_id) {
                 Ok(Some(key)) => {
                     populated_config.key_content = Some(key.content);
                     populated_config.key_passphrase = key.passphrase;
@@ -164,6 +411,46 @@ pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<St
         }
     }
 
+    resolve_jump_keys(app, &mut populated_config)?;
+
+    Ok(populated_config)
+}
+
+/// Load the private keys of jump hosts that authenticate with a stored key.
+/// The legacy single jump host is expanded into `jump_hosts` so its key is
+/// carried along too.
+fn resolve_jump_keys(app: &AppHandle, config: &mut SshConnConfig) -> Result<(), String> {
+    config.jump_hosts = config.jump_chain();
+    for hop in config.jump_hosts.iter_mut() {
+        if hop.auth_type.as_deref() != Some("key") {
+            continue;
+        }
+        let key_id = hop.ssh_key_id.ok_or_else(|| {
+            format!(
+                "Jump host {} uses key authentication but has no key",
+                hop.host
+            )
+        })?;
+        match crate::db::get_ssh_key_by_id(app, key_id) {
+            Ok(Some(key)) => {
+                hop.key_content = Some(key.content);
+                hop.key_passphrase = key.passphrase;
+            }
+            Ok(None) => {
+                return Err(format!("SSH Key with ID {} not found", key_id));
+            }
+            Err(e) => {
+                return Err(format!("Failed to fetch SSH Key: {}", e));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<String, String> {
+    let populated_config = resolve_test_config(&app, config)?;
+
     execute_ssh_operation(move || {
         let session =
             super::connection::establish_connection_with_retry(&populated_config, None, None)?;
@@ -174,12 +461,28 @@ pub async fn test_connection(app: AppHandle, config: SshConnConfig) -> Result<St
     .await
 }
 
+/// List the authentication methods the host offers the configured user, so
+/// the connection editor can disable the ones it does not support.
+#[tauri::command]
+pub async fn list_auth_methods(
+    app: AppHandle,
+    config: SshConnConfig,
+) -> Result<Vec<String>, String> {
+    let populated_config = resolve_test_config(&app, config)?;
+
+    execute_ssh_operation(move || {
+        super::connection::list_auth_methods_internal(&populated_config, None)
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn connect(
     app: AppHandle,
     state: State<'_, AppState>,
     config: SshConnConfig,
     id: Option<String>,
+    shell: Option<ShellOptions>,
 ) -> Result<String, String> {
     // Use OS type from connection config with fallback to Linux for backward compatibility
     let os_info = config
@@ -225,6 +528,7 @@ pub async fn connect(
                 }
             }
         }
+        resolve_jump_keys(&app, &mut populated_config)?;
 
         let config_clone = populated_config.clone();
         let shutdown_signal_clone = shutdown_signal.clone();
@@ -237,11 +541,43 @@ pub async fn connect(
             app_settings.as_ref().map(|s| s.reconnect.clone());
         // 从设置中获取最大后台会话数，默认为 6（比原来的 3 更大，减少阻塞）
         // 架构护栏：至少保留 2 个后台会话，避免传输占用导致目录浏览/刷新被阻塞。
-        let max_background_sessions: usize = app_settings
+        // A per-connection override is taken as-is (down to 1) so small hosts
+        // can opt out of the guardrail.
+        let max_background_sessions: usize = match populated_config.max_background_sessions {
+            Some(count) => count.max(1) as usize,
+            None => app_settings
+                .as_ref()
+                .map(|s| s.ssh_pool.max_background_sessions as usize)
+                .unwrap_or(6)
+                .max(2),
+        };
+        let idle_reaping = app_settings.as_ref().and_then(|s| {
+            let pool = &s.ssh_pool;
+            (pool.enable_auto_cleanup && pool.idle_session_timeout_minutes > 0).then(|| {
+                (
+                    Duration::from_secs(pool.idle_session_timeout_minutes as u64 * 60),
+                    Duration::from_secs(pool.cleanup_interval_minutes.max(1) as u64 * 60),
+                )
+            })
+        });
+
+        let idle_timeout = (populated_config.idle_timeout_minutes > 0)
+            .then(|| Duration::from_secs(populated_config.idle_timeout_minutes as u64 * 60));
+        let on_idle_timeout =
+            idle_disconnect_handler(app.clone(), id.clone(), shutdown_signal.clone());
+        let heartbeat_settings = app_settings
             .as_ref()
-            .map(|s| s.ssh_pool.max_background_sessions as usize)
-            .unwrap_or(6)
-            .max(2);
+            .map(|s| s.heartbeat.clone())
+            .unwrap_or_default();
+        let status_monitor = SessionStatusMonitor {
+            interval: Duration::from_secs(
+                heartbeat_settings.app_heartbeat_interval_secs.max(1) as u64
+            ),
+            auto_reconnect: reconnect_settings
+                .as_ref()
+                .is_none_or(|s| s.enable_auto_reconnect),
+            emit: session_status_emitter(app.clone(), id.clone()),
+        };
 
         // Establish connection and spawn manager thread
         let senders = tokio::task::spawn_blocking(move || {
@@ -250,22 +586,34 @@ pub async fn connect(
                 timeout_settings.as_ref(),
                 reconnect_settings.as_ref(),
             )?;
-            let pool = super::connection::SessionSshPool::with_reconnect_settings(
+            let mut pool = super::connection::SessionSshPool::with_reconnect_settings(
                 config_clone.clone(),
                 max_background_sessions,
                 timeout_settings,
                 reconnect_settings,
             )
             .map_err(|e| e.to_string())?;
+            if let Some((idle_timeout, interval)) = idle_reaping {
+                pool = pool.with_idle_reaping(idle_timeout, interval);
+            }
 
             let (shell_tx, shell_rx) = std::sync::mpsc::channel();
             let (ops_tx, ops_rx) = std::sync::mpsc::channel();
-            let mut manager = SshManager::new(
+            let mut manager = SshManager::with_heartbeat_settings(
                 session,
                 pool.clone(),
                 shell_rx,
                 shutdown_signal_clone.clone(),
-            );
+                heartbeat_settings,
+            )
+            .with_agent_forwarding(config_clone.agent_forwarding)
+            .with_x11_forwarding(config_clone.x11_forwarding)
+            .with_init_command(config_clone.init_command.clone())
+            .with_remote_rc(config_clone.remote_rc.clone())
+            .with_status_monitor(status_monitor);
+            if let Some(timeout) = idle_timeout {
+                manager = manager.with_idle_timeout(timeout, on_idle_timeout);
+            }
 
             std::thread::spawn(move || {
                 manager.run();
@@ -291,19 +639,35 @@ pub async fn connect(
     let mut client = SshClient {
         client_type,
         shell_tx: None, // Will be set by start_shell_thread
+        shells: HashMap::new(),
         owner_cache: Arc::new(Mutex::new(HashMap::new())),
         shutdown_signal,
         os_info: Some(os_info),
+        remote_os: None,
         asset_id: None,
         access_endpoint_id: None,
         credential_ref_id: None,
         bastion_chain_id: None,
+        connection_id: config.id,
     };
 
     // Start shell thread (or init shell via manager)
     // Note: start_shell_thread for SSH now just returns a sender that wraps SshCommand::Shell*
-    let shell_tx = start_shell_thread(app.clone(), &mut client, id.clone())
-        .map_err(|e| format!("Failed to start shell thread: {}", e))?;
+    let shell_tx = start_shell_thread(
+        app.clone(),
+        &mut client,
+        id.clone(),
+        shell.unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed to start shell thread: {}", e))?;
+
+    // The SSH manager types the init command into each shell it opens; a WSL
+    // shell gets it here, as input queued right behind the shell start.
+    if let (ClientType::Wsl(_), Some(command)) = (&client.client_type, &config.init_command) {
+        if !command.trim().is_empty() {
+            let _ = shell_tx.send(ShellMsg::Data(init_command_input(command).into_bytes()));
+        }
+    }
 
     // Update client with the shell transmitter
     client.shell_tx = Some(shell_tx);
@@ -328,6 +692,9 @@ pub async fn disconnect(state: State<'_, AppState>, id: String) -> Result<(), St
     if let Some(client) = client {
         shutdown_client(client);
     }
+    state.forwards.stop_session(&id);
+    stop_session_watches(&state, &id);
+    drop_pending_commands(&state, &id);
 
     Ok(())
 }
@@ -358,22 +725,25 @@ pub async fn cleanup_and_reconnect(state: State<'_, AppState>, id: String) -> Re
 
 #[tauri::command]
 pub async fn cancel_transfer(
+    app: AppHandle,
     state: State<'_, AppState>,
     transfer_id: String,
 ) -> Result<(), String> {
-    if let Some(transfer_state) = state
+    let transfer_state = state
         .transfers
         .lock()
         .map_err(|e| e.to_string())?
         .get(&transfer_id)
-    {
+        .cloned();
+    if let Some(transfer_state) = transfer_state {
         transfer_state.cancel_flag.store(true, Ordering::Relaxed);
 
         // Update status immediately if possible
-        let mut data = transfer_state.data.lock().map_err(|e| e.to_string())?;
-        if data.status == "running" || data.status == "pending" {
-            data.status = "cancelled".to_string();
-        }
+        transfer_state.update(&app, |data| {
+            if data.status == "running" || data.status == "pending" || data.status == "paused" {
+                data.status = "cancelled".to_string();
+            }
+        });
     }
     Ok(())
 }
@@ -399,3 +769,56 @@ pub async fn get_os_info(state: State<'_, AppState>, id: String) -> Result<Strin
     let client = clients.get(&id).ok_or("Session not found")?;
     Ok(client.os_info.clone().unwrap_or("Unknown".to_string()))
 }
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDiagnostics {
+    pub session_id: String,
+    pub os_info: Option<String>,
+    #[serde(flatten)]
+    pub pool: PoolDiagnostics,
+}
+
+/// Pool state of every open SSH session (WSL sessions have no pool and are
+/// left out), for the debug panel and bug reports.
+#[tauri::command]
+pub async fn get_session_diagnostics(
+    state: State<'_, AppState>,
+) -> Result<Vec<SessionDiagnostics>, String> {
+    let clients: Vec<(String, SshClient)> = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients
+            .iter()
+            .map(|(id, client)| (id.clone(), client.clone()))
+            .collect()
+    };
+
+    execute_ssh_operation(move || {
+        let mut report = Vec::new();
+        for (session_id, client) in clients {
+            let ClientType::Ssh(senders) = &client.client_type else {
+                continue;
+            };
+            let (tx, rx) = std::sync::mpsc::channel();
+            if senders
+                .ops
+                .send(SshCommand::GetPoolDiagnostics { listener: tx })
+                .is_err()
+            {
+                continue;
+            }
+            // The liveness probe may wait on the main session; don't let one
+            // stuck connection hold up the whole report.
+            if let Ok(Ok(pool)) = rx.recv_timeout(Duration::from_secs(5)) {
+                report.push(SessionDiagnostics {
+                    session_id,
+                    os_info: client.os_info.clone(),
+                    pool,
+                });
+            }
+        }
+        report.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        Ok(report)
+    })
+    .await
+}
@@ -1,16 +1,34 @@
-use super::client::{AppState, ClientType};
+use super::client::{AppState, ClientType, PendingCommand};
+use super::manager::SUDO_INCORRECT_PASSWORD;
 use super::wsl;
 use crate::ssh::{
     emit_command_output, execute_ssh_operation, ExecStreamContext, ExecTarget, SshCommand,
+    DEFAULT_EXEC_TIMEOUT_SECS,
 };
-use std::io::Read;
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, RecvTimeoutError, Sender};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
-use tauri::{AppHandle, State};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+/// How long a command held back by the danger check waits for
+/// `confirm_dangerous_command` before it is dropped.
+const PENDING_COMMAND_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Returned (serialized, as the error) by `exec_command` instead of running
+/// an AI-issued command that matches a dangerous pattern.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmationRequired<'a> {
+    confirmation_required: bool,
+    tool_call_id: &'a str,
+    command: &'a str,
+    matched_patterns: Vec<String>,
+}
 
 enum WslStreamEvent {
     Chunk { data: String, stream: &'static str },
@@ -27,7 +45,7 @@ where
             match reader.read(&mut buf) {
                 Ok(0) => break,
                 Ok(n) => {
-                    let chunk = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    let chunk = wsl::decode_wsl_output(&buf[..n]);
                     if sender
                         .send(WslStreamEvent::Chunk {
                             data: chunk,
@@ -46,6 +64,102 @@ where
     });
 }
 
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Patterns found in `command`. Matching ignores runs of whitespace and only
+/// starts at a word boundary, so `rm -rf` does not match `perform -rf`.
+fn find_dangerous_patterns(command: &str, patterns: &[String]) -> Vec<String> {
+    let command = collapse_whitespace(command);
+    patterns
+        .iter()
+        .filter(|pattern| {
+            let needle = collapse_whitespace(pattern);
+            !needle.is_empty()
+                && command.match_indices(&needle).any(|(pos, _)| {
+                    command[..pos]
+                        .chars()
+                        .next_back()
+                        .is_none_or(|c| !c.is_alphanumeric() && c != '_' && c != '-')
+                })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Register a flag under `command_id` for `cancel_command_execution` to set.
+/// `None` without an ID, as nothing could cancel the command.
+pub(crate) fn register_cancel_flag(
+    state: &AppState,
+    command_id: Option<&str>,
+) -> Result<Option<Arc<AtomicBool>>, String> {
+    let Some(cmd_id) = command_id else {
+        return Ok(None);
+    };
+    let flag = Arc::new(AtomicBool::new(false));
+    state
+        .command_cancellations
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(cmd_id.to_string(), flag.clone());
+    Ok(Some(flag))
+}
+
+/// Drop the flag `register_cancel_flag` registered once the command is done.
+pub(crate) fn unregister_cancel_flag(state: &AppState, command_id: Option<&str>) {
+    if let Some(cmd_id) = command_id {
+        if let Ok(mut cancellations) = state.command_cancellations.lock() {
+            cancellations.remove(cmd_id);
+        }
+    }
+}
+
+/// Check an AI-issued command against the dangerous patterns from the
+/// settings. A match is not run: the error is a JSON `ConfirmationRequired`
+/// object and the command waits for `confirm_dangerous_command` with
+/// `tool_call_id`.
+pub(crate) fn hold_if_dangerous(
+    app_handle: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    command: &str,
+    tool_call_id: &str,
+) -> Result<(), String> {
+    let patterns = crate::db::get_settings(app_handle.clone())?
+        .command_safety
+        .dangerous_command_patterns;
+    let matched_patterns = find_dangerous_patterns(command, &patterns);
+    if matched_patterns.is_empty() {
+        return Ok(());
+    }
+    let prompt = serde_json::to_string(&ConfirmationRequired {
+        confirmation_required: true,
+        tool_call_id,
+        command,
+        matched_patterns,
+    })
+    .map_err(|e| e.to_string())?;
+    let mut pending = state
+        .pending_dangerous_commands
+        .lock()
+        .map_err(|e| e.to_string())?;
+    let now = Instant::now();
+    prune_expired_commands(&mut pending, now);
+    pending.insert(
+        tool_call_id.to_string(),
+        PendingCommand {
+            session_id: session_id.to_string(),
+            command: command.to_string(),
+            held_at: now,
+        },
+    );
+    Err(prompt)
+}
+
+/// Run a command on the session. Commands with a `tool_call_id` come from the
+/// AI assistant and go through `hold_if_dangerous` first.
+/// `timeout_secs` defaults to `DEFAULT_EXEC_TIMEOUT_SECS`.
 #[tauri::command]
 pub async fn exec_command(
     app_handle: AppHandle,
@@ -53,6 +167,127 @@ pub async fn exec_command(
     id: String,
     command: String,
     tool_call_id: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<String, String> {
+    if let Some(ref cmd_id) = tool_call_id {
+        hold_if_dangerous(&app_handle, &state, &id, &command, cmd_id)?;
+    }
+
+    run_command(app_handle, &state, id, command, tool_call_id, timeout_secs).await
+}
+
+/// Run a command held back by `hold_if_dangerous`.
+#[tauri::command]
+pub async fn confirm_dangerous_command(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    tool_call_id: String,
+) -> Result<String, String> {
+    let pending = {
+        let mut pending = state
+            .pending_dangerous_commands
+            .lock()
+            .map_err(|e| e.to_string())?;
+        prune_expired_commands(&mut pending, Instant::now());
+        pending
+            .remove(&tool_call_id)
+            .ok_or("No command is waiting for confirmation (it may have expired)")?
+    };
+
+    run_command(
+        app_handle,
+        &state,
+        pending.session_id,
+        pending.command,
+        Some(tool_call_id),
+        None,
+    )
+    .await
+}
+
+/// Forget held-back commands older than `PENDING_COMMAND_TTL`.
+fn prune_expired_commands(pending: &mut HashMap<String, PendingCommand>, now: Instant) {
+    pending.retain(|_, command| now.duration_since(command.held_at) < PENDING_COMMAND_TTL);
+}
+
+/// Forget the held-back commands of a session that is going away.
+pub(crate) fn drop_pending_commands(state: &AppState, session_id: &str) {
+    if let Ok(mut pending) = state.pending_dangerous_commands.lock() {
+        pending.retain(|_, command| command.session_id != session_id);
+    }
+}
+
+/// Drop a command held back by the danger check without running it.
+#[tauri::command]
+pub fn reject_dangerous_command(
+    state: State<'_, AppState>,
+    tool_call_id: String,
+) -> Result<(), String> {
+    state
+        .pending_dangerous_commands
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&tool_call_id);
+    Ok(())
+}
+
+/// Fill `{{name}}` placeholders (whitespace inside the braces is ignored).
+/// Values are inserted verbatim, so the snippet decides on quoting. Fails
+/// listing every placeholder without a parameter.
+fn render_snippet(template: &str, params: &HashMap<String, String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        match params.get(name) {
+            Some(value) => rendered.push_str(value),
+            None => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    rendered.push_str(rest);
+
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing snippet parameters: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(rendered)
+}
+
+/// Run a saved snippet on a session after filling in its placeholders.
+#[tauri::command]
+pub async fn run_snippet(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    snippet_id: i64,
+    params: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let snippet = crate::db::get_snippet_by_id(&app_handle, snippet_id)?
+        .ok_or_else(|| format!("Snippet {} not found", snippet_id))?;
+    let command = render_snippet(&snippet.command, &params.unwrap_or_default())?;
+
+    run_command(app_handle, &state, id, command, None, None).await
+}
+
+pub(crate) async fn run_command(
+    app_handle: AppHandle,
+    state: &AppState,
+    id: String,
+    command: String,
+    tool_call_id: Option<String>,
+    timeout_secs: Option<u64>,
 ) -> Result<String, String> {
     let client = {
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
@@ -60,17 +295,9 @@ pub async fn exec_command(
     };
 
     // Setup cancellation if tool_call_id is provided
-    let cancel_flag = if let Some(ref cmd_id) = tool_call_id {
-        let flag = Arc::new(AtomicBool::new(false));
-        let mut cancellations = state
-            .command_cancellations
-            .lock()
-            .map_err(|e| e.to_string())?;
-        cancellations.insert(cmd_id.clone(), flag.clone());
-        Some(flag)
-    } else {
-        None
-    };
+    let cancel_flag = register_cancel_flag(state, tool_call_id.as_deref())?;
+
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS));
 
     let tool_call_id_clone = tool_call_id.clone();
     let target = if tool_call_id_clone.is_some() {
@@ -99,6 +326,7 @@ pub async fn exec_command(
                         cancel_flag,
                         target,
                         stream,
+                        timeout: Some(timeout),
                     })
                     .map_err(|e| format!("Failed to send command: {}", e))?;
 
@@ -145,6 +373,7 @@ pub async fn exec_command(
 
                 let mut output = String::new();
                 let mut completed_readers = 0;
+                let started = Instant::now();
 
                 while completed_readers < 2 {
                     if let Some(ref flag) = cancel_flag {
@@ -154,6 +383,11 @@ pub async fn exec_command(
                             return Err("Command cancelled by user".to_string());
                         }
                     }
+                    if started.elapsed() >= timeout {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(format!("Command timed out after {}s", timeout.as_secs()));
+                    }
 
                     match rx.recv_timeout(Duration::from_millis(50)) {
                         Ok(WslStreamEvent::Chunk {
@@ -180,14 +414,132 @@ pub async fn exec_command(
         }
     };
 
-    // Cleanup cancellation flag
-    if let Some(cmd_id) = tool_call_id_clone {
-        if let Ok(mut cancellations) = state.command_cancellations.lock() {
-            cancellations.remove(&cmd_id);
+    unregister_cancel_flag(state, tool_call_id_clone.as_deref());
+
+    result
+}
+
+/// Run `command` with sudo, typing `password` at sudo's prompt. Fails with
+/// `SUDO_INCORRECT_PASSWORD` when the password is rejected; when sudo does not
+/// ask (NOPASSWD or cached credentials) the password is simply unused. SSH
+/// runs stop after `DEFAULT_EXEC_TIMEOUT_SECS`, or earlier through
+/// `cancel_command_execution` with `command_id`.
+#[tauri::command]
+pub async fn exec_command_sudo(
+    state: State<'_, AppState>,
+    id: String,
+    command: String,
+    password: String,
+    command_id: Option<String>,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let cancel_flag = register_cancel_flag(&state, command_id.as_deref())?;
+            let result = execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::ExecSudo {
+                        command,
+                        password,
+                        listener: tx,
+                        cancel_flag,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await;
+            unregister_cancel_flag(&state, command_id.as_deref());
+            result
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                // No PTY here: sudo -S reads the password line from stdin
+                let mut child = wsl::spawn_bash(
+                    &distro,
+                    "sudo -S -p '' -- sh -c \"$1\"",
+                    &[command],
+                    Stdio::piped(),
+                    Stdio::piped(),
+                    Stdio::piped(),
+                )?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = writeln!(stdin, "{}", password);
+                }
+                let output = child.wait_with_output().map_err(|e| e.to_string())?;
+                let stderr = wsl::decode_wsl_output(&output.stderr);
+                if !output.status.success() && stderr.contains("incorrect password attempt") {
+                    return Err(SUDO_INCORRECT_PASSWORD.to_string());
+                }
+                let mut text = wsl::decode_wsl_output(&output.stdout);
+                text.push_str(&stderr);
+                Ok(text)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
         }
     }
+}
 
-    result
+/// Change the login password of the session's user by running `passwd` and
+/// answering its prompts. Fails with the server's reason, e.g. "password
+/// unchanged" or a password quality complaint, when it is not changed.
+#[tauri::command]
+pub async fn change_remote_password(
+    state: State<'_, AppState>,
+    id: String,
+    current_password: String,
+    new_password: String,
+) -> Result<(), String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let sender = match &client.client_type {
+        ClientType::Ssh(senders) => senders.ops.clone(),
+        ClientType::Wsl(_) => {
+            return Err("Changing the password is not supported for WSL sessions".to_string())
+        }
+    };
+
+    execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::ChangePassword {
+                current_password,
+                new_password,
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await
+}
+
+/// Prints the working directory of the user's most recently started shell
+/// on a terminal (read from `/proc`), or `pwd` of the background session
+/// where that is not available (e.g. no procfs).
+const SHELL_CWD_SCRIPT: &str = r#"cwd=$(ps -u "$(id -u)" -o pid=,tty=,comm= --sort=-start_time 2>/dev/null \
+  | awk '$2 ~ /^pts\// && $3 ~ /^-?(ba|z|k|da|fi|tc|c)?sh$/ { print $1 }' \
+  | while read -r pid; do readlink "/proc/$pid/cwd" 2>/dev/null && break; done)
+if [ -n "$cwd" ]; then printf '%s\n' "$cwd"; else pwd; fi"#;
+
+/// Payload of `file-manager-navigate`.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileManagerNavigatePayload {
+    pub session_id: String,
+    pub path: String,
 }
 
 #[tauri::command]
@@ -199,19 +551,61 @@ pub async fn get_working_directory(
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
         clients.get(&id).ok_or("Session not found")?.clone()
     };
+    run_directory_script(&client.client_type, "pwd").await
+}
 
-    match &client.client_type {
+/// Open the file manager at the terminal's current directory: use the last
+/// OSC 7 report when the shell sent one, otherwise look it up on the host,
+/// then emit `file-manager-navigate` for the UI. Returns the directory.
+#[tauri::command]
+pub async fn sync_file_manager_to_cwd(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let reported = state
+        .shell_cwds
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .cloned();
+    let path = match reported {
+        Some(path) => path,
+        None => run_directory_script(&client.client_type, SHELL_CWD_SCRIPT).await?,
+    };
+    if path.is_empty() {
+        return Err("Could not determine the terminal's working directory".to_string());
+    }
+    let _ = app.emit(
+        "file-manager-navigate",
+        FileManagerNavigatePayload {
+            session_id: id,
+            path: path.clone(),
+        },
+    );
+    Ok(path)
+}
+
+/// Run a script that prints a directory and return it trimmed.
+async fn run_directory_script(client_type: &ClientType, script: &str) -> Result<String, String> {
+    let script = script.to_string();
+    match client_type {
         ClientType::Ssh(senders) => {
             let sender = senders.ops.clone();
             execute_ssh_operation(move || {
                 let (tx, rx) = std::sync::mpsc::channel();
                 sender
                     .send(SshCommand::Exec {
-                        command: "pwd".to_string(),
+                        command: script,
                         listener: tx,
                         cancel_flag: None,
                         target: ExecTarget::FileBrowser,
                         stream: None,
+                        timeout: None,
                     })
                     .map_err(|e| format!("Failed to send command: {}", e))?;
 
@@ -225,11 +619,98 @@ pub async fn get_working_directory(
         }
         ClientType::Wsl(distro) => {
             let distro = distro.clone();
-            tokio::task::spawn_blocking(move || {
-                wsl::run_bash_text(&distro, "pwd", &[])
-            })
-            .await
-            .map_err(|e| format!("Task join error: {}", e))?
+            tokio::task::spawn_blocking(move || wsl::run_bash_text(&distro, &script, &[]))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+                .map(|dir| dir.trim().to_string())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_find_dangerous_patterns() {
+        let list = patterns(&["rm -rf", "mkfs", ":(){ :|:& };:"]);
+        assert_eq!(
+            find_dangerous_patterns("cd /tmp && rm  -rf build", &list),
+            vec!["rm -rf"]
+        );
+        assert_eq!(
+            find_dangerous_patterns("mkfs.ext4 /dev/sdb1", &list),
+            vec!["mkfs"]
+        );
+        assert_eq!(
+            find_dangerous_patterns(":(){ :|:& };:", &list),
+            vec![":(){ :|:& };:"]
+        );
+    }
+
+    #[test]
+    fn test_dangerous_patterns_need_word_boundary() {
+        let list = patterns(&["rm -rf", "halt"]);
+        assert!(find_dangerous_patterns("perform -rf", &list).is_empty());
+        assert!(find_dangerous_patterns("systemctl status asphalt", &list).is_empty());
+        assert!(find_dangerous_patterns("ls -la", &list).is_empty());
+        assert!(find_dangerous_patterns("ls", &patterns(&["  "])).is_empty());
+    }
+
+    #[test]
+    fn test_pending_commands_expire() {
+        let now = Instant::now();
+        let held = |command: &str, age: Duration| PendingCommand {
+            session_id: "s1".to_string(),
+            command: command.to_string(),
+            held_at: now - age,
+        };
+        let mut pending = HashMap::new();
+        pending.insert("fresh".to_string(), held("rm -rf /tmp/a", Duration::ZERO));
+        pending.insert(
+            "stale".to_string(),
+            held(
+                "rm -rf /tmp/b",
+                PENDING_COMMAND_TTL + Duration::from_secs(1),
+            ),
+        );
+        prune_expired_commands(&mut pending, now);
+        assert!(pending.contains_key("fresh"));
+        assert!(!pending.contains_key("stale"));
+    }
+
+    fn snippet_params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_snippet_fills_placeholders() {
+        let params = snippet_params(&[("path", "/var/log"), ("lines", "50")]);
+        assert_eq!(
+            render_snippet("tail -n {{lines}} {{ path }}/syslog", &params).unwrap(),
+            "tail -n 50 /var/log/syslog"
+        );
+        assert_eq!(
+            render_snippet("du -sh {{path}} {{path}}", &params).unwrap(),
+            "du -sh /var/log /var/log"
+        );
+        assert_eq!(render_snippet("uptime", &params).unwrap(), "uptime");
+        assert_eq!(render_snippet("echo {{", &params).unwrap(), "echo {{");
+    }
+
+    #[test]
+    fn test_render_snippet_reports_missing_parameters() {
+        let params = snippet_params(&[("path", "/tmp")]);
+        assert_eq!(
+            render_snippet("cp {{src}} {{path}} && rm {{src}} {{dst}}", &params).unwrap_err(),
+            "Missing snippet parameters: src, dst"
+        );
+    }
+}
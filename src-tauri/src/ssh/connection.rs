@@ -1,15 +1,22 @@
 use crate::models::{Connection as SshConnConfig, ConnectionTimeoutSettings, ReconnectSettings};
+use crate::ssh::file_handles::{FileHandleCache, FileHandleCacheStats};
+use crate::ssh::forwards::{ForwardCounters, ForwardRegistry, ForwardSpec, ForwardingThreadHandle};
+use crate::ssh::known_hosts::HostKeyPolicy;
+use crate::ssh::proxy_command::{
+    connect_named_pipe, expand_tokens, is_named_pipe_path, spawn_proxy_command, ProxyCommand,
+};
 use crate::ssh::{
-    get_connection_timeout, get_jump_host_timeout, get_local_forward_timeout,
-    get_sftp_operation_timeout, ssh2_retry, HealthAction, PoolHealthChecker, PoolHealthReport,
-    ReconnectManager, SessionHealth, SessionHealthMetadata, SshErrorClassifier, SshErrorType,
+    get_connection_timeout, get_forward_bind_address, get_jump_host_timeout,
+    get_local_forward_timeout, get_sftp_operation_timeout, ssh2_retry, HealthAction,
+    PoolHealthChecker, PoolHealthReport, ReconnectManager, SessionHealth, SessionHealthMetadata,
+    SshErrorClassifier, SshErrorType,
 };
 use socket2::{Domain, Protocol, Socket, Type};
-use ssh2::Session;
+use ssh2::{MethodType, Session};
 use std::collections::HashMap;
 use std::io::{ErrorKind, Read, Write};
 use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -53,52 +60,93 @@ impl HealthCheckCache {
     }
 }
 
-pub struct ForwardingThreadHandle {
-    thread_handle: std::thread::JoinHandle<()>,
-    shutdown_signal: Arc<AtomicBool>,
-}
-
 pub struct ManagedSession {
     pub session: Session,
-    pub jump_session: Option<Session>,
-    pub forward_listener: Option<TcpListener>,
-    pub forwarding_handle: Option<ForwardingThreadHandle>,
+    /// Sessions on each jump host, outermost first
+    pub jump_sessions: Vec<Session>,
+    /// One local forwarding listener/thread per jump host
+    pub forward_listeners: Vec<TcpListener>,
+    /// `ForwardRegistry` IDs of the forwarding threads behind this session
+    pub forward_ids: Vec<String>,
+    /// The `ProxyCommand` child the session runs through, if any
+    pub proxy: Option<ProxyCommand>,
     /// Health metadata for tracking session health
     pub health_metadata: SessionHealthMetadata,
+    /// How long each phase of establishing this session took
+    pub timings: ConnectTimings,
+}
+
+/// Phase durations of `establish_connection_internal`. With jump hosts,
+/// `tcp_connect` covers the whole chain up to the forwarded stream to the target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectTimings {
+    pub tcp_connect: Duration,
+    pub handshake: Duration,
+    pub auth: Duration,
+}
+
+/// Occupancy of one background session pool.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolUsage {
+    pub sessions: usize,
+    /// Sessions currently locked by an operation
+    pub busy: usize,
+    pub capacity: usize,
+}
+
+/// Point-in-time view of a `SessionSshPool`, for debugging slow file operations.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolDiagnostics {
+    pub main_session_alive: bool,
+    pub file_browser: PoolUsage,
+    pub transfer: PoolUsage,
+    pub ai: PoolUsage,
+    pub status_session_open: bool,
+    /// Bytes moved by transfers on this connection since it was opened
+    pub bytes_transferred: u64,
+    pub file_handles: FileHandleCacheStats,
+}
+
+/// An SFTP file handle kept open by the pool's `FileHandleCache`, with the
+/// session it belongs to. Use it only while holding that session's lock.
+pub struct CachedFile {
+    pub session: Arc<Mutex<ManagedSession>>,
+    /// Shared by the cached handles of one session, so they hold a single
+    /// channel open rather than one each
+    pub sftp: Arc<ssh2::Sftp>,
+    pub file: ssh2::File,
 }
 
 impl Drop for ManagedSession {
     fn drop(&mut self) {
-        // Shutdown forwarding thread if exists
-        if let Some(handle) = &mut self.forwarding_handle {
-            handle.shutdown_signal.store(true, Ordering::Relaxed);
-            // Give the thread a moment to shutdown gracefully
-            let handle = std::mem::replace(&mut handle.thread_handle, thread::spawn(|| {})); // Replace with empty thread to take ownership
-            let _ = handle.join();
+        self.release();
+    }
+}
+
+impl ManagedSession {
+    /// Stop the forwarding threads and close every session in the chain, the
+    /// target first and then the jump hosts from the innermost outwards.
+    fn release(&mut self) {
+        let forwards = ForwardRegistry::shared();
+        for id in self.forward_ids.drain(..) {
+            forwards.stop(&id);
         }
 
-        // Close SSH sessions
-        if let Some(ref jump_sess) = self.jump_session {
+        let _ = self.session.disconnect(None, "", None);
+        for jump_sess in self.jump_sessions.drain(..).rev() {
             let _ = jump_sess.disconnect(None, "", None);
         }
-        let _ = self.session.disconnect(None, "", None);
+        // Kills the proxy process
+        self.proxy.take();
 
-        // Close TCP listener
-        if let Some(ref listener) = self.forward_listener {
+        // Wake any listener still blocked in accept
+        for listener in self.forward_listeners.drain(..) {
             let _ = listener.set_nonblocking(true);
-            let _ = TcpStream::connect(listener.local_addr().unwrap());
-        }
-    }
-}
-
-impl ForwardingThreadHandle {
-    pub fn new(
-        thread_handle: std::thread::JoinHandle<()>,
-        shutdown_signal: Arc<AtomicBool>,
-    ) -> Self {
-        Self {
-            thread_handle,
-            shutdown_signal,
+            if let Ok(addr) = listener.local_addr() {
+                let _ = TcpStream::connect(addr);
+            }
         }
     }
 }
@@ -110,6 +158,34 @@ impl std::ops::Deref for ManagedSession {
     }
 }
 
+/// How a session answered a liveness probe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionProbe {
+    /// The keepalive went out
+    Keepalive,
+    /// The keepalive failed, but the server still ran a command
+    Channel,
+    Dead,
+}
+
+/// Probe `session` with a keepalive, falling back to running `true` on a new
+/// channel when the keepalive fails.
+pub(crate) fn probe_session(session: &Session) -> SessionProbe {
+    if ssh2_retry(|| session.keepalive_send()).is_ok() {
+        return SessionProbe::Keepalive;
+    }
+    match ssh2_retry(|| session.channel_session()) {
+        Ok(mut channel) => match ssh2_retry(|| channel.exec("true")) {
+            Ok(_) => {
+                let _ = channel.close();
+                SessionProbe::Channel
+            }
+            Err(_) => SessionProbe::Dead,
+        },
+        Err(_) => SessionProbe::Dead,
+    }
+}
+
 /// 会话级SSH连接池：1个主会话（终端专用）+ 1个AI会话 + N个文件浏览器会话 + M个传输专用会话 + 1个状态栏会话（懒加载）
 ///
 /// 五个场景严格隔离：
@@ -135,6 +211,31 @@ pub struct SessionSshPool {
     connection_stagger_count: Arc<Mutex<u32>>,                  // 连接交错计数器，用于指数退避
     timeout_settings: Option<ConnectionTimeoutSettings>,        // 超时设置
     reconnect_settings: Option<ReconnectSettings>,              // 重连设置
+    idle_timeout: Option<Duration>, // 空闲后台会话回收阈值，None 表示不回收
+    idle_reap_interval: Duration,   // 空闲回收检查间隔
+    last_idle_reap: Arc<Mutex<Instant>>, // 上次空闲回收时间
+    bytes_transferred: Arc<AtomicU64>, // 传输字节累计（诊断用）
+    // 低延迟 SFTP 阻塞会话（懒加载单例，仅 low_latency 连接使用）
+    low_latency_session: Arc<Mutex<Option<Arc<Mutex<ManagedSession>>>>>,
+    // 最近读取文件的 SFTP 句柄（仅 sftp_handle_cache 连接使用）
+    file_handles: Arc<Mutex<FileHandleCache<CachedFile>>>,
+    // 空闲断开：最近一次活动（相对 activity_epoch 的毫秒数）与进行中的操作数
+    activity_epoch: Instant,
+    last_activity_ms: Arc<AtomicU64>,
+    active_operations: Arc<AtomicUsize>,
+}
+
+/// Keeps the session from counting as idle while an operation runs; see
+/// `SessionSshPool::begin_activity`.
+pub struct ActivityGuard {
+    pool: SessionSshPool,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        self.pool.active_operations.fetch_sub(1, Ordering::Relaxed);
+        self.pool.touch_activity();
+    }
 }
 
 impl SessionSshPool {
@@ -183,9 +284,26 @@ impl SessionSshPool {
             connection_stagger_count: Arc::new(Mutex::new(0)),
             timeout_settings,
             reconnect_settings,
+            idle_timeout: None,
+            idle_reap_interval: Duration::from_secs(5 * 60),
+            last_idle_reap: Arc::new(Mutex::new(Instant::now())),
+            bytes_transferred: Arc::new(AtomicU64::new(0)),
+            low_latency_session: Arc::new(Mutex::new(None)),
+            file_handles: Arc::new(Mutex::new(FileHandleCache::default())),
+            activity_epoch: Instant::now(),
+            last_activity_ms: Arc::new(AtomicU64::new(0)),
+            active_operations: Arc::new(AtomicUsize::new(0)),
         })
     }
 
+    /// Close background sessions idle for longer than `idle_timeout`, checked
+    /// at most every `interval` from `heartbeat_check`.
+    pub fn with_idle_reaping(mut self, idle_timeout: Duration, interval: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self.idle_reap_interval = interval;
+        self
+    }
+
     /// Capacity hint for metadata-style background operations.
     pub fn file_browser_capacity(&self) -> usize {
         self.max_file_browser_sessions.max(1)
@@ -218,7 +336,8 @@ impl SessionSshPool {
         let sessions = self.transfer_pool.lock().map_err(|e| e.to_string())?;
 
         for session in sessions.iter() {
-            if let Ok(_guard) = session.try_lock() {
+            if let Ok(mut guard) = session.try_lock() {
+                guard.health_metadata.mark_used();
                 return Ok(Some(session.clone()));
             }
         }
@@ -247,8 +366,9 @@ impl SessionSshPool {
 
             // 1. 尝试寻找当前没有被其它线程锁定的"空闲"会话
             for session in sessions.iter() {
-                if let Ok(_guard) = session.try_lock() {
+                if let Ok(mut guard) = session.try_lock() {
                     // 能够立即拿到锁，说明它是空闲的
+                    guard.health_metadata.mark_used();
                     return Ok(session.clone());
                 }
             }
@@ -324,7 +444,8 @@ impl SessionSshPool {
             let sessions = self.ai_pool.lock().map_err(|e| e.to_string())?;
 
             for session in sessions.iter() {
-                if let Ok(_guard) = session.try_lock() {
+                if let Ok(mut guard) = session.try_lock() {
+                    guard.health_metadata.mark_used();
                     return Ok(session.clone());
                 }
             }
@@ -397,8 +518,9 @@ impl SessionSshPool {
 
             // 1. 尝试寻找当前没有被其它线程锁定的"空闲"会话
             for session in sessions.iter() {
-                if let Ok(_guard) = session.try_lock() {
+                if let Ok(mut guard) = session.try_lock() {
                     // 能够立即拿到锁，说明它是空闲的
+                    guard.health_metadata.mark_used();
                     return Ok(session.clone());
                 }
             }
@@ -489,10 +611,274 @@ impl SessionSshPool {
         Ok(pool.as_ref().unwrap().clone())
     }
 
+    /// Open a new standalone session with this pool's config and reconnect
+    /// settings, for replacing a dead terminal session.
+    pub fn connect_new_session(&self) -> Result<ManagedSession, String> {
+        establish_connection_with_retry(
+            &self.config,
+            self.timeout_settings.as_ref(),
+            self.reconnect_settings.as_ref(),
+        )
+    }
+
+    /// Forward `local_port` on the configured bind address to
+    /// `remote_host:remote_port` over a dedicated session, one channel per
+    /// accepted connection. Port 0 picks a free port. Returns the forward ID.
+    pub fn start_local_forward(
+        &self,
+        session_id: &str,
+        local_port: u16,
+        remote_host: String,
+        remote_port: u16,
+    ) -> Result<String, String> {
+        let bind_address = get_forward_bind_address(self.timeout_settings.as_ref());
+        let listener = bind_forward_listener(&bind_address, local_port)?;
+        let local_port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to get local port: {}", e))?
+            .port();
+        let session = self.connect_new_session()?;
+
+        let shutdown_signal = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(ForwardCounters::default());
+        let remote_target = join_host_port(&remote_host, remote_port);
+
+        let thread_shutdown = shutdown_signal.clone();
+        let thread_counters = counters.clone();
+        let thread_handle = thread::spawn(move || {
+            let mut connections: Vec<thread::JoinHandle<()>> = Vec::new();
+            while !thread_shutdown.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((local_stream, _)) => {
+                        let sess = session.session.clone();
+                        let host = remote_host.clone();
+                        let shutdown = thread_shutdown.clone();
+                        let counters = thread_counters.clone();
+                        connections.retain(|connection| !connection.is_finished());
+                        connections.push(thread::spawn(move || {
+                            if let Some(channel) =
+                                open_direct_channel(&sess, &host, remote_port, &shutdown)
+                            {
+                                pump_forwarded_connection(
+                                    local_stream,
+                                    channel,
+                                    &shutdown,
+                                    &counters,
+                                );
+                            }
+                        }));
+                    }
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                        // Only sends once the keepalive interval has passed
+                        let _ = session.keepalive_send();
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(_) => break,
+                }
+            }
+            // Stop open connections too if the listener failed
+            thread_shutdown.store(true, Ordering::Relaxed);
+            for connection in connections {
+                let _ = connection.join();
+            }
+            drop(session);
+        });
+
+        Ok(ForwardRegistry::shared().register(
+            ForwardSpec {
+                kind: "local",
+                local_port,
+                remote_target,
+                via: self.config.host.clone(),
+                session_id: Some(session_id.to_string()),
+            },
+            ForwardingThreadHandle::new(thread_handle, shutdown_signal),
+            counters,
+        ))
+    }
+
+    /// Whether file-browser SFTP should use the blocking fast path.
+    pub fn low_latency(&self) -> bool {
+        self.config.low_latency
+    }
+
+    /// Dedicated blocking session for low-latency SFTP (lazy singleton).
+    ///
+    /// Pool sessions are non-blocking, so every SFTP round trip that is not
+    /// ready yet costs an `ssh2_retry` back-off sleep. In blocking mode libssh2
+    /// waits on the socket instead and returns as soon as the reply arrives;
+    /// the session timeout stands in for the retry deadline.
+    pub fn get_low_latency_session(&self) -> Result<Arc<Mutex<ManagedSession>>, String> {
+        let mut slot = self.low_latency_session.lock().map_err(|e| e.to_string())?;
+        if let Some(session) = slot.as_ref() {
+            return Ok(session.clone());
+        }
+
+        let new_session = establish_connection_with_retry(
+            &self.config,
+            self.timeout_settings.as_ref(),
+            self.reconnect_settings.as_ref(),
+        )?;
+        new_session.session.set_blocking(true);
+        let timeout_ms = self
+            .sftp_operation_timeout()
+            .as_millis()
+            .min(u32::MAX as u128) as u32;
+        new_session.session.set_timeout(timeout_ms);
+        let session_arc = Arc::new(Mutex::new(new_session));
+        *slot = Some(session_arc.clone());
+        Ok(session_arc)
+    }
+
+    /// Drop the low-latency session; the next request reconnects it.
+    pub fn reset_low_latency_session(&self) {
+        self.clear_file_handles();
+        let session = match self.low_latency_session.lock() {
+            Ok(mut slot) => slot.take(),
+            Err(_) => return,
+        };
+        if let Some(session_arc) = session {
+            if let Ok(mut sess) = session_arc.lock() {
+                sess.release();
+            }
+        }
+    }
+
+    /// Whether repeated SFTP reads may reuse an open file handle.
+    pub fn sftp_handle_cache(&self) -> bool {
+        self.config.sftp_handle_cache
+    }
+
+    /// Run `op` on the handle cache. Whatever it drops is closed after the
+    /// lock is released, since closing waits on the server.
+    fn with_file_handles<R>(
+        &self,
+        op: impl FnOnce(&mut FileHandleCache<CachedFile>) -> (R, Vec<CachedFile>),
+    ) -> Option<R> {
+        let (result, dropped) = {
+            let mut cache = self.file_handles.lock().ok()?;
+            op(&mut cache)
+        };
+        drop(dropped);
+        Some(result)
+    }
+
+    /// Current invalidation generation; read it before opening a file and
+    /// pass it to `put_file_handle`.
+    pub fn file_handle_generation(&self) -> u64 {
+        self.file_handles
+            .lock()
+            .map(|cache| cache.generation())
+            .unwrap_or(0)
+    }
+
+    pub fn take_file_handle(&self, path: &str) -> Option<CachedFile> {
+        self.with_file_handles(|cache| cache.take(path, Instant::now()))
+            .flatten()
+    }
+
+    /// The SFTP channel the cached handles of `session` were opened on.
+    pub fn cached_sftp(&self, session: &Arc<Mutex<ManagedSession>>) -> Option<Arc<ssh2::Sftp>> {
+        let cache = self.file_handles.lock().ok()?;
+        cache.find_map(|cached| Arc::ptr_eq(&cached.session, session).then(|| cached.sftp.clone()))
+    }
+
+    pub fn put_file_handle(&self, path: &str, handle: CachedFile, generation: u64) {
+        self.with_file_handles(|cache| ((), cache.put(path, handle, generation, Instant::now())));
+    }
+
+    /// Forget the handles for `path` and anything below it, after it was
+    /// written, moved or deleted.
+    pub fn invalidate_file_handles(&self, path: &str) {
+        self.with_file_handles(|cache| ((), cache.invalidate(path)));
+    }
+
+    pub fn clear_file_handles(&self) {
+        self.with_file_handles(|cache| ((), cache.clear()));
+    }
+
+    pub fn record_file_handle_read(&self, hit: bool, elapsed: Duration) {
+        if let Ok(mut cache) = self.file_handles.lock() {
+            cache.record_read(hit, elapsed);
+        }
+    }
+
+    /// Whether the connection is configured to bypass SFTP.
+    pub fn force_scp(&self) -> bool {
+        self.config.force_scp
+    }
+
     pub fn sftp_operation_timeout(&self) -> Duration {
         get_sftp_operation_timeout(self.timeout_settings.as_ref())
     }
 
+    /// Record user activity (shell I/O, commands) for the idle timeout.
+    pub fn touch_activity(&self) {
+        let now = self.activity_epoch.elapsed().as_millis() as u64;
+        self.last_activity_ms.fetch_max(now, Ordering::Relaxed);
+    }
+
+    /// Count an operation as activity for as long as the guard lives, so a
+    /// long transfer or command never trips the idle timeout.
+    pub fn begin_activity(&self) -> ActivityGuard {
+        self.active_operations.fetch_add(1, Ordering::Relaxed);
+        self.touch_activity();
+        ActivityGuard { pool: self.clone() }
+    }
+
+    /// Time since the last activity; zero while an operation is running.
+    pub fn idle_duration(&self) -> Duration {
+        if self.active_operations.load(Ordering::Relaxed) > 0 {
+            return Duration::ZERO;
+        }
+        let last = Duration::from_millis(self.last_activity_ms.load(Ordering::Relaxed));
+        self.activity_epoch.elapsed().saturating_sub(last)
+    }
+
+    /// Count bytes moved by a transfer, for `diagnostics`.
+    pub fn record_transferred(&self, bytes: u64) {
+        self.bytes_transferred.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn pool_usage(pool: &Mutex<Vec<Arc<Mutex<ManagedSession>>>>, capacity: usize) -> PoolUsage {
+        let Ok(sessions) = pool.lock() else {
+            return PoolUsage::default();
+        };
+        PoolUsage {
+            sessions: sessions.len(),
+            busy: sessions.iter().filter(|s| s.try_lock().is_err()).count(),
+            capacity,
+        }
+    }
+
+    /// Snapshot of the pool. Never waits on a busy background session; only the
+    /// main session is locked for the liveness probe.
+    pub fn diagnostics(&self) -> PoolDiagnostics {
+        let main_session_alive = self
+            .main_session
+            .lock()
+            .ok()
+            .and_then(|sess| self.is_session_alive(&sess).ok())
+            .unwrap_or(false);
+        PoolDiagnostics {
+            main_session_alive,
+            file_browser: Self::pool_usage(&self.file_browser_pool, self.max_file_browser_sessions),
+            transfer: Self::pool_usage(&self.transfer_pool, self.max_transfer_sessions),
+            ai: Self::pool_usage(&self.ai_pool, self.max_ai_sessions),
+            status_session_open: self
+                .status_pool
+                .lock()
+                .map(|status| status.is_some())
+                .unwrap_or(false),
+            bytes_transferred: self.bytes_transferred.load(Ordering::Relaxed),
+            file_handles: self
+                .file_handles
+                .lock()
+                .map(|cache| cache.stats())
+                .unwrap_or_default(),
+        }
+    }
+
     /// 检查并清理断开的连接
     pub fn cleanup_disconnected(&self) {
         // 检查文件浏览器会话
@@ -593,10 +979,57 @@ impl SessionSshPool {
 
         // 检查后台会话
         self.cleanup_disconnected();
+        self.reap_idle_sessions();
 
         Ok(())
     }
 
+    /// 回收空闲过久的后台会话（每个池至少保留一个），返回关闭的会话数
+    pub fn reap_idle_sessions(&self) -> usize {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return 0;
+        };
+        match self.last_idle_reap.lock() {
+            Ok(mut last) if last.elapsed() >= self.idle_reap_interval => *last = Instant::now(),
+            _ => return 0,
+        }
+        // 缓存的文件句柄持有会话引用，先关闭过期的，否则会话永远不算空闲
+        self.with_file_handles(|cache| ((), cache.expire(Instant::now())));
+
+        let mut reaped = Vec::new();
+        for pool in [&self.file_browser_pool, &self.transfer_pool, &self.ai_pool] {
+            reaped.extend(Self::take_idle(pool, idle_timeout));
+        }
+        // 在池锁之外断开连接
+        let count = reaped.len();
+        drop(reaped);
+        count
+    }
+
+    fn take_idle(
+        pool: &Mutex<Vec<Arc<Mutex<ManagedSession>>>>,
+        idle_timeout: Duration,
+    ) -> Vec<Arc<Mutex<ManagedSession>>> {
+        let mut reaped = Vec::new();
+        let Ok(mut sessions) = pool.lock() else {
+            return reaped;
+        };
+        let mut i = sessions.len();
+        while i > 0 && sessions.len() > 1 {
+            i -= 1;
+            // 被其它线程持有（引用或锁）的会话不算空闲；try_lock 避免等待正在执行的操作
+            let idle = Arc::strong_count(&sessions[i]) == 1
+                && sessions[i]
+                    .try_lock()
+                    .map(|sess| sess.health_metadata.last_used.elapsed() >= idle_timeout)
+                    .unwrap_or(false);
+            if idle {
+                reaped.push(sessions.remove(i));
+            }
+        }
+        reaped
+    }
+
     /// 检查单个会话是否存活（优化版：优先轻量级检测，带缓存）
     fn is_session_alive(&self, session: &ManagedSession) -> Result<bool, String> {
         // 使用会话地址作为缓存key
@@ -612,25 +1045,7 @@ impl SessionSshPool {
         }
 
         // 2. 优先使用轻量级 keepalive_send 检测
-        let result = match ssh2_retry(|| session.session.keepalive_send()) {
-            Ok(_) => {
-                // keepalive 成功，连接很可能还活着
-                true
-            }
-            Err(_) => {
-                // keepalive 失败，使用更可靠的 channel 检测
-                match ssh2_retry(|| session.channel_session()) {
-                    Ok(mut channel) => match ssh2_retry(|| channel.exec("true")) {
-                        Ok(_) => {
-                            let _ = channel.close();
-                            true
-                        }
-                        Err(_) => false,
-                    },
-                    Err(_) => false,
-                }
-            }
-        };
+        let result = probe_session(&session.session) != SessionProbe::Dead;
 
         // 3. 更新缓存
         if let Ok(mut cache) = self.health_cache.lock() {
@@ -644,46 +1059,14 @@ impl SessionSshPool {
     pub fn close_all(&self) {
         // 关闭主会话
         if let Ok(mut main_sess) = self.main_session.lock() {
-            // Close forwarding thread first
-            if let Some(mut handle) = main_sess.forwarding_handle.take() {
-                handle.shutdown_signal.store(true, Ordering::Relaxed);
-                let thread_handle =
-                    std::mem::replace(&mut handle.thread_handle, thread::spawn(|| {})); // Replace with empty thread
-                let _ = thread_handle.join();
-            }
-            // Close sessions
-            if let Some(ref jump_sess) = main_sess.jump_session {
-                let _ = jump_sess.disconnect(None, "", None);
-            }
-            let _ = main_sess.session.disconnect(None, "", None);
-            // Close listener
-            if let Some(ref listener) = main_sess.forward_listener {
-                let _ = listener.set_nonblocking(true);
-                let _ = TcpStream::connect(listener.local_addr().unwrap());
-            }
+            main_sess.release();
         }
 
         // Close AI sessions
         if let Ok(mut sessions) = self.ai_pool.lock() {
             for session_arc in sessions.drain(..) {
                 if let Ok(mut sess) = session_arc.lock() {
-                    // Close forwarding thread first
-                    if let Some(mut handle) = sess.forwarding_handle.take() {
-                        handle.shutdown_signal.store(true, Ordering::Relaxed);
-                        let thread_handle =
-                            std::mem::replace(&mut handle.thread_handle, thread::spawn(|| {}));
-                        let _ = thread_handle.join();
-                    }
-                    // Close sessions
-                    if let Some(ref jump_sess) = sess.jump_session {
-                        let _ = jump_sess.disconnect(None, "", None);
-                    }
-                    let _ = sess.session.disconnect(None, "", None);
-                    // Close listener
-                    if let Some(ref listener) = sess.forward_listener {
-                        let _ = listener.set_nonblocking(true);
-                        let _ = TcpStream::connect(listener.local_addr().unwrap());
-                    }
+                    sess.release();
                 }
             }
         }
@@ -692,48 +1075,17 @@ impl SessionSshPool {
         if let Ok(mut sessions) = self.file_browser_pool.lock() {
             for session in sessions.drain(..) {
                 if let Ok(mut sess) = session.lock() {
-                    // Close forwarding thread first
-                    if let Some(mut handle) = sess.forwarding_handle.take() {
-                        handle.shutdown_signal.store(true, Ordering::Relaxed);
-                        let thread_handle =
-                            std::mem::replace(&mut handle.thread_handle, thread::spawn(|| {})); // Replace with empty thread
-                        let _ = thread_handle.join();
-                    }
-                    // Close sessions
-                    if let Some(ref jump_sess) = sess.jump_session {
-                        let _ = jump_sess.disconnect(None, "", None);
-                    }
-                    let _ = sess.session.disconnect(None, "", None);
-                    // Close listener
-                    if let Some(ref listener) = sess.forward_listener {
-                        let _ = listener.set_nonblocking(true);
-                        let _ = TcpStream::connect(listener.local_addr().unwrap());
-                    }
+                    sess.release();
                 }
             }
         }
+
+        self.reset_low_latency_session();
     }
 
     /// 显式清理 ManagedSession 的所有资源
     fn cleanup_managed_session(session: &mut ManagedSession) {
-        // 1. 先关闭转发线程
-        if let Some(mut handle) = session.forwarding_handle.take() {
-            handle.shutdown_signal.store(true, Ordering::Relaxed);
-            let thread_handle = std::mem::replace(&mut handle.thread_handle, thread::spawn(|| {}));
-            let _ = thread_handle.join();
-        }
-
-        // 2. 关闭 SSH 会话
-        if let Some(ref jump_sess) = session.jump_session {
-            let _ = jump_sess.disconnect(None, "", None);
-        }
-        let _ = session.session.disconnect(None, "", None);
-
-        // 3. 关闭 TCP 监听器
-        if let Some(ref listener) = session.forward_listener {
-            let _ = listener.set_nonblocking(true);
-            let _ = TcpStream::connect(listener.local_addr().unwrap());
-        }
+        session.release();
     }
 
     fn rebuild_main(&self) -> Result<(), String> {
@@ -792,6 +1144,8 @@ impl SessionSshPool {
             }
         }
 
+        self.reset_low_latency_session();
+
         // 清空缓存
         if let Ok(mut cache) = self.health_cache.lock() {
             *cache = HealthCheckCache::new();
@@ -960,6 +1314,7 @@ impl SessionSshPool {
         };
 
         if let Some(session_arc) = removed_session {
+            self.clear_file_handles();
             if let Ok(mut sess) = session_arc.lock() {
                 Self::cleanup_managed_session(&mut sess);
             }
@@ -1012,12 +1367,25 @@ pub fn establish_connection_with_retry(
     timeout_settings: Option<&ConnectionTimeoutSettings>,
     reconnect_settings: Option<&ReconnectSettings>,
 ) -> Result<ManagedSession, String> {
+    retry_transient(reconnect_settings, || {
+        establish_connection_internal(config, timeout_settings)
+    })
+}
+
+/// Run `connect` until it succeeds, backing off between attempts. Only
+/// transient failures (refused, timed out, EAGAIN) are retried; an auth
+/// failure or host key problem returns at once, since hammering the server
+/// with bad credentials gets the client banned by tools like fail2ban.
+fn retry_transient<T>(
+    reconnect_settings: Option<&ReconnectSettings>,
+    mut connect: impl FnMut() -> Result<T, String>,
+) -> Result<T, String> {
     // Create reconnect manager with settings or defaults
     let settings = reconnect_settings.cloned().unwrap_or_default();
     let mut reconnect_manager = ReconnectManager::new(settings);
 
     loop {
-        match establish_connection_internal(config, timeout_settings) {
+        match connect() {
             Ok(session) => {
                 // Connection successful - reset and return
                 reconnect_manager.reset();
@@ -1064,41 +1432,197 @@ pub fn establish_connection_with_retry(
     }
 }
 
-fn establish_connection_internal(
+/// Key exchange offered in legacy mode: modern methods first, then the SHA-1
+/// groups old embedded servers are limited to.
+const LEGACY_KEX_ALGORITHMS: &str = "curve25519-sha256,ecdh-sha2-nistp256,diffie-hellman-group14-sha256,diffie-hellman-group-exchange-sha256,diffie-hellman-group14-sha1,diffie-hellman-group-exchange-sha1,diffie-hellman-group1-sha1";
+/// Ciphers offered in legacy mode, CBC modes last.
+const LEGACY_CIPHERS: &str =
+    "aes128-ctr,aes192-ctr,aes256-ctr,aes128-cbc,aes192-cbc,aes256-cbc,3des-cbc";
+/// Host key types accepted in legacy mode, including `ssh-rsa` (SHA-1) and DSA.
+const LEGACY_HOST_KEY_ALGORITHMS: &str =
+    "ssh-ed25519,ecdsa-sha2-nistp256,rsa-sha2-512,rsa-sha2-256,ssh-rsa,ssh-dss";
+
+/// Apply the connection's optional kex/cipher/MAC preferences before the
+/// handshake. Unset fields keep the libssh2 defaults, or the `LEGACY_*`
+/// bundles in legacy mode. Returns a description of each preference applied,
+/// for naming the culprit if the handshake fails.
+fn apply_method_prefs(sess: &Session, config: &SshConnConfig) -> Result<Vec<String>, String> {
+    let legacy = |bundle: &'static str| config.legacy_mode.then_some(bundle);
+    let prefs = [
+        (
+            "kex_algorithms",
+            config.kex_algorithms.as_deref(),
+            legacy(LEGACY_KEX_ALGORITHMS),
+            &[MethodType::Kex][..],
+        ),
+        (
+            "ciphers",
+            config.ciphers.as_deref(),
+            legacy(LEGACY_CIPHERS),
+            &[MethodType::CryptCs, MethodType::CryptSc][..],
+        ),
+        (
+            "mac_algorithms",
+            config.mac_algorithms.as_deref(),
+            None,
+            &[MethodType::MacCs, MethodType::MacSc][..],
+        ),
+        (
+            "host key algorithms",
+            None,
+            legacy(LEGACY_HOST_KEY_ALGORITHMS),
+            &[MethodType::HostKey][..],
+        ),
+    ];
+
+    let mut applied = Vec::new();
+    for (field, value, bundle, method_types) in prefs {
+        let supported = || {
+            sess.supported_algs(method_types[0])
+                .map_err(|e| format!("Failed to query supported {}: {}", field, e))
+        };
+        let list = if let Some(list) = normalize_method_list(value) {
+            // libssh2 silently drops names it does not know, so check them up front
+            let supported = supported()?;
+            if let Some(unknown) = list.split(',').find(|name| !supported.contains(name)) {
+                return Err(format!(
+                    "Unsupported algorithm '{}' in {} (supported: {})",
+                    unknown,
+                    field,
+                    supported.join(",")
+                ));
+            }
+            list
+        } else if let Some(bundle) = bundle {
+            // The bundle is best effort: keep whatever this libssh2 build has
+            let supported = supported()?;
+            let list: Vec<&str> = bundle
+                .split(',')
+                .filter(|name| supported.contains(name))
+                .collect();
+            if list.is_empty() {
+                continue;
+            }
+            list.join(",")
+        } else {
+            continue;
+        };
+        for method_type in method_types {
+            sess.method_pref(*method_type, &list)
+                .map_err(|e| format!("Invalid {} '{}': {}", field, list, e))?;
+        }
+        applied.push(format!("{} '{}'", field, list));
+    }
+    Ok(applied)
+}
+
+/// Trim a comma-separated algorithm list, dropping empty entries. `None` when
+/// nothing is left.
+fn normalize_method_list(value: Option<&str>) -> Option<String> {
+    let names: Vec<&str> = value?
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect();
+    (!names.is_empty()).then(|| names.join(","))
+}
+
+/// A handshaken but unauthenticated session to the target, plus whatever
+/// keeps the jump chain in front of it alive.
+struct Transport {
+    sess: Session,
+    jump_sessions: Vec<Session>,
+    forward_listeners: Vec<TcpListener>,
+    forward_ids: Vec<String>,
+    proxy: Option<ProxyCommand>,
+    target_host: String,
+    target_port: u16,
+    tcp_connect: Duration,
+    handshake: Duration,
+}
+
+/// Connect to the target (through the jump chain, if any) and run the SSH
+/// handshake, stopping short of authenticating to the target itself.
+fn open_transport(
     config: &SshConnConfig,
     timeout_settings: Option<&ConnectionTimeoutSettings>,
-) -> Result<ManagedSession, String> {
+) -> Result<Transport, String> {
     let mut sess = Session::new().map_err(|e| e.to_string())?;
-    let mut jump_session_holder = None;
-    let mut listener_holder = None;
-    let mut forwarding_handle = None;
+    let mut jump_sessions: Vec<Session> = Vec::new();
+    let mut forward_listeners = Vec::new();
+    let mut forward_ids = Vec::new();
+    let mut proxy = None;
 
     let connection_timeout = get_connection_timeout(timeout_settings);
     let jump_host_timeout = get_jump_host_timeout(timeout_settings);
     let local_forward_timeout = get_local_forward_timeout(timeout_settings);
-
-    if let Some(jump_host) = &config.jump_host {
-        if !jump_host.trim().is_empty() {
-            // Jump Host Logic
-            let jump_port = config.jump_port.unwrap_or(22);
-            let jump_addr = format!("{}:{}", jump_host, jump_port);
-
-            // Connect to jump host with longer timeout
-            let jump_tcp = connect_with_timeout(&jump_addr, jump_host_timeout)
-                .map_err(|e| format!("Jump host connection failed: {}", e))?;
+    let connect_started = Instant::now();
+
+    let (target_host, target_port) = resolve_target(&config.host, config.port);
+    let jump_chain = config.jump_chain();
+    let socket_path = config
+        .socket_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty());
+    let proxy_command = config
+        .proxy_command
+        .as_deref()
+        .map(str::trim)
+        .filter(|command| !command.is_empty());
+    if let Some(path) = socket_path.filter(|path| is_named_pipe_path(path)) {
+        let stream = connect_named_pipe(path, connection_timeout)
+            .map_err(|e| format!("Connection failed: {}", e))?;
+        sess.set_tcp_stream(stream);
+    } else if let Some(path) = socket_path {
+        let socket = connect_unix_socket(path, connection_timeout)
+            .map_err(|e| format!("Connection failed: {}", e))?;
+        sess.set_tcp_stream(socket);
+    } else if let Some(command) = proxy_command {
+        let command = expand_tokens(command, &target_host, target_port, &config.username);
+        let (stream, child) = spawn_proxy_command(&command)?;
+        sess.set_tcp_stream(stream);
+        proxy = Some(child);
+    } else if jump_chain.is_empty() {
+        // Direct connection
+        let tcp = connect_with_timeout(&target_host, target_port, connection_timeout)
+            .map_err(|e| format!("Connection failed: {}", e))?;
+        sess.set_tcp_stream(tcp);
+    } else {
+        // Jump Host Logic: each hop is reached through the one before it, and
+        // the last hop forwards to the target.
+        for (index, hop) in jump_chain.iter().enumerate() {
+            let (hop_host, hop_port) = resolve_target(&hop.host, hop.port.unwrap_or(22));
+            let jump_tcp = match jump_sessions.last() {
+                // Connect to the first jump host with longer timeout
+                None => connect_with_timeout(&hop_host, hop_port, jump_host_timeout)
+                    .map_err(|e| format!("Jump host {} connection failed: {}", hop.host, e))?,
+                Some(prev_sess) => {
+                    let via = &jump_chain[index - 1].host;
+                    let (listener, forward_id, stream) =
+                        forward_through(prev_sess, via, hop_host, hop_port, local_forward_timeout)
+                            .map_err(|e| format!("Jump host {}: {}", hop.host, e))?;
+                    forward_listeners.push(listener);
+                    forward_ids.push(forward_id);
+                    stream
+                }
+            };
 
             let mut jump_sess = Session::new().map_err(|e| e.to_string())?;
             jump_sess.set_tcp_stream(jump_tcp);
             jump_sess
                 .handshake()
-                .map_err(|e| format!("Jump handshake failed: {}", e))?;
-
-            jump_sess
-                .userauth_password(
-                    config.jump_username.as_deref().unwrap_or(""),
-                    config.jump_password.as_deref().unwrap_or(""),
-                )
-                .map_err(|e| format!("Jump auth failed: {}", e))?;
+                .map_err(|e| format!("Jump host {} handshake failed: {}", hop.host, e))?;
+
+            authenticate(
+                &jump_sess,
+                &hop.username,
+                hop.auth_type.as_deref(),
+                hop.password.as_deref(),
+                hop.key_content.as_deref(),
+                hop.key_passphrase.as_deref(),
+            )
+            .map_err(|e| format!("Jump host {} (hop {}): {}", hop.host, index + 1, e))?;
 
             // 核心修复：跳板机也需要 Keepalive！
             jump_sess.set_keepalive(true, 15);
@@ -1106,171 +1630,378 @@ fn establish_connection_internal(
             // Enable non-blocking mode for the jump session
             jump_sess.set_blocking(false);
 
-            // Local Port Forwarding Pattern
-            let listener = TcpListener::bind("127.0.0.1:0")
-                .map_err(|e| format!("Failed to bind local port: {}", e))?;
-
-            listener
-                .set_nonblocking(true)
-                .map_err(|e| format!("Failed to set listener non-blocking: {}", e))?;
-
-            let local_port = listener
-                .local_addr()
-                .map_err(|e| format!("Failed to get local port: {}", e))?
-                .port();
-
-            // Create shutdown signal for forwarding thread
-            let shutdown_signal = Arc::new(AtomicBool::new(false));
-
-            // 2. Start port forwarding thread
-            let jump_sess_clone = jump_sess.clone();
-            let target_host = config.host.clone();
-            let target_port = config.port;
-            let listener_clone = listener
-                .try_clone()
-                .map_err(|e| format!("Failed to clone listener: {}", e))?;
-            let shutdown_signal_clone = shutdown_signal.clone();
-
-            let thread_handle = thread::spawn(move || {
-                // 优化：只接受一个连接。因为这是一对一的映射。
-                let start = std::time::Instant::now();
-                let mut accepted = false;
-
-                while !shutdown_signal_clone.load(Ordering::Relaxed) && !accepted {
-                    if start.elapsed().as_secs() > 10 {
-                        break;
-                    }
+            jump_sessions.push(jump_sess);
+        }
 
-                    match listener_clone.accept() {
-                        Ok((mut local_stream, _)) => {
-                            accepted = true;
-                            let jump_sess_inner = jump_sess_clone.clone();
-                            let host = target_host.clone();
-                            let port = target_port;
-                            let shutdown_inner = shutdown_signal_clone.clone();
-
-                            // Open direct-tcpip channel
-                            let mut channel = loop {
-                                match jump_sess_inner.channel_direct_tcpip(&host, port, None) {
-                                    Ok(c) => break c,
-                                    Err(e) if e.code() == ssh2::ErrorCode::Session(-37) => {
-                                        // EAGAIN
-                                        if shutdown_inner.load(Ordering::Relaxed) {
-                                            return;
-                                        }
-                                        thread::sleep(Duration::from_millis(10));
-                                        continue;
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Failed to establish SSH tunnel: {}", e);
-                                        return;
-                                    }
-                                }
-                            };
-
-                            if let Err(_) = local_stream.set_nonblocking(true) {
-                                return;
-                            }
+        let last_hop = jump_sessions
+            .last()
+            .ok_or_else(|| "Jump host chain is empty".to_string())?;
+        let via = &jump_chain[jump_chain.len() - 1].host;
+        let (listener, forward_id, tcp_stream) = forward_through(
+            last_hop,
+            via,
+            target_host.clone(),
+            target_port,
+            local_forward_timeout,
+        )?;
+        sess.set_tcp_stream(tcp_stream);
 
-                            let mut buf = [0u8; 32768]; // 32KB buffer
-
-                            while !shutdown_inner.load(Ordering::Relaxed) {
-                                let mut has_data = false;
-
-                                // Read from Local -> Write to Remote
-                                match local_stream.read(&mut buf) {
-                                    Ok(0) => break, // EOF
-                                    Ok(n) => {
-                                        has_data = true;
-                                        let mut pos = 0;
-                                        while pos < n {
-                                            match channel.write(&buf[pos..n]) {
-                                                Ok(written) => pos += written,
-                                                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-                                                    thread::sleep(Duration::from_millis(1));
-                                                }
-                                                Err(_) => return, // Pipe broken
-                                            }
-                                        }
-                                    }
-                                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
-                                    Err(_) => break,
-                                }
-
-                                // Read from Remote -> Write to Local
-                                match channel.read(&mut buf) {
-                                    Ok(0) => break, // EOF
-                                    Ok(n) => {
-                                        has_data = true;
-                                        let mut pos = 0;
-                                        while pos < n {
-                                            match local_stream.write(&buf[pos..n]) {
-                                                Ok(written) => pos += written,
-                                                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-                                                    thread::sleep(Duration::from_millis(1));
-                                                }
-                                                Err(_) => return,
-                                            }
-                                        }
-                                    }
-                                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
-                                    Err(_) => break,
-                                }
-
-                                if !has_data {
-                                    thread::sleep(Duration::from_millis(2));
-                                }
-                            }
+        // Store handles
+        forward_listeners.push(listener);
+        forward_ids.push(forward_id);
+    }
+
+    let method_prefs = apply_method_prefs(&sess, config)?;
+
+    let tcp_connect = connect_started.elapsed();
+    let handshake_started = Instant::now();
+    sess.handshake().map_err(|e| {
+        if method_prefs.is_empty() {
+            format!("Handshake failed: {}", e)
+        } else {
+            format!(
+                "Handshake failed with the configured {}: {}",
+                method_prefs.join(", "),
+                e
+            )
+        }
+    })?;
+    let handshake = handshake_started.elapsed();
+
+    Ok(Transport {
+        sess,
+        jump_sessions,
+        forward_listeners,
+        forward_ids,
+        proxy,
+        target_host,
+        target_port,
+        tcp_connect,
+        handshake,
+    })
+}
+
+pub(crate) fn establish_connection_internal(
+    config: &SshConnConfig,
+    timeout_settings: Option<&ConnectionTimeoutSettings>,
+) -> Result<ManagedSession, String> {
+    let Transport {
+        sess,
+        jump_sessions,
+        forward_listeners,
+        forward_ids,
+        proxy,
+        target_host,
+        target_port,
+        tcp_connect,
+        handshake,
+    } = open_transport(config, timeout_settings)?;
+
+    let policy = HostKeyPolicy::resolve(config.host_key_policy.as_deref(), timeout_settings);
+    verify_host_key(&sess, &target_host, target_port, config.legacy_mode, policy)?;
+
+    let auth_started = Instant::now();
+    authenticate(
+        &sess,
+        &config.username,
+        config.auth_type.as_deref(),
+        config.password.as_deref(),
+        config.key_content.as_deref(),
+        config.key_passphrase.as_deref(),
+    )?;
+
+    let auth = auth_started.elapsed();
+
+    // Enable keepalive for the main session
+    sess.set_keepalive(true, 15);
+
+    // Set non-blocking mode for concurrency
+    sess.set_blocking(false);
+
+    Ok(ManagedSession {
+        session: sess,
+        jump_sessions,
+        forward_listeners,
+        forward_ids,
+        proxy,
+        health_metadata: SessionHealthMetadata::new(),
+        timings: ConnectTimings {
+            tcp_connect,
+            handshake,
+            auth,
+        },
+    })
+}
+
+/// Handshake with the target and ask which authentication methods it offers
+/// `config.username`, without authenticating. Jump hosts are still
+/// authenticated as usual to reach the target.
+pub(crate) fn list_auth_methods_internal(
+    config: &SshConnConfig,
+    timeout_settings: Option<&ConnectionTimeoutSettings>,
+) -> Result<Vec<String>, String> {
+    let Transport {
+        sess,
+        jump_sessions,
+        forward_listeners,
+        forward_ids,
+        proxy,
+        tcp_connect,
+        handshake,
+        ..
+    } = open_transport(config, timeout_settings)?;
+
+    let methods = sess.auth_methods(&config.username).map(parse_auth_methods);
+
+    // Dropping the managed session disconnects the target and the jump chain
+    drop(ManagedSession {
+        session: sess,
+        jump_sessions,
+        forward_listeners,
+        forward_ids,
+        proxy,
+        health_metadata: SessionHealthMetadata::new(),
+        timings: ConnectTimings {
+            tcp_connect,
+            handshake,
+            auth: Duration::ZERO,
+        },
+    });
+
+    methods.map_err(|e| format!("Failed to query authentication methods: {}", e))
+}
+
+/// Split the server's comma-separated method list, e.g.
+/// `publickey,password,keyboard-interactive`.
+fn parse_auth_methods(methods: &str) -> Vec<String> {
+    methods
+        .split(',')
+        .map(str::trim)
+        .filter(|method| !method.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Bind a forwarding listener on the host part of `bind_address`. Port 0
+/// picks a free port; a port that is already taken is reported as such.
+fn bind_forward_listener(bind_address: &str, local_port: u16) -> Result<TcpListener, String> {
+    let (bind_host, _) = split_host_port(bind_address);
+    let listener = TcpListener::bind(join_host_port(&bind_host, local_port)).map_err(|e| {
+        if e.kind() == ErrorKind::AddrInUse {
+            format!(
+                "Local port {} is already in use on {}",
+                local_port, bind_host
+            )
+        } else {
+            format!("Failed to bind local port on {}: {}", bind_address, e)
+        }
+    })?;
+
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set listener non-blocking: {}", e))?;
+    Ok(listener)
+}
+
+/// Open a direct-tcpip channel to `host:port`, retrying while the
+/// non-blocking session would block. `None` on failure or shutdown.
+fn open_direct_channel(
+    sess: &Session,
+    host: &str,
+    port: u16,
+    shutdown: &AtomicBool,
+) -> Option<ssh2::Channel> {
+    loop {
+        match sess.channel_direct_tcpip(host, port, None) {
+            Ok(c) => return Some(c),
+            Err(e) if e.code() == ssh2::ErrorCode::Session(-37) => {
+                // EAGAIN
+                if shutdown.load(Ordering::Relaxed) {
+                    return None;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) => {
+                eprintln!("Failed to establish SSH tunnel: {}", e);
+                return None;
+            }
+        }
+    }
+}
+
+/// Copy data both ways between a forwarded local connection and its channel
+/// until either side closes or `shutdown` is set.
+fn pump_forwarded_connection(
+    mut local_stream: TcpStream,
+    mut channel: ssh2::Channel,
+    shutdown: &AtomicBool,
+    counters: &ForwardCounters,
+) {
+    if local_stream.set_nonblocking(true).is_err() {
+        return;
+    }
+
+    let mut buf = [0u8; 32768]; // 32KB buffer
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let mut has_data = false;
+
+        // Read from Local -> Write to Remote
+        match local_stream.read(&mut buf) {
+            Ok(0) => break, // EOF
+            Ok(n) => {
+                has_data = true;
+                let mut pos = 0;
+                while pos < n {
+                    match channel.write(&buf[pos..n]) {
+                        Ok(written) => {
+                            pos += written;
+                            counters.tx.fetch_add(written as u64, Ordering::Relaxed);
                         }
                         Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-                            thread::sleep(Duration::from_millis(100));
+                            thread::sleep(Duration::from_millis(1));
                         }
-                        Err(_) => {
-                            break;
+                        Err(_) => return, // Pipe broken
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        // Read from Remote -> Write to Local
+        match channel.read(&mut buf) {
+            Ok(0) => break, // EOF
+            Ok(n) => {
+                has_data = true;
+                let mut pos = 0;
+                while pos < n {
+                    match local_stream.write(&buf[pos..n]) {
+                        Ok(written) => {
+                            pos += written;
+                            counters.rx.fetch_add(written as u64, Ordering::Relaxed);
                         }
+                        Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(1));
+                        }
+                        Err(_) => return,
                     }
                 }
-            });
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
 
-            // 3. Connect to the local forwarded port
-            let connect_addr = format!("127.0.0.1:{}", local_port);
-            let tcp_stream =
-                connect_with_timeout(&connect_addr, local_forward_timeout).map_err(|e| {
-                    format!(
-                        "Failed to connect to local forwarded port {}: {}",
-                        local_port, e
-                    )
-                })?;
+        if !has_data {
+            thread::sleep(Duration::from_millis(2));
+        }
+    }
+}
 
-            sess.set_tcp_stream(tcp_stream);
+/// Forward a local loopback port to `host:port` through `jump_sess` and
+/// connect to it. The forwarding thread serves exactly one connection, so the
+/// relay never follows the user's forward bind address onto the network.
+fn forward_through(
+    jump_sess: &Session,
+    via: &str,
+    target_host: String,
+    target_port: u16,
+    local_forward_timeout: Duration,
+) -> Result<(TcpListener, String, TcpStream), String> {
+    // Local Port Forwarding Pattern
+    let listener = bind_forward_listener("127.0.0.1", 0)?;
+
+    let local_port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get local port: {}", e))?
+        .port();
+
+    // Create shutdown signal for forwarding thread
+    let shutdown_signal = Arc::new(AtomicBool::new(false));
+    let counters = Arc::new(ForwardCounters::default());
+    let remote_target = join_host_port(&target_host, target_port);
+
+    // Start port forwarding thread
+    let jump_sess_clone = jump_sess.clone();
+    let listener_clone = listener
+        .try_clone()
+        .map_err(|e| format!("Failed to clone listener: {}", e))?;
+    let shutdown_signal_clone = shutdown_signal.clone();
+    let counters_clone = counters.clone();
+
+    let thread_handle = thread::spawn(move || {
+        // 优化：只接受一个连接。因为这是一对一的映射。
+        let start = std::time::Instant::now();
+        let mut accepted = false;
+
+        while !shutdown_signal_clone.load(Ordering::Relaxed) && !accepted {
+            if start.elapsed().as_secs() > 10 {
+                break;
+            }
 
-            // Store handles
-            forwarding_handle = Some(ForwardingThreadHandle::new(thread_handle, shutdown_signal));
-            jump_session_holder = Some(jump_sess);
-            listener_holder = Some(listener);
-        } else {
-            // Direct connection
-            let addr_str = format!("{}:{}", config.host, config.port);
-            let tcp = connect_with_timeout(&addr_str, connection_timeout)
-                .map_err(|e| format!("Connection failed: {}", e))?;
-            sess.set_tcp_stream(tcp);
+            match listener_clone.accept() {
+                Ok((local_stream, _)) => {
+                    accepted = true;
+                    if let Some(channel) = open_direct_channel(
+                        &jump_sess_clone,
+                        &target_host,
+                        target_port,
+                        &shutdown_signal_clone,
+                    ) {
+                        pump_forwarded_connection(
+                            local_stream,
+                            channel,
+                            &shutdown_signal_clone,
+                            &counters_clone,
+                        );
+                    }
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(_) => {
+                    break;
+                }
+            }
         }
-    } else {
-        // Direct connection
-        let addr_str = format!("{}:{}", config.host, config.port);
-        let tcp = connect_with_timeout(&addr_str, connection_timeout)
-            .map_err(|e| format!("Connection failed: {}", e))?;
-        sess.set_tcp_stream(tcp);
-    };
+    });
 
-    sess.handshake()
-        .map_err(|e| format!("Handshake failed: {}", e))?;
+    // Connect to the local forwarded port
+    let tcp_stream =
+        connect_with_timeout("127.0.0.1", local_port, local_forward_timeout).map_err(|e| {
+            format!(
+                "Failed to connect to local forwarded port {}: {}",
+                local_port, e
+            )
+        })?;
+
+    let forward_id = ForwardRegistry::shared().register(
+        ForwardSpec {
+            kind: "jump",
+            local_port,
+            remote_target,
+            via: via.to_string(),
+            session_id: None,
+        },
+        ForwardingThreadHandle::new(thread_handle, shutdown_signal),
+        counters,
+    );
 
-    // Implement TOFU (Trust On First Use) Host Key Verification
-    verify_host_key(&sess, &config.host, config.port)?;
+    Ok((listener, forward_id, tcp_stream))
+}
 
-    if config.auth_type.as_deref() == Some("key") {
-        if let Some(key_content) = &config.key_content {
+/// Authenticate `sess` with a password or a private key. Shared by the target
+/// session and every jump host in the chain.
+fn authenticate(
+    sess: &Session,
+    username: &str,
+    auth_type: Option<&str>,
+    password: Option<&str>,
+    key_content: Option<&str>,
+    key_passphrase: Option<&str>,
+) -> Result<(), String> {
+    if auth_type == Some("key") {
+        if let Some(key_content) = key_content {
             // Write key to a temporary file because ssh2 requires a file path for userauth_pubkey_file
             // We use std::env::temp_dir() and a random filename
             use ssh_key::PrivateKey;
@@ -1332,15 +2063,11 @@ fn establish_connection_internal(
             // Create RAII guard to ensure cleanup
             let _guard = TempFileGuard::new(key_path.clone(), pub_key_path.clone());
 
-            let passphrase = config.key_passphrase.as_deref();
+            let passphrase = key_passphrase;
 
             // Try to authenticate with the explicit public key path
-            let auth_res = sess.userauth_pubkey_file(
-                &config.username,
-                Some(&pub_key_path),
-                &key_path,
-                passphrase,
-            );
+            let auth_res =
+                sess.userauth_pubkey_file(username, Some(&pub_key_path), &key_path, passphrase);
 
             auth_res.map_err(|e| {
                 let hint = if passphrase.is_some() {
@@ -1355,47 +2082,30 @@ fn establish_connection_internal(
         }
     } else {
         // Default to password
-        sess.userauth_password(&config.username, config.password.as_deref().unwrap_or(""))
+        sess.userauth_password(username, password.unwrap_or(""))
             .map_err(|e| format!("Password authentication failed: {}", e))?;
     }
 
-    // Enable keepalive for the main session
-    sess.set_keepalive(true, 15);
-
-    // Set non-blocking mode for concurrency
-    sess.set_blocking(false);
-
-    Ok(ManagedSession {
-        session: sess,
-        jump_session: jump_session_holder,
-        forward_listener: listener_holder,
-        forwarding_handle,
-        health_metadata: SessionHealthMetadata::new(),
-    })
+    Ok(())
 }
 
-fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<(), String> {
+/// In `legacy` mode, host keys that libssh2 cannot check or record (e.g. DSA
+/// keys with no known_hosts format) are accepted with a warning instead of
+/// failing; a changed key is still rejected.
+pub(crate) fn verify_host_key(
+    session: &Session,
+    host: &str,
+    port: u16,
+    legacy: bool,
+    policy: HostKeyPolicy,
+) -> Result<(), String> {
     use ssh2::{CheckResult, HashType, KnownHostFileKind};
 
     let mut known_hosts = session
         .known_hosts()
         .map_err(|e| format!("Failed to init known hosts: {}", e))?;
 
-    // Try to find the known_hosts file
-    let ssh_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?
-        .join(".ssh");
-
-    if !ssh_dir.exists() {
-        std::fs::create_dir_all(&ssh_dir)
-            .map_err(|e| format!("Failed to create .ssh directory: {}", e))?;
-    }
-
-    let known_hosts_path = ssh_dir.join("known_hosts");
-    if !known_hosts_path.exists() {
-        std::fs::File::create(&known_hosts_path)
-            .map_err(|e| format!("Failed to create known_hosts file: {}", e))?;
-    }
+    let known_hosts_path = super::known_hosts::known_hosts_path()?;
 
     // Load existing known_hosts
     known_hosts
@@ -1407,16 +2117,35 @@ fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<(), Strin
     match known_hosts.check_port(host, port, key) {
         CheckResult::Match => Ok(()),
         CheckResult::NotFound => {
-            // TOFU: Trust On First Use - Auto Accept
-            println!(
-                "Host key not found for {}:{}. Auto-accepting...",
-                host, port
-            );
+            match policy {
+                HostKeyPolicy::Strict => {
+                    return Err(format!(
+                        "Host key for {}:{} is not in known_hosts and the host key policy \
+                         is strict. Add the key to known_hosts first or relax the policy.",
+                        host, port
+                    ));
+                }
+                HostKeyPolicy::Ask => super::known_hosts::ask_host_key(host, port, key)?,
+                HostKeyPolicy::AcceptNew => {
+                    println!(
+                        "Host key not found for {}:{}. Auto-accepting...",
+                        host, port
+                    );
+                }
+            }
 
             // Add to in-memory known hosts
-            known_hosts
-                .add(host, key, "", key_type.into())
-                .map_err(|e| format!("Failed to add host key: {}", e))?;
+            if let Err(e) = known_hosts.add(host, key, "", key_type.into()) {
+                if legacy {
+                    println!(
+                        "WARNING: legacy mode: could not record {:?} host key for {}:{} ({}); \
+                         continuing without host key verification",
+                        key_type, host, port, e
+                    );
+                    return Ok(());
+                }
+                return Err(format!("Failed to add host key: {}", e));
+            }
 
             // Write back to file
             known_hosts
@@ -1448,13 +2177,64 @@ fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<(), Strin
                 host, port, fingerprint
             ))
         }
+        CheckResult::Failure if legacy => {
+            println!(
+                "WARNING: legacy mode: host key check for {}:{} failed internally; \
+                 continuing without host key verification",
+                host, port
+            );
+            Ok(())
+        }
         CheckResult::Failure => Err("Host key verification failed with internal error".to_string()),
     }
 }
 
 // 跨平台兼容的带超时和Keepalive的Socket连接函数
-fn connect_with_timeout(addr_str: &str, timeout: Duration) -> Result<TcpStream, String> {
-    let addrs = addr_str
+/// Split a host as entered in the UI into the bare host and an embedded port,
+/// if any. Accepts hostnames, IPv4, `host:port`, bare IPv6 literals (zone
+/// identifiers such as `%eth0` included) and bracketed `[v6]` or `[v6]:port`.
+pub(crate) fn split_host_port(input: &str) -> (String, Option<u16>) {
+    let input = input.trim();
+    if let Some(rest) = input.strip_prefix('[') {
+        if let Some((host, tail)) = rest.split_once(']') {
+            let port = tail.strip_prefix(':').and_then(|port| port.parse().ok());
+            return (host.to_string(), port);
+        }
+    }
+    // A bare IPv6 literal has several colons and never carries a port
+    if let Some((host, port)) = input.rsplit_once(':') {
+        if !host.contains(':') {
+            if let Ok(port) = port.parse() {
+                return (host.to_string(), Some(port));
+            }
+        }
+    }
+    (input.to_string(), None)
+}
+
+/// Bare host and port to dial; a port typed into the host field wins.
+pub(crate) fn resolve_target(host: &str, port: u16) -> (String, u16) {
+    let (host, embedded_port) = split_host_port(host);
+    (host, embedded_port.unwrap_or(port))
+}
+
+/// `host:port` for display, with IPv6 literals bracketed.
+pub(crate) fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+pub(crate) fn connect_with_timeout(
+    host: &str,
+    port: u16,
+    timeout: Duration,
+) -> Result<TcpStream, String> {
+    let addr_str = join_host_port(host, port);
+    // Resolve the bare host so getaddrinfo sees zone identifiers unbracketed
+    let addrs = (host, port)
         .to_socket_addrs()
         .map_err(|e| format!("Invalid address '{}': {}", addr_str, e))?
         .collect::<Vec<_>>();
@@ -1524,9 +2304,28 @@ fn connect_with_timeout(addr_str: &str, timeout: Duration) -> Result<TcpStream,
     ))
 }
 
+/// Connect to sshd over a Unix domain socket. On Windows this needs AF_UNIX
+/// support (Windows 10 1803+); named pipes go through `connect_named_pipe`.
+pub(crate) fn connect_unix_socket(path: &str, timeout: Duration) -> Result<Socket, String> {
+    let addr = socket2::SockAddr::unix(path)
+        .map_err(|e| format!("Invalid socket path '{}': {}", path, e))?;
+    let socket = Socket::new(Domain::UNIX, Type::STREAM, None)
+        .map_err(|e| format!("Failed to create socket: {}", e))?;
+    socket
+        .connect_timeout(&addr, timeout)
+        .map_err(|e| format!("Failed to connect to socket '{}': {}", path, e))?;
+    Ok(socket)
+}
+
 // Helper to install public key
 // Helper to install public key
-pub fn install_public_key(session: &ssh2::Session, public_key: &str) -> Result<(), String> {
+/// Returns false without touching the file when `skip_if_present` is set and
+/// a key with the same fingerprint is already authorized.
+pub fn install_public_key(
+    session: &ssh2::Session,
+    public_key: &str,
+    skip_if_present: bool,
+) -> Result<bool, String> {
     // 1. Init SFTP
     let sftp = crate::ssh::utils::open_sftp_with_timeout(session, get_sftp_operation_timeout(None))
         .map_err(|e| format!("SFTP init failed: {}", e))?;
@@ -1536,11 +2335,23 @@ pub fn install_public_key(session: &ssh2::Session, public_key: &str) -> Result<(
     // 0o700 is rwx------
     let _ = ssh2_retry(|| sftp.mkdir(std::path::Path::new(".ssh"), 0o700));
 
+    if skip_if_present {
+        let fingerprint = crate::ssh::authorized_keys::fingerprint(public_key)
+            .ok_or("Failed to parse the public key")?;
+        let existing = crate::ssh::authorized_keys::read(&sftp)?;
+        if crate::ssh::authorized_keys::parse(&existing)
+            .iter()
+            .any(|key| key.fingerprint == fingerprint)
+        {
+            return Ok(false);
+        }
+    }
+
     // 3. Append to authorized_keys
     use ssh2::OpenFlags;
 
     // We strictly use forward slashes for remote paths to ensure compatibility with Linux servers
-    let auth_keys_path = std::path::Path::new(".ssh/authorized_keys");
+    let auth_keys_path = std::path::Path::new(crate::ssh::authorized_keys::AUTHORIZED_KEYS_PATH);
 
     let mut file = ssh2_retry(|| {
         sftp.open_mode(
@@ -1555,22 +2366,9 @@ pub fn install_public_key(session: &ssh2::Session, public_key: &str) -> Result<(
     // Append newline to ensure separation
     let content = format!("\n{}\n", public_key.trim());
 
-    // Handle non-blocking IO writing
-    let bytes = content.as_bytes();
-    let mut pos = 0;
-    while pos < bytes.len() {
-        match file.write(&bytes[pos..]) {
-            Ok(0) => return Err("Write returned 0 bytes".to_string()),
-            Ok(n) => pos += n,
-            Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                thread::sleep(Duration::from_millis(10));
-                continue;
-            }
-            Err(e) => return Err(format!("Failed to write key: {}", e)),
-        }
-    }
+    crate::ssh::authorized_keys::write_all(&mut file, content.as_bytes())?;
 
-    Ok(())
+    Ok(true)
 }
 
 #[tauri::command]
@@ -1578,6 +2376,7 @@ pub async fn install_ssh_key(
     app: AppHandle,
     connection_id: i64,
     key_id: i64,
+    skip_if_installed: Option<bool>,
 ) -> Result<(), String> {
     let db_path = crate::db::get_db_path(&app);
     let conn_db = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
@@ -1605,24 +2404,14 @@ pub async fn install_ssh_key(
     .map_err(|e| e.to_string())??;
 
     // 3. Derive Public Key
-    // We stored private key content. We need to parse it and get public key.
-    // We can use ssh_key crate again.
-    let public_key = {
-        use ssh_key::PrivateKey;
-        let priv_key = PrivateKey::from_openssh(&key.content)
-            .map_err(|e| format!("Invalid private key in DB: {}", e))?;
-
-        priv_key
-            .public_key()
-            .to_openssh()
-            .map_err(|e| format!("Failed to derive public key: {}", e))?
-    };
+    let public_key = crate::ssh::keys::public_key_openssh(&key.content)?;
 
     // 4. Install
     // session_pool.session is the ssh2::Session
     // We need to run blocking operations on it.
     let sess = session_pool.session.clone();
-    tokio::task::spawn_blocking(move || install_public_key(&sess, &public_key))
+    let skip_if_present = skip_if_installed.unwrap_or(false);
+    tokio::task::spawn_blocking(move || install_public_key(&sess, &public_key, skip_if_present))
         .await
         .map_err(|e| e.to_string())??;
 
@@ -1664,3 +2453,133 @@ pub async fn install_ssh_key(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_unix_socket_fails_to_connect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sshd.sock");
+        let err = connect_unix_socket(&path.to_string_lossy(), Duration::from_secs(1)).unwrap_err();
+        assert!(err.contains("sshd.sock"), "{}", err);
+    }
+
+    fn fast_retries() -> ReconnectSettings {
+        ReconnectSettings {
+            max_reconnect_attempts: 3,
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn auth_failure_is_not_retried() {
+        let mut attempts = 0;
+        let result: Result<(), String> = retry_transient(Some(&fast_retries()), || {
+            attempts += 1;
+            Err("Password authentication failed: [Session(-18)] Authentication failed".to_string())
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn refused_connection_is_retried_until_it_succeeds() {
+        let mut attempts = 0;
+        let result = retry_transient(Some(&fast_retries()), || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(
+                    "Connection failed: Failed to connect to 'h:22': Connection refused"
+                        .to_string(),
+                )
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn split_host_port_handles_hostnames_and_ipv4() {
+        assert_eq!(
+            split_host_port("example.com"),
+            ("example.com".to_string(), None)
+        );
+        assert_eq!(
+            split_host_port(" example.com:2222 "),
+            ("example.com".to_string(), Some(2222))
+        );
+        assert_eq!(split_host_port("10.0.0.5"), ("10.0.0.5".to_string(), None));
+        assert_eq!(
+            split_host_port("10.0.0.5:22"),
+            ("10.0.0.5".to_string(), Some(22))
+        );
+    }
+
+    #[test]
+    fn split_host_port_handles_ipv6_literals() {
+        assert_eq!(split_host_port("::1"), ("::1".to_string(), None));
+        assert_eq!(split_host_port("[::1]"), ("::1".to_string(), None));
+        assert_eq!(split_host_port("[::1]:22"), ("::1".to_string(), Some(22)));
+        assert_eq!(
+            split_host_port("2001:db8::10"),
+            ("2001:db8::10".to_string(), None)
+        );
+        assert_eq!(
+            split_host_port("fe80::1%eth0"),
+            ("fe80::1%eth0".to_string(), None)
+        );
+        assert_eq!(
+            split_host_port("[fe80::1%eth0]:2200"),
+            ("fe80::1%eth0".to_string(), Some(2200))
+        );
+    }
+
+    #[test]
+    fn resolve_target_prefers_embedded_port() {
+        assert_eq!(resolve_target("[::1]:2222", 22), ("::1".to_string(), 2222));
+        assert_eq!(resolve_target("::1", 22), ("::1".to_string(), 22));
+        assert_eq!(resolve_target("host", 2200), ("host".to_string(), 2200));
+    }
+
+    #[test]
+    fn join_host_port_brackets_ipv6() {
+        assert_eq!(join_host_port("10.0.0.5", 22), "10.0.0.5:22");
+        assert_eq!(join_host_port("example.com", 22), "example.com:22");
+        assert_eq!(join_host_port("::1", 22), "[::1]:22");
+        assert_eq!(join_host_port("fe80::1%eth0", 22), "[fe80::1%eth0]:22");
+    }
+
+    #[test]
+    fn literals_resolve_to_socket_addrs() {
+        let v4: Vec<SocketAddr> = ("127.0.0.1", 22).to_socket_addrs().unwrap().collect();
+        assert_eq!(v4, vec!["127.0.0.1:22".parse().unwrap()]);
+        let (host, port) = resolve_target("[::1]:2222", 22);
+        let v6: Vec<SocketAddr> = (host.as_str(), port).to_socket_addrs().unwrap().collect();
+        assert_eq!(v6, vec!["[::1]:2222".parse().unwrap()]);
+    }
+
+    #[test]
+    fn parse_auth_methods_splits_server_list() {
+        assert_eq!(
+            parse_auth_methods("publickey,password,keyboard-interactive"),
+            vec!["publickey", "password", "keyboard-interactive"]
+        );
+        assert_eq!(parse_auth_methods(" publickey, ,"), vec!["publickey"]);
+        assert!(parse_auth_methods("").is_empty());
+    }
+
+    #[test]
+    fn normalize_method_list_trims_entries() {
+        assert_eq!(
+            normalize_method_list(Some(" aes128-ctr, ,aes256-ctr ")),
+            Some("aes128-ctr,aes256-ctr".to_string())
+        );
+        assert_eq!(normalize_method_list(Some(" , ")), None);
+        assert_eq!(normalize_method_list(None), None);
+    }
+}
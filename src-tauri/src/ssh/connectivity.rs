@@ -0,0 +1,255 @@
+use super::client::resolve_test_config;
+use super::connection::establish_connection_internal;
+use super::{
+    execute_ssh_operation, get_connection_timeout, get_jump_host_timeout, get_local_forward_timeout,
+};
+use crate::models::{Connection as SshConnConfig, ConnectionTimeoutSettings};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::Semaphore;
+
+/// Hosts checked at once when the caller does not pass `max_parallel`.
+const DEFAULT_HEALTH_CHECK_PARALLELISM: usize = 8;
+/// Round trips timed by `measure_latency` unless the caller asks otherwise.
+const DEFAULT_RTT_SAMPLES: u32 = 3;
+const MAX_RTT_SAMPLES: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConnectionHealthStatus {
+    Ok,
+    AuthFailed,
+    Unreachable,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionHealthResult {
+    pub connection_id: Option<i64>,
+    pub name: String,
+    pub host: String,
+    pub status: ConnectionHealthStatus,
+    /// Time to a fully authenticated session; only set when the check succeeded.
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyReport {
+    pub tcp_connect_ms: u64,
+    pub handshake_ms: u64,
+    pub auth_ms: u64,
+    /// Median of the channel-open round trips; `None` when no samples were taken.
+    pub rtt_ms: Option<u64>,
+    pub samples: Vec<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HealthCheckProgressPayload {
+    completed: usize,
+    total: usize,
+    result: ConnectionHealthResult,
+}
+
+fn classify_failure(error: &str) -> ConnectionHealthStatus {
+    let msg = error.to_lowercase();
+    if msg.contains("authentication failed")
+        || msg.contains("auth failed")
+        || msg.contains("ssh key")
+        || msg.contains("passphrase")
+    {
+        ConnectionHealthStatus::AuthFailed
+    } else if msg.contains("timed out") || msg.contains("timeout") {
+        ConnectionHealthStatus::Timeout
+    } else {
+        ConnectionHealthStatus::Unreachable
+    }
+}
+
+/// Upper bound for one check. The TCP connects honour the configured
+/// timeouts themselves, but the handshake and auth block without one, so allow
+/// one more connection timeout on top for those.
+fn check_deadline(
+    config: &SshConnConfig,
+    timeout_settings: Option<&ConnectionTimeoutSettings>,
+) -> Duration {
+    let connect = get_connection_timeout(timeout_settings);
+    let hops = config.jump_chain().len() as u32;
+    if hops > 0 {
+        (get_jump_host_timeout(timeout_settings) + get_local_forward_timeout(timeout_settings))
+            * hops
+            + connect
+    } else {
+        connect * 2
+    }
+}
+
+async fn check_connection(
+    app: &AppHandle,
+    config: SshConnConfig,
+    timeout_settings: Option<ConnectionTimeoutSettings>,
+) -> ConnectionHealthResult {
+    let mut result = ConnectionHealthResult {
+        connection_id: config.id,
+        name: config.name.clone(),
+        host: config.host.clone(),
+        status: ConnectionHealthStatus::Unreachable,
+        latency_ms: None,
+        error: None,
+    };
+
+    let config = match resolve_test_config(app, config) {
+        Ok(config) => config,
+        Err(error) => {
+            result.status = classify_failure(&error);
+            result.error = Some(error);
+            return result;
+        }
+    };
+
+    let deadline = check_deadline(&config, timeout_settings.as_ref());
+    let started = Instant::now();
+    // Single attempt: retries would hide flaky hosts and stretch the run.
+    let attempt = tokio::task::spawn_blocking(move || {
+        let session = establish_connection_internal(&config, timeout_settings.as_ref())?;
+        let _ = session.session.disconnect(None, "Health Check", None);
+        Ok::<(), String>(())
+    });
+
+    match tokio::time::timeout(deadline, attempt).await {
+        Ok(Ok(Ok(()))) => {
+            result.status = ConnectionHealthStatus::Ok;
+            result.latency_ms = Some(started.elapsed().as_millis() as u64);
+        }
+        Ok(Ok(Err(error))) => {
+            result.status = classify_failure(&error);
+            result.error = Some(error);
+        }
+        Ok(Err(error)) => {
+            result.error = Some(format!("Task join error: {}", error));
+        }
+        Err(_) => {
+            result.status = ConnectionHealthStatus::Timeout;
+            result.error = Some(format!("No response within {}s", deadline.as_secs()));
+        }
+    }
+    result
+}
+
+/// Check every stored connection by connecting and authenticating once, at
+/// most `max_parallel` at a time. Emits `health-check-progress` as each host
+/// finishes and returns the results in the stored connection order.
+#[command]
+pub async fn test_all_connections(
+    app: AppHandle,
+    max_parallel: Option<usize>,
+) -> Result<Vec<ConnectionHealthResult>, String> {
+    let connections = crate::db::get_connections(app.clone())?;
+    let timeout_settings = crate::db::get_settings(app.clone())
+        .ok()
+        .map(|settings| settings.connection_timeout);
+
+    let total = connections.len();
+    let semaphore = Arc::new(Semaphore::new(
+        max_parallel
+            .unwrap_or(DEFAULT_HEALTH_CHECK_PARALLELISM)
+            .max(1),
+    ));
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    let mut tasks = Vec::with_capacity(total);
+    for config in connections {
+        let app = app.clone();
+        let semaphore = semaphore.clone();
+        let completed = completed.clone();
+        let timeout_settings = timeout_settings.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+            let result = check_connection(&app, config, timeout_settings).await;
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = app.emit(
+                "health-check-progress",
+                HealthCheckProgressPayload {
+                    completed: done,
+                    total,
+                    result: result.clone(),
+                },
+            );
+            Ok::<_, String>(result)
+        }));
+    }
+
+    let mut results = Vec::with_capacity(total);
+    for task in tasks {
+        results.push(
+            task.await
+                .map_err(|e| format!("Task join error: {}", e))??,
+        );
+    }
+    Ok(results)
+}
+
+/// Time opening (and closing) a session channel, a full round trip that the
+/// server has to answer, unlike a keepalive.
+fn sample_round_trips(session: &ssh2::Session, count: u32) -> Result<Vec<u64>, String> {
+    session.set_blocking(true);
+    let mut samples = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let started = Instant::now();
+        let mut channel = session
+            .channel_session()
+            .map_err(|e| format!("Failed to open channel: {}", e))?;
+        samples.push(started.elapsed().as_millis() as u64);
+        let _ = channel.close();
+    }
+    Ok(samples)
+}
+
+fn median(samples: &[u64]) -> Option<u64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied()
+}
+
+/// Connect to a stored connection and time the TCP connect, SSH handshake
+/// and authentication separately, then `samples` round trips on the open
+/// session. The RTT (or the TCP connect time when no samples were taken) is
+/// stored as the connection's last latency.
+#[command]
+pub async fn measure_latency(
+    app: AppHandle,
+    id: i64,
+    samples: Option<u32>,
+) -> Result<LatencyReport, String> {
+    let config = crate::db::get_connection_by_id(&app, id)?.ok_or("Connection not found")?;
+    let config = resolve_test_config(&app, config)?;
+    let timeout_settings = crate::db::get_settings(app.clone())
+        .ok()
+        .map(|settings| settings.connection_timeout);
+    let sample_count = samples.unwrap_or(DEFAULT_RTT_SAMPLES).min(MAX_RTT_SAMPLES);
+
+    let report = execute_ssh_operation(move || {
+        let session = establish_connection_internal(&config, timeout_settings.as_ref())?;
+        let samples = sample_round_trips(&session.session, sample_count);
+        let _ = session.session.disconnect(None, "Latency Check", None);
+        let samples = samples?;
+        Ok(LatencyReport {
+            tcp_connect_ms: session.timings.tcp_connect.as_millis() as u64,
+            handshake_ms: session.timings.handshake.as_millis() as u64,
+            auth_ms: session.timings.auth.as_millis() as u64,
+            rtt_ms: median(&samples),
+            samples,
+        })
+    })
+    .await?;
+
+    let latency = report.rtt_ms.unwrap_or(report.tcp_connect_ms);
+    crate::db::record_connection_latency(&app, id, latency as i64)?;
+    Ok(report)
+}
@@ -0,0 +1,443 @@
+use super::client::{AppState, ClientType};
+use super::connection::SessionSshPool;
+use super::manager::SshCommand;
+use crate::models::RemoteDirWatch;
+use crate::ssh::execute_ssh_operation;
+use crate::ssh::file_ops::escape_shell_arg;
+use crate::ssh::utils::ssh2_retry;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+/// How long `inotifywait -r` may spend setting up its watches before the
+/// watcher gives up on it and polls instead.
+const INOTIFY_SETUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the polling fallback re-reads the directory.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Payload of the `dir-changed:{id}` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirChange {
+    pub watch_id: String,
+    /// Full remote path of the entry that changed
+    pub path: String,
+    /// "created", "deleted", "modified", "moved_from", "moved_to" or
+    /// "attrib"; "stopped" when the watch ended on its own, e.g. because the
+    /// connection dropped or the directory was removed
+    pub kind: String,
+    pub is_dir: bool,
+}
+
+/// What the watcher thread needs, sent along with `SshCommand::WatchDirectory`.
+pub struct DirWatchRequest {
+    pub watch_id: String,
+    pub path: String,
+    pub event_name: String,
+    pub app_handle: AppHandle,
+    /// Set by `unwatch_remote_directory`
+    pub stop: Arc<AtomicBool>,
+}
+
+/// A running watch, as tracked in `AppState::dir_watches`.
+pub struct DirWatchHandle {
+    pub session_id: String,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EntryState {
+    is_dir: bool,
+    size: u64,
+    mtime: u64,
+}
+
+/// Parse an `inotifywait --format '%e %w%f'` line such as
+/// `CREATE,ISDIR /srv/app/logs` into its kind, path and whether it is a
+/// directory. Anything else inotifywait prints comes back as `None`.
+fn parse_inotify_line(line: &str) -> Option<(&'static str, &str, bool)> {
+    let (events, path) = line.split_once(' ')?;
+    let mut kind = None;
+    let mut is_dir = false;
+    for event in events.split(',') {
+        let event_kind = match event {
+            "ISDIR" => {
+                is_dir = true;
+                continue;
+            }
+            "CREATE" => "created",
+            "DELETE" | "DELETE_SELF" => "deleted",
+            "CLOSE_WRITE" | "MODIFY" => "modified",
+            "MOVED_FROM" | "MOVE_SELF" => "moved_from",
+            "MOVED_TO" => "moved_to",
+            "ATTRIB" => "attrib",
+            _ => continue,
+        };
+        kind.get_or_insert(event_kind);
+    }
+    Some((kind?, path, is_dir))
+}
+
+/// What changed between two listings of a directory, by entry name, in
+/// name order.
+fn diff_listings(
+    old: &HashMap<String, EntryState>,
+    new: &HashMap<String, EntryState>,
+) -> Vec<(String, &'static str, bool)> {
+    let mut changes: Vec<(String, &'static str, bool)> = new
+        .iter()
+        .filter_map(|(name, state)| match old.get(name) {
+            None => Some((name.clone(), "created", state.is_dir)),
+            Some(previous) if previous != state => Some((name.clone(), "modified", state.is_dir)),
+            Some(_) => None,
+        })
+        .chain(
+            old.iter()
+                .filter(|(name, _)| !new.contains_key(*name))
+                .map(|(name, state)| (name.clone(), "deleted", state.is_dir)),
+        )
+        .collect();
+    changes.sort();
+    changes
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+/// Start `inotifywait -m -r` on `path` and wait until its watches are set
+/// up. The PTY makes sshd hang it up when the channel or session closes.
+fn start_inotify(session: &ssh2::Session, path: &str) -> Result<ssh2::Channel, String> {
+    let mut channel = ssh2_retry(|| session.channel_session()).map_err(|e| e.to_string())?;
+    ssh2_retry(|| channel.request_pty("dumb", None, Some((400, 24, 0, 0))))
+        .map_err(|e| e.to_string())?;
+    let command = format!(
+        "command -v inotifywait >/dev/null 2>&1 || {{ echo 'inotifywait is not installed'; exit 127; }}; \
+         exec inotifywait -m -r -e create,delete,close_write,moved_from,moved_to,attrib,delete_self,move_self \
+         --format '%e %w%f' -- '{}'",
+        escape_shell_arg(path)
+    );
+    ssh2_retry(|| channel.exec(&command)).map_err(|e| e.to_string())?;
+
+    // "Watches established." is printed once inotifywait is watching
+    let started = Instant::now();
+    let mut output = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if started.elapsed() >= INOTIFY_SETUP_TIMEOUT {
+            let _ = channel.close();
+            return Err("Timed out setting up inotify watches".to_string());
+        }
+        match channel.read(&mut buf) {
+            Ok(0) => {
+                let output = String::from_utf8_lossy(&output);
+                let reason = output.trim();
+                return Err(if reason.is_empty() {
+                    "inotifywait exited".to_string()
+                } else {
+                    reason.to_string()
+                });
+            }
+            Ok(n) => {
+                output.extend_from_slice(&buf[..n]);
+                if String::from_utf8_lossy(&output).contains("Watches established") {
+                    return Ok(channel);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn pump_inotify(
+    session: &ssh2::Session,
+    channel: &mut ssh2::Channel,
+    stopped: &dyn Fn() -> bool,
+    emit: &dyn Fn(&str, &str, bool),
+) -> Result<(), String> {
+    let mut pending = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        if stopped() {
+            let _ = channel.close();
+            return Ok(());
+        }
+        match channel.read(&mut buf) {
+            Ok(0) => return Err("inotifywait exited".to_string()),
+            Ok(n) => {
+                pending.extend_from_slice(&buf[..n]);
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    if let Some((kind, path, is_dir)) =
+                        parse_inotify_line(line.trim_end_matches(['\r', '\n']))
+                    {
+                        emit(path, kind, is_dir);
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                // Only sends once the keepalive interval has passed
+                let _ = session.keepalive_send();
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn list_dir(sftp: &ssh2::Sftp, path: &str) -> Result<HashMap<String, EntryState>, String> {
+    let entries = ssh2_retry(|| sftp.readdir(Path::new(path))).map_err(|e| e.to_string())?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(entry_path, stat)| {
+            let name = entry_path.file_name()?.to_string_lossy().into_owned();
+            if name == "." || name == ".." {
+                return None;
+            }
+            let state = EntryState {
+                is_dir: stat.is_dir(),
+                size: stat.size.unwrap_or(0),
+                mtime: stat.mtime.unwrap_or(0),
+            };
+            Some((name, state))
+        })
+        .collect())
+}
+
+fn poll_directory(
+    sftp: &ssh2::Sftp,
+    path: &str,
+    mut snapshot: HashMap<String, EntryState>,
+    stopped: &dyn Fn() -> bool,
+    emit: &dyn Fn(&str, &str, bool),
+) -> Result<(), String> {
+    loop {
+        let next_poll = Instant::now() + POLL_INTERVAL;
+        while Instant::now() < next_poll {
+            if stopped() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        let current = list_dir(sftp, path)?;
+        for (name, kind, is_dir) in diff_listings(&snapshot, &current) {
+            emit(&join_path(path, &name), kind, is_dir);
+        }
+        snapshot = current;
+    }
+}
+
+/// Body of a watcher thread. Watches `request.path` on a session of its own,
+/// with `inotifywait` when the server has it and by re-reading the directory
+/// (non-recursively) otherwise. The method used, "inotify" or "poll", is sent
+/// on `ready` once watching; changes are emitted until `request.stop` or
+/// `closed` (the connection's shutdown signal) is set.
+pub(crate) fn run_dir_watch(
+    pool: SessionSshPool,
+    request: DirWatchRequest,
+    closed: Arc<AtomicBool>,
+    ready: Sender<Result<String, String>>,
+) {
+    let DirWatchRequest {
+        watch_id,
+        path,
+        event_name,
+        app_handle,
+        stop,
+    } = request;
+    let session = match pool.connect_new_session() {
+        Ok(session) => session,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    let stopped = || stop.load(Ordering::Relaxed) || closed.load(Ordering::Relaxed);
+    let emit = |path: &str, kind: &str, is_dir: bool| {
+        let _ = app_handle.emit(
+            &event_name,
+            DirChange {
+                watch_id: watch_id.clone(),
+                path: path.to_string(),
+                kind: kind.to_string(),
+                is_dir,
+            },
+        );
+    };
+
+    let result = match start_inotify(&session.session, &path) {
+        Ok(mut channel) => {
+            let _ = ready.send(Ok("inotify".to_string()));
+            pump_inotify(&session.session, &mut channel, &stopped, &emit)
+        }
+        Err(reason) => {
+            eprintln!(
+                "[DirWatch] inotify unavailable for {}, polling: {}",
+                path, reason
+            );
+            let snapshot = ssh2_retry(|| session.session.sftp())
+                .map_err(|e| e.to_string())
+                .and_then(|sftp| list_dir(&sftp, &path).map(|snapshot| (sftp, snapshot)));
+            match snapshot {
+                Ok((sftp, snapshot)) => {
+                    let _ = ready.send(Ok("poll".to_string()));
+                    poll_directory(&sftp, &path, snapshot, &stopped, &emit)
+                }
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                    return;
+                }
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        if !stopped() {
+            eprintln!("[DirWatch] Watch on {} ended: {}", path, e);
+            emit(&path, "stopped", true);
+        }
+    }
+}
+
+/// Watch a remote directory and emit `dir-changed:{id}` for each change
+/// under it. Recursive when the server has `inotifywait`; otherwise only the
+/// directory's own entries are compared every few seconds.
+#[tauri::command]
+pub async fn watch_remote_directory(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<RemoteDirWatch, String> {
+    let sender = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        match &clients.get(&id).ok_or("Session not found")?.client_type {
+            ClientType::Ssh(senders) => senders.ops.clone(),
+            ClientType::Wsl(_) => {
+                return Err("Watching directories is not supported for WSL sessions".to_string())
+            }
+        }
+    };
+
+    let watch_id = uuid::Uuid::new_v4().to_string();
+    let stop = Arc::new(AtomicBool::new(false));
+    let request = DirWatchRequest {
+        watch_id: watch_id.clone(),
+        path,
+        event_name: format!("dir-changed:{}", id),
+        app_handle,
+        stop: stop.clone(),
+    };
+    let method = execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::WatchDirectory {
+                request,
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await?;
+
+    state.dir_watches.lock().map_err(|e| e.to_string())?.insert(
+        watch_id.clone(),
+        DirWatchHandle {
+            session_id: id,
+            stop,
+        },
+    );
+    Ok(RemoteDirWatch { watch_id, method })
+}
+
+#[tauri::command]
+pub fn unwatch_remote_directory(
+    state: State<'_, AppState>,
+    watch_id: String,
+) -> Result<(), String> {
+    let watch = state
+        .dir_watches
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&watch_id)
+        .ok_or("Watch not found")?;
+    watch.stop.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop and forget every watch on `session_id`, when it disconnects.
+pub(crate) fn stop_session_watches(state: &AppState, session_id: &str) {
+    if let Ok(mut watches) = state.dir_watches.lock() {
+        watches.retain(|_, watch| {
+            if watch.session_id != session_id {
+                return true;
+            }
+            watch.stop.store(true, Ordering::Relaxed);
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inotifywait_events() {
+        assert_eq!(
+            parse_inotify_line("CREATE,ISDIR /srv/app/logs"),
+            Some(("created", "/srv/app/logs", true))
+        );
+        assert_eq!(
+            parse_inotify_line("CLOSE_WRITE,CLOSE /srv/app/my file.txt"),
+            Some(("modified", "/srv/app/my file.txt", false))
+        );
+        assert_eq!(
+            parse_inotify_line("MOVED_TO /srv/app/b"),
+            Some(("moved_to", "/srv/app/b", false))
+        );
+        assert_eq!(parse_inotify_line("Watches established."), None);
+        assert_eq!(parse_inotify_line("OPEN /srv/app/a"), None);
+    }
+
+    #[test]
+    fn diffs_directory_listings() {
+        let state = |is_dir, size, mtime| EntryState {
+            is_dir,
+            size,
+            mtime,
+        };
+        let old = HashMap::from([
+            ("kept".to_string(), state(false, 10, 1)),
+            ("edited".to_string(), state(false, 10, 1)),
+            ("gone".to_string(), state(true, 0, 1)),
+        ]);
+        let new = HashMap::from([
+            ("kept".to_string(), state(false, 10, 1)),
+            ("edited".to_string(), state(false, 12, 2)),
+            ("added".to_string(), state(false, 1, 2)),
+        ]);
+        assert_eq!(
+            diff_listings(&old, &new),
+            vec![
+                ("added".to_string(), "created", false),
+                ("edited".to_string(), "modified", false),
+                ("gone".to_string(), "deleted", true),
+            ]
+        );
+        assert_eq!(join_path("/", "etc"), "/etc");
+        assert_eq!(join_path("/srv/app/", "a"), "/srv/app/a");
+    }
+}
@@ -0,0 +1,186 @@
+use super::client::AppState;
+use super::system::run_client_command_with_status;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+/// Error returned when the `docker` CLI is missing on the host.
+pub const DOCKER_NOT_INSTALLED: &str = "DOCKER_NOT_INSTALLED";
+/// Error returned when the user cannot reach the Docker daemon socket
+/// (typically not a member of the `docker` group).
+pub const DOCKER_PERMISSION_DENIED: &str = "DOCKER_PERMISSION_DENIED";
+
+const CONTAINER_ACTIONS: &[&str] = &["start", "stop", "restart", "rm"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerContainer {
+    pub id: String,
+    pub image: String,
+    pub status: String,
+    pub state: String,
+    pub ports: String,
+    pub names: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerActionResult {
+    pub exit_code: i32,
+    pub output: String,
+}
+
+/// One line of `docker ps --format '{{json .}}'`
+#[derive(Deserialize)]
+struct DockerPsLine {
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(rename = "Image", default)]
+    image: String,
+    #[serde(rename = "Status", default)]
+    status: String,
+    #[serde(rename = "State", default)]
+    state: String,
+    #[serde(rename = "Ports", default)]
+    ports: String,
+    #[serde(rename = "Names", default)]
+    names: String,
+}
+
+fn docker_script(docker_args: &str) -> String {
+    format!(
+        "export LC_ALL=C; if command -v docker >/dev/null 2>&1; then docker {} 2>&1; else echo {}; (exit 127); fi",
+        docker_args, DOCKER_NOT_INSTALLED
+    )
+}
+
+fn classify_docker_output(output: String, exit_code: i32) -> Result<(String, i32), String> {
+    if exit_code == 127 && output.trim() == DOCKER_NOT_INSTALLED {
+        return Err(DOCKER_NOT_INSTALLED.to_string());
+    }
+    let lower = output.to_lowercase();
+    if exit_code != 0 && lower.contains("permission denied") && lower.contains("docker") {
+        return Err(DOCKER_PERMISSION_DENIED.to_string());
+    }
+    Ok((output, exit_code))
+}
+
+fn parse_docker_ps(raw: &str) -> Vec<DockerContainer> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<DockerPsLine>(line).ok())
+        .map(|line| DockerContainer {
+            id: line.id,
+            image: line.image,
+            status: line.status,
+            state: line.state,
+            ports: line.ports,
+            names: line.names,
+        })
+        .collect()
+}
+
+/// List all containers (running and stopped) via `docker ps -a`.
+#[command]
+pub async fn list_docker_containers(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<DockerContainer>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let script = docker_script("ps -a --no-trunc --format '{{json .}}'");
+    let (output, exit_code) =
+        run_client_command_with_status(&client.client_type, script, None).await?;
+    let (output, exit_code) = classify_docker_output(output, exit_code)?;
+    if exit_code != 0 {
+        return Err(format!("docker exited with code {}: {}", exit_code, output));
+    }
+    Ok(parse_docker_ps(&output))
+}
+
+/// Run `docker start|stop|restart|rm <container>`.
+#[command]
+pub async fn docker_container_action(
+    state: State<'_, AppState>,
+    id: String,
+    container_id: String,
+    action: String,
+) -> Result<DockerActionResult, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    if !CONTAINER_ACTIONS.contains(&action.as_str()) {
+        return Err(format!("Unsupported container action: {}", action));
+    }
+    if container_id.is_empty()
+        || container_id.starts_with('-')
+        || !container_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    {
+        return Err(format!("Invalid container id: {}", container_id));
+    }
+
+    let script = docker_script(&format!("{} '{}'", action, container_id));
+    let (output, exit_code) =
+        run_client_command_with_status(&client.client_type, script, None).await?;
+    let (output, exit_code) = classify_docker_output(output, exit_code)?;
+    Ok(DockerActionResult { exit_code, output })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_docker_ps_reads_json_lines() {
+        let raw = concat!(
+            "WARNING: Error loading config file: /root/.docker/config.json\n",
+            r#"{"Command":"\"nginx -g 'daemon of…\"","ID":"3f4e8a1b2c","Image":"nginx:1.25","Names":"web","Ports":"0.0.0.0:8080->80/tcp","State":"running","Status":"Up 2 hours"}"#,
+            "\n",
+            r#"{"ID":"9a8b7c","Image":"redis","Names":"cache","State":"exited","Status":"Exited (0) 3 days ago"}"#,
+            "\n",
+        );
+        let containers = parse_docker_ps(raw);
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].id, "3f4e8a1b2c");
+        assert_eq!(containers[0].image, "nginx:1.25");
+        assert_eq!(containers[0].names, "web");
+        assert_eq!(containers[0].ports, "0.0.0.0:8080->80/tcp");
+        assert_eq!(containers[0].state, "running");
+        assert_eq!(containers[0].status, "Up 2 hours");
+        assert_eq!(containers[1].ports, "");
+        assert!(parse_docker_ps("").is_empty());
+    }
+
+    #[test]
+    fn classify_docker_output_detects_missing_cli_and_permissions() {
+        assert_eq!(
+            classify_docker_output(format!("{}\n", DOCKER_NOT_INSTALLED), 127).unwrap_err(),
+            DOCKER_NOT_INSTALLED
+        );
+        let denied = "permission denied while trying to connect to the Docker daemon socket at unix:///var/run/docker.sock: Get \"http://%2Fvar%2Frun%2Fdocker.sock/v1.24/containers/json\": dial unix /var/run/docker.sock: connect: permission denied";
+        assert_eq!(
+            classify_docker_output(denied.to_string(), 1).unwrap_err(),
+            DOCKER_PERMISSION_DENIED
+        );
+    }
+
+    #[test]
+    fn classify_docker_output_passes_daemon_errors_through() {
+        let down = "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is the docker daemon running?";
+        assert_eq!(
+            classify_docker_output(down.to_string(), 1).unwrap(),
+            (down.to_string(), 1)
+        );
+        assert_eq!(
+            classify_docker_output("abc123\n".to_string(), 0).unwrap(),
+            ("abc123\n".to_string(), 0)
+        );
+    }
+}
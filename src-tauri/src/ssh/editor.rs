@@ -0,0 +1,378 @@
+use super::client::{AppState, ClientType};
+use super::file_ops::sanitize_temp_file_name;
+use super::manager::SshCommand;
+use super::wsl;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+/// Quiet period after the last change event before uploading, so editors that
+/// save through several writes or a rename only trigger one upload.
+const SYNC_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSyncedPayload {
+    pub id: String,
+    pub remote_path: String,
+    pub local_path: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileSyncErrorPayload {
+    id: String,
+    remote_path: String,
+    error: String,
+}
+
+/// Temp location for an edited file: one directory per remote path so the
+/// watcher only sees events for that file and the original name is kept.
+fn editor_temp_path(session_id: &str, remote_path: &str) -> Result<PathBuf, String> {
+    let mut hasher = DefaultHasher::new();
+    remote_path.hash(&mut hasher);
+    let session_dir: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let remote_name = remote_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    if matches!(remote_name, "." | "..") {
+        return Err(format!("Cannot edit '{}': not a file name", remote_path));
+    }
+
+    Ok(std::env::temp_dir()
+        .join("ssh-assistant-edit")
+        .join(session_dir)
+        .join(format!("{:016x}", hasher.finish()))
+        .join(sanitize_temp_file_name(remote_name)))
+}
+
+fn read_remote_bytes(client_type: &ClientType, path: &str) -> Result<Vec<u8>, String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let (tx, rx) = channel();
+            senders
+                .ops
+                .send(SshCommand::SftpRead {
+                    path: path.to_string(),
+                    max_len: None,
+                    listener: tx,
+                })
+                .map_err(|e| format!("Failed to send command: {}", e))?;
+            rx.recv()
+                .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+        }
+        ClientType::Wsl(distro) => {
+            let script = r#"target="$1"
+cat -- "$target"
+"#;
+            let output = wsl::run_bash_output(distro, script, &[path.to_string()])?;
+            if output.status.success() {
+                Ok(output.stdout)
+            } else {
+                Err(wsl::decode_wsl_output(&output.stderr).trim().to_string())
+            }
+        }
+    }
+}
+
+fn write_remote_bytes(
+    client_type: &ClientType,
+    path: &str,
+    content: Vec<u8>,
+) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let (tx, rx) = channel();
+            senders
+                .ops
+                .send(SshCommand::SftpWrite {
+                    path: path.to_string(),
+                    content,
+                    mode: None,
+                    listener: tx,
+                })
+                .map_err(|e| format!("Failed to send command: {}", e))?;
+            rx.recv()
+                .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+        }
+        ClientType::Wsl(distro) => {
+            let script = r#"target="$1"
+cat > "$target"
+"#;
+            let mut child = wsl::spawn_bash(
+                distro,
+                script,
+                &[path.to_string()],
+                std::process::Stdio::piped(),
+                std::process::Stdio::null(),
+                std::process::Stdio::piped(),
+            )?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(&content).map_err(|e| e.to_string())?;
+            }
+            let output = child.wait_with_output().map_err(|e| e.to_string())?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(wsl::decode_wsl_output(&output.stderr).trim().to_string())
+            }
+        }
+    }
+}
+
+/// Launch the configured editor command (e.g. `code --wait`) with the file
+/// appended, or hand the file to the OS default application.
+fn launch_editor(editor: Option<&str>, file: &Path) -> Result<(), String> {
+    let Some(command_line) = editor.map(str::trim).filter(|s| !s.is_empty()) else {
+        return tauri_plugin_opener::open_path(file, None::<&str>)
+            .map_err(|e| format!("Failed to open file: {}", e));
+    };
+
+    let parts = shell_words::split(command_line)
+        .map_err(|e| format!("Invalid editor command '{}': {}", command_line, e))?;
+    let Some((program, args)) = parts.split_first() else {
+        return Err("Editor command is empty".to_string());
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = std::process::Command::new(resolve_program(program));
+        cmd.creation_flags(0x08000000);
+        cmd
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = std::process::Command::new(program);
+
+    let mut child = cmd
+        .args(args)
+        .arg(file)
+        .spawn()
+        .map_err(|e| format!("Failed to launch editor '{}': {}", program, e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Find a bare program name on PATH using PATHEXT, so `.cmd` shims such as
+/// VS Code's `code` start without wrapping the command line in `cmd /C`.
+/// std quotes the arguments of a resolved batch file and refuses any it
+/// cannot pass safely.
+#[cfg(target_os = "windows")]
+fn resolve_program(program: &str) -> PathBuf {
+    let path = Path::new(program);
+    if path.extension().is_some() || path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    let search_path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&search_path)
+        .flat_map(|dir| {
+            extensions
+                .split(';')
+                .filter(|ext| !ext.is_empty())
+                .map(move |ext| dir.join(format!("{}{}", program, ext)))
+        })
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn touches_file(event: &Event, file: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == file.file_name())
+}
+
+/// Watch the temp file and upload it after each debounced change. The thread
+/// (and the watcher it owns) ends once the session's shutdown signal is set.
+fn spawn_sync_watcher(
+    app: AppHandle,
+    id: String,
+    watch_key: String,
+    client_type: ClientType,
+    shutdown_signal: Arc<AtomicBool>,
+    remote_path: String,
+    local_path: PathBuf,
+) -> Result<(), String> {
+    let watch_dir = local_path
+        .parent()
+        .ok_or("Invalid temp file path")?
+        .to_path_buf();
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    watcher
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", watch_dir.display(), e))?;
+
+    std::thread::spawn(move || {
+        let mut last_synced = std::fs::read(&local_path).ok();
+        let mut pending = false;
+
+        while !shutdown_signal.load(Ordering::Relaxed) {
+            match rx.recv_timeout(SYNC_DEBOUNCE) {
+                Ok(Ok(event)) => {
+                    if touches_file(&event, &local_path) {
+                        pending = true;
+                    }
+                }
+                Ok(Err(_)) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    if !pending {
+                        continue;
+                    }
+                    pending = false;
+
+                    // Editors fire events for saves that leave the content unchanged.
+                    let Ok(content) = std::fs::read(&local_path) else {
+                        continue;
+                    };
+                    if last_synced.as_ref() == Some(&content) {
+                        continue;
+                    }
+
+                    let size = content.len() as u64;
+                    match write_remote_bytes(&client_type, &remote_path, content.clone()) {
+                        Ok(()) => {
+                            last_synced = Some(content);
+                            let _ = app.emit(
+                                "file-synced",
+                                FileSyncedPayload {
+                                    id: id.clone(),
+                                    remote_path: remote_path.clone(),
+                                    local_path: local_path.to_string_lossy().to_string(),
+                                    size,
+                                },
+                            );
+                        }
+                        Err(error) => {
+                            let _ = app.emit(
+                                "file-sync-error",
+                                FileSyncErrorPayload {
+                                    id: id.clone(),
+                                    remote_path: remote_path.clone(),
+                                    error,
+                                },
+                            );
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        if let Ok(mut watches) = app.state::<AppState>().editor_watches.lock() {
+            watches.remove(&watch_key);
+        }
+        // The hash directory only ever holds this file.
+        drop(watcher);
+        let _ = std::fs::remove_dir_all(&watch_dir);
+    });
+
+    Ok(())
+}
+
+/// Download a remote file to a temp copy, open it in the user's editor and
+/// upload it back whenever it is saved. Returns the local temp path.
+#[command]
+pub async fn open_remote_file_in_editor(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let watch_key = format!("{}:{}", id, path);
+    let local_path = editor_temp_path(&id, &path)?;
+    let already_watching = state
+        .editor_watches
+        .lock()
+        .map_err(|e| e.to_string())?
+        .contains(&watch_key);
+
+    // A file that is already being synced keeps its local edits; just reopen it.
+    if !already_watching {
+        let client_type = client.client_type.clone();
+        let remote_path = path.clone();
+        let content =
+            tokio::task::spawn_blocking(move || read_remote_bytes(&client_type, &remote_path))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))??;
+
+        if let Some(parent) = local_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&local_path, content).map_err(|e| e.to_string())?;
+
+        spawn_sync_watcher(
+            app.clone(),
+            id.clone(),
+            watch_key.clone(),
+            client.client_type.clone(),
+            client.shutdown_signal.clone(),
+            path.clone(),
+            local_path.clone(),
+        )?;
+        state
+            .editor_watches
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(watch_key);
+    }
+
+    let editor = crate::db::get_settings(app.clone())
+        .ok()
+        .and_then(|settings| settings.file_manager.default_editor);
+    launch_editor(editor.as_deref(), &local_path)?;
+
+    Ok(local_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_temp_path_stays_inside_the_hash_directory() {
+        let path = editor_temp_path("s1", "/srv/..\\..\\x.bat").unwrap();
+        let hash_dir = path.parent().unwrap();
+        assert!(hash_dir.starts_with(std::env::temp_dir().join("ssh-assistant-edit")));
+        assert_eq!(path.file_name().unwrap(), "x.bat");
+
+        let path = editor_temp_path("s1", "/etc/nginx/nginx.conf").unwrap();
+        assert_eq!(path.file_name().unwrap(), "nginx.conf");
+    }
+
+    #[test]
+    fn editor_temp_path_rejects_dot_names() {
+        assert!(editor_temp_path("s1", "/srv/..").is_err());
+        assert!(editor_temp_path("s1", "/srv/./").is_err());
+    }
+}
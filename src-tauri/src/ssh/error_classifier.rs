@@ -62,12 +62,12 @@ impl SshErrorClassifier {
             | io::ErrorKind::TimedOut
             | io::ErrorKind::Interrupted
             | io::ErrorKind::ConnectionReset
-            | io::ErrorKind::ConnectionAborted => SshErrorType::Temporary,
+            | io::ErrorKind::ConnectionAborted
+            // sshd may simply be restarting
+            | io::ErrorKind::ConnectionRefused => SshErrorType::Temporary,
 
             // Permanent errors
-            io::ErrorKind::NotFound
-            | io::ErrorKind::PermissionDenied
-            | io::ErrorKind::ConnectionRefused => SshErrorType::Permanent,
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => SshErrorType::Permanent,
 
             // Other errors - check message content
             _ => Self::classify_from_string(&msg),
@@ -90,14 +90,17 @@ impl SshErrorClassifier {
             "incorrect password",
             "key authentication failed",
             "publickey authentication failed",
+            "too many authentication failures",
             "host key verification failed",
             "host key mismatch",
+            // Unknown keys refused by the host key policy or the user
+            "host key for",
+            "unknown host key",
             "no such host",
             "name or service not known",
             "nodename nor servname provided",
             "address not available",
             "network is unreachable",
-            "connection refused",
             "protocol error",
             "invalid",
             "unsupported",
@@ -227,6 +230,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classify_refused_as_temporary() {
+        let msg = "Failed to connect to '10.0.0.5:22': Connection refused (os error 111)";
+        assert_eq!(
+            SshErrorClassifier::classify_from_string(msg),
+            SshErrorType::Temporary
+        );
+    }
+
+    #[test]
+    fn test_classify_repeated_auth_failures_as_permanent() {
+        for msg in [
+            "Received disconnect: Too many authentication failures",
+            "Host key for example.com:22 was rejected",
+        ] {
+            assert_eq!(
+                SshErrorClassifier::classify_from_string(msg),
+                SshErrorType::Permanent
+            );
+        }
+    }
+
     #[test]
     fn test_should_retry() {
         assert!(SshErrorClassifier::should_retry(SshErrorType::Temporary));
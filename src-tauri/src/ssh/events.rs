@@ -56,6 +56,33 @@ impl ConnectionEventEmitter {
         }
     }
 
+    /// Emit a session's liveness status both on `connection:status` and on its
+    /// own `session-status:{id}` channel.
+    pub fn emit_session_status(
+        &self,
+        session_id: &str,
+        status: ConnectionStatus,
+        details: Option<&str>,
+    ) {
+        let event = ConnectionStatusEvent {
+            session_id: session_id.to_string(),
+            status,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            details: details.map(|s| s.to_string()),
+            metrics: None,
+        };
+
+        if let Err(e) = self.app_handle.emit(EVENT_CONNECTION_STATUS, &event) {
+            eprintln!("Failed to emit connection status event: {}", e);
+        }
+        if let Err(e) = self
+            .app_handle
+            .emit(&format!("session-status:{}", session_id), &event)
+        {
+            eprintln!("Failed to emit session status event: {}", e);
+        }
+    }
+
     /// Emit a connection error event
     pub fn emit_error(&self, session_id: &str, error: &str) {
         let event = ConnectionStatusEvent {
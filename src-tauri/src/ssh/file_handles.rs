@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long an unused handle stays open. Kept short because another program
+/// may replace the file (editors often save through a rename), and an open
+/// handle keeps reading the old copy.
+pub const FILE_HANDLE_TTL: Duration = Duration::from_secs(5);
+
+/// Open handles kept per connection.
+pub const MAX_FILE_HANDLES: usize = 16;
+
+/// Read counters of a connection's `FileHandleCache`, for comparing reads
+/// served from an open handle with reads that had to open the file.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandleCacheStats {
+    pub open_handles: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Total time of the reads served from a cached handle
+    pub hit_read_micros: u64,
+    /// Total time of the reads that opened the file first
+    pub miss_read_micros: u64,
+}
+
+struct CachedHandle<H> {
+    handle: H,
+    last_used: Instant,
+}
+
+/// Open remote file handles keyed by path, for repeated small reads of the
+/// same file. Handles are taken out while in use and put back afterwards.
+///
+/// Methods that drop handles hand them back to the caller instead, so closing
+/// them (a server round trip) happens after the cache lock is released.
+pub struct FileHandleCache<H> {
+    entries: HashMap<String, CachedHandle<H>>,
+    /// Bumped on every invalidation; a handle opened under an older
+    /// generation may predate a write and is not cached
+    generation: u64,
+    stats: FileHandleCacheStats,
+}
+
+impl<H> Default for FileHandleCache<H> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            generation: 0,
+            stats: FileHandleCacheStats::default(),
+        }
+    }
+}
+
+impl<H> FileHandleCache<H> {
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Remove and return the handle for `path` unless it has expired.
+    /// Expired handles, this one included, come back in the second value.
+    pub fn take(&mut self, path: &str, now: Instant) -> (Option<H>, Vec<H>) {
+        let expired = self.expire(now);
+        let handle = self.entries.remove(path).map(|cached| cached.handle);
+        (handle, expired)
+    }
+
+    /// Cache `handle` for `path`, unless the cache was invalidated since
+    /// `generation` was read. Returns whatever is no longer cached: a
+    /// replaced or least recently used handle, or `handle` itself.
+    pub fn put(&mut self, path: &str, handle: H, generation: u64, now: Instant) -> Vec<H> {
+        if generation != self.generation {
+            return vec![handle];
+        }
+        let mut dropped = self.expire(now);
+        if let Some(old) = self.entries.insert(
+            path.to_string(),
+            CachedHandle {
+                handle,
+                last_used: now,
+            },
+        ) {
+            dropped.push(old.handle);
+        }
+        while self.entries.len() > MAX_FILE_HANDLES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(path, _)| path.clone());
+            match oldest.and_then(|path| self.entries.remove(&path)) {
+                Some(cached) => dropped.push(cached.handle),
+                None => break,
+            }
+        }
+        dropped
+    }
+
+    /// Drop the handles for `path` and anything below it, for when it was
+    /// written, renamed or deleted.
+    pub fn invalidate(&mut self, path: &str) -> Vec<H> {
+        self.generation += 1;
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        let stale: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|cached| cached.as_str() == path || cached.starts_with(&dir))
+            .cloned()
+            .collect();
+        stale
+            .iter()
+            .filter_map(|path| self.entries.remove(path))
+            .map(|cached| cached.handle)
+            .collect()
+    }
+
+    /// Drop every handle, e.g. when the sessions they belong to close.
+    pub fn clear(&mut self) -> Vec<H> {
+        self.generation += 1;
+        self.entries
+            .drain()
+            .map(|(_, cached)| cached.handle)
+            .collect()
+    }
+
+    /// The first `f(handle)` that is `Some`, over the cached handles.
+    pub fn find_map<R>(&self, mut f: impl FnMut(&H) -> Option<R>) -> Option<R> {
+        self.entries.values().find_map(|cached| f(&cached.handle))
+    }
+
+    pub fn record_read(&mut self, hit: bool, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        if hit {
+            self.stats.hits += 1;
+            self.stats.hit_read_micros += micros;
+        } else {
+            self.stats.misses += 1;
+            self.stats.miss_read_micros += micros;
+        }
+    }
+
+    pub fn stats(&self) -> FileHandleCacheStats {
+        FileHandleCacheStats {
+            open_handles: self.entries.len(),
+            ..self.stats
+        }
+    }
+
+    /// Drop the handles unused for `FILE_HANDLE_TTL`.
+    pub fn expire(&mut self, now: Instant) -> Vec<H> {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, cached)| now.duration_since(cached.last_used) >= FILE_HANDLE_TTL)
+            .map(|(path, _)| path.clone())
+            .collect();
+        expired
+            .iter()
+            .filter_map(|path| self.entries.remove(path))
+            .map(|cached| cached.handle)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_fresh_handles_and_expires_old_ones() {
+        let mut cache = FileHandleCache::default();
+        let start = Instant::now();
+        assert!(cache
+            .put("/etc/hosts", 1, cache.generation(), start)
+            .is_empty());
+
+        let (handle, expired) = cache.take("/etc/hosts", start + Duration::from_secs(1));
+        assert_eq!(handle, Some(1));
+        assert!(expired.is_empty());
+
+        cache.put("/etc/hosts", 1, cache.generation(), start);
+        let (handle, expired) = cache.take("/etc/hosts", start + FILE_HANDLE_TTL);
+        assert_eq!(handle, None);
+        assert_eq!(expired, vec![1]);
+    }
+
+    #[test]
+    fn invalidation_covers_children_and_reads_in_flight() {
+        let mut cache = FileHandleCache::default();
+        let now = Instant::now();
+        cache.put("/srv/app/a.txt", 1, cache.generation(), now);
+        cache.put("/srv/app/sub/b.txt", 2, cache.generation(), now);
+        cache.put("/srv/application.log", 3, cache.generation(), now);
+
+        let in_flight = cache.generation();
+        let mut dropped = cache.invalidate("/srv/app");
+        dropped.sort();
+        assert_eq!(dropped, vec![1, 2]);
+        assert_eq!(cache.stats().open_handles, 1);
+
+        // Opened before the invalidation, so it may hold the old contents
+        assert_eq!(cache.put("/srv/app/a.txt", 4, in_flight, now), vec![4]);
+    }
+
+    #[test]
+    fn stays_bounded_by_evicting_least_recently_used() {
+        let mut cache = FileHandleCache::default();
+        let start = Instant::now();
+        for i in 0..MAX_FILE_HANDLES {
+            let at = start + Duration::from_millis(i as u64);
+            assert!(cache
+                .put(&format!("/f{}", i), i, cache.generation(), at)
+                .is_empty());
+        }
+        let at = start + Duration::from_millis(MAX_FILE_HANDLES as u64);
+        assert_eq!(cache.put("/new", 99, cache.generation(), at), vec![0]);
+        assert_eq!(cache.stats().open_handles, MAX_FILE_HANDLES);
+    }
+}
@@ -1,24 +1,37 @@
 use super::client::{AppState, ClientType};
-use super::manager::SshCommand;
+use super::manager::{SshCommand, MAX_DUPLICATE_ATTEMPTS};
 use super::wsl;
 use crate::models::FileEntry;
 use crate::models::Transfer;
-use crate::ssh::client::TransferState;
+use crate::models::{
+    AggregateTransferProgress, BatchItemError, DeleteResult, ItemCount, RemoteFileStat,
+    DEFAULT_REQUIRE_CONFIRM_THRESHOLD,
+};
+use crate::ssh::client::{
+    aggregate_transfer_progress, emit_transfers_updated, list_transfers, record_transfer_progress,
+    TransferState,
+};
 use crate::ssh::execute_ssh_operation;
+use crate::ssh::transfer_queue::acquire_transfer_slot;
+use crate::ssh::utils::{
+    get_sftp_buffer_size, get_sftp_read_buffer_size, hash_candidates, parse_remote_hash,
+    remote_hash_script, wait_while_paused, HashAlgorithm,
+};
 use crate::ssh::ExecTarget;
+use base64::{engine::general_purpose, Engine as _};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::ssh::ProgressPayload;
+use crate::ssh::{BatchProgressPayload, ProgressPayload};
 
 #[derive(Clone, serde::Serialize)]
-struct ErrorPayload {
-    id: String,
-    error: String,
+pub(crate) struct ErrorPayload {
+    pub(crate) id: String,
+    pub(crate) error: String,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -29,7 +42,7 @@ pub struct FilePageResponse {
     pub has_more: bool,
 }
 
-fn append_file_audit_event(
+pub(crate) fn append_file_audit_event(
     app_handle: &AppHandle,
     state: &State<'_, AppState>,
     session_id: &str,
@@ -53,7 +66,7 @@ fn append_file_audit_event(
     }
 }
 
-fn escape_shell_arg(value: &str) -> String {
+pub(crate) fn escape_shell_arg(value: &str) -> String {
     value.replace('\'', "'\"'\"'")
 }
 
@@ -65,7 +78,7 @@ fn normalize_wsl_dir(path: &str) -> &str {
     }
 }
 
-fn list_wsl_entries(distro: &str, path: &str) -> Result<Vec<FileEntry>, String> {
+pub(crate) fn list_wsl_entries(distro: &str, path: &str) -> Result<Vec<FileEntry>, String> {
     let normalized = normalize_wsl_dir(path).to_string();
     let script = r#"target="$1"
 cd "$target" >/dev/null 2>&1 || exit 1
@@ -116,19 +129,289 @@ find . -mindepth 1 -maxdepth 1 -printf '%P\t%y\t%s\t%T@\n'
     Ok(entries)
 }
 
+/// How many leading bytes are inspected when deciding whether a file is binary.
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryFileInfo {
+    pub is_binary: bool,
+    pub mime_hint: String,
+    pub size: usize,
+}
+
+/// Result of `read_remote_file`: text is serialized as a plain string (as
+/// before), binary files as a `BinaryFileInfo` object. With `with_stat` either
+/// is wrapped as `{ content, mtime, size }`.
+#[derive(Clone, serde::Serialize)]
+#[serde(untagged)]
+pub enum RemoteFileContent {
+    Text(String),
+    Binary(BinaryFileInfo),
+    WithStat {
+        content: Box<RemoteFileContent>,
+        #[serde(flatten)]
+        stat: RemoteFileStat,
+    },
+}
+
+/// Prefix of the error `write_remote_file` returns when the file changed on
+/// the server since it was read.
+pub(crate) const WRITE_CONFLICT: &str = "conflict";
+
+/// `WRITE_CONFLICT` error unless `current` still matches the expected stat.
+/// The size is compared too, since a change within the same second leaves a
+/// one-second mtime untouched.
+fn check_unchanged(
+    path: &str,
+    expected_mtime: Option<i64>,
+    expected_size: Option<u64>,
+    current: Option<RemoteFileStat>,
+) -> Result<(), String> {
+    let Some(stat) = current else {
+        return Err(format!(
+            "{}: {} was removed on the server",
+            WRITE_CONFLICT, path
+        ));
+    };
+    if expected_mtime.is_some_and(|mtime| mtime != stat.mtime) {
+        return Err(format!(
+            "{}: {} was modified on the server (mtime {}, expected {})",
+            WRITE_CONFLICT,
+            path,
+            stat.mtime,
+            expected_mtime.unwrap_or_default()
+        ));
+    }
+    if expected_size.is_some_and(|size| size != stat.size) {
+        return Err(format!(
+            "{}: {} was modified on the server (size {}, expected {})",
+            WRITE_CONFLICT,
+            path,
+            stat.size,
+            expected_size.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Current mtime and size of `path`, or `None` if it does not exist.
+pub(crate) async fn remote_file_stat(
+    client_type: &ClientType,
+    path: String,
+) -> Result<Option<RemoteFileStat>, String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpStat { path, listener: tx })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = r#"target="$1"
+if [ -e "$target" ]; then stat -c '%Y %s' -- "$target"; fi
+"#;
+                let output = wsl::run_bash_text(&distro, script, &[path])?;
+                let invalid = || format!("Unexpected stat output: {}", output);
+                let mut fields = output.split_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some(mtime), Some(size)) => Ok(Some(RemoteFileStat {
+                        mtime: mtime.parse().map_err(|_| invalid())?,
+                        size: size.parse().map_err(|_| invalid())?,
+                    })),
+                    _ => Ok(None),
+                }
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+/// Guess a MIME type from well-known magic bytes.
+fn detect_mime_from_magic(data: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"\xca\xfe\xba\xbe", "application/java-vm"),
+        (b"\x00asm", "application/wasm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| data.starts_with(magic)) {
+        return mime;
+    }
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    if data.len() >= 262 && &data[257..262] == b"ustar" {
+        return "application/x-tar";
+    }
+    "application/octet-stream"
+}
+
+/// Decode file bytes as UTF-8 text, or report them as binary when the leading
+/// bytes contain NULs or invalid UTF-8. `may_be_truncated` allows a multi-byte
+/// character cut off by `max_bytes` at the end of the buffer.
+fn decode_remote_content(
+    mut data: Vec<u8>,
+    may_be_truncated: bool,
+) -> Result<RemoteFileContent, String> {
+    let sniff = &data[..data.len().min(BINARY_SNIFF_BYTES)];
+    let sniff_is_binary = sniff.contains(&0)
+        || match std::str::from_utf8(sniff) {
+            Ok(_) => false,
+            // An incomplete sequence at the end of the sniff window is not proof of binary data
+            Err(e) => e.error_len().is_some(),
+        };
+
+    if !sniff_is_binary {
+        match String::from_utf8(data) {
+            Ok(text) => return Ok(RemoteFileContent::Text(text)),
+            Err(e) => {
+                let utf8_error = e.utf8_error();
+                data = e.into_bytes();
+                if may_be_truncated && utf8_error.error_len().is_none() {
+                    data.truncate(utf8_error.valid_up_to());
+                    return String::from_utf8(data)
+                        .map(RemoteFileContent::Text)
+                        .map_err(|e| format!("UTF-8 Error: {}", e));
+                }
+            }
+        }
+    }
+
+    Ok(RemoteFileContent::Binary(BinaryFileInfo {
+        is_binary: true,
+        mime_hint: detect_mime_from_magic(&data).to_string(),
+        size: data.len(),
+    }))
+}
+
+/// Share of U+FFFD replacement characters tolerated when decoding with an
+/// explicit encoding before the result is rejected as a likely mismatch.
+const MAX_REPLACEMENT_RATIO: f64 = 0.01;
+
+/// Resolve an encoding label ("gbk", "latin1", "shift_jis", ...). `None` or an
+/// empty label means UTF-8.
+fn resolve_encoding(label: Option<&str>) -> Result<&'static encoding_rs::Encoding, String> {
+    match label.map(str::trim).filter(|value| !value.is_empty()) {
+        None => Ok(encoding_rs::UTF_8),
+        Some(value) => encoding_rs::Encoding::for_label(value.as_bytes())
+            .ok_or_else(|| format!("Unsupported encoding: {}", value)),
+    }
+}
+
+/// Decode text in a non-UTF-8 encoding, rejecting output dominated by replacement characters.
+fn decode_with_encoding(
+    data: &[u8],
+    encoding: &'static encoding_rs::Encoding,
+) -> Result<RemoteFileContent, String> {
+    let is_utf16 = encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE;
+    if !is_utf16 && data[..data.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return Ok(RemoteFileContent::Binary(BinaryFileInfo {
+            is_binary: true,
+            mime_hint: detect_mime_from_magic(data).to_string(),
+            size: data.len(),
+        }));
+    }
+
+    let (text, _) = encoding.decode_without_bom_handling(data);
+    let replacements = text.chars().filter(|c| *c == '\u{FFFD}').count();
+    let total = text.chars().count().max(1);
+    if replacements as f64 / total as f64 > MAX_REPLACEMENT_RATIO {
+        return Err(format!(
+            "Failed to decode file as {}: {} invalid sequences. Try another encoding.",
+            encoding.name(),
+            replacements
+        ));
+    }
+    Ok(RemoteFileContent::Text(text.into_owned()))
+}
+
+/// Encode editor content for writing; UTF-8 passes through unchanged.
+fn encode_with_encoding(content: String, label: Option<&str>) -> Result<Vec<u8>, String> {
+    let encoding = resolve_encoding(label)?;
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(content.into_bytes());
+    }
+    let (bytes, _, had_errors) = encoding.encode(&content);
+    if had_errors {
+        return Err(format!(
+            "Content contains characters that cannot be represented in {}",
+            encoding.name()
+        ));
+    }
+    Ok(bytes.into_owned())
+}
+
 #[tauri::command]
 pub async fn read_remote_file(
     state: State<'_, AppState>,
     id: String,
     path: String,
     max_bytes: Option<u64>,
-) -> Result<String, String> {
+    encoding: Option<String>,
+    with_stat: Option<bool>,
+) -> Result<RemoteFileContent, String> {
     let client = {
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
         clients.get(&id).ok_or("Session not found")?.clone()
     };
+    let encoding = resolve_encoding(encoding.as_deref())?;
+    // Stat before reading so a change made mid-read still counts as a conflict
+    let stat = if with_stat.unwrap_or(false) {
+        Some(
+            remote_file_stat(&client.client_type, path.clone())
+                .await?
+                .ok_or_else(|| format!("File not found: {}", path))?,
+        )
+    } else {
+        None
+    };
+    let data = read_remote_bytes(&client.client_type, path, max_bytes).await?;
+    let content = if encoding == encoding_rs::UTF_8 {
+        decode_remote_content(data, max_bytes.is_some())
+    } else {
+        decode_with_encoding(&data, encoding)
+    }?;
+
+    Ok(match stat {
+        Some(stat) => RemoteFileContent::WithStat {
+            content: Box::new(content),
+            stat,
+        },
+        None => content,
+    })
+}
 
-    match &client.client_type {
+/// Raw contents of `path`, or only its first `max_bytes` bytes.
+async fn read_remote_bytes(
+    client_type: &ClientType,
+    path: String,
+    max_bytes: Option<u64>,
+) -> Result<Vec<u8>, String> {
+    match client_type {
         ClientType::Ssh(senders) => {
             let sender = senders.ops.clone();
             execute_ssh_operation(move || {
@@ -141,20 +424,17 @@ pub async fn read_remote_file(
                     })
                     .map_err(|e| format!("Failed to send command: {}", e))?;
 
-                let data = rx
-                    .recv()
-                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())??;
-
-                String::from_utf8(data).map_err(|e| format!("UTF-8 Error: {}", e))
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
             })
             .await
         }
         ClientType::Wsl(distro) => {
             let distro = distro.clone();
             tokio::task::spawn_blocking(move || {
-                let mut args = vec![path];
-                let script = if max_bytes.is_some() {
-                    args.push(max_bytes.unwrap().to_string());
+                let mut args = vec![path.clone()];
+                let script = if let Some(limit) = max_bytes {
+                    args.push(limit.to_string());
                     r#"target="$1"
 limit="$2"
 head -c "$limit" -- "$target"
@@ -166,9 +446,9 @@ cat -- "$target"
                 };
                 let output = wsl::run_bash_output(&distro, script, &args)?;
                 if output.status.success() {
-                    String::from_utf8(output.stdout).map_err(|e| e.to_string())
+                    Ok(output.stdout)
                 } else {
-                    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                    Err(wsl::read_error(&distro, &path, &output))
                 }
             })
             .await
@@ -177,6 +457,365 @@ cat -- "$target"
     }
 }
 
+/// Largest image or PDF `get_file_preview` downloads to inline as a data URL.
+const PREVIEW_MAX_MEDIA_BYTES: u64 = 10 * 1024 * 1024;
+
+/// How much of any other file `get_file_preview` reads for its text snippet.
+const PREVIEW_SNIPPET_BYTES: u64 = 64 * 1024;
+
+/// Result of `get_file_preview`, tagged by `kind`.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FilePreview {
+    /// Image or PDF inlined as a `data:` URL
+    #[serde(rename_all = "camelCase")]
+    Media {
+        mime: String,
+        data_url: String,
+        size: u64,
+    },
+    /// Image or PDF over `PREVIEW_MAX_MEDIA_BYTES`; nothing was downloaded
+    #[serde(rename_all = "camelCase")]
+    TooLarge { mime: String, size: u64, limit: u64 },
+    /// Leading `PREVIEW_SNIPPET_BYTES` of any other file, decoded like
+    /// `read_remote_file` does
+    #[serde(rename_all = "camelCase")]
+    Snippet {
+        content: RemoteFileContent,
+        size: u64,
+        truncated: bool,
+    },
+}
+
+/// MIME type of previewable media, judged by file extension.
+fn preview_mime_from_extension(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        "ico" => Some("image/x-icon"),
+        "svg" => Some("image/svg+xml"),
+        "pdf" => Some("application/pdf"),
+        _ => None,
+    }
+}
+
+/// Whether `data` starts the way a `mime` file from
+/// `preview_mime_from_extension` should. BMP, ICO and SVG signatures are too
+/// weak to sniff on their own, so they only confirm an extension.
+fn preview_magic_matches(mime: &str, data: &[u8]) -> bool {
+    match mime {
+        "image/bmp" => data.starts_with(b"BM"),
+        "image/x-icon" => data.starts_with(b"\x00\x00\x01\x00"),
+        "image/svg+xml" => data[..data.len().min(1024)]
+            .windows(4)
+            .any(|window| window == b"<svg"),
+        _ => detect_mime_from_magic(data) == mime,
+    }
+}
+
+/// MIME type of previewable media recognized from magic bytes alone.
+fn sniff_preview_mime(data: &[u8]) -> Option<&'static str> {
+    match detect_mime_from_magic(data) {
+        mime @ ("image/png" | "image/jpeg" | "image/gif" | "image/webp" | "application/pdf") => {
+            Some(mime)
+        }
+        _ => None,
+    }
+}
+
+/// Preview a remote file for the file manager. Images and PDFs up to
+/// `PREVIEW_MAX_MEDIA_BYTES` come back as a data URL; anything else as a text
+/// snippet (or binary info) from its first `PREVIEW_SNIPPET_BYTES`. The type is
+/// taken from the extension but only trusted when the magic bytes agree.
+#[tauri::command]
+pub async fn get_file_preview(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<FilePreview, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let size = remote_file_stat(&client.client_type, path.clone())
+        .await?
+        .ok_or_else(|| format!("File not found: {}", path))?
+        .size;
+    let media_fits = size <= PREVIEW_MAX_MEDIA_BYTES;
+
+    let extension_mime = preview_mime_from_extension(&path);
+    let data = match extension_mime {
+        Some(mime) if !media_fits => {
+            return Ok(FilePreview::TooLarge {
+                mime: mime.to_string(),
+                size,
+                limit: PREVIEW_MAX_MEDIA_BYTES,
+            })
+        }
+        Some(_) => {
+            read_remote_bytes(&client.client_type, path, Some(PREVIEW_MAX_MEDIA_BYTES)).await?
+        }
+        None => {
+            let head = read_remote_bytes(
+                &client.client_type,
+                path.clone(),
+                Some(PREVIEW_SNIPPET_BYTES),
+            )
+            .await?;
+            // Media without a telling extension: fetch the rest if it fits
+            if media_fits && (head.len() as u64) < size && sniff_preview_mime(&head).is_some() {
+                read_remote_bytes(&client.client_type, path, Some(PREVIEW_MAX_MEDIA_BYTES)).await?
+            } else {
+                head
+            }
+        }
+    };
+
+    // A misnamed file is previewed by what its magic bytes say it is
+    let mime = match extension_mime {
+        Some(mime) if preview_magic_matches(mime, &data) => Some(mime),
+        _ => sniff_preview_mime(&data),
+    };
+    if let Some(mime) = mime.filter(|_| media_fits) {
+        return Ok(FilePreview::Media {
+            mime: mime.to_string(),
+            data_url: format!(
+                "data:{};base64,{}",
+                mime,
+                general_purpose::STANDARD.encode(&data)
+            ),
+            size,
+        });
+    }
+
+    let mut data = data;
+    data.truncate(PREVIEW_SNIPPET_BYTES as usize);
+    let truncated = (data.len() as u64) < size;
+    Ok(FilePreview::Snippet {
+        content: decode_remote_content(data, truncated)?,
+        size,
+        truncated,
+    })
+}
+
+/// Reduce a remote file name to a single safe path component, so joining it
+/// onto the temp dir can never climb out of it.
+pub(crate) fn sanitize_temp_file_name(remote_name: &str) -> String {
+    let last = remote_name
+        .rsplit(['/', '\\'])
+        .find(|part| !part.is_empty())
+        .unwrap_or("");
+    let cleaned: String = last
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Windows drops trailing dots and spaces, which would turn ".." back into a parent dir
+    let cleaned = cleaned.trim_end_matches(['.', ' ']);
+    if cleaned.is_empty() {
+        "download".to_string()
+    } else if is_windows_device_name(cleaned) {
+        format!("_{}", cleaned)
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Names Windows maps to devices whatever the extension, e.g. `NUL.txt`.
+fn is_windows_device_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or("").trim_end();
+    let upper = stem.to_ascii_uppercase();
+    matches!(upper.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.len() == 4
+            && matches!(upper.as_bytes()[3], b'1'..=b'9'))
+}
+
+/// Temp copies opened by `download_temp_and_open` older than this are
+/// removed on the next open; by then the application has long read them.
+const TEMP_OPEN_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Remove the per-download directories under `root` last modified more than
+/// `max_age` ago. Best effort: a file still open elsewhere may refuse to go.
+fn prune_temp_open_dirs(root: &Path, max_age: std::time::Duration) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= max_age);
+        if stale {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Stream `remote_path` into `local_path` through the regular SFTP download,
+/// so large files are never held in memory.
+async fn download_to_temp(
+    app: &AppHandle,
+    client_type: &ClientType,
+    remote_path: String,
+    local_path: &Path,
+) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let app_handle = app.clone();
+            let local_path = local_path.to_string_lossy().to_string();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpDownload {
+                        remote_path,
+                        local_path,
+                        transfer_id: format!("open-{}", uuid::Uuid::new_v4()),
+                        app_handle,
+                        listener: tx,
+                        cancel_flag: Arc::new(AtomicBool::new(false)),
+                        pause_flag: Arc::new(AtomicBool::new(false)),
+                        rate_limit: None,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+                recv_transfer_result(rx)
+                    .unwrap_or_else(|| Err("Download ended without a result".to_string()))
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let source = wsl::to_windows_path(distro, &remote_path)?;
+            let target = local_path.to_path_buf();
+            tokio::task::spawn_blocking(move || std::fs::copy(&source, &target))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+                .map_err(|e| format!("Failed to copy {}: {}", remote_path, e))?;
+            Ok(())
+        }
+    }
+}
+
+/// Download a remote file into a fresh temp directory and open it with the
+/// OS default application. Returns the local temp path.
+#[tauri::command]
+pub async fn download_temp_and_open(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    remote_path: String,
+    remote_name: String,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    // One directory per download keeps the original name without clobbering
+    // a copy that is still open from an earlier double-click
+    let root = std::env::temp_dir().join("ssh-assistant-open");
+    prune_temp_open_dirs(&root, TEMP_OPEN_MAX_AGE);
+    let temp_dir = root.join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+    let local_path = temp_dir.join(sanitize_temp_file_name(&remote_name));
+
+    if let Err(e) = download_to_temp(&app, &client.client_type, remote_path, &local_path).await {
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        return Err(e);
+    }
+
+    tauri_plugin_opener::open_path(&local_path, None::<&str>)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+
+    Ok(local_path.to_string_lossy().to_string())
+}
+
+/// Upper bound for a single `read_remote_file_range` call.
+const MAX_RANGE_READ_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Read `length` bytes starting at `offset`, returned base64-encoded since the
+/// range may cut through multi-byte characters or contain binary data.
+#[tauri::command]
+pub async fn read_remote_file_range(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    if length > MAX_RANGE_READ_BYTES {
+        return Err(format!(
+            "Requested range too large: {} bytes (max {})",
+            length, MAX_RANGE_READ_BYTES
+        ));
+    }
+
+    let data = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpReadRange {
+                        path,
+                        offset,
+                        length: length as usize,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                // tail -c +N seeks directly on regular files, so large offsets stay cheap
+                let script = r#"target="$1"
+start="$2"
+limit="$3"
+tail -c +"$start" -- "$target" | head -c "$limit"
+"#;
+                let args = vec![path.clone(), (offset + 1).to_string(), length.to_string()];
+                let output = wsl::run_bash_output(&distro, script, &args)?;
+                if output.status.success() {
+                    Ok(output.stdout)
+                } else {
+                    Err(wsl::read_error(&distro, &path, &output))
+                }
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??
+        }
+    };
+
+    Ok(general_purpose::STANDARD.encode(data))
+}
+
+/// `mode` is "overwrite" (the default), "append", or "atomic", which writes a
+/// temp file beside `path` and renames it over the original on success.
+///
+/// With `expected_mtime` and `expected_size` (from `read_remote_file` with
+/// `with_stat`) the write is refused with a `WRITE_CONFLICT` error if the file
+/// was changed or removed on the server since. Returns the file's stat after
+/// writing, to use as the next expected values.
 #[tauri::command]
 pub async fn write_remote_file(
     app_handle: AppHandle,
@@ -185,12 +824,21 @@ pub async fn write_remote_file(
     path: String,
     content: String,
     mode: Option<String>,
-) -> Result<(), String> {
+    encoding: Option<String>,
+    expected_mtime: Option<i64>,
+    expected_size: Option<u64>,
+) -> Result<Option<RemoteFileStat>, String> {
     let client = {
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
         clients.get(&id).ok_or("Session not found")?.clone()
     };
     let audit_path = path.clone();
+    let content = encode_with_encoding(content, encoding.as_deref())?;
+
+    if expected_mtime.is_some() || expected_size.is_some() {
+        let current = remote_file_stat(&client.client_type, path.clone()).await?;
+        check_unchanged(&path, expected_mtime, expected_size, current)?;
+    }
 
     let result = match &client.client_type {
         ClientType::Ssh(senders) => {
@@ -201,13 +849,10 @@ pub async fn write_remote_file(
             execute_ssh_operation(move || {
                 let (tx, rx) = std::sync::mpsc::channel();
 
-                // Convert content to bytes
-                let content_bytes = command_content.into_bytes();
-
                 sender
                     .send(SshCommand::SftpWrite {
                         path: command_path,
-                        content: content_bytes,
+                        content: command_content,
                         mode: command_mode,
                         listener: tx,
                     })
@@ -227,6 +872,13 @@ pub async fn write_remote_file(
                 let script = if wsl_mode.as_deref() == Some("append") {
                     r#"target="$1"
 cat >> "$target"
+"#
+                } else if wsl_mode.as_deref() == Some("atomic") {
+                    r#"target="$1"
+tmp="$target.tmp.$$"
+cat > "$tmp" || { rm -f "$tmp"; exit 1; }
+[ ! -e "$target" ] || chmod --reference="$target" "$tmp"
+mv -f "$tmp" "$target" || { rm -f "$tmp"; exit 1; }
 "#
                 } else {
                     r#"target="$1"
@@ -243,15 +895,13 @@ cat > "$target"
                     std::process::Stdio::piped(),
                 )?;
                 if let Some(stdin) = child.stdin.as_mut() {
-                    stdin
-                        .write_all(wsl_content.as_bytes())
-                        .map_err(|e| e.to_string())?;
+                    stdin.write_all(&wsl_content).map_err(|e| e.to_string())?;
                 }
                 let output = child.wait_with_output().map_err(|e| e.to_string())?;
                 if output.status.success() {
                     Ok(())
                 } else {
-                    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                    Err(wsl::decode_wsl_output(&output.stderr).trim().to_string())
                 }
             })
             .await
@@ -271,11 +921,96 @@ cat > "$target"
         );
     }
 
-    result
+    result?;
+    remote_file_stat(&client.client_type, path).await
+}
+
+/// Sender for the `cat`/`tee` commands, which only make sense on SSH: WSL
+/// files are always read and written through the shell already.
+fn exec_fallback_sender(
+    state: &State<'_, AppState>,
+    id: &str,
+) -> Result<std::sync::mpsc::Sender<SshCommand>, String> {
+    let clients = state.clients.lock().map_err(|e| e.to_string())?;
+    match &clients.get(id).ok_or("Session not found")?.client_type {
+        ClientType::Ssh(senders) => Ok(senders.ops.clone()),
+        ClientType::Wsl(_) => Err("The cat/tee fallback is only for SSH sessions".to_string()),
+    }
+}
+
+/// `read_remote_file` through `cat` on an exec channel, for servers with
+/// SFTP disabled. Limited to `scp::EXEC_FILE_MAX_BYTES`; binary files come
+/// back as `BinaryFileInfo` like they do from `read_remote_file`.
+#[tauri::command]
+pub async fn read_remote_file_via_cat(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    max_bytes: Option<u64>,
+) -> Result<RemoteFileContent, String> {
+    let sender = exec_fallback_sender(&state, &id)?;
+    let data = execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::CatRead {
+                path,
+                max_len: max_bytes.map(|n| n as usize),
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await?;
+    decode_remote_content(data, max_bytes.is_some())
+}
+
+/// Overwrite a file by piping `content` into `tee` on an exec channel, for
+/// servers with SFTP disabled. Text only, up to `scp::EXEC_FILE_MAX_BYTES`.
+#[tauri::command]
+pub async fn write_remote_file_via_tee(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    if content.contains('\0') {
+        return Err("Refusing to write binary content through tee".to_string());
+    }
+    let sender = exec_fallback_sender(&state, &id)?;
+    let command_path = path.clone();
+    execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::TeeWrite {
+                path: command_path,
+                content: content.into_bytes(),
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await?;
+
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.written",
+        "Wrote remote file",
+        Some(path.as_str()),
+        "warning",
+    );
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn list_files(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     id: String,
     path: String,
@@ -284,8 +1019,9 @@ pub async fn list_files(
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
         clients.get(&id).ok_or("Session not found")?.clone()
     };
+    let visited_path = path.clone();
 
-    match &client.client_type {
+    let entries = match &client.client_type {
         ClientType::Ssh(senders) => {
             let sender = senders.ops.clone();
             execute_ssh_operation(move || {
@@ -302,10 +1038,19 @@ pub async fn list_files(
         ClientType::Wsl(distro) => {
             let distro = distro.clone();
             tokio::task::spawn_blocking(move || list_wsl_entries(&distro, &path))
-            .await
-            .map_err(|e| format!("Task join error: {}", e))?
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }?;
+
+    // Feed the per-connection recent directories list
+    if let Some(connection_id) = client.connection_id {
+        if let Err(e) = crate::db::record_recent_path(&app_handle, connection_id, &visited_path) {
+            eprintln!("[FileOps] Failed to record recent path: {}", e);
         }
     }
+
+    Ok(entries)
 }
 
 #[tauri::command]
@@ -428,13 +1173,195 @@ pub async fn create_directory(
     result
 }
 
+/// Create `path` along with any missing parents, like `mkdir -p`, and return
+/// it. An existing directory is not an error.
 #[tauri::command]
-pub async fn create_file(
+pub async fn create_directory_recursive(
     app_handle: AppHandle,
     state: State<'_, AppState>,
     id: String,
     path: String,
-) -> Result<(), String> {
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let audit_path = path.clone();
+
+    let result = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let command_path = path.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpMkdirAll {
+                        path: command_path,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                wsl::run_bash_text(&distro, r#"mkdir -p -- "$1""#, &[path.clone()])?;
+                Ok(path)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    if result.is_ok() {
+        append_file_audit_event(
+            &app_handle,
+            &state,
+            &id,
+            "file.directoryCreated",
+            "Created remote directory",
+            Some(audit_path.as_str()),
+            "warning",
+        );
+    }
+
+    result
+}
+
+/// Copy a single file on the server to `<path>.bak`, or `<path>.bak.N` when
+/// that is taken, without a local round trip. Returns the new path.
+#[tauri::command]
+pub async fn duplicate_file(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let result = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let command_path = path.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpDuplicate {
+                        path: command_path,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = r#"src="$1"
+max="$2"
+[ -f "$src" ] || { echo "$src is not a regular file" >&2; exit 1; }
+dest="$src.bak"
+n=1
+while [ -e "$dest" ]; do
+  [ "$n" -lt "$max" ] || { echo "No free name to duplicate $src to" >&2; exit 1; }
+  dest="$src.bak.$n"
+  n=$((n + 1))
+done
+cp -p -- "$src" "$dest" && printf '%s' "$dest"
+"#;
+                let args = [path, MAX_DUPLICATE_ATTEMPTS.to_string()];
+                wsl::run_bash_text(&distro, script, &args)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    if let Ok(dest) = &result {
+        append_file_audit_event(
+            &app_handle,
+            &state,
+            &id,
+            "file.duplicated",
+            "Duplicated remote file",
+            Some(format!("{} -> {}", path, dest).as_str()),
+            "warning",
+        );
+    }
+
+    result
+}
+
+/// Like `touch`: set `path`'s access and modification time to `time` (Unix
+/// seconds, default now), creating an empty file if it does not exist.
+#[tauri::command]
+pub async fn touch_file(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    time: Option<u64>,
+) -> Result<(), String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let time = match time {
+        Some(time) => time,
+        None => SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs(),
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpTouch {
+                        path,
+                        time,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                // The file lives inside the distro, so use its own touch
+                let script = r#"touch -d "@$2" -- "$1""#;
+                wsl::run_bash_text(&distro, script, &[path, time.to_string()]).map(|_| ())
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn create_file(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<(), String> {
     let client = {
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
         clients.get(&id).ok_or("Session not found")?.clone()
@@ -486,6 +1413,117 @@ pub async fn create_file(
     result
 }
 
+/// Whether the session user can create files in directory `path`, found by
+/// creating and deleting a small probe file rather than reading mode bits.
+#[tauri::command]
+pub async fn check_writable(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<bool, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpCheckWritable { path, listener: tx })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                // noclobber makes the redirect fail rather than truncate
+                let script = r#"[ -d "$1" ] || { echo "$1 is not a directory" >&2; exit 1; }
+probe="${1%/}/.ssh-assistant-write-test-$2"
+if (set -C; : > "$probe") 2>/dev/null; then rm -f -- "$probe"; echo yes; else echo no; fi"#;
+                let args = [path, uuid::Uuid::new_v4().to_string()];
+                wsl::run_bash_text(&distro, script, &args).map(|out| out == "yes")
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+/// Count the files and bytes under `path`, which may be a file or directory.
+/// Over SFTP, counting stops once more than `limit` files were seen and the
+/// result is marked truncated.
+#[tauri::command]
+pub async fn count_items(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    limit: Option<u64>,
+) -> Result<ItemCount, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpCountItems {
+                        path,
+                        limit,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                let escaped = escape_shell_arg(&path);
+                let output = wsl::run_bash_text(
+                    &distro,
+                    &format!(
+                        "find '{p}' -type f 2>/dev/null | wc -l; find '{p}' -mindepth 1 -type d 2>/dev/null | wc -l; du -sb '{p}' 2>/dev/null | cut -f1",
+                        p = escaped
+                    ),
+                    &[],
+                )?;
+                let mut numbers = output.lines().map(|line| line.trim().parse::<u64>());
+                let mut next = || {
+                    numbers
+                        .next()
+                        .and_then(|n| n.ok())
+                        .ok_or_else(|| format!("Unexpected count output: {}", output))
+                };
+                Ok(ItemCount {
+                    file_count: next()?,
+                    dir_count: next()?,
+                    total_size: next()?,
+                    truncated: false,
+                })
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+/// Delete a remote item. Directories holding more files than the
+/// `require_confirm_threshold` setting are left alone and reported as
+/// "confirmation-required"; `delete_item_confirmed` then deletes them.
 #[tauri::command]
 pub async fn delete_item(
     app_handle: AppHandle,
@@ -493,6 +1531,38 @@ pub async fn delete_item(
     id: String,
     path: String,
     is_dir: bool,
+) -> Result<DeleteResult, String> {
+    let threshold = crate::db::get_settings(app_handle.clone())
+        .map(|s| s.file_manager.require_confirm_threshold)
+        .unwrap_or(DEFAULT_REQUIRE_CONFIRM_THRESHOLD);
+    let counts = if is_dir && threshold > 0 {
+        let counts = count_items(state.clone(), id.clone(), path.clone(), Some(threshold)).await?;
+        if counts.truncated || counts.file_count > threshold {
+            return Ok(DeleteResult {
+                status: "confirmation-required".to_string(),
+                counts,
+            });
+        }
+        counts
+    } else {
+        ItemCount::default()
+    };
+
+    delete_item_confirmed(app_handle, state, id, path, is_dir).await?;
+    Ok(DeleteResult {
+        status: "deleted".to_string(),
+        counts,
+    })
+}
+
+/// Delete a remote item without the size guard, after the user confirmed.
+#[tauri::command]
+pub async fn delete_item_confirmed(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    is_dir: bool,
 ) -> Result<(), String> {
     let client = {
         let clients = state.clients.lock().map_err(|e| e.to_string())?;
@@ -619,6 +1689,343 @@ pub async fn rename_item(
     result
 }
 
+/// Run a bash script once per path on WSL, mirroring the progress and error
+/// collection of the SFTP batch helpers.
+fn run_wsl_batch(
+    app_handle: &AppHandle,
+    distro: &str,
+    script: &str,
+    paths: &[String],
+    extra_args: &[String],
+    batch_id: &str,
+) -> Vec<BatchItemError> {
+    let total = paths.len();
+    let mut errors = Vec::new();
+    for (index, path) in paths.iter().enumerate() {
+        let mut args = vec![path.clone()];
+        args.extend_from_slice(extra_args);
+        if let Err(error) = wsl::run_bash_text(distro, script, &args) {
+            errors.push(BatchItemError {
+                path: path.clone(),
+                error,
+            });
+        }
+        let _ = app_handle.emit(
+            "batch-progress",
+            BatchProgressPayload {
+                id: batch_id.to_string(),
+                completed: index + 1,
+                total,
+            },
+        );
+    }
+    errors
+}
+
+/// Delete many files or directories in one call. Per-item failures are
+/// returned instead of aborting the batch.
+#[tauri::command]
+pub async fn batch_delete(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    batch_id: String,
+    paths: Vec<String>,
+) -> Result<Vec<BatchItemError>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let total = paths.len();
+
+    let errors = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let app = app_handle.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpBatchDelete {
+                        paths,
+                        batch_id,
+                        app_handle: app,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let app = app_handle.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = r#"rm -rf -- "$1""#;
+                run_wsl_batch(&app, &distro, script, &paths, &[], &batch_id)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.batchDeleted",
+        "Deleted remote items",
+        Some(format!("{} of {} items deleted", total - errors.len(), total).as_str()),
+        "warning",
+    );
+
+    Ok(errors)
+}
+
+/// Move many files or directories into `target_dir` in one call. Per-item
+/// failures are returned instead of aborting the batch.
+#[tauri::command]
+pub async fn batch_move(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    batch_id: String,
+    paths: Vec<String>,
+    target_dir: String,
+) -> Result<Vec<BatchItemError>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let total = paths.len();
+    let audit_target = target_dir.clone();
+
+    let errors = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let app = app_handle.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpBatchMove {
+                        paths,
+                        target_dir,
+                        batch_id,
+                        app_handle: app,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let app = app_handle.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = r#"mv -t "$2" -- "$1""#;
+                run_wsl_batch(&app, &distro, script, &paths, &[target_dir], &batch_id)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.batchMoved",
+        "Moved remote items",
+        Some(
+            format!(
+                "{} of {} items moved to {}",
+                total - errors.len(),
+                total,
+                audit_target
+            )
+            .as_str(),
+        ),
+        "warning",
+    );
+
+    Ok(errors)
+}
+
+/// Set the permissions of many files or directories in one call, like
+/// `chmod [-R]`. Per-item failures are returned instead of aborting the batch.
+#[tauri::command]
+pub async fn batch_chmod(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    batch_id: String,
+    paths: Vec<String>,
+    mode: u32,
+    recursive: Option<bool>,
+) -> Result<Vec<BatchItemError>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let total = paths.len();
+    let recursive = recursive.unwrap_or(false);
+
+    let errors = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let app = app_handle.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpBatchChmod {
+                        paths,
+                        mode,
+                        recursive,
+                        batch_id,
+                        app_handle: app,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let app = app_handle.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = if recursive {
+                    r#"chmod -R "$2" -- "$1""#
+                } else {
+                    r#"chmod "$2" -- "$1""#
+                };
+                let octal = format!("{:o}", mode);
+                run_wsl_batch(&app, &distro, script, &paths, &[octal], &batch_id)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.batchPermissionsChanged",
+        "Changed remote permissions",
+        Some(
+            format!(
+                "{} of {} items set to {:o}{}",
+                total - errors.len(),
+                total,
+                mode,
+                if recursive { " recursively" } else { "" }
+            )
+            .as_str(),
+        ),
+        "warning",
+    );
+
+    Ok(errors)
+}
+
+/// Set the numeric owner and/or group of many files or directories in one
+/// call, like `chown [-R] uid:gid`. Whichever of the two is omitted is left
+/// unchanged. Per-item failures are returned instead of aborting the batch.
+#[tauri::command]
+pub async fn batch_chown(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    batch_id: String,
+    paths: Vec<String>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    recursive: Option<bool>,
+) -> Result<Vec<BatchItemError>, String> {
+    if uid.is_none() && gid.is_none() {
+        return Err("Either an owner or a group is required".to_string());
+    }
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let total = paths.len();
+    let recursive = recursive.unwrap_or(false);
+    let owner = format!(
+        "{}{}",
+        uid.map(|uid| uid.to_string()).unwrap_or_default(),
+        gid.map(|gid| format!(":{}", gid)).unwrap_or_default()
+    );
+
+    let errors = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let app = app_handle.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpBatchChown {
+                        paths,
+                        uid,
+                        gid,
+                        recursive,
+                        batch_id,
+                        app_handle: app,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let app = app_handle.clone();
+            let owner = owner.clone();
+            tokio::task::spawn_blocking(move || {
+                let script = if recursive {
+                    r#"chown -R "$2" -- "$1""#
+                } else {
+                    r#"chown "$2" -- "$1""#
+                };
+                run_wsl_batch(&app, &distro, script, &paths, &[owner], &batch_id)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    };
+
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.batchOwnerChanged",
+        "Changed remote ownership",
+        Some(
+            format!(
+                "{} of {} items set to {}{}",
+                total - errors.len(),
+                total,
+                owner,
+                if recursive { " recursively" } else { "" }
+            )
+            .as_str(),
+        ),
+        "warning",
+    );
+
+    Ok(errors)
+}
+
 #[tauri::command]
 pub async fn change_file_permission(
     state: State<'_, AppState>,
@@ -665,24 +2072,112 @@ pub async fn change_file_permission(
 
 #[tauri::command]
 pub async fn get_transfers(state: State<'_, AppState>) -> Result<Vec<Transfer>, String> {
-    let transfers_map = state.transfers.lock().map_err(|e| e.to_string())?;
-    let mut transfers = Vec::new();
-    for state in transfers_map.values() {
-        let transfer = state.data.lock().map_err(|e| e.to_string())?;
-        transfers.push(transfer.clone());
-    }
-    // Sort by created_at DESC
-    transfers.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-    Ok(transfers)
+    list_transfers(&state)
+}
+
+/// Combined progress, rate and ETA across unfinished transfers; also pushed
+/// as `aggregate-progress` whenever the transfer list changes.
+#[tauri::command]
+pub async fn get_aggregate_transfer_progress(
+    state: State<'_, AppState>,
+) -> Result<AggregateTransferProgress, String> {
+    aggregate_transfer_progress(&state)
 }
 
 #[tauri::command]
-pub async fn remove_transfer(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let mut transfers = state.transfers.lock().map_err(|e| e.to_string())?;
-    transfers.remove(&id);
+pub async fn remove_transfer(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let removed = state
+        .transfers
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&id);
+    if removed.is_some() {
+        emit_transfers_updated(&app);
+    }
     Ok(())
 }
 
+/// Hash a remote file with `algorithm`, or with sha256 falling back to md5
+/// where the server has no sha256 tool.
+pub(crate) fn remote_file_hash(
+    client_type: &ClientType,
+    remote_path: &str,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<String, String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let (tx, rx) = std::sync::mpsc::channel();
+            senders
+                .ops
+                .send(SshCommand::RemoteFileHash {
+                    path: remote_path.to_string(),
+                    algorithm,
+                    listener: tx,
+                })
+                .map_err(|e| format!("Failed to send command: {}", e))?;
+            rx.recv()
+                .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+        }
+        ClientType::Wsl(distro) => {
+            let algorithms = hash_candidates(algorithm);
+            let output =
+                wsl::run_bash_output(distro, &remote_hash_script(&algorithms, remote_path), &[])?;
+            parse_remote_hash(
+                &algorithms,
+                remote_path,
+                &wsl::decode_wsl_output(&output.stdout),
+                output.status.code().unwrap_or(-1),
+            )
+        }
+    }
+}
+
+/// Re-read both copies of a completed transfer and compare their hashes. A
+/// mismatch, or a failure to hash either side, marks it "verify-failed".
+fn verify_transfer(transfer: &TransferState, app: &AppHandle, client_type: &ClientType) {
+    let (local_path, remote_path) = match transfer.data.lock() {
+        Ok(data) => (data.local_path.clone(), data.remote_path.clone()),
+        Err(_) => return,
+    };
+    let hashes = remote_file_hash(client_type, &remote_path, None).and_then(|remote| {
+        let local =
+            crate::ssh::utils::compute_local_file_hash_like(Path::new(&local_path), &remote)?;
+        Ok((local, remote))
+    });
+
+    transfer.update(app, |data| match hashes {
+        Ok((local, remote)) => {
+            if local != remote {
+                data.status = "verify-failed".to_string();
+                data.error = Some(format!(
+                    "Checksum mismatch: local {} != remote {}",
+                    local, remote
+                ));
+            }
+            data.local_hash = Some(local);
+            data.remote_hash = Some(remote);
+        }
+        Err(e) => {
+            data.status = "verify-failed".to_string();
+            data.error = Some(format!("Verification failed: {}", e));
+        }
+    });
+}
+
+/// Wait for the manager's transfer result, however long the transfer runs.
+/// A stalled transfer is failed by the manager's transfer loop, which gives
+/// up after repeated `WouldBlock`s, and that error arrives here. `None` means
+/// the manager dropped the transfer without a reply.
+pub(crate) fn recv_transfer_result(
+    rx: std::sync::mpsc::Receiver<Result<(), String>>,
+) -> Option<Result<(), String>> {
+    rx.recv().ok()
+}
+
 #[tauri::command]
 pub async fn download_file(
     app: AppHandle,
@@ -691,6 +2186,8 @@ pub async fn download_file(
     transfer_id: String,
     remote_path: String,
     local_path: String,
+    rate_limit: Option<u64>,
+    verify: Option<bool>,
 ) -> Result<String, String> {
     eprintln!(
         "[DEBUG] download_file called: id={}, transfer_id={}, remote_path={}, local_path={}",
@@ -703,6 +2200,8 @@ pub async fn download_file(
     };
 
     let cancel_flag = Arc::new(AtomicBool::new(false));
+    let verify = verify.unwrap_or(false);
+    let verify_client = client.client_type.clone();
 
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -727,17 +2226,23 @@ pub async fn download_file(
         transferred: 0,
         created_at: now,
         error: None,
+        local_hash: None,
+        remote_hash: None,
     };
 
     let transfer_state = Arc::new(TransferState {
         data: Mutex::new(transfer),
         cancel_flag: cancel_flag.clone(),
+        pause_flag: Arc::new(AtomicBool::new(false)),
+        progress_offset: AtomicU64::new(0),
     });
 
     {
         let mut transfers = state.transfers.lock().map_err(|e| e.to_string())?;
         transfers.insert(transfer_id.clone(), transfer_state.clone());
     }
+    state.transfer_queue.enqueue(&transfer_id);
+    emit_transfers_updated(&app);
 
     let t_id_ssh = transfer_id.clone();
     let t_id_wsl = transfer_id.clone();
@@ -750,16 +2255,16 @@ pub async fn download_file(
             let sender = senders.ops.clone();
             let app_handle = app.clone();
             let cancel_flag = transfer_state_ssh.cancel_flag.clone();
+            let pause_flag = transfer_state_ssh.pause_flag.clone();
             let transfer_id = t_id_ssh;
 
-            // Set status to running
-            {
-                let mut data = transfer_state_ssh.data.lock().unwrap();
-                data.status = "running".to_string();
-            }
-
             let tid_spawn = transfer_id.clone();
             tokio::spawn(async move {
+                let Some(_slot) =
+                    acquire_transfer_slot(&app, &transfer_state_ssh, &tid_spawn).await
+                else {
+                    return;
+                };
                 let (tx, rx) = std::sync::mpsc::channel();
                 let res = sender.send(SshCommand::SftpDownload {
                     remote_path,
@@ -768,6 +2273,8 @@ pub async fn download_file(
                     app_handle,
                     listener: tx,
                     cancel_flag,
+                    pause_flag: pause_flag.clone(),
+                    rate_limit,
                 });
 
                 if let Err(e) = res {
@@ -781,23 +2288,29 @@ pub async fn download_file(
                     return;
                 }
 
-                let recv_result = tokio::task::spawn_blocking(move || {
-                    rx.recv_timeout(std::time::Duration::from_secs(600)).ok()
-                })
-                .await
-                .ok()
-                .flatten();
+                let recv_result = tokio::task::spawn_blocking(move || recv_transfer_result(rx))
+                    .await
+                    .ok()
+                    .flatten();
 
                 match recv_result {
                     Some(Ok(_)) => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "completed".to_string();
-                        data.transferred = data.total_size;
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "completed".to_string();
+                            data.transferred = data.total_size;
+                        });
+                        if verify {
+                            let _ = tokio::task::spawn_blocking(move || {
+                                verify_transfer(&transfer_state_ssh, &app, &verify_client)
+                            })
+                            .await;
+                        }
                     }
                     Some(Err(e)) => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "error".to_string();
-                        data.error = Some(e.clone());
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "error".to_string();
+                            data.error = Some(e.clone());
+                        });
                         let _ = app.emit(
                             "transfer-error",
                             ErrorPayload {
@@ -807,14 +2320,15 @@ pub async fn download_file(
                         );
                     }
                     None => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "error".to_string();
-                        data.error = Some("Download timeout or channel closed".to_string());
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "error".to_string();
+                            data.error = Some("Download ended without a result".to_string());
+                        });
                         let _ = app.emit(
                             "transfer-error",
                             ErrorPayload {
                                 id: tid_spawn.clone(),
-                                error: "Download timeout or channel closed".to_string(),
+                                error: "Download ended without a result".to_string(),
                             },
                         );
                     }
@@ -828,99 +2342,30 @@ pub async fn download_file(
             let distro = distro.clone();
             tokio::task::spawn_blocking(move || {
                 let current_transfer_id = t_id_wsl;
-                {
-                    let mut data = transfer_state_wsl.data.lock().unwrap();
-                    data.status = "running".to_string();
-                }
-
-                let escaped_remote = escape_shell_arg(&remote_path);
-                let total_size = wsl::run_bash_text(
-                    &distro,
-                    &format!("stat -c %s '{}'", escaped_remote),
-                    &[],
-                )
-                .ok()
-                .and_then(|value| value.trim().parse::<u64>().ok())
-                .unwrap_or(0);
-                {
-                    let mut data = transfer_state_wsl.data.lock().unwrap();
-                    data.total_size = total_size;
-                }
+                let Some(_slot) = tokio::runtime::Handle::current().block_on(
+                    acquire_transfer_slot(&app, &transfer_state_wsl, &current_transfer_id),
+                ) else {
+                    return Ok(());
+                };
 
-                let mut remote = wsl::spawn_bash(
+                let transferred = wsl_download_file(
                     &distro,
-                    &format!("cat '{}'", escaped_remote),
-                    &[],
-                    std::process::Stdio::null(),
-                    std::process::Stdio::piped(),
-                    std::process::Stdio::piped(),
+                    &remote_path,
+                    &local_path,
+                    &transfer_state_wsl,
+                    &app,
+                    &current_transfer_id,
                 )?;
-                let mut remote_stdout = remote
-                    .stdout
-                    .take()
-                    .ok_or("Failed to capture WSL download stdout".to_string())?;
-                let mut local = std::fs::File::create(&local_path).map_err(|e| e.to_string())?;
-
-                let mut buffer = [0u8; 8192];
-                let mut transferred = 0u64;
-                let mut last_emit = std::time::Instant::now();
-
-                loop {
-                    if cancel_flag.load(Ordering::Relaxed) {
-                        {
-                            let mut data = transfer_state_wsl.data.lock().unwrap();
-                            data.status = "cancelled".to_string();
-                        }
-                        return Err("Download cancelled".to_string());
-                    }
-                    let n = remote_stdout.read(&mut buffer).map_err(|e| e.to_string())?;
-                    if n == 0 {
-                        break;
-                    }
-                    local.write_all(&buffer[..n]).map_err(|e| e.to_string())?;
-                    transferred += n as u64;
-
-                    {
-                        let mut data = transfer_state_wsl.data.lock().unwrap();
-                        data.transferred = transferred;
-                    }
-
-                    if last_emit.elapsed().as_millis() > 100 {
-                        let _ = app.emit(
-                            "transfer-progress",
-                            ProgressPayload {
-                                id: current_transfer_id.clone(),
-                                transferred,
-                                total: total_size,
-                            },
-                        );
-                        last_emit = std::time::Instant::now();
-                    }
-                }
 
-                {
-                    let mut data = transfer_state_wsl.data.lock().unwrap();
+                transfer_state_wsl.update(&app, |data| {
                     data.status = "completed".to_string();
-                    data.transferred = total_size;
-                }
-                let _ = app.emit(
-                    "transfer-progress",
-                    ProgressPayload {
-                        id: current_transfer_id.clone(),
-                        transferred: total_size,
-                        total: total_size,
-                    },
-                );
+                    data.transferred = transferred;
+                });
 
-                let output = remote.wait_with_output().map_err(|e| e.to_string())?;
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                    if !stderr.is_empty() {
-                        return Err(stderr);
-                    }
+                if verify {
+                    verify_transfer(&transfer_state_wsl, &app, &verify_client);
                 }
-
-                Ok(())
+                Ok::<(), String>(())
             });
             // WSL branch returns the JoinHandle, but we need to unify return type or just let it run.
             // We want to return Ok(transfer_id)
@@ -950,6 +2395,9 @@ pub async fn upload_file(
     transfer_id: String,
     local_path: String,
     remote_path: String,
+    skip_space_check: Option<bool>,
+    rate_limit: Option<u64>,
+    verify: Option<bool>,
 ) -> Result<String, String> {
     eprintln!(
         "[DEBUG] upload_file called: id={}, transfer_id={}, local_path={}, remote_path={}",
@@ -962,6 +2410,8 @@ pub async fn upload_file(
     };
 
     let cancel_flag = Arc::new(AtomicBool::new(false));
+    let verify = verify.unwrap_or(false);
+    let verify_client = client.client_type.clone();
 
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -986,17 +2436,23 @@ pub async fn upload_file(
         transferred: 0,
         created_at: now,
         error: None,
+        local_hash: None,
+        remote_hash: None,
     };
 
     let transfer_state = Arc::new(TransferState {
         data: Mutex::new(transfer),
         cancel_flag: cancel_flag.clone(),
+        pause_flag: Arc::new(AtomicBool::new(false)),
+        progress_offset: AtomicU64::new(0),
     });
 
     {
         let mut transfers = state.transfers.lock().map_err(|e| e.to_string())?;
         transfers.insert(transfer_id.clone(), transfer_state.clone());
     }
+    state.transfer_queue.enqueue(&transfer_id);
+    emit_transfers_updated(&app);
 
     let t_id_ssh = transfer_id.clone();
     let t_id_wsl = transfer_id.clone();
@@ -1008,17 +2464,17 @@ pub async fn upload_file(
             let sender = senders.ops.clone();
             let app_handle = app.clone();
             let cancel_flag = transfer_state_ssh.cancel_flag.clone();
+            let pause_flag = transfer_state_ssh.pause_flag.clone();
             let transfer_id = t_id_ssh;
 
-            // Set status to running
-            {
-                let mut data = transfer_state_ssh.data.lock().unwrap();
-                data.status = "running".to_string();
-            }
-
             let tid_spawn = transfer_id.clone();
 
             tokio::spawn(async move {
+                let Some(_slot) =
+                    acquire_transfer_slot(&app, &transfer_state_ssh, &tid_spawn).await
+                else {
+                    return;
+                };
                 let (tx, rx) = std::sync::mpsc::channel();
                 let res = sender.send(SshCommand::SftpUpload {
                     local_path,
@@ -1027,6 +2483,9 @@ pub async fn upload_file(
                     app_handle,
                     listener: tx,
                     cancel_flag,
+                    pause_flag: pause_flag.clone(),
+                    skip_space_check: skip_space_check.unwrap_or(false),
+                    rate_limit,
                 });
 
                 if let Err(e) = res {
@@ -1040,23 +2499,29 @@ pub async fn upload_file(
                     return;
                 }
 
-                let recv_result = tokio::task::spawn_blocking(move || {
-                    rx.recv_timeout(std::time::Duration::from_secs(600)).ok()
-                })
-                .await
-                .ok()
-                .flatten();
+                let recv_result = tokio::task::spawn_blocking(move || recv_transfer_result(rx))
+                    .await
+                    .ok()
+                    .flatten();
 
                 match recv_result {
                     Some(Ok(_)) => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "completed".to_string();
-                        data.transferred = data.total_size;
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "completed".to_string();
+                            data.transferred = data.total_size;
+                        });
+                        if verify {
+                            let _ = tokio::task::spawn_blocking(move || {
+                                verify_transfer(&transfer_state_ssh, &app, &verify_client)
+                            })
+                            .await;
+                        }
                     }
                     Some(Err(e)) => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "error".to_string();
-                        data.error = Some(e.clone());
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "error".to_string();
+                            data.error = Some(e.clone());
+                        });
                         let _ = app.emit(
                             "transfer-error",
                             ErrorPayload {
@@ -1066,14 +2531,15 @@ pub async fn upload_file(
                         );
                     }
                     None => {
-                        let mut data = transfer_state_ssh.data.lock().unwrap();
-                        data.status = "error".to_string();
-                        data.error = Some("Upload timeout or channel closed".to_string());
+                        transfer_state_ssh.update(&app, |data| {
+                            data.status = "error".to_string();
+                            data.error = Some("Upload ended without a result".to_string());
+                        });
                         let _ = app.emit(
                             "transfer-error",
                             ErrorPayload {
                                 id: tid_spawn.clone(),
-                                error: "Upload timeout or channel closed".to_string(),
+                                error: "Upload ended without a result".to_string(),
                             },
                         );
                     }
@@ -1087,101 +2553,30 @@ pub async fn upload_file(
             tokio::task::spawn_blocking(move || {
                 let current_transfer_id = t_id_wsl;
                 let ts = transfer_state_wsl;
-                {
-                    let mut data = ts.data.lock().unwrap();
-                    data.status = "running".to_string();
-                }
-
-                let mut local = std::fs::File::open(&local_path).map_err(|e| e.to_string())?;
-                let metadata = local.metadata().map_err(|e| e.to_string())?;
-                let total_size = metadata.len();
-                {
-                    let mut data = ts.data.lock().unwrap();
-                    data.total_size = total_size;
-                }
+                let Some(_slot) = tokio::runtime::Handle::current()
+                    .block_on(acquire_transfer_slot(&app, &ts, &current_transfer_id))
+                else {
+                    return Ok(());
+                };
 
-                let escaped_remote = escape_shell_arg(&remote_path);
-                let _ = wsl::run_bash_text(
-                    &distro,
-                    &format!("mkdir -p \"$(dirname '{}')\"", escaped_remote),
-                    &[],
-                );
-                let mut remote = wsl::spawn_bash(
+                let total_size = wsl_upload_file(
                     &distro,
-                    &format!("cat > '{}'", escaped_remote),
-                    &[],
-                    std::process::Stdio::piped(),
-                    std::process::Stdio::null(),
-                    std::process::Stdio::piped(),
+                    &local_path,
+                    &remote_path,
+                    &ts,
+                    &app,
+                    &current_transfer_id,
                 )?;
-                let mut remote_stdin = remote
-                    .stdin
-                    .take()
-                    .ok_or("Failed to capture WSL upload stdin".to_string())?;
-
-                let mut buffer = [0u8; 8192];
-                let mut transferred = 0u64;
-                let mut last_emit = std::time::Instant::now();
-
-                loop {
-                    if ts.cancel_flag.load(Ordering::Relaxed) {
-                        {
-                            let mut data = ts.data.lock().unwrap();
-                            data.status = "cancelled".to_string();
-                        }
-                        return Err("Upload cancelled".to_string());
-                    }
-                    let n = local.read(&mut buffer).map_err(|e| e.to_string())?;
-                    if n == 0 {
-                        break;
-                    }
-                    remote_stdin
-                        .write_all(&buffer[..n])
-                        .map_err(|e| e.to_string())?;
-                    transferred += n as u64;
-
-                    {
-                        let mut data = ts.data.lock().unwrap();
-                        data.transferred = transferred;
-                    }
-
-                    if last_emit.elapsed().as_millis() > 100 {
-                        let _ = app.emit(
-                            "transfer-progress",
-                            ProgressPayload {
-                                id: current_transfer_id.clone(),
-                                transferred,
-                                total: total_size,
-                            },
-                        );
-                        last_emit = std::time::Instant::now();
-                    }
-                }
 
-                {
-                    let mut data = ts.data.lock().unwrap();
+                ts.update(&app, |data| {
                     data.status = "completed".to_string();
                     data.transferred = total_size;
-                }
-                let _ = app.emit(
-                    "transfer-progress",
-                    ProgressPayload {
-                        id: current_transfer_id.clone(),
-                        transferred: total_size,
-                        total: total_size,
-                    },
-                );
+                });
 
-                drop(remote_stdin);
-                let output = remote.wait_with_output().map_err(|e| e.to_string())?;
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                    if !stderr.is_empty() {
-                        return Err(stderr);
-                    }
+                if verify {
+                    verify_transfer(&ts, &app, &verify_client);
                 }
-
-                Ok(())
+                Ok::<(), String>(())
             });
             // As with download, allow background processing
             return Ok(transfer_id);
@@ -1191,6 +2586,412 @@ pub async fn upload_file(
     Ok(transfer_id)
 }
 
+/// Stream a local file into the distro with `cat`, recording progress on
+/// `transfer`. Marks the transfer cancelled when it is cancelled midway.
+/// Returns the number of bytes sent.
+pub(crate) fn wsl_upload_file(
+    distro: &str,
+    local_path: &str,
+    remote_path: &str,
+    transfer: &TransferState,
+    app: &AppHandle,
+    transfer_id: &str,
+) -> Result<u64, String> {
+    let mut local = std::fs::File::open(local_path).map_err(|e| e.to_string())?;
+    let metadata = local.metadata().map_err(|e| e.to_string())?;
+    let total_size = metadata.len();
+    record_transfer_progress(app, transfer_id, 0, total_size);
+
+    let escaped_remote = escape_shell_arg(remote_path);
+    let _ = wsl::run_bash_text(
+        distro,
+        &format!("mkdir -p \"$(dirname '{}')\"", escaped_remote),
+        &[],
+    );
+    let mut remote = wsl::spawn_bash(
+        distro,
+        &format!("cat > '{}'", escaped_remote),
+        &[],
+        std::process::Stdio::piped(),
+        std::process::Stdio::null(),
+        std::process::Stdio::piped(),
+    )?;
+    let mut remote_stdin = remote
+        .stdin
+        .take()
+        .ok_or("Failed to capture WSL upload stdin".to_string())?;
+
+    let mut buffer = vec![0u8; get_sftp_buffer_size(Some(app))];
+    let mut transferred = 0u64;
+    let mut last_emit = std::time::Instant::now();
+
+    loop {
+        wait_while_paused(&transfer.pause_flag, &transfer.cancel_flag);
+        if transfer.cancel_flag.load(Ordering::Relaxed) {
+            transfer.update(app, |data| {
+                data.status = "cancelled".to_string();
+            });
+            return Err("Upload cancelled".to_string());
+        }
+        let n = local.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        remote_stdin
+            .write_all(&buffer[..n])
+            .map_err(|e| e.to_string())?;
+        transferred += n as u64;
+
+        if last_emit.elapsed().as_millis() > 100 {
+            record_transfer_progress(app, transfer_id, transferred, total_size);
+            last_emit = std::time::Instant::now();
+        }
+    }
+    record_transfer_progress(app, transfer_id, total_size, total_size);
+
+    drop(remote_stdin);
+    let output = remote.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = wsl::decode_wsl_output(&output.stderr).trim().to_string();
+        if !stderr.is_empty() {
+            return Err(stderr);
+        }
+    }
+    Ok(total_size)
+}
+
+/// Stream a file out of the distro with `cat`, recording progress on
+/// `transfer`. Marks the transfer cancelled when it is cancelled midway.
+/// Returns the number of bytes received.
+pub(crate) fn wsl_download_file(
+    distro: &str,
+    remote_path: &str,
+    local_path: &str,
+    transfer: &TransferState,
+    app: &AppHandle,
+    transfer_id: &str,
+) -> Result<u64, String> {
+    let escaped_remote = escape_shell_arg(remote_path);
+    let total_size = wsl::run_bash_text(distro, &format!("stat -c %s '{}'", escaped_remote), &[])
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    record_transfer_progress(app, transfer_id, 0, total_size);
+
+    let mut remote = wsl::spawn_bash(
+        distro,
+        &format!("cat '{}'", escaped_remote),
+        &[],
+        std::process::Stdio::null(),
+        std::process::Stdio::piped(),
+        std::process::Stdio::piped(),
+    )?;
+    let mut remote_stdout = remote
+        .stdout
+        .take()
+        .ok_or("Failed to capture WSL download stdout".to_string())?;
+    let mut local = std::fs::File::create(local_path).map_err(|e| e.to_string())?;
+
+    let mut buffer = vec![0u8; get_sftp_read_buffer_size(Some(app))];
+    let mut transferred = 0u64;
+    let mut last_emit = std::time::Instant::now();
+
+    loop {
+        wait_while_paused(&transfer.pause_flag, &transfer.cancel_flag);
+        if transfer.cancel_flag.load(Ordering::Relaxed) {
+            // cat would otherwise block forever on the full pipe
+            let _ = remote.kill();
+            transfer.update(app, |data| {
+                data.status = "cancelled".to_string();
+            });
+            return Err("Download cancelled".to_string());
+        }
+        let n = remote_stdout.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        local.write_all(&buffer[..n]).map_err(|e| e.to_string())?;
+        transferred += n as u64;
+
+        if last_emit.elapsed().as_millis() > 100 {
+            record_transfer_progress(app, transfer_id, transferred, total_size);
+            last_emit = std::time::Instant::now();
+        }
+    }
+    record_transfer_progress(app, transfer_id, transferred, transferred);
+
+    drop(remote_stdout);
+    let output = remote.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = wsl::decode_wsl_output(&output.stderr).trim().to_string();
+        if !stderr.is_empty() {
+            return Err(stderr);
+        }
+    }
+    Ok(transferred)
+}
+
+/// A local directory flattened for upload: remote directories to create,
+/// parents first, and the files to send.
+struct LocalTree {
+    dirs: Vec<String>,
+    files: Vec<LocalTreeFile>,
+}
+
+struct LocalTreeFile {
+    local_path: String,
+    remote_path: String,
+    size: u64,
+}
+
+/// Walk `local_dir` and map every entry to the same relative path under
+/// `remote_dir`, joined with forward slashes. Symlinked directories are
+/// skipped so a link cycle cannot recurse forever.
+fn collect_local_tree(local_dir: &Path, remote_dir: &str) -> Result<LocalTree, String> {
+    let mut tree = LocalTree {
+        dirs: Vec::new(),
+        files: Vec::new(),
+    };
+    let root = remote_dir.trim_end_matches('/');
+    let mut pending = vec![(local_dir.to_path_buf(), root.to_string())];
+    while let Some((dir, remote)) = pending.pop() {
+        tree.dirs.push(remote.clone());
+        let mut entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        entries.sort_by_key(|entry| entry.file_name());
+        // Reversed so directories pop off the stack in name order
+        for entry in entries.into_iter().rev() {
+            let path = entry.path();
+            let remote_child = format!("{}/{}", remote, entry.file_name().to_string_lossy());
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                pending.push((path, remote_child));
+            } else if let Ok(metadata) = std::fs::metadata(&path) {
+                if metadata.is_file() {
+                    tree.files.push(LocalTreeFile {
+                        local_path: path.to_string_lossy().to_string(),
+                        remote_path: remote_child,
+                        size: metadata.len(),
+                    });
+                }
+            }
+        }
+    }
+    tree.files.reverse();
+    Ok(tree)
+}
+
+/// Create the tree's directories and send its files one by one under a
+/// single transfer, carrying the finished bytes in `progress_offset`.
+async fn upload_tree(
+    app: &AppHandle,
+    client_type: &ClientType,
+    tree: &LocalTree,
+    transfer: &Arc<TransferState>,
+    transfer_id: &str,
+    rate_limit: Option<u64>,
+) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            for dir in &tree.dirs {
+                let (tx, rx) = std::sync::mpsc::channel();
+                senders
+                    .ops
+                    .send(SshCommand::SftpMkdirAll {
+                        path: dir.clone(),
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+                tokio::task::spawn_blocking(move || rx.recv())
+                    .await
+                    .map_err(|e| format!("Task join error: {}", e))?
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())??;
+            }
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            let dirs = tree.dirs.clone();
+            tokio::task::spawn_blocking(move || {
+                wsl::run_bash_text(&distro, r#"mkdir -p -- "$@""#, &dirs)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+        }
+    }
+
+    let mut done = 0u64;
+    for file in &tree.files {
+        if transfer.cancel_flag.load(Ordering::Relaxed) {
+            return Err("Upload cancelled".to_string());
+        }
+        transfer.progress_offset.store(done, Ordering::Relaxed);
+        match client_type {
+            ClientType::Ssh(senders) => {
+                let (tx, rx) = std::sync::mpsc::channel();
+                senders
+                    .ops
+                    .send(SshCommand::SftpUpload {
+                        local_path: file.local_path.clone(),
+                        remote_path: file.remote_path.clone(),
+                        transfer_id: transfer_id.to_string(),
+                        app_handle: app.clone(),
+                        listener: tx,
+                        cancel_flag: transfer.cancel_flag.clone(),
+                        pause_flag: transfer.pause_flag.clone(),
+                        skip_space_check: false,
+                        rate_limit,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+                tokio::task::spawn_blocking(move || recv_transfer_result(rx))
+                    .await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| Err("Upload ended without a result".to_string()))?;
+            }
+            ClientType::Wsl(distro) => {
+                let distro = distro.clone();
+                let local_path = file.local_path.clone();
+                let remote_path = file.remote_path.clone();
+                let transfer = transfer.clone();
+                let app = app.clone();
+                let transfer_id = transfer_id.to_string();
+                tokio::task::spawn_blocking(move || {
+                    wsl_upload_file(
+                        &distro,
+                        &local_path,
+                        &remote_path,
+                        &transfer,
+                        &app,
+                        &transfer_id,
+                    )
+                })
+                .await
+                .map_err(|e| format!("Task join error: {}", e))??;
+            }
+        }
+        done += file.size;
+    }
+    Ok(())
+}
+
+/// Upload a local directory to `remote_path`, recreating its subdirectories,
+/// as a single transfer whose progress covers every file in the tree.
+#[tauri::command]
+pub async fn upload_directory(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    transfer_id: String,
+    local_path: String,
+    remote_path: String,
+    rate_limit: Option<u64>,
+) -> Result<String, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let local_dir = PathBuf::from(&local_path);
+    if !local_dir.is_dir() {
+        return Err(format!("{} is not a directory", local_path));
+    }
+    let tree = {
+        let remote_path = remote_path.clone();
+        tokio::task::spawn_blocking(move || collect_local_tree(&local_dir, &remote_path))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??
+    };
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+
+    let name = Path::new(&local_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let transfer = Transfer {
+        id: transfer_id.clone(),
+        session_id: id.clone(),
+        name,
+        local_path: local_path.clone(),
+        remote_path: remote_path.clone(),
+        transfer_type: "upload".to_string(),
+        status: "pending".to_string(),
+        total_size: tree.files.iter().map(|file| file.size).sum(),
+        transferred: 0,
+        created_at: now,
+        error: None,
+        local_hash: None,
+        remote_hash: None,
+    };
+
+    let transfer_state = Arc::new(TransferState {
+        data: Mutex::new(transfer),
+        cancel_flag: Arc::new(AtomicBool::new(false)),
+        pause_flag: Arc::new(AtomicBool::new(false)),
+        progress_offset: AtomicU64::new(0),
+    });
+
+    {
+        let mut transfers = state.transfers.lock().map_err(|e| e.to_string())?;
+        transfers.insert(transfer_id.clone(), transfer_state.clone());
+    }
+    state.transfer_queue.enqueue(&transfer_id);
+    emit_transfers_updated(&app);
+
+    let tid_spawn = transfer_id.clone();
+    tokio::spawn(async move {
+        let Some(_slot) = acquire_transfer_slot(&app, &transfer_state, &tid_spawn).await else {
+            return;
+        };
+
+        let result = upload_tree(
+            &app,
+            &client.client_type,
+            &tree,
+            &transfer_state,
+            &tid_spawn,
+            rate_limit,
+        )
+        .await;
+
+        match result {
+            Ok(()) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "completed".to_string();
+                    data.transferred = data.total_size;
+                });
+            }
+            Err(_) if transfer_state.cancel_flag.load(Ordering::Relaxed) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "cancelled".to_string();
+                });
+            }
+            Err(e) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "error".to_string();
+                    data.error = Some(e.clone());
+                });
+                let _ = app.emit(
+                    "transfer-error",
+                    ErrorPayload {
+                        id: tid_spawn.clone(),
+                        error: e,
+                    },
+                );
+            }
+        }
+    });
+
+    Ok(transfer_id)
+}
+
 #[tauri::command]
 pub async fn download_file_with_progress(
     app: AppHandle,
@@ -1200,8 +3001,19 @@ pub async fn download_file_with_progress(
     remote_path: String,
     local_path: String,
     _resume: bool,
+    rate_limit: Option<u64>,
 ) -> Result<String, String> {
-    download_file(app, state, id, transfer_id, remote_path, local_path).await
+    download_file(
+        app,
+        state,
+        id,
+        transfer_id,
+        remote_path,
+        local_path,
+        rate_limit,
+        None,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -1213,8 +3025,21 @@ pub async fn upload_file_with_progress(
     local_path: String,
     remote_path: String,
     _resume: bool,
+    skip_space_check: Option<bool>,
+    rate_limit: Option<u64>,
 ) -> Result<String, String> {
-    upload_file(app, state, id, transfer_id, local_path, remote_path).await
+    upload_file(
+        app,
+        state,
+        id,
+        transfer_id,
+        local_path,
+        remote_path,
+        skip_space_check,
+        rate_limit,
+        None,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -1246,6 +3071,7 @@ pub async fn search_remote_files(
                         cancel_flag: None,
                         target: ExecTarget::FileBrowser,
                         stream: None,
+                        timeout: None,
                     })
                     .map_err(|e| format!("Failed to send command: {}", e))?;
 
@@ -1293,7 +3119,7 @@ pub async fn search_remote_files(
                     .output()
                     .map_err(|e| e.to_string())?;
 
-                let out_str = String::from_utf8_lossy(&output.stdout);
+                let out_str = wsl::decode_wsl_output(&output.stdout);
                 let mut entries = Vec::new();
                 for line in out_str.lines() {
                     let line = line.trim();
@@ -1346,7 +3172,6 @@ use crate::db::{
     cleanup_old_transfer_records, get_transfer_records_by_client, save_transfer_record,
     TransferRecord as DbTransferRecord,
 };
-use crate::ssh::client::cancel_transfer;
 use crate::ssh::transfer::{TransferManager, TransferOperation, TransferSettings};
 
 /// Start a transfer using the new TransferManager
@@ -1391,6 +3216,33 @@ pub async fn start_transfer_with_manager(
             os_type: client.os_info.clone(),
             key_content: None,
             key_passphrase: None,
+            tags: Vec::new(),
+            last_connected: None,
+            last_latency_ms: None,
+            force_scp: false,
+            max_background_sessions: None,
+            jump_hosts: Vec::new(),
+            jump_auth_type: None,
+            jump_key_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            is_favorite: false,
+            agent_forwarding: false,
+            x11_forwarding: false,
+            low_latency: false,
+            idle_timeout_minutes: 0,
+            kex_algorithms: None,
+            ciphers: None,
+            mac_algorithms: None,
+            legacy_mode: false,
+            init_command: None,
+            remote_rc: None,
+            host_key_policy: None,
+            socket_path: None,
+            proxy_command: None,
+            sftp_handle_cache: false,
+            notes: None,
         }
     };
 
@@ -1479,28 +3331,53 @@ pub async fn start_transfer_with_manager(
     Ok(transfer_id)
 }
 
-/// Pause a running transfer
+/// Pause a running transfer. The transfer loop idles (still honouring
+/// cancel) until `resume_transfer` is called.
 #[tauri::command]
 pub async fn pause_transfer(
     app: AppHandle,
     state: State<'_, AppState>,
     transfer_id: String,
 ) -> Result<(), String> {
-    // For now, we'll use the existing cancel mechanism
-    // In a full implementation, you'd have a TransferManager instance per client
-    cancel_transfer(state, transfer_id).await
+    let transfer_state = find_transfer(&state, &transfer_id)?;
+    transfer_state.update(&app, |data| {
+        if data.status == "running" || data.status == "pending" {
+            transfer_state.pause_flag.store(true, Ordering::Relaxed);
+            data.status = "paused".to_string();
+        }
+    });
+    Ok(())
 }
 
-/// Resume a paused transfer
+/// Resume a paused transfer from its current offset. A transfer paused
+/// while queued goes back to waiting for a slot.
 #[tauri::command]
 pub async fn resume_transfer(
     app: AppHandle,
     state: State<'_, AppState>,
     transfer_id: String,
 ) -> Result<(), String> {
-    // For now, return an error indicating this needs the TransferManager
-    // In a full implementation, you'd retrieve the TransferManager and call resume
-    Err("Resume functionality requires TransferManager integration. Use the existing upload/download commands for now.".to_string())
+    let transfer_state = find_transfer(&state, &transfer_id)?;
+    let queued = state.transfer_queue.is_waiting(&transfer_id);
+    transfer_state.update(&app, |data| {
+        if data.status == "paused" {
+            data.status = if queued { "pending" } else { "running" }.to_string();
+            transfer_state.pause_flag.store(false, Ordering::Relaxed);
+        }
+    });
+    Ok(())
+}
+
+/// Clone the transfer out of the map so the map lock is not held while the
+/// transfer is updated (which re-locks it to emit `transfers-updated`).
+fn find_transfer(state: &AppState, transfer_id: &str) -> Result<Arc<TransferState>, String> {
+    state
+        .transfers
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(transfer_id)
+        .cloned()
+        .ok_or_else(|| "Transfer not found".to_string())
 }
 
 /// Get transfer records from database
@@ -1517,3 +3394,63 @@ pub async fn get_transfer_records(
 pub async fn cleanup_old_transfers(app: AppHandle, days_old: i64) -> Result<usize, String> {
     cleanup_old_transfer_records(&app, days_old)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_temp_file_name_keeps_a_single_component() {
+        assert_eq!(sanitize_temp_file_name("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_temp_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_temp_file_name("dir\\sub\\file.txt"), "file.txt");
+        assert_eq!(sanitize_temp_file_name("logs/"), "logs");
+    }
+
+    #[test]
+    fn sanitize_temp_file_name_rejects_parent_and_reserved_names() {
+        assert_eq!(sanitize_temp_file_name(".."), "download");
+        assert_eq!(sanitize_temp_file_name("/"), "download");
+        assert_eq!(sanitize_temp_file_name(". . "), "download");
+        assert_eq!(sanitize_temp_file_name("a:b*?.txt"), "a_b__.txt");
+        assert_eq!(sanitize_temp_file_name("name\u{0}.txt"), "name_.txt");
+        assert_eq!(sanitize_temp_file_name("CON"), "_CON");
+        assert_eq!(sanitize_temp_file_name("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_temp_file_name("com1.log"), "_com1.log");
+        assert_eq!(sanitize_temp_file_name("console.log"), "console.log");
+        assert_eq!(sanitize_temp_file_name("com10"), "com10");
+    }
+
+    #[test]
+    fn check_unchanged_compares_mtime_and_size() {
+        let stat = RemoteFileStat {
+            mtime: 1_700_000_000,
+            size: 42,
+        };
+        assert!(check_unchanged("/f", Some(stat.mtime), Some(42), Some(stat)).is_ok());
+        assert!(check_unchanged("/f", Some(stat.mtime), None, Some(stat)).is_ok());
+        let same_second = check_unchanged("/f", Some(stat.mtime), Some(41), Some(stat));
+        assert!(same_second.unwrap_err().starts_with(WRITE_CONFLICT));
+        let touched = check_unchanged("/f", Some(stat.mtime - 1), Some(42), Some(stat));
+        assert!(touched.unwrap_err().starts_with(WRITE_CONFLICT));
+    }
+
+    #[test]
+    fn check_unchanged_reports_a_removed_file() {
+        let err = check_unchanged("/f", Some(1), Some(1), None).unwrap_err();
+        assert!(err.starts_with(WRITE_CONFLICT) && err.contains("removed"));
+    }
+
+    #[test]
+    fn prune_temp_open_dirs_removes_only_stale_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("copy");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("file.txt"), b"data").unwrap();
+
+        prune_temp_open_dirs(root.path(), TEMP_OPEN_MAX_AGE);
+        assert!(dir.exists());
+        prune_temp_open_dirs(root.path(), std::time::Duration::ZERO);
+        assert!(!dir.exists());
+    }
+}
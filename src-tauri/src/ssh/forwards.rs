@@ -0,0 +1,239 @@
+use super::client::{AppState, ClientType, TransferRateWindow};
+use super::manager::SshCommand;
+use super::utils::execute_ssh_operation;
+use crate::models::ForwardInfo;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tauri::State;
+use uuid::Uuid;
+
+pub struct ForwardingThreadHandle {
+    thread_handle: JoinHandle<()>,
+    shutdown_signal: Arc<AtomicBool>,
+}
+
+impl ForwardingThreadHandle {
+    pub fn new(thread_handle: JoinHandle<()>, shutdown_signal: Arc<AtomicBool>) -> Self {
+        Self {
+            thread_handle,
+            shutdown_signal,
+        }
+    }
+
+    /// Signal the thread and wait for it to exit.
+    fn stop(self) {
+        self.shutdown_signal.store(true, Ordering::Relaxed);
+        let _ = self.thread_handle.join();
+    }
+}
+
+/// What a forward connects, recorded when it is registered.
+pub struct ForwardSpec {
+    pub kind: &'static str,
+    pub local_port: u16,
+    pub remote_target: String,
+    pub via: String,
+    /// App session that owns the forward; `None` for jump-host forwards,
+    /// which live and die with their SSH session
+    pub session_id: Option<String>,
+}
+
+/// Byte counters updated by a forwarding thread as it copies data.
+#[derive(Default)]
+pub struct ForwardCounters {
+    /// Remote to local
+    pub rx: AtomicU64,
+    /// Local to remote
+    pub tx: AtomicU64,
+}
+
+struct RegisteredForward {
+    handle: ForwardingThreadHandle,
+    spec: ForwardSpec,
+    counters: Arc<ForwardCounters>,
+    rx_rate: TransferRateWindow,
+    tx_rate: TransferRateWindow,
+    started_at: i64,
+}
+
+/// Every forwarding thread the app has running, keyed by forward ID. Sessions
+/// keep only the IDs of their forwards and stop them here when released.
+#[derive(Default)]
+pub struct ForwardRegistry {
+    forwards: Mutex<HashMap<String, RegisteredForward>>,
+}
+
+impl ForwardRegistry {
+    /// The process-wide registry. Forwards are started deep inside connection
+    /// setup, where no `AppHandle` is available, so `AppState` holds a clone.
+    pub fn shared() -> Arc<ForwardRegistry> {
+        static SHARED: OnceLock<Arc<ForwardRegistry>> = OnceLock::new();
+        SHARED.get_or_init(Default::default).clone()
+    }
+
+    /// Track a started forward; `counters` are updated by its thread.
+    /// Returns the forward ID.
+    pub fn register(
+        &self,
+        spec: ForwardSpec,
+        handle: ForwardingThreadHandle,
+        counters: Arc<ForwardCounters>,
+    ) -> String {
+        let id = Uuid::new_v4().to_string();
+        // Start both rate windows at zero so the first listing has a baseline
+        let now = Instant::now();
+        let mut rx_rate = TransferRateWindow::default();
+        let mut tx_rate = TransferRateWindow::default();
+        rx_rate.sample(now, 0);
+        tx_rate.sample(now, 0);
+        if let Ok(mut forwards) = self.forwards.lock() {
+            prune_finished(&mut forwards);
+            forwards.insert(
+                id.clone(),
+                RegisteredForward {
+                    handle,
+                    spec,
+                    counters,
+                    rx_rate,
+                    tx_rate,
+                    started_at: chrono::Utc::now().timestamp_millis(),
+                },
+            );
+        }
+        id
+    }
+
+    /// Stop a forward and forget it. Returns false if the ID is unknown.
+    pub fn stop(&self, id: &str) -> bool {
+        let removed = match self.forwards.lock() {
+            Ok(mut forwards) => forwards.remove(id),
+            Err(_) => None,
+        };
+        // Join outside the lock; the thread may take a moment to notice
+        match removed {
+            Some(forward) => {
+                forward.handle.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop every forward started for an app session, e.g. on disconnect.
+    pub fn stop_session(&self, session_id: &str) {
+        let ids: Vec<String> = match self.forwards.lock() {
+            Ok(forwards) => forwards
+                .iter()
+                .filter(|(_, forward)| forward.spec.session_id.as_deref() == Some(session_id))
+                .map(|(id, _)| id.clone())
+                .collect(),
+            Err(_) => return,
+        };
+        for id in ids {
+            self.stop(&id);
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<ForwardInfo>, String> {
+        let mut forwards = self.forwards.lock().map_err(|e| e.to_string())?;
+        prune_finished(&mut forwards);
+        let now = Instant::now();
+        let mut list: Vec<ForwardInfo> = forwards
+            .iter_mut()
+            .map(|(id, forward)| {
+                let rx_bytes = forward.counters.rx.load(Ordering::Relaxed);
+                let tx_bytes = forward.counters.tx.load(Ordering::Relaxed);
+                ForwardInfo {
+                    id: id.clone(),
+                    kind: forward.spec.kind.to_string(),
+                    local_port: forward.spec.local_port,
+                    remote_target: forward.spec.remote_target.clone(),
+                    via: forward.spec.via.clone(),
+                    session_id: forward.spec.session_id.clone(),
+                    rx_bytes,
+                    tx_bytes,
+                    rx_bytes_per_second: forward.rx_rate.sample(now, rx_bytes),
+                    tx_bytes_per_second: forward.tx_rate.sample(now, tx_bytes),
+                    started_at: forward.started_at,
+                }
+            })
+            .collect();
+        list.sort_by_key(|forward| forward.started_at);
+        Ok(list)
+    }
+}
+
+/// Drop forwards whose thread already exited, e.g. after the forwarded
+/// connection closed or a connection attempt was abandoned half-way.
+fn prune_finished(forwards: &mut HashMap<String, RegisteredForward>) {
+    forwards.retain(|_, forward| !forward.handle.thread_handle.is_finished());
+}
+
+/// Forwarding threads currently owned by the app, oldest first.
+#[tauri::command]
+pub async fn list_forwards(state: State<'_, AppState>) -> Result<Vec<ForwardInfo>, String> {
+    state.forwards.list()
+}
+
+/// Forward a local port to `remote_host:remote_port` through session `id`.
+/// `local_port` 0 (the default) picks a free port; a fixed port that is
+/// already taken fails with an "already in use" error.
+#[tauri::command]
+pub async fn start_local_forward(
+    state: State<'_, AppState>,
+    id: String,
+    remote_host: String,
+    remote_port: u16,
+    local_port: Option<u16>,
+) -> Result<ForwardInfo, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let sender = match &client.client_type {
+        ClientType::Ssh(senders) => senders.ops.clone(),
+        ClientType::Wsl(_) => return Err("WSL sessions do not support port forwarding".to_string()),
+    };
+
+    let forward_id = execute_ssh_operation(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        sender
+            .send(SshCommand::StartLocalForward {
+                session_id: id,
+                local_port: local_port.unwrap_or(0),
+                remote_host,
+                remote_port,
+                listener: tx,
+            })
+            .map_err(|e| format!("Failed to send command: {}", e))?;
+
+        rx.recv()
+            .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+    })
+    .await?;
+
+    state
+        .forwards
+        .list()?
+        .into_iter()
+        .find(|forward| forward.id == forward_id)
+        .ok_or_else(|| "Forward stopped right after starting".to_string())
+}
+
+/// Stop a forwarding thread. Stopping a jump-host forward cuts off the
+/// session that runs through it.
+#[tauri::command]
+pub async fn stop_forward(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let forwards = state.forwards.clone();
+    let stopped = tokio::task::spawn_blocking(move || forwards.stop(&id))
+        .await
+        .map_err(|e| e.to_string())?;
+    if stopped {
+        Ok(())
+    } else {
+        Err("Forward not found".to_string())
+    }
+}
@@ -1,6 +1,18 @@
 use ssh_key::rand_core::OsRng;
 use ssh_key::{Algorithm, LineEnding, PrivateKey};
 
+/// The OpenSSH public key line (`ssh-ed25519 AAAA... comment`) for a stored
+/// private key. Works for passphrase-protected keys too: OpenSSH keeps the
+/// public half unencrypted.
+pub fn public_key_openssh(private_key: &str) -> Result<String, String> {
+    let private_key = PrivateKey::from_openssh(private_key)
+        .map_err(|e| format!("Invalid private key in DB: {}", e))?;
+    private_key
+        .public_key()
+        .to_openssh()
+        .map_err(|e| format!("Failed to derive public key: {}", e))
+}
+
 pub fn generate_key_pair(
     algorithm: &str,
     passphrase: Option<&str>,
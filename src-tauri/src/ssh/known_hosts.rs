@@ -0,0 +1,412 @@
+use super::connection::{connect_with_timeout, resolve_target, verify_host_key};
+use crate::models::{ConnectionTimeoutSettings, KnownHostEntry};
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How long `reset_known_host` waits to reach the server and read its key.
+const KEY_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an `ask` prompt waits for `confirm_host_key` before the
+/// connection is refused.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub const DEFAULT_HOST_KEY_POLICY: &str = "ask";
+
+/// What to do when a server presents a key that is not in known_hosts yet.
+/// Changed keys are always rejected, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HostKeyPolicy {
+    /// Refuse the connection
+    Strict,
+    /// Emit `host-key-prompt` and wait for `confirm_host_key`
+    Ask,
+    /// Record the key and carry on (trust on first use)
+    AcceptNew,
+}
+
+impl HostKeyPolicy {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "strict" => Some(Self::Strict),
+            "ask" => Some(Self::Ask),
+            "accept-new" => Some(Self::AcceptNew),
+            _ => None,
+        }
+    }
+
+    /// The connection's own policy if set, else the global setting. Anything
+    /// missing or unrecognised falls back to `Ask`.
+    pub(crate) fn resolve(
+        connection: Option<&str>,
+        settings: Option<&ConnectionTimeoutSettings>,
+    ) -> Self {
+        connection
+            .and_then(Self::parse)
+            .or_else(|| settings.and_then(|s| Self::parse(&s.host_key_policy)))
+            .unwrap_or(Self::Ask)
+    }
+}
+
+pub fn validate_host_key_policy(value: &str) -> Result<(), String> {
+    match HostKeyPolicy::parse(value) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Invalid host key policy '{}': expected strict, ask or accept-new",
+            value
+        )),
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HostKeyPromptPayload {
+    prompt_id: String,
+    host: String,
+    port: u16,
+    key_type: String,
+    fingerprint: String,
+}
+
+/// Unknown-host prompts waiting for an answer from the UI. Host keys are
+/// checked deep inside connection setup, where no `AppHandle` is available,
+/// so the handle is attached once at startup.
+#[derive(Default)]
+struct HostKeyPrompts {
+    app: OnceLock<AppHandle>,
+    pending: Mutex<HashMap<String, mpsc::Sender<bool>>>,
+}
+
+fn prompts() -> &'static HostKeyPrompts {
+    static PROMPTS: OnceLock<HostKeyPrompts> = OnceLock::new();
+    PROMPTS.get_or_init(Default::default)
+}
+
+pub fn attach_app_handle(app: AppHandle) {
+    let _ = prompts().app.set(app);
+}
+
+/// Ask the UI whether to trust `key` for `host:port`, blocking until it
+/// answers. No answer within `PROMPT_TIMEOUT` counts as a refusal.
+pub(crate) fn ask_host_key(host: &str, port: u16, key: &[u8]) -> Result<(), String> {
+    let fingerprint = fingerprint(key);
+    let prompts = prompts();
+    let app = prompts.app.get().ok_or_else(|| {
+        format!(
+            "Unknown host key for {}:{} ({}) and no window to confirm it in",
+            host, port, fingerprint
+        )
+    })?;
+
+    let prompt_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = mpsc::channel();
+    prompts
+        .pending
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(prompt_id.clone(), tx);
+
+    let payload = HostKeyPromptPayload {
+        prompt_id: prompt_id.clone(),
+        host: host.to_string(),
+        port,
+        key_type: key_type_name(key).unwrap_or("unknown").to_string(),
+        fingerprint: fingerprint.clone(),
+    };
+    let answer = match app.emit("host-key-prompt", payload) {
+        Ok(()) => rx.recv_timeout(PROMPT_TIMEOUT).ok(),
+        Err(_) => None,
+    };
+    if let Ok(mut pending) = prompts.pending.lock() {
+        pending.remove(&prompt_id);
+    }
+
+    match answer {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!(
+            "Host key for {}:{} ({}) was rejected",
+            host, port, fingerprint
+        )),
+        None => Err(format!(
+            "Host key for {}:{} ({}) was not confirmed in time",
+            host, port, fingerprint
+        )),
+    }
+}
+
+/// `~/.ssh/known_hosts`, created empty (along with `~/.ssh`) if missing.
+pub(crate) fn known_hosts_path() -> Result<PathBuf, String> {
+    let ssh_dir = dirs::home_dir()
+        .ok_or("Could not find home directory")?
+        .join(".ssh");
+
+    if !ssh_dir.exists() {
+        std::fs::create_dir_all(&ssh_dir)
+            .map_err(|e| format!("Failed to create .ssh directory: {}", e))?;
+    }
+
+    let known_hosts_path = ssh_dir.join("known_hosts");
+    if !known_hosts_path.exists() {
+        std::fs::File::create(&known_hosts_path)
+            .map_err(|e| format!("Failed to create known_hosts file: {}", e))?;
+    }
+    Ok(known_hosts_path)
+}
+
+struct KnownHostLine<'a> {
+    marker: Option<&'a str>,
+    hosts: &'a str,
+    key_type: &'a str,
+    key: &'a str,
+    comment: String,
+}
+
+/// Split a known_hosts line into its fields; `None` for blank lines,
+/// comments and lines too short to hold a key.
+fn parse_line(line: &str) -> Option<KnownHostLine<'_>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let mut fields = line.split_whitespace();
+    let mut hosts = fields.next()?;
+    let marker = if hosts.starts_with('@') {
+        let marker = hosts;
+        hosts = fields.next()?;
+        Some(marker)
+    } else {
+        None
+    };
+    Some(KnownHostLine {
+        marker,
+        hosts,
+        key_type: fields.next()?,
+        key: fields.next()?,
+        comment: fields.collect::<Vec<_>>().join(" "),
+    })
+}
+
+/// OpenSSH-style `SHA256:` fingerprint of a raw public key blob.
+fn fingerprint(blob: &[u8]) -> String {
+    format!(
+        "SHA256:{}",
+        general_purpose::STANDARD_NO_PAD.encode(Sha256::digest(blob))
+    )
+}
+
+/// Algorithm name a public key blob starts with, e.g. `ssh-ed25519`.
+fn key_type_name(blob: &[u8]) -> Option<&str> {
+    let len = u32::from_be_bytes(blob.get(..4)?.try_into().ok()?) as usize;
+    std::str::from_utf8(blob.get(4..4 + len)?).ok()
+}
+
+/// Whether `line` holds a key for `host:port`. libssh2 does the matching, so
+/// hashed names and `[host]:port` patterns behave exactly as they do when a
+/// connection is verified. Marker lines are never matched.
+fn line_matches(session: &Session, line: &str, host: &str, port: u16) -> bool {
+    let Some(parsed) = parse_line(line) else {
+        return false;
+    };
+    if parsed.marker.is_some() {
+        return false;
+    }
+    let Ok(key) = general_purpose::STANDARD.decode(parsed.key) else {
+        return false;
+    };
+    let Ok(mut known_hosts) = session.known_hosts() else {
+        return false;
+    };
+    if known_hosts
+        .read_str(line.trim(), KnownHostFileKind::OpenSSH)
+        .is_err()
+    {
+        return false;
+    }
+    matches!(
+        known_hosts.check_port(host, port, &key),
+        CheckResult::Match | CheckResult::Mismatch
+    )
+}
+
+/// Rewrite known_hosts without the entries for `host:port`. Other lines,
+/// comments included, are kept byte for byte. Returns how many were removed.
+fn remove_entries(host: &str, port: u16) -> Result<usize, String> {
+    let path = known_hosts_path()?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read known_hosts file: {}", e))?;
+    let session = Session::new().map_err(|e| e.to_string())?;
+
+    let mut removed = 0;
+    let kept: String = content
+        .split_inclusive('\n')
+        .filter(|line| {
+            let matched = line_matches(&session, line, host, port);
+            if matched {
+                removed += 1;
+            }
+            !matched
+        })
+        .collect();
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    // Replace the file in one step so a failed write cannot truncate it
+    let tmp_path = path.with_file_name(format!("known_hosts.tmp.{}", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp_path, kept)
+        .and_then(|_| std::fs::rename(&tmp_path, &path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tmp_path);
+            format!("Failed to write known_hosts file: {}", e)
+        })?;
+    Ok(removed)
+}
+
+/// Answer a `host-key-prompt` event: `accept` records the key and lets the
+/// connection continue, anything else refuses it.
+#[tauri::command]
+pub fn confirm_host_key(prompt_id: String, accept: bool) -> Result<(), String> {
+    let sender = prompts()
+        .pending
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&prompt_id)
+        .ok_or("Host key prompt not found or already expired")?;
+    sender
+        .send(accept)
+        .map_err(|_| "Host key prompt already expired".to_string())
+}
+
+/// Every key entry in `~/.ssh/known_hosts`, in file order.
+#[tauri::command]
+pub fn list_known_hosts() -> Result<Vec<KnownHostEntry>, String> {
+    let path = known_hosts_path()?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read known_hosts file: {}", e))?;
+
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let parsed = parse_line(line)?;
+            let blob = general_purpose::STANDARD.decode(parsed.key).ok()?;
+            Some(KnownHostEntry {
+                line: index + 1,
+                hosts: parsed.hosts.split(',').map(str::to_string).collect(),
+                hashed: parsed.hosts.starts_with("|1|"),
+                marker: parsed.marker.map(str::to_string),
+                key_type: parsed.key_type.to_string(),
+                fingerprint: fingerprint(&blob),
+                comment: parsed.comment,
+            })
+        })
+        .collect())
+}
+
+/// Delete the stored keys for `host:port` (hashed entries included), e.g.
+/// after the server was reinstalled. The next connection records the key it
+/// presents. Returns the number of entries removed.
+#[tauri::command]
+pub fn remove_known_host(host: String, port: u16) -> Result<usize, String> {
+    let (host, port) = resolve_target(&host, port);
+    remove_entries(&host, port)
+}
+
+/// Replace the stored keys for `host:port` with the key the server presents
+/// right now. Returns its fingerprint. The key is fetched before anything is
+/// removed, so an unreachable server leaves known_hosts untouched.
+#[tauri::command]
+pub async fn reset_known_host(host: String, port: u16) -> Result<String, String> {
+    let (host, port) = resolve_target(&host, port);
+    tokio::task::spawn_blocking(move || {
+        let tcp = connect_with_timeout(&host, port, KEY_FETCH_TIMEOUT)?;
+        let mut session = Session::new().map_err(|e| e.to_string())?;
+        session.set_tcp_stream(tcp);
+        session.set_timeout(KEY_FETCH_TIMEOUT.as_millis() as u32);
+        session
+            .handshake()
+            .map_err(|e| format!("SSH handshake failed: {}", e))?;
+        let (key, _) = session.host_key().ok_or("Failed to get remote host key")?;
+        let fingerprint = fingerprint(key);
+
+        remove_entries(&host, port)?;
+        // With the old entries gone this records the key as on first use
+        verify_host_key(&session, &host, port, false, HostKeyPolicy::AcceptNew)?;
+        Ok(fingerprint)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_hashed_and_marker_lines() {
+        let plain = parse_line("example.com,10.0.0.1 ssh-ed25519 AAAAC3Nz host key\n").unwrap();
+        assert_eq!(plain.marker, None);
+        assert_eq!(plain.hosts, "example.com,10.0.0.1");
+        assert_eq!(plain.key_type, "ssh-ed25519");
+        assert_eq!(plain.key, "AAAAC3Nz");
+        assert_eq!(plain.comment, "host key");
+
+        let hashed = parse_line("|1|c2FsdA==|aGFzaA== ssh-rsa AAAAB3Nz").unwrap();
+        assert_eq!(hashed.hosts, "|1|c2FsdA==|aGFzaA==");
+        assert_eq!(hashed.comment, "");
+
+        let marker = parse_line("@revoked * ssh-rsa AAAAB3Nz").unwrap();
+        assert_eq!(marker.marker, Some("@revoked"));
+        assert_eq!(marker.hosts, "*");
+    }
+
+    #[test]
+    fn skips_blank_comment_and_short_lines() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("   # comment").is_none());
+        assert!(parse_line("example.com ssh-ed25519").is_none());
+        assert!(parse_line("@cert-authority *.example.com ssh-rsa").is_none());
+    }
+
+    #[test]
+    fn resolves_policy_from_connection_then_settings() {
+        let settings = ConnectionTimeoutSettings {
+            host_key_policy: "strict".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            HostKeyPolicy::resolve(Some("accept-new"), Some(&settings)),
+            HostKeyPolicy::AcceptNew
+        );
+        assert_eq!(
+            HostKeyPolicy::resolve(None, Some(&settings)),
+            HostKeyPolicy::Strict
+        );
+        assert_eq!(
+            HostKeyPolicy::resolve(Some("bogus"), None),
+            HostKeyPolicy::Ask
+        );
+        assert!(validate_host_key_policy("accept-new").is_ok());
+        assert!(validate_host_key_policy("yes").is_err());
+    }
+
+    #[test]
+    fn fingerprint_matches_openssh_format() {
+        // sha256("") in unpadded base64, as ssh-keygen -l would print it
+        assert_eq!(
+            key_type_name(b"\0\0\0\x0bssh-ed25519\0\0\0\x20"),
+            Some("ssh-ed25519")
+        );
+        assert_eq!(key_type_name(b"\0\0\0\x0bssh"), None);
+        assert_eq!(
+            fingerprint(b""),
+            "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+    }
+}
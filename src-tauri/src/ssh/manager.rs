@@ -1,13 +1,25 @@
-use super::connection::{ManagedSession, SessionSshPool};
+use super::authorized_keys;
+use super::client::{record_transfer_progress, AppState};
+use super::connection::{
+    probe_session, CachedFile, ManagedSession, PoolDiagnostics, SessionProbe, SessionSshPool,
+};
+use super::dir_watch::{run_dir_watch, DirWatchRequest};
 use super::heartbeat::{HeartbeatAction, HeartbeatManager, HeartbeatResult};
 use super::network_monitor::NetworkMonitor;
-use super::{emit_command_output, ExecStreamContext, ShellMsg};
+use super::scp;
+use super::transfer::RateLimiter;
+use super::trash::TrashAction;
+use super::utils::HashAlgorithm;
+use super::x11::{self, RawChannel, X11Display};
+use super::{emit_command_output, ExecStreamContext, ShellMsg, DEFAULT_EXEC_TIMEOUT_SECS};
 use crate::models::{
-    DiskUsage, FileEntry, HeartbeatSettings, NetworkAdaptiveSettings, ServerStatus,
+    AuthorizedKey, BatchItemError, ConnectionStatus, DiskUsage, FileEntry, HeartbeatSettings,
+    ItemCount, NetworkAdaptiveSettings, PathFreeSpace, RemoteFileStat, ServerStatus, TrashEntry,
 };
 use crate::ssh::file_ops::FilePageResponse;
 
-use std::io::{ErrorKind, Read, Write};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
@@ -40,18 +52,28 @@ pub enum ExecTarget {
 
 /// Commands sent to the SSH Manager Actor
 pub enum SshCommand {
-    /// Open a shell channel
+    /// Open a shell channel; output is delivered to `sender`
     ShellOpen {
+        shell_id: String,
+        term_type: String,
         cols: u16,
         rows: u16,
         sender: Sender<ShellMsg>,
     },
     /// Write data to shell
-    ShellWrite(Vec<u8>),
+    ShellWrite { shell_id: String, data: Vec<u8> },
     /// Resize shell
-    ShellResize { rows: u16, cols: u16 },
+    ShellResize {
+        shell_id: String,
+        rows: u16,
+        cols: u16,
+    },
     /// Close shell
-    ShellClose,
+    ShellClose { shell_id: String },
+    /// Send EOF on the shell channel
+    ShellEof { shell_id: String },
+    /// Send an RFC 4335 break request on the shell channel
+    ShellBreak { shell_id: String },
     /// Execute a single command
     Exec {
         command: String,
@@ -59,6 +81,28 @@ pub enum SshCommand {
         cancel_flag: Option<Arc<AtomicBool>>,
         target: ExecTarget,
         stream: Option<ExecStreamContext>,
+        /// Close the channel and fail once the command has run this long
+        timeout: Option<Duration>,
+    },
+    /// Execute a single command and report its exit status with the output
+    ExecWithStatus {
+        command: String,
+        listener: Sender<Result<(String, i32), String>>,
+        cancel_flag: Option<Arc<AtomicBool>>,
+        target: ExecTarget,
+    },
+    /// Execute a command under `sudo -S` on a PTY, answering the password prompt
+    ExecSudo {
+        command: String,
+        password: String,
+        listener: Sender<Result<String, String>>,
+        cancel_flag: Option<Arc<AtomicBool>>,
+    },
+    /// Run `passwd` on a PTY and answer its prompts
+    ChangePassword {
+        current_password: String,
+        new_password: String,
+        listener: Sender<Result<(), String>>,
     },
     /// List directory (SFTP)
     SftpLs {
@@ -78,6 +122,13 @@ pub enum SshCommand {
         max_len: Option<usize>, // Added max_len support
         listener: Sender<Result<Vec<u8>, String>>,
     },
+    /// Read a byte range of a file (SFTP)
+    SftpReadRange {
+        path: String,
+        offset: u64,
+        length: usize,
+        listener: Sender<Result<Vec<u8>, String>>,
+    },
     /// Write file (SFTP)
     SftpWrite {
         path: String,
@@ -85,16 +136,58 @@ pub enum SshCommand {
         mode: Option<String>,
         listener: Sender<Result<(), String>>,
     },
+    /// Read file with `cat` over an exec channel, skipping SFTP
+    CatRead {
+        path: String,
+        max_len: Option<usize>,
+        listener: Sender<Result<Vec<u8>, String>>,
+    },
+    /// Write file by piping it into `tee` over an exec channel, skipping SFTP
+    TeeWrite {
+        path: String,
+        content: Vec<u8>,
+        listener: Sender<Result<(), String>>,
+    },
     /// Create directory (SFTP)
     SftpMkdir {
         path: String,
         listener: Sender<Result<(), String>>,
     },
+    /// Create a directory and any missing parents (SFTP)
+    SftpMkdirAll {
+        path: String,
+        listener: Sender<Result<String, String>>,
+    },
+    /// Set a file's atime/mtime, creating it empty if missing (SFTP)
+    SftpTouch {
+        path: String,
+        time: u64,
+        listener: Sender<Result<(), String>>,
+    },
+    /// Copy a file server-side to the first free `duplicate_name` (SFTP)
+    SftpDuplicate {
+        path: String,
+        listener: Sender<Result<String, String>>,
+    },
     /// Create file (SFTP) - Empty
     SftpCreate {
         path: String,
         listener: Sender<Result<(), String>>,
     },
+    /// Whether files can be created in a directory (SFTP probe)
+    SftpCheckWritable {
+        path: String,
+        listener: Sender<Result<bool, String>>,
+    },
+    /// Parse the login user's `~/.ssh/authorized_keys` (SFTP)
+    ListAuthorizedKeys {
+        listener: Sender<Result<Vec<AuthorizedKey>, String>>,
+    },
+    /// Rewrite `~/.ssh/authorized_keys` without a key (SFTP)
+    RemoveAuthorizedKey {
+        fingerprint: String,
+        listener: Sender<Result<usize, String>>,
+    },
     /// Change permissions (SFTP)
     SftpChmod {
         path: String,
@@ -113,6 +206,58 @@ pub enum SshCommand {
         new_path: String,
         listener: Sender<Result<(), String>>,
     },
+    /// Count files, directories and bytes under a path, stopping past
+    /// `limit` files (SFTP)
+    SftpCountItems {
+        path: String,
+        limit: Option<u64>,
+        listener: Sender<Result<ItemCount, String>>,
+    },
+    /// Stat a file; `None` when it does not exist (SFTP)
+    SftpStat {
+        path: String,
+        listener: Sender<Result<Option<RemoteFileStat>, String>>,
+    },
+    /// Move to, list, restore from or empty the remote trash (SFTP)
+    SftpTrash {
+        action: TrashAction,
+        listener: Sender<Result<Vec<TrashEntry>, String>>,
+    },
+    /// Delete many items on one held SFTP session, reporting `batch-progress`
+    SftpBatchDelete {
+        paths: Vec<String>,
+        batch_id: String,
+        app_handle: tauri::AppHandle,
+        listener: Sender<Result<Vec<BatchItemError>, String>>,
+    },
+    /// Move many items into a directory on one held SFTP session
+    SftpBatchMove {
+        paths: Vec<String>,
+        target_dir: String,
+        batch_id: String,
+        app_handle: tauri::AppHandle,
+        listener: Sender<Result<Vec<BatchItemError>, String>>,
+    },
+    /// Change the permissions of many items on one held SFTP session
+    SftpBatchChmod {
+        paths: Vec<String>,
+        mode: u32,
+        recursive: bool,
+        batch_id: String,
+        app_handle: tauri::AppHandle,
+        listener: Sender<Result<Vec<BatchItemError>, String>>,
+    },
+    /// Change the numeric owner and/or group of many items on one held
+    /// SFTP session
+    SftpBatchChown {
+        paths: Vec<String>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        recursive: bool,
+        batch_id: String,
+        app_handle: tauri::AppHandle,
+        listener: Sender<Result<Vec<BatchItemError>, String>>,
+    },
     /// Download File (Streaming) - uses transfer_pool to avoid blocking general operations
     SftpDownload {
         remote_path: String,
@@ -121,6 +266,9 @@ pub enum SshCommand {
         app_handle: tauri::AppHandle,
         listener: Sender<Result<(), String>>,
         cancel_flag: Arc<AtomicBool>,
+        pause_flag: Arc<AtomicBool>,
+        /// Bytes per second for this transfer only; `None` uses the global limit
+        rate_limit: Option<u64>,
     },
     /// Upload File (Streaming) - uses transfer_pool to avoid blocking general operations
     SftpUpload {
@@ -130,6 +278,11 @@ pub enum SshCommand {
         app_handle: tauri::AppHandle,
         listener: Sender<Result<(), String>>,
         cancel_flag: Arc<AtomicBool>,
+        pause_flag: Arc<AtomicBool>,
+        /// Skip the pre-upload free-space check (for servers with unreliable statvfs)
+        skip_space_check: bool,
+        /// Bytes per second for this transfer only; `None` uses the global limit
+        rate_limit: Option<u64>,
     },
     /// Get server status (uses status session pool)
     GetServerStatus {
@@ -140,7 +293,42 @@ pub enum SshCommand {
         path: String,
         listener: Sender<Result<DiskUsage, String>>,
     },
+    /// Get free space of the filesystem backing a path (uses status session pool)
+    GetPathFreeSpace {
+        path: String,
+        listener: Sender<Result<PathFreeSpace, String>>,
+    },
+    /// Hash a remote file with sha256sum, falling back to md5sum (uses transfer
+    /// session pool, since it reads the whole file). `None` when neither tool
+    /// is available.
+    RemoteFileHash {
+        path: String,
+        algorithm: Option<HashAlgorithm>,
+        listener: Sender<Result<String, String>>,
+    },
+    /// Snapshot of the session pool; answered outside the worker pools so it
+    /// still works when they are saturated
+    GetPoolDiagnostics {
+        listener: Sender<Result<PoolDiagnostics, String>>,
+    },
+    /// Start a local port forward on its own session; answered outside the
+    /// worker pools since connecting can take a while. Replies with the
+    /// forward ID.
+    StartLocalForward {
+        session_id: String,
+        local_port: u16,
+        remote_host: String,
+        remote_port: u16,
+        listener: Sender<Result<String, String>>,
+    },
 
+    /// Watch a directory on a session of its own; answered outside the worker
+    /// pools since the watch runs until stopped. Replies with the method used
+    /// once watching.
+    WatchDirectory {
+        request: DirWatchRequest,
+        listener: Sender<Result<String, String>>,
+    },
     /// Shutdown the manager
     Shutdown,
 }
@@ -151,17 +339,403 @@ pub struct SshManager {
     receiver: Receiver<SshCommand>,
     shutdown_signal: Arc<AtomicBool>, // Shared with client to force shutdown if needed
 
-    // Active Channels
-    shell_channel: Option<ssh2::Channel>,
-    shell_sender: Option<Sender<ShellMsg>>,
+    // Active shell channels, keyed by shell id
+    shells: HashMap<String, ShellChannel>,
+    // Request agent forwarding on new shell channels
+    agent_forwarding: bool,
+    // Request X11 forwarding on new shell channels; the display is resolved
+    // when the first such shell opens
+    x11_forwarding: bool,
+    x11_display: Option<Arc<X11Display>>,
+    // Typed into every new shell channel (`Connection::init_command`)
+    init_command: Option<String>,
+    // `Connection::remote_rc`, and where it was uploaded when the manager started
+    remote_rc: Option<String>,
+    remote_rc_path: Option<String>,
 
     // Heartbeat Manager
     heartbeat_manager: HeartbeatManager,
+    idle_backoff: IdleBackoff,
+    // Stands in for the heartbeat while shells are open
+    last_shell_keepalive: Instant,
+
+    // Liveness reporting / auto-reconnect
+    status_monitor: Option<SessionStatusMonitor>,
+    last_status_check: Instant,
+    last_reported_status: Option<ConnectionStatus>,
+    // Set while a worker thread reconnects; shells wait for its session
+    reconnect: Option<Receiver<Result<ManagedSession, String>>>,
+
+    // Disconnect after this long without activity, then run the callback
+    idle_timeout: Option<Duration>,
+    on_idle_timeout: Option<Box<dyn FnOnce() + Send>>,
 
     // Network Monitor
     network_monitor: Arc<Mutex<NetworkMonitor>>,
 }
 
+const IDLE_WAIT_MIN: Duration = Duration::from_millis(5);
+const IDLE_WAIT_MAX: Duration = Duration::from_millis(200);
+/// Lower ceiling while a terminal is open so output after a quiet spell
+/// still shows up promptly.
+const SHELL_IDLE_WAIT_MAX: Duration = Duration::from_millis(50);
+/// Hashing reads the whole remote file, so allow for large ones.
+const REMOTE_HASH_TIMEOUT: Duration = Duration::from_secs(600);
+/// Prompt passed to `sudo -p` so the password request can be spotted in the
+/// PTY output and stripped from the result.
+const SUDO_PROMPT: &str = "__SSH_ASSISTANT_SUDO_PROMPT__";
+/// Error returned by `exec_command_sudo` when sudo rejects the password.
+pub(crate) const SUDO_INCORRECT_PASSWORD: &str = "Incorrect sudo password";
+
+/// Upper bound for a whole `passwd` run, prompts included.
+const PASSWD_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PasswdPrompt {
+    Current,
+    New,
+    Retype,
+}
+
+/// Recognise a `passwd` prompt on the last, unterminated line of output:
+/// PAM/shadow-utils ("Current password:", "(current) UNIX password:",
+/// "New password:", "Retype new password:") as well as the BSD and macOS
+/// wording ("Old Password:", "Retype New Password:").
+fn passwd_prompt(line: &str) -> Option<PasswdPrompt> {
+    let line = line.trim().to_lowercase();
+    if !line.ends_with(':') || !line.contains("password") {
+        return None;
+    }
+    if [
+        "retype", "re-enter", "reenter", "again", "confirm", "repeat",
+    ]
+    .iter()
+    .any(|word| line.contains(word))
+    {
+        Some(PasswdPrompt::Retype)
+    } else if line.contains("new") {
+        Some(PasswdPrompt::New)
+    } else {
+        Some(PasswdPrompt::Current)
+    }
+}
+
+/// The line in which `passwd` explains why the password was not changed,
+/// e.g. "passwd: password unchanged" or "BAD PASSWORD: it is too short".
+fn passwd_failure_reason(output: &str) -> Option<String> {
+    const MARKERS: &[&str] = &[
+        "unchanged",
+        "bad password",
+        "token manipulation error",
+        "authentication failure",
+        "do not match",
+        "must wait longer",
+        "exhausted maximum",
+        "too short",
+        "too simple",
+        "too similar",
+        "palindrome",
+        "dictionary",
+        "sorry",
+    ];
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| {
+            let lower = line.to_lowercase();
+            MARKERS.iter().any(|marker| lower.contains(marker))
+        })
+        .map(str::to_string)
+}
+
+/// Type `secret` and Enter into a non-blocking channel.
+fn write_secret_line(channel: &mut ssh2::Channel, secret: &str) -> Result<(), String> {
+    write_all_retrying(channel, format!("{}\n", secret).as_bytes())
+}
+
+/// Upper bound for the commands that create and remove the remote rc file.
+const REMOTE_RC_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Start an interactive bash reading `rc_path` instead of `~/.bashrc`, or an
+/// interactive sh reading it through `$ENV` where bash is missing. Run via
+/// `sh -c` so it works whatever the login shell is.
+fn remote_rc_shell_command(rc_path: &str) -> String {
+    format!(
+        "exec sh -c 'if command -v bash >/dev/null 2>&1; then exec bash --rcfile \"$0\" -i; else ENV=\"$0\" exec sh -i; fi' '{}'",
+        crate::ssh::file_ops::escape_shell_arg(rc_path)
+    )
+}
+
+/// `command` as typed into a shell: sent verbatim, ending with a newline.
+pub(crate) fn init_command_input(command: &str) -> String {
+    if command.ends_with('\n') {
+        command.to_string()
+    } else {
+        format!("{}\n", command)
+    }
+}
+
+/// How many `duplicate_name` candidates to try before giving up.
+pub(crate) const MAX_DUPLICATE_ATTEMPTS: u32 = 100;
+
+/// Candidate name for the `n`th attempt at duplicating `path`: `path.bak`,
+/// then `path.bak.1`, `path.bak.2`, ...
+pub(crate) fn duplicate_name(path: &str, n: u32) -> String {
+    match n {
+        0 => format!("{}.bak", path),
+        n => format!("{}.bak.{}", path, n),
+    }
+}
+
+/// Exponential idle wait for the manager loop: doubles on each idle pass
+/// from `IDLE_WAIT_MIN` up to a cap, and drops back on any activity.
+#[derive(Debug)]
+struct IdleBackoff {
+    current: Duration,
+}
+
+impl Default for IdleBackoff {
+    fn default() -> Self {
+        Self {
+            current: IDLE_WAIT_MIN,
+        }
+    }
+}
+
+impl IdleBackoff {
+    fn next(&mut self, cap: Duration) -> Duration {
+        let wait = self.current.min(cap);
+        self.current = (self.current * 2).min(IDLE_WAIT_MAX);
+        wait
+    }
+
+    fn reset(&mut self) {
+        self.current = IDLE_WAIT_MIN;
+    }
+}
+
+/// What the manager needs from a shell's channel: ssh2's own, or the raw
+/// channel a shell that forwards X11 runs on.
+pub(crate) trait ShellIo: Read + Write + Send {
+    fn request_pty(&mut self, term: &str, cols: u32, rows: u32) -> Result<(), ssh2::Error>;
+    fn request_pty_size(&mut self, cols: u32, rows: u32) -> Result<(), ssh2::Error>;
+    fn request_auth_agent_forwarding(&mut self) -> Result<(), ssh2::Error>;
+    fn process_startup(&mut self, request: &str, message: Option<&str>) -> Result<(), ssh2::Error>;
+    fn send_eof(&mut self) -> Result<(), ssh2::Error>;
+    fn close(&mut self) -> Result<(), ssh2::Error>;
+    fn wait_close(&mut self) -> Result<(), ssh2::Error>;
+    fn exit_status(&self) -> Result<i32, ssh2::Error>;
+}
+
+impl ShellIo for ssh2::Channel {
+    fn request_pty(&mut self, term: &str, cols: u32, rows: u32) -> Result<(), ssh2::Error> {
+        ssh2::Channel::request_pty(self, term, None, Some((cols, rows, 0, 0)))
+    }
+
+    fn request_pty_size(&mut self, cols: u32, rows: u32) -> Result<(), ssh2::Error> {
+        ssh2::Channel::request_pty_size(self, cols, rows, None, None)
+    }
+
+    fn request_auth_agent_forwarding(&mut self) -> Result<(), ssh2::Error> {
+        ssh2::Channel::request_auth_agent_forwarding(self)
+    }
+
+    fn process_startup(&mut self, request: &str, message: Option<&str>) -> Result<(), ssh2::Error> {
+        ssh2::Channel::process_startup(self, request, message)
+    }
+
+    fn send_eof(&mut self) -> Result<(), ssh2::Error> {
+        ssh2::Channel::send_eof(self)
+    }
+
+    fn close(&mut self) -> Result<(), ssh2::Error> {
+        ssh2::Channel::close(self)
+    }
+
+    fn wait_close(&mut self) -> Result<(), ssh2::Error> {
+        ssh2::Channel::wait_close(self)
+    }
+
+    fn exit_status(&self) -> Result<i32, ssh2::Error> {
+        ssh2::Channel::exit_status(self)
+    }
+}
+
+/// An interactive shell on the main session and the sink for its output.
+struct ShellChannel {
+    channel: Box<dyn ShellIo>,
+    sender: Sender<ShellMsg>,
+    // PTY parameters, kept so the shell can be reopened after a reconnect
+    term_type: String,
+    cols: u16,
+    rows: u16,
+}
+
+/// Receives each status change with optional details.
+pub type StatusEmitter = Box<dyn Fn(ConnectionStatus, Option<&str>) + Send>;
+
+/// Periodic liveness reporting for the manager's session. Every `interval`
+/// the session is probed and `emit` is called when its status changes; a
+/// session found dead gets one reconnect attempt when `auto_reconnect` is set.
+pub struct SessionStatusMonitor {
+    pub interval: Duration,
+    pub auto_reconnect: bool,
+    pub emit: StatusEmitter,
+}
+
+/// Write all of `content`, waiting out `WouldBlock`. A write that accepts
+/// nothing is an error rather than a reason to spin forever.
+pub(crate) fn write_all_retrying(writer: &mut impl Write, content: &[u8]) -> Result<(), String> {
+    let mut pos = 0;
+    while pos < content.len() {
+        match writer.write(&content[pos..]) {
+            Ok(0) => {
+                return Err(format!(
+                    "Write stalled after {} of {} bytes",
+                    pos,
+                    content.len()
+                ))
+            }
+            Ok(n) => pos += n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+type DirListing = Vec<(std::path::PathBuf, ssh2::FileStat)>;
+
+fn sftp_readdir(sftp: &ssh2::Sftp, dir: &Path) -> Result<DirListing, String> {
+    crate::ssh::utils::ssh2_retry(|| sftp.readdir(dir)).map_err(|e| e.to_string())
+}
+
+/// Depth-first walk of the tree under `dir`, reading each directory with
+/// `list`. `visit` sees every entry, a directory after its contents, and
+/// returns `false` to stop the walk, which then returns `false` as well.
+fn walk_tree<L, F>(list: &mut L, dir: &Path, visit: &mut F) -> Result<bool, String>
+where
+    L: FnMut(&Path) -> Result<DirListing, String>,
+    F: FnMut(&Path, &ssh2::FileStat) -> Result<bool, String>,
+{
+    for (child, stat) in list(dir)? {
+        let Some(name) = child.file_name() else {
+            continue;
+        };
+        if name == "." || name == ".." {
+            continue;
+        }
+        if stat.is_dir() && !walk_tree(list, &child, visit)? {
+            return Ok(false);
+        }
+        if !visit(&child, &stat)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Tally the files, directories and bytes under `dir`. With a `limit` the
+/// walk stops at the first file past it and the count is marked truncated.
+fn count_tree<L>(list: &mut L, dir: &Path, limit: Option<u64>) -> Result<ItemCount, String>
+where
+    L: FnMut(&Path) -> Result<DirListing, String>,
+{
+    let mut counts = ItemCount::default();
+    walk_tree(list, dir, &mut |_, stat| {
+        if stat.is_dir() {
+            counts.dir_count += 1;
+        } else if limit.is_some_and(|limit| counts.file_count >= limit) {
+            counts.truncated = true;
+            return Ok(false);
+        } else {
+            counts.file_count += 1;
+            counts.total_size += stat.size.unwrap_or(0);
+        }
+        Ok(true)
+    })?;
+    Ok(counts)
+}
+
+/// Status reported for a probe of the main session.
+fn probe_status(probe: SessionProbe) -> ConnectionStatus {
+    match probe {
+        SessionProbe::Keepalive => ConnectionStatus::Connected,
+        SessionProbe::Channel => ConnectionStatus::Degraded,
+        SessionProbe::Dead => ConnectionStatus::Disconnected,
+    }
+}
+
+/// What the status monitor does with a fresh probe result.
+#[derive(Debug, PartialEq)]
+enum StatusStep {
+    Unchanged,
+    Report(ConnectionStatus),
+    Reconnect,
+}
+
+/// A session newly found dead gets one reconnect attempt; if that fails the
+/// status stays `Disconnected` until a probe succeeds again.
+fn status_step(
+    previous: Option<ConnectionStatus>,
+    current: ConnectionStatus,
+    auto_reconnect: bool,
+) -> StatusStep {
+    if current == ConnectionStatus::Disconnected
+        && auto_reconnect
+        && previous != Some(ConnectionStatus::Disconnected)
+    {
+        StatusStep::Reconnect
+    } else if previous == Some(current) {
+        StatusStep::Unchanged
+    } else {
+        StatusStep::Report(current)
+    }
+}
+
+/// Outcome of one non-blocking read from a shell channel.
+#[derive(Debug, PartialEq)]
+enum ShellPoll {
+    Data,
+    Idle,
+    Closed,
+}
+
+/// Read once from `reader` and forward whatever arrived. EOF and read errors
+/// report `Closed`; closing the channel and notifying the UI is left to the
+/// caller.
+fn poll_shell_output<R: Read>(
+    reader: &mut R,
+    sender: &Sender<ShellMsg>,
+    shell_id: &str,
+) -> ShellPoll {
+    let mut buf = [0u8; 4096];
+    match reader.read(&mut buf) {
+        Ok(0) => ShellPoll::Closed,
+        Ok(n) => {
+            let _ = sender.send(ShellMsg::Data(buf[..n].to_vec()));
+            ShellPoll::Data
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => ShellPoll::Idle,
+        Err(e) => {
+            eprintln!("Shell {} error: {}", shell_id, e);
+            ShellPoll::Closed
+        }
+    }
+}
+
+/// Close a finished shell channel and read its exit status, or -1 if the
+/// server never sent one (e.g. the connection dropped).
+fn finish_shell_channel(channel: &mut dyn ShellIo) -> i32 {
+    let _ = channel.close();
+    if crate::ssh::utils::ssh2_retry(|| channel.wait_close()).is_err() {
+        return -1;
+    }
+    channel.exit_status().unwrap_or(-1)
+}
+
 type OperationTask = Box<dyn FnOnce(SessionSshPool) + Send + 'static>;
 
 #[derive(Clone)]
@@ -170,11 +744,14 @@ struct WorkerPool {
 }
 
 impl WorkerPool {
+    /// `tracks_activity` marks tasks as user activity for the idle timeout;
+    /// background polling (the status bar) must not keep a session alive.
     fn new(
         name: &str,
         size: usize,
         pool: SessionSshPool,
         shutdown_signal: Arc<AtomicBool>,
+        tracks_activity: bool,
     ) -> Self {
         let (sender, receiver) = std::sync::mpsc::channel::<OperationTask>();
         let shared_receiver = Arc::new(Mutex::new(receiver));
@@ -201,7 +778,10 @@ impl WorkerPool {
                     };
 
                     match task {
-                        Ok(task) => task(pool.clone()),
+                        Ok(task) => {
+                            let _activity = tracks_activity.then(|| pool.begin_activity());
+                            task(pool.clone())
+                        }
                         Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
                         Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
                     }
@@ -242,26 +822,36 @@ impl OpsScheduler {
                 pool.ai_capacity(),
                 pool.clone(),
                 shutdown_signal.clone(),
+                true,
             ),
             metadata: WorkerPool::new(
                 "metadata",
                 metadata_capacity,
                 pool.clone(),
                 shutdown_signal.clone(),
+                true,
             ),
             mutate: WorkerPool::new(
                 "mutate",
                 mutate_capacity,
                 pool.clone(),
                 shutdown_signal.clone(),
+                true,
             ),
             transfer: WorkerPool::new(
                 "transfer",
                 transfer_capacity,
                 pool.clone(),
                 shutdown_signal.clone(),
+                true,
+            ),
+            status: WorkerPool::new(
+                "status",
+                pool.status_capacity(),
+                pool,
+                shutdown_signal,
+                false,
             ),
-            status: WorkerPool::new("status", pool.status_capacity(), pool, shutdown_signal),
         }
     }
 
@@ -273,6 +863,7 @@ impl OpsScheduler {
                 cancel_flag,
                 target,
                 stream,
+                timeout,
             } => {
                 let worker = match target {
                     ExecTarget::Ai => &self.ai,
@@ -287,12 +878,68 @@ impl OpsScheduler {
                         cancel_flag.as_ref(),
                         target,
                         stream.as_ref(),
+                        timeout,
+                    );
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::ExecWithStatus {
+                command,
+                listener,
+                cancel_flag,
+                target,
+            } => {
+                let worker = match target {
+                    ExecTarget::Ai => &self.ai,
+                    ExecTarget::FileBrowser => &self.metadata,
+                    ExecTarget::Status => &self.status,
+                };
+                let reply = listener.clone();
+                if let Err(error) = worker.submit(move |pool| {
+                    let res = SshManager::bg_exec_with_status(
+                        pool,
+                        &command,
+                        cancel_flag.as_ref(),
+                        target,
+                        None,
+                        None,
                     );
                     let _ = reply.send(res);
                 }) {
                     let _ = listener.send(Err(error));
                 }
             }
+            SshCommand::ExecSudo {
+                command,
+                password,
+                listener,
+                cancel_flag,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res =
+                        SshManager::bg_exec_sudo(pool, &command, &password, cancel_flag.as_ref());
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::ChangePassword {
+                current_password,
+                new_password,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res =
+                        SshManager::bg_change_password(pool, &current_password, &new_password);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
             SshCommand::SftpLs { path, listener } => {
                 let reply = listener.clone();
                 if let Err(error) = self.metadata.submit(move |pool| {
@@ -329,6 +976,20 @@ impl OpsScheduler {
                     let _ = listener.send(Err(error));
                 }
             }
+            SshCommand::SftpReadRange {
+                path,
+                offset,
+                length,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res = SshManager::bg_sftp_read_range(pool, &path, offset, length);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
             SshCommand::SftpWrite {
                 path,
                 content,
@@ -343,57 +1004,150 @@ impl OpsScheduler {
                     let _ = listener.send(Err(error));
                 }
             }
-            SshCommand::SftpMkdir { path, listener } => {
+            SshCommand::CatRead {
+                path,
+                max_len,
+                listener,
+            } => {
                 let reply = listener.clone();
-                if let Err(error) = self.mutate.submit(move |pool| {
-                    let res = SshManager::bg_sftp_simple(pool, &path, |sftp, p| {
-                        sftp.mkdir(p, 0o755).map_err(|e| e.to_string())
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res = SshManager::with_file_browser_session(pool, |sess| {
+                        scp::exec_read_file(sess, &path, max_len)
                     });
                     let _ = reply.send(res);
                 }) {
                     let _ = listener.send(Err(error));
                 }
             }
-            SshCommand::SftpCreate { path, listener } => {
+            SshCommand::TeeWrite {
+                path,
+                content,
+                listener,
+            } => {
                 let reply = listener.clone();
                 if let Err(error) = self.mutate.submit(move |pool| {
-                    let res = SshManager::bg_sftp_simple(pool, &path, |sftp, p| {
-                        sftp.create(p).map_err(|e| e.to_string()).map(|_| ())
+                    let res = SshManager::with_file_browser_session(pool, |sess| {
+                        scp::exec_write_file(sess, &path, &content, false)
                     });
                     let _ = reply.send(res);
                 }) {
                     let _ = listener.send(Err(error));
                 }
             }
-            SshCommand::SftpChmod {
-                path,
-                mode,
-                listener,
-            } => {
+            SshCommand::SftpMkdir { path, listener } => {
                 let reply = listener.clone();
                 if let Err(error) = self.mutate.submit(move |pool| {
-                    let res = SshManager::bg_sftp_simple(pool, &path, move |sftp, p| {
-                        sftp.setstat(
-                            p,
-                            ssh2::FileStat {
-                                perm: Some(mode),
-                                size: None,
-                                uid: None,
-                                gid: None,
-                                atime: None,
-                                mtime: None,
-                            },
-                        )
-                        .map_err(|e| e.to_string())
+                    let res = SshManager::bg_sftp_simple(pool, &path, |sftp, p| {
+                        sftp.mkdir(p, 0o755).map_err(|e| e.to_string())
                     });
                     let _ = reply.send(res);
                 }) {
                     let _ = listener.send(Err(error));
                 }
             }
-            SshCommand::SftpDelete {
-                path,
-                is_dir,
+            SshCommand::SftpMkdirAll { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_mkdir_all(pool, &path);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpTouch {
+                path,
+                time,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_simple(pool, &path, |sftp, p| {
+                        SshManager::sftp_touch(sftp, p, time)
+                    });
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpDuplicate { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_duplicate(pool, &path);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpCreate { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_simple(pool, &path, |sftp, p| {
+                        sftp.create(p).map_err(|e| e.to_string()).map(|_| ())
+                    });
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpCheckWritable { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_check_writable(pool, &path);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::ListAuthorizedKeys { listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res = SshManager::bg_list_authorized_keys(pool);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::RemoveAuthorizedKey {
+                fingerprint,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_remove_authorized_key(pool, &fingerprint);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpChmod {
+                path,
+                mode,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_simple(pool, &path, move |sftp, p| {
+                        sftp.setstat(
+                            p,
+                            ssh2::FileStat {
+                                perm: Some(mode),
+                                size: None,
+                                uid: None,
+                                gid: None,
+                                atime: None,
+                                mtime: None,
+                            },
+                        )
+                        .map_err(|e| e.to_string())
+                    });
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpDelete {
+                path,
+                is_dir,
                 listener,
             } => {
                 let reply = listener.clone();
@@ -417,6 +1171,123 @@ impl OpsScheduler {
                     let _ = listener.send(Err(error));
                 }
             }
+            SshCommand::SftpCountItems {
+                path,
+                limit,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_count_items(pool, &path, limit);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpStat { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.metadata.submit(move |pool| {
+                    let res = SshManager::bg_sftp_stat(pool, &path);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpTrash { action, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::with_file_browser_sftp(pool, |sftp| {
+                        super::trash::run_sftp(sftp, &action)
+                    });
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpBatchDelete {
+                paths,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res =
+                        SshManager::bg_sftp_batch_delete(pool, &paths, &batch_id, &app_handle);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpBatchMove {
+                paths,
+                target_dir,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_batch_move(
+                        pool,
+                        &paths,
+                        &target_dir,
+                        &batch_id,
+                        &app_handle,
+                    );
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpBatchChmod {
+                paths,
+                mode,
+                recursive,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_batch_chmod(
+                        pool,
+                        &paths,
+                        mode,
+                        recursive,
+                        &batch_id,
+                        &app_handle,
+                    );
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::SftpBatchChown {
+                paths,
+                uid,
+                gid,
+                recursive,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.mutate.submit(move |pool| {
+                    let res = SshManager::bg_sftp_batch_chown(
+                        pool,
+                        &paths,
+                        uid,
+                        gid,
+                        recursive,
+                        &batch_id,
+                        &app_handle,
+                    );
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
             SshCommand::SftpDownload {
                 remote_path,
                 local_path,
@@ -424,6 +1295,8 @@ impl OpsScheduler {
                 app_handle,
                 listener,
                 cancel_flag,
+                pause_flag,
+                rate_limit,
             } => {
                 let reply = listener.clone();
                 if let Err(error) = self.transfer.submit(move |pool| {
@@ -434,6 +1307,8 @@ impl OpsScheduler {
                         &transfer_id,
                         &app_handle,
                         &cancel_flag,
+                        &pause_flag,
+                        rate_limit,
                     );
                     let _ = reply.send(res);
                 }) {
@@ -447,6 +1322,9 @@ impl OpsScheduler {
                 app_handle,
                 listener,
                 cancel_flag,
+                skip_space_check,
+                pause_flag,
+                rate_limit,
             } => {
                 let reply = listener.clone();
                 if let Err(error) = self.transfer.submit(move |pool| {
@@ -457,6 +1335,9 @@ impl OpsScheduler {
                         &transfer_id,
                         &app_handle,
                         &cancel_flag,
+                        &pause_flag,
+                        skip_space_check,
+                        rate_limit,
                     );
                     let _ = reply.send(res);
                 }) {
@@ -481,11 +1362,38 @@ impl OpsScheduler {
                     let _ = listener.send(Err(error));
                 }
             }
+            SshCommand::GetPathFreeSpace { path, listener } => {
+                let reply = listener.clone();
+                if let Err(error) = self.status.submit(move |pool| {
+                    let res = SshManager::bg_get_path_free_space(pool, &path);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
+            SshCommand::RemoteFileHash {
+                path,
+                algorithm,
+                listener,
+            } => {
+                let reply = listener.clone();
+                if let Err(error) = self.transfer.submit(move |pool| {
+                    let res = SshManager::bg_remote_file_hash(pool, &path, algorithm);
+                    let _ = reply.send(res);
+                }) {
+                    let _ = listener.send(Err(error));
+                }
+            }
             SshCommand::Shutdown
+            | SshCommand::GetPoolDiagnostics { .. }
+            | SshCommand::StartLocalForward { .. }
+            | SshCommand::WatchDirectory { .. }
             | SshCommand::ShellOpen { .. }
-            | SshCommand::ShellWrite(_)
+            | SshCommand::ShellWrite { .. }
             | SshCommand::ShellResize { .. }
-            | SshCommand::ShellClose => {}
+            | SshCommand::ShellClose { .. }
+            | SshCommand::ShellEof { .. }
+            | SshCommand::ShellBreak { .. } => {}
         }
     }
 }
@@ -522,13 +1430,72 @@ impl SshManager {
             pool,
             receiver,
             shutdown_signal,
-            shell_channel: None,
-            shell_sender: None,
+            shells: HashMap::new(),
+            agent_forwarding: false,
+            x11_forwarding: false,
+            x11_display: None,
+            init_command: None,
+            remote_rc: None,
+            remote_rc_path: None,
             heartbeat_manager,
+            idle_backoff: IdleBackoff::default(),
+            last_shell_keepalive: Instant::now(),
+            idle_timeout: None,
+            on_idle_timeout: None,
+            status_monitor: None,
+            last_status_check: Instant::now(),
+            last_reported_status: None,
+            reconnect: None,
             network_monitor,
         }
     }
 
+    /// Request auth-agent forwarding on every shell channel opened later.
+    pub fn with_agent_forwarding(mut self, enabled: bool) -> Self {
+        self.agent_forwarding = enabled;
+        self
+    }
+
+    /// Request X11 forwarding to the local display on every shell channel
+    /// opened later.
+    pub fn with_x11_forwarding(mut self, enabled: bool) -> Self {
+        self.x11_forwarding = enabled;
+        self
+    }
+
+    /// Type `command` into every shell opened later, including the shells
+    /// reopened after a reconnect. Blank commands are ignored.
+    pub fn with_init_command(mut self, command: Option<String>) -> Self {
+        self.init_command = command.filter(|command| !command.trim().is_empty());
+        self
+    }
+
+    /// Start shells with `rc` as their startup file. It is uploaded once when
+    /// the manager starts running, before any shell opens, and deleted on
+    /// shutdown. Blank files are ignored.
+    pub fn with_remote_rc(mut self, rc: Option<String>) -> Self {
+        self.remote_rc = rc.filter(|rc| !rc.trim().is_empty());
+        self
+    }
+
+    /// Report session liveness (and reconnect a dead session) via `monitor`.
+    pub fn with_status_monitor(mut self, monitor: SessionStatusMonitor) -> Self {
+        self.status_monitor = Some(monitor);
+        self
+    }
+
+    /// Shut down once the pool has seen no activity for `timeout`, calling
+    /// `on_timeout` first so the client can be removed and the UI notified.
+    pub fn with_idle_timeout(
+        mut self,
+        timeout: Duration,
+        on_timeout: Box<dyn FnOnce() + Send>,
+    ) -> Self {
+        self.idle_timeout = Some(timeout);
+        self.on_idle_timeout = Some(on_timeout);
+        self
+    }
+
     /// Update heartbeat settings at runtime
     pub fn update_heartbeat_settings(&mut self, settings: HeartbeatSettings) {
         self.heartbeat_manager.update_settings(settings);
@@ -562,7 +1529,7 @@ impl SshManager {
         receiver: Receiver<SshCommand>,
         shutdown_signal: Arc<AtomicBool>,
     ) {
-        let scheduler = OpsScheduler::new(pool, shutdown_signal.clone());
+        let scheduler = OpsScheduler::new(pool.clone(), shutdown_signal.clone());
 
         loop {
             if shutdown_signal.load(Ordering::Relaxed) {
@@ -575,17 +1542,50 @@ impl SshManager {
                 Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
             };
 
+            Self::invalidate_file_handles(&pool, &cmd);
             match cmd {
                 SshCommand::Shutdown => {
                     shutdown_signal.store(true, Ordering::Relaxed);
                     break;
                 }
+                SshCommand::GetPoolDiagnostics { listener } => {
+                    let pool = pool.clone();
+                    thread::spawn(move || {
+                        let _ = listener.send(Ok(pool.diagnostics()));
+                    });
+                }
+                SshCommand::StartLocalForward {
+                    session_id,
+                    local_port,
+                    remote_host,
+                    remote_port,
+                    listener,
+                } => {
+                    let pool = pool.clone();
+                    thread::spawn(move || {
+                        let res = pool.start_local_forward(
+                            &session_id,
+                            local_port,
+                            remote_host,
+                            remote_port,
+                        );
+                        let _ = listener.send(res);
+                    });
+                }
+                SshCommand::WatchDirectory { request, listener } => {
+                    let pool = pool.clone();
+                    let closed = shutdown_signal.clone();
+                    thread::spawn(move || run_dir_watch(pool, request, closed, listener));
+                }
                 other => scheduler.dispatch(other),
             }
         }
     }
 
     pub fn run(&mut self) {
+        // Before any shell is pumped, so a slow server cannot stall open terminals
+        self.upload_remote_rc();
+
         loop {
             // 1. Check for shutdown
             if self.shutdown_signal.load(Ordering::Relaxed) {
@@ -606,54 +1606,41 @@ impl SshManager {
                 }
             }
 
-            // 3. Poll Shell Channel Output
-            // Correct logic attempt 2:
-            // We can't easily `take` and match without putting back in every branch.
-            // But `shell_channel` is `Option`.
-            // Let's use `if let Some(channel) = &mut self.shell_channel`
-            // But `read` requires `&mut Channel`.
-
-            let mut shell_channel_closed = false;
-            if let Some(channel) = &mut self.shell_channel {
-                let mut buf = [0u8; 4096];
-                match channel.read(&mut buf) {
-                    Ok(0) => {
-                        // EOF
-                        let _ = channel.close();
-                        if let Some(tx) = &self.shell_sender {
-                            let _ = tx.send(ShellMsg::Exit);
-                        }
-                        shell_channel_closed = true;
-                    }
-                    Ok(n) => {
-                        activity = true;
-                        if let Some(tx) = &self.shell_sender {
-                            let _ = tx.send(ShellMsg::Data(buf[..n].to_vec()));
-                        }
-                    }
-                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                        // wait
-                        // thread::sleep(Duration::from_millis(5)); // sleep at end of loop
+            // 3. Poll Shell Channel Output. A shell that hit EOF or an error is
+            // closed and dropped here, so later commands for it are no-ops,
+            // unless the whole connection died and a reconnect can reopen it.
+            // While reconnecting the old channels are dead and left alone.
+            if self.reconnect.is_none() {
+                let mut closed_shells = Vec::new();
+                for (shell_id, shell) in self.shells.iter_mut() {
+                    match poll_shell_output(&mut shell.channel, &shell.sender, shell_id) {
+                        ShellPoll::Data => activity = true,
+                        ShellPoll::Idle => {}
+                        ShellPoll::Closed => closed_shells.push(shell_id.clone()),
                     }
-                    Err(e) => {
-                        eprintln!("Shell error: {}", e);
-                        let _ = channel.close();
-                        if let Some(tx) = &self.shell_sender {
-                            let _ = tx.send(ShellMsg::Exit);
+                }
+                if self.accept_x11_channels() {
+                    activity = true;
+                }
+                if !closed_shells.is_empty() && self.connection_lost() {
+                    self.start_reconnect();
+                } else {
+                    for shell_id in closed_shells {
+                        if let Some(mut shell) = self.shells.remove(&shell_id) {
+                            let status = finish_shell_channel(shell.channel.as_mut());
+                            let _ = shell.sender.send(ShellMsg::Exited(status));
                         }
-                        shell_channel_closed = true;
                     }
                 }
             }
-            if shell_channel_closed {
-                self.shell_channel = None;
-                self.shell_sender = None;
+            if self.poll_reconnect() {
+                activity = true;
             }
 
             // 4. Maintenance checks.
             // Important: when terminal is active, avoid running potentially blocking heartbeat checks
             // in this loop to keep command input responsive.
-            if self.shell_channel.is_none() {
+            if self.shells.is_empty() {
                 let heartbeat_result = self.heartbeat_manager.perform_heartbeat(&self.session);
 
                 self.tick_network_monitor();
@@ -718,25 +1705,75 @@ impl SshManager {
                         self.heartbeat_manager.reset();
                     }
                 }
+            } else if !activity {
+                self.send_shell_keepalive();
+            }
+
+            // 5. Liveness reporting; runs with terminals open too, since a dead
+            // session otherwise goes unnoticed until the user types.
+            self.check_session_status();
+
+            // 6. Idle auto-disconnect
+            if activity {
+                self.pool.touch_activity();
+            } else if let Some(limit) = self.idle_timeout {
+                if self.pool.idle_duration() >= limit {
+                    eprintln!("[Idle] No activity for {:?}, disconnecting", limit);
+                    self.shutdown_signal.store(true, Ordering::Relaxed);
+                    if let Some(on_timeout) = self.on_idle_timeout.take() {
+                        on_timeout();
+                    }
+                    break;
+                }
             }
 
-            // 5. Sleep if idle
-            if !activity {
-                let sleep_duration = if self.shell_channel.is_some() {
-                    // Active terminal loop should stay highly responsive.
-                    Duration::from_millis(5)
+            // 7. Back off while idle. Waiting on the command channel (rather
+            // than sleeping) keeps keystrokes instant however long the wait;
+            // only remote output is subject to the backoff.
+            if activity {
+                self.idle_backoff.reset();
+            } else {
+                let cap = if !self.shells.is_empty() {
+                    SHELL_IDLE_WAIT_MAX
                 } else {
+                    // Never sleep past a keepalive/heartbeat check
                     self.heartbeat_manager
                         .get_min_check_interval()
-                        .min(Duration::from_millis(100))
+                        .clamp(IDLE_WAIT_MIN, IDLE_WAIT_MAX)
                 };
-                thread::sleep(sleep_duration);
+                let wait = self.idle_backoff.next(cap);
+                match self.receiver.recv_timeout(wait) {
+                    Ok(cmd) => {
+                        self.handle_command(cmd);
+                        self.pool.touch_activity();
+                        self.idle_backoff.reset();
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => thread::sleep(wait),
+                }
             }
         }
 
         // Cleanup
-        if let Some(mut channel) = self.shell_channel.take() {
-            let _ = channel.close();
+        for (_, mut shell) in self.shells.drain() {
+            let _ = shell.channel.close();
+        }
+        x11::forget(&self.session);
+        if let Some(path) = self.remote_rc_path.take() {
+            let command = format!(
+                "rm -f -- '{}'",
+                crate::ssh::file_ops::escape_shell_arg(&path)
+            );
+            if let Err(e) = Self::bg_exec(
+                self.pool.clone(),
+                &command,
+                None,
+                ExecTarget::FileBrowser,
+                None,
+                Some(REMOTE_RC_TIMEOUT),
+            ) {
+                eprintln!("Failed to remove remote rc file {}: {}", path, e);
+            }
         }
         let _ = self.session.disconnect(None, "Shutdown", None);
         self.pool.close_all();
@@ -747,58 +1784,111 @@ impl SshManager {
             SshCommand::Shutdown => {
                 self.shutdown_signal.store(true, Ordering::Relaxed);
             }
-            SshCommand::ShellOpen { cols, rows, sender } => {
-                // If shell exists, close it
-                if let Some(mut c) = self.shell_channel.take() {
-                    let _ = c.close();
-                }
-
-                // Create new channel using the main session
-                match crate::ssh::utils::ssh2_retry(|| self.session.channel_session()) {
-                    Ok(mut channel) => {
-                        // Non-blocking is already set on session
-                        // Standard setup
-                        if let Err(e) = crate::ssh::utils::ssh2_retry(|| {
-                            channel.request_pty(
-                                "xterm",
-                                None,
-                                Some((cols.into(), rows.into(), 0, 0)),
-                            )
-                        }) {
-                            eprintln!("Failed to request PTY: {}", e);
-                            return;
-                        }
-                        if let Err(e) = crate::ssh::utils::ssh2_retry(|| channel.shell()) {
-                            eprintln!("Failed to start shell: {}", e);
-                            return;
-                        }
-                        self.shell_channel = Some(channel);
-                        self.shell_sender = Some(sender);
+            SshCommand::ShellOpen {
+                shell_id,
+                term_type,
+                cols,
+                rows,
+                sender,
+            } => {
+                self.open_shell(shell_id, term_type, cols, rows, sender);
+            }
+            SshCommand::ShellWrite { shell_id, data } => {
+                if let Some(shell) = self.shells.get_mut(&shell_id) {
+                    let _ = shell.channel.write_all(&data);
+                }
+            }
+            SshCommand::ShellResize {
+                shell_id,
+                rows,
+                cols,
+            } => {
+                if let Some(shell) = self.shells.get_mut(&shell_id) {
+                    let _ = shell.channel.request_pty_size(cols.into(), rows.into());
+                    shell.cols = cols;
+                    shell.rows = rows;
+                }
+            }
+            SshCommand::ShellClose { shell_id } => {
+                if let Some(mut shell) = self.shells.remove(&shell_id) {
+                    let _ = shell.channel.close();
+                }
+            }
+            SshCommand::ShellEof { shell_id } => {
+                if let Some(shell) = self.shells.get_mut(&shell_id) {
+                    let channel = &mut shell.channel;
+                    if let Err(e) = crate::ssh::utils::ssh2_retry(|| channel.send_eof()) {
+                        eprintln!("Failed to send EOF to shell: {}", e);
                     }
-                    Err(e) => eprintln!("Failed to create shell channel: {}", e),
                 }
             }
-            SshCommand::ShellWrite(data) => {
-                if let Some(channel) = &mut self.shell_channel {
-                    let _ = channel.write_all(&data);
+            SshCommand::ShellBreak { shell_id } => {
+                if let Some(shell) = self.shells.get_mut(&shell_id) {
+                    let channel = &mut shell.channel;
+                    // The empty message encodes a zero break-length, which
+                    // leaves the duration to the server.
+                    if let Err(e) =
+                        crate::ssh::utils::ssh2_retry(|| channel.process_startup("break", Some("")))
+                    {
+                        eprintln!("Failed to send break to shell: {}", e);
+                    }
                 }
             }
-            SshCommand::ShellResize { rows, cols } => {
-                if let Some(channel) = &mut self.shell_channel {
-                    let _ = channel.request_pty_size(cols.into(), rows.into(), None, None);
+            other => {
+                Self::handle_ops_command(self.pool.clone(), self.shutdown_signal.clone(), other)
+            }
+        }
+    }
+
+    /// Forget the cached file handles a command is about to make stale.
+    /// Commands that may touch arbitrary files (exec, sudo) are left to the
+    /// handles' short TTL.
+    fn invalidate_file_handles(pool: &SessionSshPool, cmd: &SshCommand) {
+        if !pool.sftp_handle_cache() {
+            return;
+        }
+        match cmd {
+            SshCommand::SftpWrite { path, .. }
+            | SshCommand::TeeWrite { path, .. }
+            | SshCommand::SftpCreate { path, .. }
+            | SshCommand::SftpDelete { path, .. }
+            | SshCommand::SftpTrash {
+                action: TrashAction::Trash { path, .. },
+                ..
+            } => pool.invalidate_file_handles(path),
+            SshCommand::SftpRename {
+                old_path, new_path, ..
+            } => {
+                pool.invalidate_file_handles(old_path);
+                pool.invalidate_file_handles(new_path);
+            }
+            SshCommand::SftpUpload { remote_path, .. } => pool.invalidate_file_handles(remote_path),
+            SshCommand::SftpBatchDelete { paths, .. } => {
+                for path in paths {
+                    pool.invalidate_file_handles(path);
                 }
             }
-            SshCommand::ShellClose => {
-                if let Some(mut channel) = self.shell_channel.take() {
-                    let _ = channel.close();
+            SshCommand::SftpBatchMove {
+                paths, target_dir, ..
+            } => {
+                for path in paths {
+                    pool.invalidate_file_handles(path);
                 }
-                self.shell_sender = None;
+                pool.invalidate_file_handles(target_dir);
             }
-            other => Self::handle_ops_command(self.pool.clone(), other),
+            // The restored path is only known to the trash, and the key file
+            // is addressed relative to home rather than by the path it is read as
+            SshCommand::SftpTrash {
+                action: TrashAction::Restore { .. },
+                ..
+            }
+            | SshCommand::RemoveAuthorizedKey { .. } => pool.clear_file_handles(),
+            _ => {}
         }
     }
 
-    fn handle_ops_command(pool: SessionSshPool, cmd: SshCommand) {
+    fn handle_ops_command(pool: SessionSshPool, shutdown_signal: Arc<AtomicBool>, cmd: SshCommand) {
+        Self::invalidate_file_handles(&pool, &cmd);
         match cmd {
             SshCommand::Exec {
                 command,
@@ -806,6 +1896,7 @@ impl SshManager {
                 cancel_flag,
                 target,
                 stream,
+                timeout,
             } => {
                 let pool = pool.clone();
                 thread::spawn(move || {
@@ -815,11 +1906,54 @@ impl SshManager {
                         cancel_flag.as_ref(),
                         target,
                         stream.as_ref(),
+                        timeout,
                     );
                     let _ = listener.send(res);
                 });
             }
-            SshCommand::SftpLs { path, listener } => {
+            SshCommand::ExecWithStatus {
+                command,
+                listener,
+                cancel_flag,
+                target,
+            } => {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let res = Self::bg_exec_with_status(
+                        pool,
+                        &command,
+                        cancel_flag.as_ref(),
+                        target,
+                        None,
+                        None,
+                    );
+                    let _ = listener.send(res);
+                });
+            }
+            SshCommand::ExecSudo {
+                command,
+                password,
+                listener,
+                cancel_flag,
+            } => {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let res = Self::bg_exec_sudo(pool, &command, &password, cancel_flag.as_ref());
+                    let _ = listener.send(res);
+                });
+            }
+            SshCommand::ChangePassword {
+                current_password,
+                new_password,
+                listener,
+            } => {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    let res = Self::bg_change_password(pool, &current_password, &new_password);
+                    let _ = listener.send(res);
+                });
+            }
+            SshCommand::SftpLs { path, listener } => {
                 let res = Self::bg_sftp_ls(pool.clone(), &path);
                 let _ = listener.send(res);
             }
@@ -840,6 +1974,15 @@ impl SshManager {
                 let res = Self::bg_sftp_read(pool.clone(), &path, max_len);
                 let _ = listener.send(res);
             }
+            SshCommand::SftpReadRange {
+                path,
+                offset,
+                length,
+                listener,
+            } => {
+                let res = Self::bg_sftp_read_range(pool.clone(), &path, offset, length);
+                let _ = listener.send(res);
+            }
             SshCommand::SftpWrite {
                 path,
                 content,
@@ -849,18 +1992,71 @@ impl SshManager {
                 let res = Self::bg_sftp_write(pool.clone(), &path, &content, mode.as_deref());
                 let _ = listener.send(res);
             }
+            SshCommand::CatRead {
+                path,
+                max_len,
+                listener,
+            } => {
+                let res = Self::with_file_browser_session(pool.clone(), |sess| {
+                    scp::exec_read_file(sess, &path, max_len)
+                });
+                let _ = listener.send(res);
+            }
+            SshCommand::TeeWrite {
+                path,
+                content,
+                listener,
+            } => {
+                let res = Self::with_file_browser_session(pool.clone(), |sess| {
+                    scp::exec_write_file(sess, &path, &content, false)
+                });
+                let _ = listener.send(res);
+            }
             SshCommand::SftpMkdir { path, listener } => {
                 let res = Self::bg_sftp_simple(pool.clone(), &path, |sftp, p| {
                     sftp.mkdir(p, 0o755).map_err(|e| e.to_string())
                 });
                 let _ = listener.send(res);
             }
+            SshCommand::SftpMkdirAll { path, listener } => {
+                let res = Self::bg_sftp_mkdir_all(pool.clone(), &path);
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpTouch {
+                path,
+                time,
+                listener,
+            } => {
+                let res = Self::bg_sftp_simple(pool.clone(), &path, |sftp, p| {
+                    Self::sftp_touch(sftp, p, time)
+                });
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpDuplicate { path, listener } => {
+                let res = Self::bg_sftp_duplicate(pool.clone(), &path);
+                let _ = listener.send(res);
+            }
             SshCommand::SftpCreate { path, listener } => {
                 let res = Self::bg_sftp_simple(pool.clone(), &path, |sftp, p| {
                     sftp.create(p).map_err(|e| e.to_string()).map(|_| ())
                 });
                 let _ = listener.send(res);
             }
+            SshCommand::SftpCheckWritable { path, listener } => {
+                let res = Self::bg_sftp_check_writable(pool.clone(), &path);
+                let _ = listener.send(res);
+            }
+            SshCommand::ListAuthorizedKeys { listener } => {
+                let res = Self::bg_list_authorized_keys(pool.clone());
+                let _ = listener.send(res);
+            }
+            SshCommand::RemoveAuthorizedKey {
+                fingerprint,
+                listener,
+            } => {
+                let res = Self::bg_remove_authorized_key(pool.clone(), &fingerprint);
+                let _ = listener.send(res);
+            }
             SshCommand::SftpChmod {
                 path,
                 mode,
@@ -890,6 +2086,24 @@ impl SshManager {
                 let res = Self::bg_sftp_delete(pool.clone(), &path, is_dir);
                 let _ = listener.send(res);
             }
+            SshCommand::SftpCountItems {
+                path,
+                limit,
+                listener,
+            } => {
+                let res = Self::bg_sftp_count_items(pool.clone(), &path, limit);
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpStat { path, listener } => {
+                let res = Self::bg_sftp_stat(pool.clone(), &path);
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpTrash { action, listener } => {
+                let res = Self::with_file_browser_sftp(pool.clone(), |sftp| {
+                    super::trash::run_sftp(sftp, &action)
+                });
+                let _ = listener.send(res);
+            }
             SshCommand::SftpRename {
                 old_path,
                 new_path,
@@ -898,6 +2112,69 @@ impl SshManager {
                 let res = Self::bg_sftp_rename(pool.clone(), &old_path, &new_path);
                 let _ = listener.send(res);
             }
+            SshCommand::SftpBatchDelete {
+                paths,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let res = Self::bg_sftp_batch_delete(pool.clone(), &paths, &batch_id, &app_handle);
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpBatchMove {
+                paths,
+                target_dir,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let res = Self::bg_sftp_batch_move(
+                    pool.clone(),
+                    &paths,
+                    &target_dir,
+                    &batch_id,
+                    &app_handle,
+                );
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpBatchChmod {
+                paths,
+                mode,
+                recursive,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let res = Self::bg_sftp_batch_chmod(
+                    pool.clone(),
+                    &paths,
+                    mode,
+                    recursive,
+                    &batch_id,
+                    &app_handle,
+                );
+                let _ = listener.send(res);
+            }
+            SshCommand::SftpBatchChown {
+                paths,
+                uid,
+                gid,
+                recursive,
+                batch_id,
+                app_handle,
+                listener,
+            } => {
+                let res = Self::bg_sftp_batch_chown(
+                    pool.clone(),
+                    &paths,
+                    uid,
+                    gid,
+                    recursive,
+                    &batch_id,
+                    &app_handle,
+                );
+                let _ = listener.send(res);
+            }
             SshCommand::SftpDownload {
                 remote_path,
                 local_path,
@@ -905,6 +2182,8 @@ impl SshManager {
                 app_handle,
                 listener,
                 cancel_flag,
+                pause_flag,
+                rate_limit,
             } => {
                 let pool = pool.clone();
                 thread::spawn(move || {
@@ -915,6 +2194,8 @@ impl SshManager {
                         &transfer_id,
                         &app_handle,
                         &cancel_flag,
+                        &pause_flag,
+                        rate_limit,
                     );
                     let _ = listener.send(res);
                 });
@@ -926,6 +2207,9 @@ impl SshManager {
                 app_handle,
                 listener,
                 cancel_flag,
+                skip_space_check,
+                pause_flag,
+                rate_limit,
             } => {
                 let pool = pool.clone();
                 thread::spawn(move || {
@@ -936,6 +2220,9 @@ impl SshManager {
                         &transfer_id,
                         &app_handle,
                         &cancel_flag,
+                        &pause_flag,
+                        skip_space_check,
+                        rate_limit,
                     );
                     let _ = listener.send(res);
                 });
@@ -948,14 +2235,327 @@ impl SshManager {
                 let res = Self::bg_get_disk_usage(pool.clone(), &path);
                 let _ = listener.send(res);
             }
+            SshCommand::GetPathFreeSpace { path, listener } => {
+                let res = Self::bg_get_path_free_space(pool.clone(), &path);
+                let _ = listener.send(res);
+            }
+            SshCommand::RemoteFileHash {
+                path,
+                algorithm,
+                listener,
+            } => {
+                let res = Self::bg_remote_file_hash(pool.clone(), &path, algorithm);
+                let _ = listener.send(res);
+            }
+            SshCommand::GetPoolDiagnostics { listener } => {
+                let _ = listener.send(Ok(pool.diagnostics()));
+            }
+            SshCommand::StartLocalForward {
+                session_id,
+                local_port,
+                remote_host,
+                remote_port,
+                listener,
+            } => {
+                thread::spawn(move || {
+                    let res =
+                        pool.start_local_forward(&session_id, local_port, remote_host, remote_port);
+                    let _ = listener.send(res);
+                });
+            }
+            SshCommand::WatchDirectory { request, listener } => {
+                thread::spawn(move || run_dir_watch(pool, request, shutdown_signal, listener));
+            }
             SshCommand::Shutdown => {}
             // Shell commands should not be routed to the ops loop.
             SshCommand::ShellOpen { sender, .. } => {
                 let _ = sender.send(ShellMsg::Exit);
             }
-            SshCommand::ShellWrite(_) | SshCommand::ShellResize { .. } | SshCommand::ShellClose => {
+            SshCommand::ShellWrite { .. }
+            | SshCommand::ShellResize { .. }
+            | SshCommand::ShellClose { .. }
+            | SshCommand::ShellEof { .. }
+            | SshCommand::ShellBreak { .. } => {}
+        }
+    }
+
+    fn open_shell(
+        &mut self,
+        shell_id: String,
+        term_type: String,
+        cols: u16,
+        rows: u16,
+        sender: Sender<ShellMsg>,
+    ) {
+        // Reopening an existing shell id replaces that shell
+        if let Some(mut old) = self.shells.remove(&shell_id) {
+            let _ = old.channel.close();
+        }
+
+        let rc_path = self.remote_rc_path.clone();
+
+        // Create new channel using the main session
+        match self.open_shell_channel() {
+            Ok(mut channel) => {
+                // Non-blocking is already set on session
+                // Standard setup
+                if let Err(e) = crate::ssh::utils::ssh2_retry(|| {
+                    channel.request_pty(&term_type, cols.into(), rows.into())
+                }) {
+                    eprintln!("Failed to request PTY: {}", e);
+                    return;
+                }
+                // Must precede the shell request so the login shell
+                // sees SSH_AUTH_SOCK. Failure is not fatal.
+                if self.agent_forwarding {
+                    if let Err(e) =
+                        crate::ssh::utils::ssh2_retry(|| channel.request_auth_agent_forwarding())
+                    {
+                        eprintln!("Failed to request agent forwarding: {}", e);
+                    }
+                }
+                let started = match &rc_path {
+                    Some(path) => {
+                        let command = remote_rc_shell_command(path);
+                        crate::ssh::utils::ssh2_retry(|| {
+                            channel.process_startup("exec", Some(&command))
+                        })
+                    }
+                    None => {
+                        crate::ssh::utils::ssh2_retry(|| channel.process_startup("shell", None))
+                    }
+                };
+                if let Err(e) = started {
+                    eprintln!("Failed to start shell: {}", e);
+                    return;
+                }
+                if let Some(command) = &self.init_command {
+                    if let Err(e) =
+                        write_all_retrying(&mut channel, init_command_input(command).as_bytes())
+                    {
+                        eprintln!("Failed to send init command: {}", e);
+                    }
+                }
+                self.shells.insert(
+                    shell_id,
+                    ShellChannel {
+                        channel,
+                        sender,
+                        term_type,
+                        cols,
+                        rows,
+                    },
+                );
+            }
+            Err(e) => eprintln!("Failed to create shell channel: {}", e),
+        }
+    }
+
+    /// Open the channel for a new shell. With X11 forwarding it is a raw
+    /// channel that has already requested it, unless the local display cannot
+    /// be resolved; that and a refused request only cost the forwarding.
+    fn open_shell_channel(&mut self) -> Result<Box<dyn ShellIo>, ssh2::Error> {
+        let Some(display) = self.x11_display() else {
+            let channel = crate::ssh::utils::ssh2_retry(|| self.session.channel_session())?;
+            return Ok(Box::new(channel));
+        };
+        let mut channel =
+            crate::ssh::utils::ssh2_retry(|| RawChannel::open_session(&self.session))?;
+        x11::listen(&self.session);
+        if let Err(e) = display.request(&mut channel) {
+            eprintln!("Failed to request X11 forwarding: {}", e);
+        }
+        Ok(Box::new(channel))
+    }
+
+    /// The local display for X11 forwarding, resolved on first use; `None`
+    /// when forwarding is off or no display is available.
+    fn x11_display(&mut self) -> Option<Arc<X11Display>> {
+        if !self.x11_forwarding {
+            return None;
+        }
+        if self.x11_display.is_none() {
+            match X11Display::from_env() {
+                Ok(display) => self.x11_display = Some(Arc::new(display)),
+                Err(e) => eprintln!("X11 forwarding unavailable: {}", e),
+            }
+        }
+        self.x11_display.clone()
+    }
+
+    /// Relay the X11 connections the server opened since the last call, each
+    /// on its own thread. Returns whether there were any.
+    fn accept_x11_channels(&mut self) -> bool {
+        let Some(display) = self.x11_display.clone() else {
+            return false;
+        };
+        let channels = x11::accept(&self.session);
+        let accepted = !channels.is_empty();
+        for channel in channels {
+            let display = display.clone();
+            let shutdown = self.shutdown_signal.clone();
+            thread::spawn(move || x11::relay(channel, display, shutdown));
+        }
+        accepted
+    }
+
+    /// Upload `remote_rc` to a fresh temp file and remember its path. Shells
+    /// fall back to the normal login shell when this fails.
+    fn upload_remote_rc(&mut self) {
+        let Some(rc) = self.remote_rc.as_ref() else {
+            return;
+        };
+        let upload = || -> Result<String, String> {
+            let path = Self::bg_exec(
+                self.pool.clone(),
+                "mktemp /tmp/ssh-assistant-rc.XXXXXX",
+                None,
+                ExecTarget::FileBrowser,
+                None,
+                Some(REMOTE_RC_TIMEOUT),
+            )?
+            .trim()
+            .to_string();
+            if !path.starts_with('/') {
+                return Err(format!("mktemp failed: {}", path));
+            }
+            Self::bg_sftp_write(self.pool.clone(), &path, rc.as_bytes(), None)?;
+            Ok(path)
+        };
+        match upload() {
+            Ok(path) => self.remote_rc_path = Some(path),
+            Err(e) => eprintln!("Failed to upload remote rc file: {}", e),
+        }
+    }
+
+    /// Keep an idle terminal's connection from being dropped by middleboxes
+    /// while the heartbeat is paused. A keepalive is a connection-level
+    /// global request, so the shell channels' streams are untouched; it is
+    /// only sent between bursts of output so it cannot stall behind them.
+    fn send_shell_keepalive(&mut self) {
+        let interval = self
+            .heartbeat_manager
+            .settings()
+            .shell_keepalive_interval_secs;
+        if interval == 0
+            || self.last_shell_keepalive.elapsed() < Duration::from_secs(interval as u64)
+        {
+            return;
+        }
+        self.last_shell_keepalive = Instant::now();
+        // libssh2 skips the send unless its own interval has passed since the
+        // last packet, so align it with ours
+        self.session.set_keepalive(true, interval);
+        if let Err(e) = self.session.keepalive_send() {
+            eprintln!("[Heartbeat] Shell keepalive failed: {}", e);
+        }
+    }
+
+    /// Probe the session on the monitor's interval and report transitions.
+    fn check_session_status(&mut self) {
+        let (interval, auto_reconnect) = match &self.status_monitor {
+            Some(monitor) => (monitor.interval, monitor.auto_reconnect),
+            None => return,
+        };
+        if self.reconnect.is_some() || self.last_status_check.elapsed() < interval {
+            return;
+        }
+        self.last_status_check = Instant::now();
+
+        let status = probe_status(probe_session(&self.session));
+        match status_step(self.last_reported_status, status, auto_reconnect) {
+            StatusStep::Unchanged => {}
+            StatusStep::Report(status) => self.report_status(status, None),
+            StatusStep::Reconnect => self.start_reconnect(),
+        }
+    }
+
+    /// Whether closed shells mean the connection itself is gone and should be
+    /// reconnected rather than the shells reaped.
+    fn connection_lost(&mut self) -> bool {
+        let auto_reconnect = self
+            .status_monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.auto_reconnect);
+        auto_reconnect && probe_session(&self.session) == SessionProbe::Dead
+    }
+
+    /// Reconnect on a worker thread so shells and commands are not frozen
+    /// for the retries; `poll_reconnect` picks up the result.
+    fn start_reconnect(&mut self) {
+        if self.reconnect.is_some() {
+            return;
+        }
+        self.report_status(ConnectionStatus::Reconnecting, None);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let pool = self.pool.clone();
+        thread::spawn(move || {
+            let result = pool.connect_new_session();
+            if result.is_ok() {
+                if let Err(e) = pool.rebuild_all() {
+                    eprintln!("[Status] Failed to rebuild background sessions: {}", e);
+                }
+            }
+            let _ = tx.send(result);
+        });
+        self.reconnect = Some(rx);
+    }
+
+    /// Finish a reconnect started by `start_reconnect` once it is done.
+    /// Returns whether it finished.
+    fn poll_reconnect(&mut self) -> bool {
+        let Some(receiver) = &self.reconnect else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return false,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                Err("Reconnect stopped unexpectedly".to_string())
+            }
+        };
+        self.reconnect = None;
+        match result {
+            Ok(session) => {
+                self.replace_session(session);
+                self.report_status(ConnectionStatus::Connected, Some("Reconnected"));
             }
+            Err(e) => {
+                // The shells' channels died with the old session
+                for (_, shell) in self.shells.drain() {
+                    let _ = shell.sender.send(ShellMsg::Exited(-1));
+                }
+                self.report_status(ConnectionStatus::Disconnected, Some(&e));
+            }
+        }
+        true
+    }
+
+    fn report_status(&mut self, status: ConnectionStatus, details: Option<&str>) {
+        self.last_reported_status = Some(status);
+        if let Some(monitor) = &self.status_monitor {
+            (monitor.emit)(status, details);
+        }
+    }
+
+    /// Swap in a freshly connected session and reopen every shell on it with
+    /// its previous PTY settings.
+    fn replace_session(&mut self, session: ManagedSession) {
+        let shells: Vec<(String, ShellChannel)> = self.shells.drain().collect();
+        x11::forget(&self.session);
+        self.session = session;
+        for (shell_id, shell) in shells {
+            let ShellChannel {
+                channel,
+                sender,
+                term_type,
+                cols,
+                rows,
+            } = shell;
+            drop(channel);
+            self.open_shell(shell_id, term_type, cols, rows, sender);
         }
+        self.heartbeat_manager.reset();
     }
 
     fn tick_network_monitor(&mut self) {
@@ -1000,7 +2600,22 @@ impl SshManager {
         cancel_flag: Option<&Arc<AtomicBool>>,
         target: ExecTarget,
         stream: Option<&ExecStreamContext>,
+        timeout: Option<Duration>,
     ) -> Result<String, String> {
+        Self::bg_exec_with_status(pool, command, cancel_flag, target, stream, timeout)
+            .map(|(output, _)| output)
+    }
+
+    /// `bg_exec` that also returns the command's exit status, or -1 when the
+    /// server reported none or the command was killed by a signal.
+    fn bg_exec_with_status(
+        pool: SessionSshPool,
+        command: &str,
+        cancel_flag: Option<&Arc<AtomicBool>>,
+        target: ExecTarget,
+        stream: Option<&ExecStreamContext>,
+        timeout: Option<Duration>,
+    ) -> Result<(String, i32), String> {
         let session_mutex = match target {
             ExecTarget::Ai => pool.get_ai_session()?,
             ExecTarget::FileBrowser => pool.get_file_browser_session()?,
@@ -1018,6 +2633,7 @@ impl SshManager {
         let mut stderr_buf = [0u8; 4096];
         let mut stdout_closed = false;
         let mut stderr_closed = false;
+        let started = Instant::now();
 
         loop {
             // Check cancellation
@@ -1027,6 +2643,12 @@ impl SshManager {
                     return Err("Command cancelled".to_string());
                 }
             }
+            if let Some(timeout) = timeout {
+                if started.elapsed() >= timeout {
+                    let _ = channel.close();
+                    return Err(format!("Command timed out after {}s", timeout.as_secs()));
+                }
+            }
 
             let mut had_activity = false;
 
@@ -1074,7 +2696,173 @@ impl SshManager {
 
         crate::ssh::utils::ssh2_retry(|| channel.wait_close()).ok();
         emit_command_output(stream, String::new(), "stdout", true);
-        Ok(s)
+        let killed = channel
+            .exit_signal()
+            .is_ok_and(|signal| signal.exit_signal.is_some());
+        let status = match channel.exit_status() {
+            Ok(status) if !killed => status,
+            _ => -1,
+        };
+        Ok((s, status))
+    }
+
+    /// Run `command` as `sudo -S -p SUDO_PROMPT -- sh -c command` on a PTY and
+    /// type `password` at the prompt. A second prompt means the password was
+    /// rejected. Without a prompt (NOPASSWD or cached credentials) the output
+    /// is returned as-is. The password is never logged or echoed back.
+    fn bg_exec_sudo(
+        pool: SessionSshPool,
+        command: &str,
+        password: &str,
+        cancel_flag: Option<&Arc<AtomicBool>>,
+    ) -> Result<String, String> {
+        let session_mutex = pool.get_file_browser_session()?;
+        let session = session_mutex.lock().map_err(|e| e.to_string())?;
+
+        let mut channel = crate::ssh::utils::ssh2_retry(|| session.channel_session())
+            .map_err(|e| e.to_string())?;
+        crate::ssh::utils::ssh2_retry(|| channel.request_pty("dumb", None, Some((200, 24, 0, 0))))
+            .map_err(|e| e.to_string())?;
+        let script = format!(
+            "sudo -S -p '{}' -- sh -c '{}'",
+            SUDO_PROMPT,
+            crate::ssh::file_ops::escape_shell_arg(command)
+        );
+        crate::ssh::utils::ssh2_retry(|| channel.exec(&script)).map_err(|e| e.to_string())?;
+
+        let timeout = Duration::from_secs(DEFAULT_EXEC_TIMEOUT_SECS);
+        let deadline = Instant::now() + timeout;
+        let mut output = String::new();
+        let mut prompts = 0;
+        let mut buf = [0u8; 4096];
+        loop {
+            if cancel_flag.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+                let _ = channel.close();
+                return Err("Command cancelled".to_string());
+            }
+            if Instant::now() >= deadline {
+                let _ = channel.close();
+                return Err(format!("Command timed out after {}s", timeout.as_secs()));
+            }
+            match channel.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                    if let Some(pos) = output.find(SUDO_PROMPT) {
+                        output.replace_range(pos..pos + SUDO_PROMPT.len(), "");
+                        prompts += 1;
+                        if prompts > 1 {
+                            let _ = channel.close();
+                            return Err(SUDO_INCORRECT_PASSWORD.to_string());
+                        }
+                        write_secret_line(&mut channel, password)?;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        crate::ssh::utils::ssh2_retry(|| channel.wait_close()).ok();
+        let output = output.replace("\r\n", "\n");
+        if channel.exit_status().unwrap_or(0) != 0 && output.contains("incorrect password attempt")
+        {
+            return Err(SUDO_INCORRECT_PASSWORD.to_string());
+        }
+        // sudo ends the hidden password line with a newline of its own
+        let output = if prompts > 0 {
+            output.strip_prefix('\n').unwrap_or(&output).to_string()
+        } else {
+            output
+        };
+        Ok(output)
+    }
+
+    /// Change the login password by driving `passwd` on a PTY. Prompts are
+    /// forced to English with `LC_ALL=C` so they can be recognised. A prompt
+    /// that comes back (e.g. "New password:" after a policy complaint) means
+    /// the answer was rejected. Passwords are typed with echo off and are
+    /// never logged or returned.
+    fn bg_change_password(
+        pool: SessionSshPool,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), String> {
+        let session_mutex = pool.get_file_browser_session()?;
+        let session = session_mutex.lock().map_err(|e| e.to_string())?;
+
+        let mut channel = crate::ssh::utils::ssh2_retry(|| session.channel_session())
+            .map_err(|e| e.to_string())?;
+        crate::ssh::utils::ssh2_retry(|| channel.request_pty("dumb", None, Some((200, 24, 0, 0))))
+            .map_err(|e| e.to_string())?;
+        crate::ssh::utils::ssh2_retry(|| channel.exec("LC_ALL=C LANG=C passwd"))
+            .map_err(|e| e.to_string())?;
+
+        let rejected = |output: &str, fallback: &str| {
+            format!(
+                "Password not changed: {}",
+                passwd_failure_reason(output).unwrap_or_else(|| fallback.to_string())
+            )
+        };
+
+        let deadline = Instant::now() + PASSWD_TIMEOUT;
+        let mut output = String::new();
+        // Output before this offset has already been answered
+        let mut answered = 0;
+        let mut last_prompt = None;
+        let mut buf = [0u8; 4096];
+        loop {
+            if Instant::now() >= deadline {
+                let _ = channel.close();
+                return Err(rejected(&output, "passwd did not finish in time"));
+            }
+            match channel.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                    let pending = output[answered..].rsplit(['\n', '\r']).next();
+                    let Some(prompt) = pending.and_then(passwd_prompt) else {
+                        continue;
+                    };
+                    answered = output.len();
+                    // Asked again for what was just typed: it was refused
+                    let repeated = match prompt {
+                        PasswdPrompt::Current => last_prompt.is_some(),
+                        PasswdPrompt::New => {
+                            matches!(last_prompt, Some(PasswdPrompt::New | PasswdPrompt::Retype))
+                        }
+                        PasswdPrompt::Retype => last_prompt == Some(PasswdPrompt::Retype),
+                    };
+                    if repeated {
+                        let _ = channel.close();
+                        return Err(rejected(&output, "the server asked for it again"));
+                    }
+                    last_prompt = Some(prompt);
+                    let answer = match prompt {
+                        PasswdPrompt::Current => current_password,
+                        PasswdPrompt::New | PasswdPrompt::Retype => new_password,
+                    };
+                    write_secret_line(&mut channel, answer)?;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        crate::ssh::utils::ssh2_retry(|| channel.wait_close()).ok();
+        let status = channel.exit_status().map_err(|e| e.to_string())?;
+        if status == 0 {
+            return Ok(());
+        }
+        let output = output.replace("\r\n", "\n");
+        Err(rejected(
+            &output,
+            &format!("passwd exited with status {}", status),
+        ))
     }
 
     fn classify_sftp_init_error(err: &ssh2::Error, timeout: Duration) -> SftpInitFailure {
@@ -1108,10 +2896,45 @@ impl SshManager {
             .map_err(|e| Self::classify_sftp_init_error(&e, timeout))
     }
 
+    /// Run `op` on the blocking low-latency session. Returns `None` when the
+    /// fast path is unavailable (busy, or failed to connect/open SFTP) so the
+    /// caller falls back to the pool.
+    fn with_low_latency_sftp<R, F>(pool: &SessionSshPool, op: &mut F) -> Option<Result<R, String>>
+    where
+        F: FnMut(&ssh2::Session, &ssh2::Sftp) -> Result<R, String>,
+    {
+        let session_mutex = pool.get_low_latency_session().ok()?;
+        let session = session_mutex.try_lock().ok()?;
+        match session.session.sftp() {
+            Ok(sftp) => Some(op(&session.session, &sftp)),
+            Err(e) => {
+                eprintln!("[SFTP] Low-latency session unusable, falling back: {}", e);
+                drop(session);
+                pool.reset_low_latency_session();
+                None
+            }
+        }
+    }
+
     fn with_file_browser_sftp<R, F>(pool: SessionSshPool, mut op: F) -> Result<R, String>
     where
         F: FnMut(&ssh2::Sftp) -> Result<R, String>,
     {
+        Self::with_file_browser_sftp_session(pool, |_, sftp| op(sftp))
+    }
+
+    /// `with_file_browser_sftp` for operations that also need the session the
+    /// SFTP channel runs on, e.g. to exec a command alongside it.
+    fn with_file_browser_sftp_session<R, F>(pool: SessionSshPool, mut op: F) -> Result<R, String>
+    where
+        F: FnMut(&ssh2::Session, &ssh2::Sftp) -> Result<R, String>,
+    {
+        if pool.low_latency() {
+            if let Some(result) = Self::with_low_latency_sftp(&pool, &mut op) {
+                return result;
+            }
+        }
+
         let timeout = pool.sftp_operation_timeout();
         let mut last_error = None;
 
@@ -1122,7 +2945,7 @@ impl SshManager {
             let result = {
                 let session = session_mutex.lock().map_err(|e| e.to_string())?;
                 match Self::bg_get_sftp(&session, timeout) {
-                    Ok(sftp) => op(&sftp),
+                    Ok(sftp) => op(&session.session, &sftp),
                     Err(err) => {
                         should_recycle = err.should_recycle_session;
                         Err(err.message)
@@ -1149,6 +2972,18 @@ impl SshManager {
         Err(last_error.unwrap_or_else(|| "SFTP operation failed".to_string()))
     }
 
+    /// Run `op` on a file browser session without opening SFTP, for the
+    /// exec-based fallbacks.
+    fn with_file_browser_session<R, F>(pool: SessionSshPool, op: F) -> Result<R, String>
+    where
+        F: FnOnce(&ssh2::Session) -> Result<R, String>,
+    {
+        let session_mutex =
+            pool.get_file_browser_session_with_timeout(pool.sftp_operation_timeout())?;
+        let session = session_mutex.lock().map_err(|e| e.to_string())?;
+        op(&session.session)
+    }
+
     fn bg_sftp_ls(pool: SessionSshPool, path: &str) -> Result<Vec<FileEntry>, String> {
         Self::with_file_browser_sftp(pool, |sftp| {
             let path_path = Path::new(path);
@@ -1283,38 +3118,138 @@ impl SshManager {
         path: &str,
         max_len: Option<usize>,
     ) -> Result<Vec<u8>, String> {
+        if pool.force_scp() {
+            return Self::with_file_browser_session(pool, |sess| {
+                scp::exec_read_file(sess, path, max_len)
+            });
+        }
+        if pool.sftp_handle_cache() {
+            if let Some(result) = Self::bg_sftp_read_cached(&pool, path, 0, max_len) {
+                return result;
+            }
+        }
+        let result = Self::with_file_browser_sftp(pool.clone(), |sftp| {
+            let mut file = crate::ssh::utils::ssh2_retry(|| sftp.open(Path::new(path)))
+                .map_err(|e| e.to_string())?;
+            Self::read_sftp_file(&mut file, 0, max_len)
+        });
+        match result {
+            Err(e) if scp::is_sftp_unavailable(&e) => {
+                Self::with_file_browser_session(pool, |sess| {
+                    scp::exec_read_file(sess, path, max_len)
+                })
+            }
+            other => other,
+        }
+    }
+
+    fn bg_sftp_read_range(
+        pool: SessionSshPool,
+        path: &str,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, String> {
+        if pool.sftp_handle_cache() {
+            if let Some(result) = Self::bg_sftp_read_cached(&pool, path, offset, Some(length)) {
+                return result;
+            }
+        }
         Self::with_file_browser_sftp(pool, |sftp| {
             let mut file = crate::ssh::utils::ssh2_retry(|| sftp.open(Path::new(path)))
                 .map_err(|e| e.to_string())?;
+            Self::read_sftp_file(&mut file, offset, Some(length))
+        })
+    }
 
-            let mut buf = Vec::new();
-            let mut temp_buf = [0u8; 8192];
-            loop {
-                if let Some(max) = max_len {
-                    if buf.len() >= max {
-                        break;
-                    }
-                }
+    /// Read up to `limit` bytes (to EOF when `None`) from `offset`.
+    pub(crate) fn read_sftp_file(
+        file: &mut ssh2::File,
+        offset: u64,
+        limit: Option<usize>,
+    ) -> Result<Vec<u8>, String> {
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
 
-                match file.read(&mut temp_buf) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        buf.extend_from_slice(&temp_buf[..n]);
-                        if let Some(max) = max_len {
-                            if buf.len() > max {
-                                buf.truncate(max);
-                                break;
-                            }
-                        }
-                    }
-                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                        thread::sleep(Duration::from_millis(5));
-                    }
-                    Err(e) => return Err(e.to_string()),
+        let mut buf = Vec::with_capacity(limit.unwrap_or(0).min(1024 * 1024));
+        let mut temp_buf = [0u8; 8192];
+        loop {
+            let want = match limit {
+                Some(limit) => (limit - buf.len()).min(temp_buf.len()),
+                None => temp_buf.len(),
+            };
+            if want == 0 {
+                break;
+            }
+            match file.read(&mut temp_buf[..want]) {
+                Ok(0) => break,
+                Ok(n) => buf.extend_from_slice(&temp_buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
                 }
+                Err(e) => return Err(e.to_string()),
             }
-            Ok(buf)
-        })
+        }
+        Ok(buf)
+    }
+
+    /// Serve a read from the pool's cached handle for `path`, opening and
+    /// caching one on a miss. `None` when that fails, so the caller takes the
+    /// usual path with its retries, SCP fallback and error reporting.
+    fn bg_sftp_read_cached(
+        pool: &SessionSshPool,
+        path: &str,
+        offset: u64,
+        limit: Option<usize>,
+    ) -> Option<Result<Vec<u8>, String>> {
+        let started = Instant::now();
+        // Read before taking the handle, so an invalidation racing this read
+        // keeps it from being put back
+        let generation = pool.file_handle_generation();
+        if let Some(mut cached) = pool.take_file_handle(path) {
+            let read = match cached.session.lock() {
+                Ok(_session) => Self::read_sftp_file(&mut cached.file, offset, limit),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Ok(buf) = read {
+                pool.record_file_handle_read(true, started.elapsed());
+                pool.put_file_handle(path, cached, generation);
+                return Some(Ok(buf));
+            }
+            // Stale handle (session gone, file removed); open the file afresh
+        }
+
+        let session_mutex = if pool.low_latency() {
+            pool.get_low_latency_session().ok()
+        } else {
+            None
+        };
+        let session_mutex = match session_mutex {
+            Some(session_mutex) => session_mutex,
+            None => pool
+                .get_file_browser_session_with_timeout(pool.sftp_operation_timeout())
+                .ok()?,
+        };
+        let (buf, sftp, file) = {
+            let session = session_mutex.lock().ok()?;
+            let sftp = match pool.cached_sftp(&session_mutex) {
+                Some(sftp) => sftp,
+                None => Arc::new(Self::bg_get_sftp(&session, pool.sftp_operation_timeout()).ok()?),
+            };
+            let mut file = crate::ssh::utils::ssh2_retry(|| sftp.open(Path::new(path))).ok()?;
+            let buf = Self::read_sftp_file(&mut file, offset, limit).ok()?;
+            (buf, sftp, file)
+        };
+        pool.record_file_handle_read(false, started.elapsed());
+        pool.put_file_handle(
+            path,
+            CachedFile {
+                session: session_mutex,
+                sftp,
+                file,
+            },
+            generation,
+        );
+        Some(Ok(buf))
     }
 
     fn bg_sftp_write(
@@ -1323,9 +3258,24 @@ impl SshManager {
         content: &[u8],
         mode: Option<&str>,
     ) -> Result<(), String> {
-        Self::with_file_browser_sftp(pool, |sftp| {
+        let append = mode == Some("append");
+        let atomic = mode == Some("atomic");
+        let exec_write = |sess: &ssh2::Session| {
+            if atomic {
+                scp::exec_write_file_atomic(sess, path, content)
+            } else {
+                scp::exec_write_file(sess, path, content, append)
+            }
+        };
+        if pool.force_scp() {
+            return Self::with_file_browser_session(pool, exec_write);
+        }
+        let result = Self::with_file_browser_sftp_session(pool.clone(), |session, sftp| {
             use ssh2::OpenFlags;
-            let mut file = if mode == Some("append") {
+            if atomic {
+                return Self::sftp_write_atomic(session, sftp, path, content);
+            }
+            let mut file = if append {
                 crate::ssh::utils::ssh2_retry(|| {
                     sftp.open_mode(
                         Path::new(path),
@@ -1346,68 +3296,632 @@ impl SshManager {
             }
             .map_err(|e| e.to_string())?;
 
-            let mut pos = 0;
-            while pos < content.len() {
-                match file.write(&content[pos..]) {
-                    Ok(n) => pos += n,
-                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                        thread::sleep(Duration::from_millis(5));
+            write_all_retrying(&mut file, content)
+        });
+        match result {
+            Err(e) if scp::is_sftp_unavailable(&e) => {
+                Self::with_file_browser_session(pool, exec_write)
+            }
+            other => other,
+        }
+    }
+
+    /// Write `content` to `<path>.tmp.<uuid>` and rename it over `path`, so a
+    /// dropped connection leaves the original untouched rather than truncated.
+    /// The original's permissions carry over to the new file.
+    fn sftp_write_atomic(
+        session: &ssh2::Session,
+        sftp: &ssh2::Sftp,
+        path: &str,
+        content: &[u8],
+    ) -> Result<(), String> {
+        use ssh2::{FileStat, OpenFlags, RenameFlags};
+        let target = Path::new(path);
+        let perm = sftp
+            .stat(target)
+            .ok()
+            .and_then(|stat| stat.perm)
+            .map(|perm| perm & 0o7777)
+            .unwrap_or(0o644);
+        let tmp_path = format!("{}.tmp.{}", path, uuid::Uuid::new_v4());
+        let tmp = Path::new(&tmp_path);
+
+        let written = (|| {
+            let mut file = crate::ssh::utils::ssh2_retry(|| {
+                sftp.open_mode(
+                    tmp,
+                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE,
+                    perm as i32,
+                    ssh2::OpenType::File,
+                )
+            })
+            .map_err(|e| e.to_string())?;
+            write_all_retrying(&mut file, content)?;
+            drop(file);
+            // The open mode is subject to the server's umask
+            let stat = FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: Some(perm),
+                atime: None,
+                mtime: None,
+            };
+            let _ = crate::ssh::utils::ssh2_retry(|| sftp.setstat(tmp, stat.clone()));
+            Ok(())
+        })();
+        if let Err(e) = written {
+            let _ = sftp.unlink(tmp);
+            return Err(e);
+        }
+
+        let flags = RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE;
+        if crate::ssh::utils::ssh2_retry(|| sftp.rename(tmp, target, Some(flags))).is_ok() {
+            return Ok(());
+        }
+
+        // SFTPv3 servers such as OpenSSH refuse to rename over an existing
+        // file, but `mv -f` is a rename(2) on the server and replaces it
+        // atomically.
+        match scp::exec_rename(session, &tmp_path, path) {
+            Ok(()) => return Ok(()),
+            Err(e) => eprintln!("[SFTP] mv for atomic save of {} failed: {}", path, e),
+        }
+
+        // Without exec, move the original aside first; it is restored if the
+        // swap fails, so `path` never holds a partial write.
+        let backup_path = format!("{}.bak.{}", path, uuid::Uuid::new_v4());
+        let backup = Path::new(&backup_path);
+        if let Err(e) = crate::ssh::utils::ssh2_retry(|| sftp.rename(target, backup, None)) {
+            let _ = sftp.unlink(tmp);
+            return Err(format!("Atomic save of {} failed: {}", path, e));
+        }
+        match crate::ssh::utils::ssh2_retry(|| sftp.rename(tmp, target, None)) {
+            Ok(()) => {
+                if let Err(e) = crate::ssh::utils::ssh2_retry(|| sftp.unlink(backup)) {
+                    eprintln!("[SFTP] Failed to remove backup {}: {}", backup_path, e);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                let _ = sftp.unlink(tmp);
+                match crate::ssh::utils::ssh2_retry(|| sftp.rename(backup, target, None)) {
+                    Ok(()) => Err(format!("Atomic save of {} failed: {}", path, e)),
+                    Err(restore) => Err(format!(
+                        "Atomic save of {} failed: {}; the original is at {} ({})",
+                        path, e, backup_path, restore
+                    )),
+                }
+            }
+        }
+    }
+
+    fn bg_sftp_simple<F>(pool: SessionSshPool, path: &str, op: F) -> Result<(), String>
+    where
+        F: FnOnce(&ssh2::Sftp, &Path) -> Result<(), String>,
+    {
+        let mut op = Some(op);
+        Self::with_file_browser_sftp(pool, |sftp| {
+            op.take().expect("file browser SFTP op should run once")(sftp, Path::new(path))
+        })
+    }
+
+    /// `mkdir -p`: create each missing component of `path` in turn, shallowest
+    /// first. Returns `path`, the deepest directory now present.
+    fn bg_sftp_mkdir_all(pool: SessionSshPool, path: &str) -> Result<String, String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            let target = Path::new(path);
+            let mut missing: Vec<&Path> = target
+                .ancestors()
+                .take_while(|dir| !dir.as_os_str().is_empty() && sftp.stat(dir).is_err())
+                .collect();
+            if let Some(existing) = target.ancestors().nth(missing.len()) {
+                if !existing.as_os_str().is_empty() {
+                    let stat = crate::ssh::utils::ssh2_retry(|| sftp.stat(existing))
+                        .map_err(|e| e.to_string())?;
+                    if !stat.is_dir() {
+                        return Err(format!(
+                            "{} exists and is not a directory",
+                            existing.display()
+                        ));
                     }
-                    Err(e) => return Err(e.to_string()),
                 }
             }
-            Ok(())
+            missing.reverse();
+            for dir in missing {
+                crate::ssh::utils::ssh2_retry(|| sftp.mkdir(dir, 0o755))
+                    .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            }
+            Ok(path.to_string())
         })
     }
 
-    fn bg_sftp_simple<F>(pool: SessionSshPool, path: &str, op: F) -> Result<(), String>
+    /// Copy `path` to `path.bak` (or `path.bak.N` if taken) by streaming it
+    /// through the held SFTP session, keeping its permissions. Returns the
+    /// new path.
+    fn bg_sftp_duplicate(pool: SessionSshPool, path: &str) -> Result<String, String> {
+        use ssh2::OpenFlags;
+        Self::with_file_browser_sftp(pool, |sftp| {
+            let source = Path::new(path);
+            let stat =
+                crate::ssh::utils::ssh2_retry(|| sftp.stat(source)).map_err(|e| e.to_string())?;
+            if !stat.is_file() {
+                return Err(format!("{} is not a regular file", path));
+            }
+            let perm = stat.perm.map(|perm| perm & 0o7777).unwrap_or(0o644);
+
+            let (dest_path, mut dest) = (0..MAX_DUPLICATE_ATTEMPTS)
+                .map(|n| duplicate_name(path, n))
+                .filter(|candidate| sftp.stat(Path::new(candidate)).is_err())
+                .find_map(|candidate| {
+                    // EXCLUSIVE guards against a file appearing since the stat
+                    let file = crate::ssh::utils::ssh2_retry(|| {
+                        sftp.open_mode(
+                            Path::new(&candidate),
+                            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE,
+                            perm as i32,
+                            ssh2::OpenType::File,
+                        )
+                    })
+                    .ok()?;
+                    Some((candidate, file))
+                })
+                .ok_or_else(|| format!("No free name to duplicate {} to", path))?;
+
+            let copied = (|| {
+                let mut src = crate::ssh::utils::ssh2_retry(|| sftp.open(source))
+                    .map_err(|e| e.to_string())?;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    let n = match src.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(n) => n,
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(5));
+                            continue;
+                        }
+                        Err(e) => return Err(e.to_string()),
+                    };
+                    let mut pos = 0;
+                    while pos < n {
+                        match dest.write(&buf[pos..n]) {
+                            Ok(written) => pos += written,
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                                thread::sleep(Duration::from_millis(5));
+                            }
+                            Err(e) => return Err(e.to_string()),
+                        }
+                    }
+                }
+                Ok(())
+            })();
+            drop(dest);
+            if let Err(e) = copied {
+                let _ = sftp.unlink(Path::new(&dest_path));
+                return Err(e);
+            }
+            Ok(dest_path)
+        })
+    }
+
+    /// `touch`: create `path` if it does not exist, then set both its access
+    /// and modification time to `time` (Unix seconds).
+    fn sftp_touch(sftp: &ssh2::Sftp, path: &Path, time: u64) -> Result<(), String> {
+        if sftp.stat(path).is_err() {
+            crate::ssh::utils::ssh2_retry(|| sftp.create(path)).map_err(|e| e.to_string())?;
+        }
+        let stat = ssh2::FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: None,
+            atime: Some(time),
+            mtime: Some(time),
+        };
+        crate::ssh::utils::ssh2_retry(|| sftp.setstat(path, stat.clone()))
+            .map_err(|e| e.to_string())
+    }
+
+    fn bg_sftp_delete(pool: SessionSshPool, path: &str, is_dir: bool) -> Result<(), String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            if is_dir {
+                Self::rm_recursive_internal(sftp, Path::new(path))
+            } else {
+                crate::ssh::utils::ssh2_retry(|| sftp.unlink(Path::new(path)))
+                    .map_err(|e| e.to_string())
+            }
+        })
+    }
+
+    fn bg_sftp_stat(pool: SessionSshPool, path: &str) -> Result<Option<RemoteFileStat>, String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            match crate::ssh::utils::ssh2_retry(|| sftp.stat(Path::new(path))) {
+                Ok(stat) => Ok(Some(RemoteFileStat {
+                    mtime: stat.mtime.unwrap_or(0) as i64,
+                    size: stat.size.unwrap_or(0),
+                })),
+                Err(e)
+                    if e.code() == ssh2::ErrorCode::SFTP(crate::ssh::utils::SFTP_NO_SUCH_FILE) =>
+                {
+                    Ok(None)
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        })
+    }
+
+    /// Create and remove an empty `.ssh-assistant-write-test-<uuid>` in `dir`.
+    /// Unlike mode bits this accounts for ACLs, read-only mounts and the
+    /// effective user. The probe is removed whatever happens after it exists.
+    fn bg_sftp_check_writable(pool: SessionSshPool, dir: &str) -> Result<bool, String> {
+        use ssh2::{OpenFlags, OpenType};
+        // SSH_FX_PERMISSION_DENIED, SSH_FX_FAILURE, SSH_FX_WRITE_PROTECT,
+        // SSH_FX_NO_SPACE_ON_FILESYSTEM, SSH_FX_QUOTA_EXCEEDED
+        const NOT_WRITABLE: [i32; 5] = [3, 4, 12, 14, 15];
+
+        Self::with_file_browser_sftp(pool, |sftp| {
+            let stat = crate::ssh::utils::ssh2_retry(|| sftp.stat(Path::new(dir)))
+                .map_err(|e| e.to_string())?;
+            if !stat.is_dir() {
+                return Err(format!("{} is not a directory", dir));
+            }
+            let probe = format!(
+                "{}/.ssh-assistant-write-test-{}",
+                dir.trim_end_matches('/'),
+                uuid::Uuid::new_v4()
+            );
+            let probe = Path::new(&probe);
+            // EXCLUSIVE: never truncate something that happens to exist
+            let opened = crate::ssh::utils::ssh2_retry(|| {
+                sftp.open_mode(
+                    probe,
+                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE,
+                    0o600,
+                    OpenType::File,
+                )
+            });
+            match opened {
+                Ok(file) => {
+                    drop(file);
+                    crate::ssh::utils::ssh2_retry(|| sftp.unlink(probe)).map_err(|e| {
+                        format!(
+                            "Created write probe {} but could not remove it: {}",
+                            probe.display(),
+                            e
+                        )
+                    })?;
+                    Ok(true)
+                }
+                Err(e) => {
+                    // The server may have created the file before failing
+                    let _ = sftp.unlink(probe);
+                    match e.code() {
+                        ssh2::ErrorCode::SFTP(code) if NOT_WRITABLE.contains(&code) => Ok(false),
+                        _ => Err(e.to_string()),
+                    }
+                }
+            }
+        })
+    }
+
+    fn bg_list_authorized_keys(pool: SessionSshPool) -> Result<Vec<AuthorizedKey>, String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            authorized_keys::read(sftp).map(|content| authorized_keys::parse(&content))
+        })
+    }
+
+    fn bg_remove_authorized_key(pool: SessionSshPool, fingerprint: &str) -> Result<usize, String> {
+        Self::with_file_browser_sftp_session(pool, |session, sftp| {
+            let content = authorized_keys::read(sftp)?;
+            let (kept, removed) = authorized_keys::without_fingerprint(&content, fingerprint);
+            if removed > 0 {
+                // Atomic and keeps the file's mode, which sshd's StrictModes checks
+                Self::sftp_write_atomic(
+                    session,
+                    sftp,
+                    authorized_keys::AUTHORIZED_KEYS_PATH,
+                    kept.as_bytes(),
+                )?;
+            }
+            Ok(removed)
+        })
+    }
+
+    fn bg_sftp_count_items(
+        pool: SessionSshPool,
+        path: &str,
+        limit: Option<u64>,
+    ) -> Result<ItemCount, String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            let path = Path::new(path);
+            let stat =
+                crate::ssh::utils::ssh2_retry(|| sftp.stat(path)).map_err(|e| e.to_string())?;
+            if stat.is_dir() {
+                count_tree(&mut |dir| sftp_readdir(sftp, dir), path, limit)
+            } else {
+                Ok(ItemCount {
+                    file_count: 1,
+                    total_size: stat.size.unwrap_or(0),
+                    ..ItemCount::default()
+                })
+            }
+        })
+    }
+
+    pub(crate) fn rm_recursive_internal(sftp: &ssh2::Sftp, path: &Path) -> Result<(), String> {
+        walk_tree(
+            &mut |dir| sftp_readdir(sftp, dir),
+            path,
+            &mut |child, stat| {
+                if stat.is_dir() {
+                    crate::ssh::utils::ssh2_retry(|| sftp.rmdir(child))
+                } else {
+                    crate::ssh::utils::ssh2_retry(|| sftp.unlink(child))
+                }
+                .map_err(|e| e.to_string())?;
+                Ok(true)
+            },
+        )?;
+        crate::ssh::utils::ssh2_retry(|| sftp.rmdir(path)).map_err(|e| e.to_string())
+    }
+
+    fn bg_sftp_rename(pool: SessionSshPool, old: &str, new: &str) -> Result<(), String> {
+        Self::with_file_browser_sftp(pool, |sftp| {
+            crate::ssh::utils::ssh2_retry(|| sftp.rename(Path::new(old), Path::new(new), None))
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Run `op` for every path inside a single file-browser SFTP session,
+    /// emitting `batch-progress` after each item and collecting failures
+    /// instead of stopping at the first one.
+    fn bg_sftp_batch<F>(
+        pool: SessionSshPool,
+        paths: &[String],
+        batch_id: &str,
+        app: &tauri::AppHandle,
+        mut op: F,
+    ) -> Result<Vec<BatchItemError>, String>
     where
-        F: FnOnce(&ssh2::Sftp, &Path) -> Result<(), String>,
+        F: FnMut(&ssh2::Sftp, &Path) -> Result<(), String>,
     {
-        let mut op = Some(op);
+        use crate::ssh::BatchProgressPayload;
+        use tauri::Emitter;
+
+        let total = paths.len();
         Self::with_file_browser_sftp(pool, |sftp| {
-            op.take().expect("file browser SFTP op should run once")(sftp, Path::new(path))
+            let mut errors = Vec::new();
+            for (index, path) in paths.iter().enumerate() {
+                if let Err(error) = op(sftp, Path::new(path)) {
+                    errors.push(BatchItemError {
+                        path: path.clone(),
+                        error,
+                    });
+                }
+                let _ = app.emit(
+                    "batch-progress",
+                    BatchProgressPayload {
+                        id: batch_id.to_string(),
+                        completed: index + 1,
+                        total,
+                    },
+                );
+            }
+            Ok(errors)
         })
     }
 
-    fn bg_sftp_delete(pool: SessionSshPool, path: &str, is_dir: bool) -> Result<(), String> {
-        Self::with_file_browser_sftp(pool, |sftp| {
-            if is_dir {
-                Self::rm_recursive_internal(sftp, Path::new(path))
+    fn bg_sftp_batch_delete(
+        pool: SessionSshPool,
+        paths: &[String],
+        batch_id: &str,
+        app: &tauri::AppHandle,
+    ) -> Result<Vec<BatchItemError>, String> {
+        Self::bg_sftp_batch(pool, paths, batch_id, app, |sftp, path| {
+            let stat =
+                crate::ssh::utils::ssh2_retry(|| sftp.lstat(path)).map_err(|e| e.to_string())?;
+            if stat.is_dir() {
+                Self::rm_recursive_internal(sftp, path)
             } else {
-                crate::ssh::utils::ssh2_retry(|| sftp.unlink(Path::new(path)))
-                    .map_err(|e| e.to_string())
+                crate::ssh::utils::ssh2_retry(|| sftp.unlink(path)).map_err(|e| e.to_string())
             }
         })
     }
 
-    fn rm_recursive_internal(sftp: &ssh2::Sftp, path: &Path) -> Result<(), String> {
-        let files =
-            crate::ssh::utils::ssh2_retry(|| sftp.readdir(path)).map_err(|e| e.to_string())?;
+    fn bg_sftp_batch_move(
+        pool: SessionSshPool,
+        paths: &[String],
+        target_dir: &str,
+        batch_id: &str,
+        app: &tauri::AppHandle,
+    ) -> Result<Vec<BatchItemError>, String> {
+        let target_dir = Path::new(target_dir);
+        Self::bg_sftp_batch(pool, paths, batch_id, app, |sftp, path| {
+            let name = path
+                .file_name()
+                .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+            let destination = target_dir.join(name);
+            crate::ssh::utils::ssh2_retry(|| sftp.rename(path, &destination, None))
+                .map_err(|e| e.to_string())
+        })
+    }
 
-        for (child_path, stat) in files {
-            if let Some(name) = child_path.file_name() {
-                let name = name.to_string_lossy();
-                if name == "." || name == ".." {
-                    continue;
-                }
+    fn bg_sftp_batch_chmod(
+        pool: SessionSshPool,
+        paths: &[String],
+        mode: u32,
+        recursive: bool,
+        batch_id: &str,
+        app: &tauri::AppHandle,
+    ) -> Result<Vec<BatchItemError>, String> {
+        Self::bg_sftp_batch(pool, paths, batch_id, app, |sftp, path| {
+            Self::setstat_tree(sftp, path, recursive, &|_| {
+                Ok(ssh2::FileStat {
+                    perm: Some(mode),
+                    size: None,
+                    uid: None,
+                    gid: None,
+                    atime: None,
+                    mtime: None,
+                })
+            })
+        })
+    }
 
-                if stat.is_dir() {
-                    Self::rm_recursive_internal(sftp, &child_path)?;
-                } else {
-                    crate::ssh::utils::ssh2_retry(|| sftp.unlink(&child_path))
-                        .map_err(|e| e.to_string())?;
+    fn bg_sftp_batch_chown(
+        pool: SessionSshPool,
+        paths: &[String],
+        uid: Option<u32>,
+        gid: Option<u32>,
+        recursive: bool,
+        batch_id: &str,
+        app: &tauri::AppHandle,
+    ) -> Result<Vec<BatchItemError>, String> {
+        Self::bg_sftp_batch(pool, paths, batch_id, app, |sftp, path| {
+            Self::setstat_tree(sftp, path, recursive, &|current| {
+                // SFTP sets owner and group together, so keep whichever one
+                // is not being changed
+                let (Some(uid), Some(gid)) = (uid.or(current.uid), gid.or(current.gid)) else {
+                    return Err("Server did not report the current owner".to_string());
+                };
+                Ok(ssh2::FileStat {
+                    perm: None,
+                    size: None,
+                    uid: Some(uid),
+                    gid: Some(gid),
+                    atime: None,
+                    mtime: None,
+                })
+            })
+        })
+    }
+
+    /// Apply `attrs(current stat)` to `path` and, when `recursive`, to
+    /// everything below it. A symlink given as `path` is followed like
+    /// `chmod -R` does; symlinks found while descending are left alone.
+    fn setstat_tree<F>(
+        sftp: &ssh2::Sftp,
+        path: &Path,
+        recursive: bool,
+        attrs: &F,
+    ) -> Result<(), String>
+    where
+        F: Fn(&ssh2::FileStat) -> Result<ssh2::FileStat, String>,
+    {
+        let stat = crate::ssh::utils::ssh2_retry(|| sftp.stat(path)).map_err(|e| e.to_string())?;
+        Self::setstat_entry(sftp, path, &stat, recursive, attrs)
+    }
+
+    fn setstat_entry<F>(
+        sftp: &ssh2::Sftp,
+        path: &Path,
+        stat: &ssh2::FileStat,
+        recursive: bool,
+        attrs: &F,
+    ) -> Result<(), String>
+    where
+        F: Fn(&ssh2::FileStat) -> Result<ssh2::FileStat, String>,
+    {
+        // Errors name the item, which inside a directory is not the path
+        // the batch reports
+        let failed = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+        // Children first, so a mode without the search bit does not lock
+        // us out of the directory before it has been walked
+        if recursive && stat.is_dir() {
+            let entries =
+                crate::ssh::utils::ssh2_retry(|| sftp.readdir(path)).map_err(|e| failed(&e))?;
+            for (child_path, child_stat) in entries {
+                let Some(name) = child_path.file_name() else {
+                    continue;
+                };
+                if name == "." || name == ".." || child_stat.file_type().is_symlink() {
+                    continue;
                 }
+                Self::setstat_entry(sftp, &child_path, &child_stat, true, attrs)?;
             }
         }
-        crate::ssh::utils::ssh2_retry(|| sftp.rmdir(path)).map_err(|e| e.to_string())
+        let new_stat = attrs(stat).map_err(|e| failed(&e))?;
+        crate::ssh::utils::ssh2_retry(|| sftp.setstat(path, new_stat.clone()))
+            .map_err(|e| failed(&e))
     }
 
-    fn bg_sftp_rename(pool: SessionSshPool, old: &str, new: &str) -> Result<(), String> {
-        Self::with_file_browser_sftp(pool, |sftp| {
-            crate::ssh::utils::ssh2_retry(|| sftp.rename(Path::new(old), Path::new(new), None))
-                .map_err(|e| e.to_string())
-        })
+    /// Open SFTP on a transfer session, or `None` when the connection forces
+    /// SCP or the server refuses the SFTP subsystem.
+    fn bg_get_transfer_sftp(
+        pool: &SessionSshPool,
+        session: &ManagedSession,
+    ) -> Result<Option<ssh2::Sftp>, String> {
+        if pool.force_scp() {
+            return Ok(None);
+        }
+        match Self::bg_get_sftp(session, pool.sftp_operation_timeout()) {
+            Ok(sftp) => Ok(Some(sftp)),
+            Err(err) if scp::is_sftp_unavailable(&err.message) => Ok(None),
+            Err(err) => Err(err.message),
+        }
+    }
+
+    /// Limiter for one transfer: a private one for an explicit `rate_limit`
+    /// (0 = unlimited), otherwise the shared one set to the configured rate.
+    fn transfer_rate_limiter(
+        app: &tauri::AppHandle,
+        rate_limit: Option<u64>,
+    ) -> Option<Arc<RateLimiter>> {
+        use tauri::Manager;
+
+        let limiter = match rate_limit {
+            Some(rate) => Arc::new(RateLimiter::new(rate)),
+            None => {
+                let rate = crate::db::get_settings(app.clone())
+                    .map(|settings| settings.file_manager.max_transfer_rate_bytes_per_sec)
+                    .unwrap_or(0);
+                let shared = app.state::<AppState>().transfer_rate_limiter.clone();
+                shared.set_rate(rate);
+                shared
+            }
+        };
+        (limiter.rate() > 0).then_some(limiter)
+    }
+
+    /// Run an SCP transfer, emitting `transfer-progress` at the same cadence
+    /// as the SFTP path.
+    fn bg_scp_transfer<F>(
+        pool: &SessionSshPool,
+        app: &tauri::AppHandle,
+        transfer_id: &str,
+        limiter: Option<Arc<RateLimiter>>,
+        cancel_flag: &AtomicBool,
+        pause_flag: &AtomicBool,
+        run: F,
+    ) -> Result<(), String>
+    where
+        F: FnOnce(&mut dyn FnMut(u64, u64)) -> Result<(), String>,
+    {
+        let mut last_emit = Instant::now();
+        let mut last_emit_transferred = 0u64;
+        let mut final_total = 0u64;
+        let mut last_transferred = 0u64;
+        let mut on_progress = |transferred: u64, total: u64| {
+            final_total = total;
+            crate::ssh::utils::wait_while_paused(pause_flag, cancel_flag);
+            let chunk = transferred.saturating_sub(last_transferred);
+            if let Some(limiter) = &limiter {
+                limiter.throttle(chunk, cancel_flag);
+            }
+            pool.record_transferred(chunk);
+            last_transferred = transferred;
+            if last_emit.elapsed().as_millis() > 250
+                || transferred.saturating_sub(last_emit_transferred) >= 256 * 1024
+            {
+                record_transfer_progress(app, transfer_id, transferred, total);
+                last_emit = Instant::now();
+                last_emit_transferred = transferred;
+            }
+        };
+        run(&mut on_progress)?;
+
+        record_transfer_progress(app, transfer_id, final_total, final_total);
+        Ok(())
     }
 
     // --- Transfer Functions using dedicated Transfer Pool ---
@@ -1421,10 +3935,9 @@ impl SshManager {
         transfer_id: &str,
         app: &tauri::AppHandle,
         cancel_flag: &Arc<AtomicBool>,
+        pause_flag: &Arc<AtomicBool>,
+        rate_limit: Option<u64>,
     ) -> Result<(), String> {
-        use crate::ssh::ProgressPayload;
-        use tauri::Emitter;
-
         eprintln!(
             "[DEBUG] bg_sftp_download_with_pool ENTER: transfer_id={}, remote={}",
             transfer_id, remote_path
@@ -1443,6 +3956,7 @@ impl SshManager {
         );
 
         let session_guard = session_mutex.lock().map_err(|e| e.to_string())?;
+        let limiter = Self::transfer_rate_limiter(app, rate_limit);
 
         struct BlockingRestoreGuard<'a> {
             sess: &'a ssh2::Session,
@@ -1465,8 +3979,26 @@ impl SshManager {
             sess: &session_guard.session,
             was_blocking,
         };
-        let sftp = Self::bg_get_sftp(&session_guard, pool.sftp_operation_timeout())
-            .map_err(|e| e.message)?;
+        let Some(sftp) = Self::bg_get_transfer_sftp(&pool, &session_guard)? else {
+            return Self::bg_scp_transfer(
+                &pool,
+                app,
+                transfer_id,
+                limiter,
+                cancel_flag,
+                pause_flag,
+                |on_progress| {
+                    scp::scp_download(
+                        &session_guard.session,
+                        remote_path,
+                        local_path,
+                        crate::ssh::utils::get_sftp_read_buffer_size(Some(app)),
+                        cancel_flag,
+                        on_progress,
+                    )
+                },
+            );
+        };
 
         let mut remote = crate::ssh::utils::ssh2_retry(|| sftp.open(Path::new(remote_path)))
             .map_err(|e| e.to_string())?;
@@ -1477,17 +4009,27 @@ impl SshManager {
 
         let mut local = std::fs::File::create(local_path).map_err(|e| e.to_string())?;
 
-        let mut buf = [0u8; 16384];
+        let mut buffer_size = crate::ssh::utils::get_sftp_read_buffer_size(Some(app));
+        if let Some(limiter) = &limiter {
+            buffer_size = limiter.chunk_size(buffer_size);
+        }
+        let mut buf = vec![0u8; buffer_size];
         let mut transferred = 0u64;
         let mut last_emit = Instant::now();
         let mut last_emit_transferred = 0u64;
 
         // Timeout tracking
-        let transfer_start = Instant::now();
+        let mut transfer_start = Instant::now();
         let mut last_progress_time = Instant::now();
         let mut would_block_count = 0u32;
 
         loop {
+            let paused_for = crate::ssh::utils::wait_while_paused(pause_flag, cancel_flag);
+            if !paused_for.is_zero() {
+                // Time spent paused counts toward neither timeout.
+                transfer_start += paused_for;
+                last_progress_time = Instant::now();
+            }
             if cancel_flag.load(Ordering::Relaxed) {
                 return Err("Cancelled".to_string());
             }
@@ -1511,22 +4053,20 @@ impl SshManager {
             match remote.read(&mut buf) {
                 Ok(0) => break,
                 Ok(n) => {
+                    if let Some(limiter) = &limiter {
+                        // Throttled time is not the connection's fault.
+                        transfer_start += limiter.throttle(n as u64, cancel_flag);
+                    }
                     local.write_all(&buf[..n]).map_err(|e| e.to_string())?;
                     transferred += n as u64;
+                    pool.record_transferred(n as u64);
                     last_progress_time = Instant::now(); // Update progress time
                     would_block_count = 0; // Reset WouldBlock counter on success
 
                     if last_emit.elapsed().as_millis() > 250
                         || transferred.saturating_sub(last_emit_transferred) >= 256 * 1024
                     {
-                        let _ = app.emit(
-                            "transfer-progress",
-                            ProgressPayload {
-                                id: transfer_id.to_string(),
-                                transferred,
-                                total,
-                            },
-                        );
+                        record_transfer_progress(app, transfer_id, transferred, total);
                         last_emit = Instant::now();
                         last_emit_transferred = transferred;
                     }
@@ -1548,14 +4088,7 @@ impl SshManager {
             }
         }
 
-        let _ = app.emit(
-            "transfer-progress",
-            ProgressPayload {
-                id: transfer_id.to_string(),
-                transferred: total,
-                total,
-            },
-        );
+        record_transfer_progress(app, transfer_id, total, total);
 
         Ok(())
     }
@@ -1567,10 +4100,10 @@ impl SshManager {
         transfer_id: &str,
         app: &tauri::AppHandle,
         cancel_flag: &Arc<AtomicBool>,
+        pause_flag: &Arc<AtomicBool>,
+        skip_space_check: bool,
+        rate_limit: Option<u64>,
     ) -> Result<(), String> {
-        use crate::ssh::ProgressPayload;
-        use tauri::Emitter;
-
         eprintln!(
             "[DEBUG] bg_sftp_upload_with_pool ENTER: transfer_id={}, remote={}",
             transfer_id, remote_path
@@ -1589,6 +4122,7 @@ impl SshManager {
         );
 
         let session_guard = session_mutex.lock().map_err(|e| e.to_string())?;
+        let limiter = Self::transfer_rate_limiter(app, rate_limit);
 
         struct BlockingRestoreGuard<'a> {
             sess: &'a ssh2::Session,
@@ -1611,8 +4145,26 @@ impl SshManager {
             sess: &session_guard.session,
             was_blocking,
         };
-        let sftp = Self::bg_get_sftp(&session_guard, pool.sftp_operation_timeout())
-            .map_err(|e| e.message)?;
+        let Some(sftp) = Self::bg_get_transfer_sftp(&pool, &session_guard)? else {
+            return Self::bg_scp_transfer(
+                &pool,
+                app,
+                transfer_id,
+                limiter,
+                cancel_flag,
+                pause_flag,
+                |on_progress| {
+                    scp::scp_upload(
+                        &session_guard.session,
+                        local_path,
+                        remote_path,
+                        crate::ssh::utils::get_sftp_buffer_size(Some(app)),
+                        cancel_flag,
+                        on_progress,
+                    )
+                },
+            );
+        };
 
         let mut local = std::fs::File::open(local_path).map_err(|e| e.to_string())?;
         let metadata = local.metadata().map_err(|e| e.to_string())?;
@@ -1625,21 +4177,60 @@ impl SshManager {
             }
         }
 
+        // Refuse to start when the destination filesystem cannot hold the file,
+        // instead of failing halfway and leaving a truncated remote copy.
+        // Servers without statvfs support are let through unchecked.
+        if !skip_space_check {
+            let parent_dir = Path::new(remote_path)
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| ".".to_string());
+            match Self::sftp_statvfs(&sftp, &parent_dir) {
+                Err(e) => eprintln!("[SFTP] Free-space check skipped for {}: {}", parent_dir, e),
+                Ok(space) => {
+                    // An existing file is replaced, so its blocks count as reclaimable.
+                    let existing = sftp
+                        .stat(Path::new(remote_path))
+                        .ok()
+                        .and_then(|stat| stat.size)
+                        .unwrap_or(0);
+                    let needed = total.saturating_sub(existing);
+                    if needed > space.available {
+                        return Err(format!(
+                            "Not enough space on remote: need {}, have {}",
+                            crate::ssh::utils::format_bytes(needed),
+                            crate::ssh::utils::format_bytes(space.available)
+                        ));
+                    }
+                }
+            }
+        }
+
         let mut remote = crate::ssh::utils::ssh2_retry(|| sftp.create(Path::new(remote_path)))
             .map_err(|e| e.to_string())?;
 
-        let buffer_size = crate::ssh::utils::get_sftp_buffer_size(Some(app));
+        let mut buffer_size = crate::ssh::utils::get_sftp_buffer_size(Some(app));
+        if let Some(limiter) = &limiter {
+            buffer_size = limiter.chunk_size(buffer_size);
+        }
         let mut buf = vec![0u8; buffer_size];
         let mut transferred = 0u64;
         let mut last_emit = Instant::now();
         let mut last_emit_transferred = 0u64;
 
         // Timeout tracking
-        let transfer_start = Instant::now();
+        let mut transfer_start = Instant::now();
         let mut last_progress_time = Instant::now();
         let mut would_block_count = 0u32;
 
         loop {
+            let paused_for = crate::ssh::utils::wait_while_paused(pause_flag, cancel_flag);
+            if !paused_for.is_zero() {
+                // Time spent paused counts toward neither timeout.
+                transfer_start += paused_for;
+                last_progress_time = Instant::now();
+            }
             if cancel_flag.load(Ordering::Relaxed) {
                 return Err("Cancelled".to_string());
             }
@@ -1661,6 +4252,10 @@ impl SshManager {
             if n == 0 {
                 break;
             }
+            if let Some(limiter) = &limiter {
+                // Throttled time is not the connection's fault.
+                transfer_start += limiter.throttle(n as u64, cancel_flag);
+            }
 
             let mut pos = 0;
             while pos < n {
@@ -1668,20 +4263,14 @@ impl SshManager {
                     Ok(written) => {
                         pos += written;
                         transferred += written as u64;
+                        pool.record_transferred(written as u64);
                         last_progress_time = Instant::now(); // Update progress time
                         would_block_count = 0; // Reset WouldBlock counter on success
 
                         if last_emit.elapsed().as_millis() > 250
                             || transferred.saturating_sub(last_emit_transferred) >= 256 * 1024
                         {
-                            let _ = app.emit(
-                                "transfer-progress",
-                                ProgressPayload {
-                                    id: transfer_id.to_string(),
-                                    transferred,
-                                    total,
-                                },
-                            );
+                            record_transfer_progress(app, transfer_id, transferred, total);
                             last_emit = Instant::now();
                             last_emit_transferred = transferred;
                         }
@@ -1704,14 +4293,7 @@ impl SshManager {
             }
         }
 
-        let _ = app.emit(
-            "transfer-progress",
-            ProgressPayload {
-                id: transfer_id.to_string(),
-                transferred: total,
-                total,
-            },
-        );
+        record_transfer_progress(app, transfer_id, total, total);
         Ok(())
     }
 
@@ -1731,6 +4313,8 @@ impl SshManager {
             transfer_id,
             app,
             cancel_flag,
+            &Arc::new(AtomicBool::new(false)),
+            None,
         )
     }
 
@@ -1743,7 +4327,17 @@ impl SshManager {
         cancel_flag: &Arc<AtomicBool>,
     ) -> Result<(), String> {
         // Delegate to the new transfer pool implementation
-        Self::bg_sftp_upload_with_pool(pool, local_path, remote_path, transfer_id, app, cancel_flag)
+        Self::bg_sftp_upload_with_pool(
+            pool,
+            local_path,
+            remote_path,
+            transfer_id,
+            app,
+            cancel_flag,
+            &Arc::new(AtomicBool::new(false)),
+            false,
+            None,
+        )
     }
 
     fn create_remote_dir_recursive(sftp: &ssh2::Sftp, path: &Path) -> Result<(), ssh2::Error> {
@@ -1890,4 +4484,424 @@ impl SshManager {
             Err(format!("Invalid df output for path: {}", path))
         }
     }
+
+    fn sftp_statvfs(sftp: &ssh2::Sftp, path: &str) -> Result<PathFreeSpace, String> {
+        let target = Path::new(path);
+        let mut handle = crate::ssh::utils::ssh2_retry(|| sftp.opendir(target))
+            .or_else(|_| crate::ssh::utils::ssh2_retry(|| sftp.open(target)))
+            .map_err(|e| e.to_string())?;
+        let stat = crate::ssh::utils::ssh2_retry(|| handle.statvfs()).map_err(|e| e.to_string())?;
+
+        let block_size = if stat.f_frsize > 0 {
+            stat.f_frsize
+        } else {
+            stat.f_bsize
+        };
+        Ok(PathFreeSpace {
+            path: path.to_string(),
+            total: stat.f_blocks.saturating_mul(block_size),
+            free: stat.f_bfree.saturating_mul(block_size),
+            available: stat.f_bavail.saturating_mul(block_size),
+            block_size,
+        })
+    }
+
+    /// Hash a remote file with `algorithm`, or with sha256 falling back to
+    /// md5 when `None`.
+    fn bg_remote_file_hash(
+        pool: SessionSshPool,
+        path: &str,
+        algorithm: Option<HashAlgorithm>,
+    ) -> Result<String, String> {
+        let session_mutex = pool.get_transfer_session()?;
+        let session = session_mutex.lock().map_err(|e| e.to_string())?;
+        crate::ssh::utils::get_remote_file_hash(&session, path, algorithm, REMOTE_HASH_TIMEOUT)
+    }
+
+    /// Get free space for a path via SFTP statvfs, falling back to `df -Pk`
+    /// when the server lacks the statvfs@openssh.com extension.
+    fn bg_get_path_free_space(pool: SessionSshPool, path: &str) -> Result<PathFreeSpace, String> {
+        let timeout = pool.sftp_operation_timeout();
+        let session_mutex = pool.get_status_session()?;
+        let session = session_mutex.lock().map_err(|e| e.to_string())?;
+
+        let statvfs = Self::bg_get_sftp(&session, timeout)
+            .map_err(|e| e.message)
+            .and_then(|sftp| Self::sftp_statvfs(&sftp, path));
+        if let Ok(space) = statvfs {
+            return Ok(space);
+        }
+
+        let cmd = format!(
+            "df -Pk '{}' 2>/dev/null | tail -1",
+            crate::ssh::file_ops::escape_shell_arg(path)
+        );
+        let mut channel = crate::ssh::utils::ssh2_retry(|| session.channel_session())
+            .map_err(|e| e.to_string())?;
+        crate::ssh::utils::ssh2_retry(|| channel.exec(&cmd)).map_err(|e| e.to_string())?;
+
+        let mut output = String::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            match channel.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let _ = channel.wait_close();
+
+        crate::ssh::system::parse_df_free_space(path, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_passwd_prompts_across_distros() {
+        for (line, prompt) in [
+            ("Current password: ", PasswdPrompt::Current),
+            ("(current) UNIX password:", PasswdPrompt::Current),
+            ("Old Password:", PasswdPrompt::Current),
+            ("New password: ", PasswdPrompt::New),
+            ("Enter new UNIX password:", PasswdPrompt::New),
+            ("Retype new password:", PasswdPrompt::Retype),
+            ("Retype New Password:", PasswdPrompt::Retype),
+            ("Re-enter new password:", PasswdPrompt::Retype),
+        ] {
+            assert_eq!(passwd_prompt(line), Some(prompt), "{}", line);
+        }
+        assert_eq!(passwd_prompt("Changing password for alice."), None);
+        assert_eq!(passwd_prompt("passwd: password updated successfully"), None);
+    }
+
+    #[test]
+    fn finds_passwd_failure_reason() {
+        let output = "Changing password for alice.\nCurrent password: \nNew password: \n\
+                      BAD PASSWORD: The password is shorter than 8 characters\nNew password: ";
+        assert_eq!(
+            passwd_failure_reason(output).as_deref(),
+            Some("BAD PASSWORD: The password is shorter than 8 characters")
+        );
+        assert_eq!(
+            passwd_failure_reason(
+                "passwd: Authentication token manipulation error\npasswd: password unchanged"
+            )
+            .as_deref(),
+            Some("passwd: Authentication token manipulation error")
+        );
+        assert_eq!(
+            passwd_failure_reason("passwd: password updated successfully"),
+            None
+        );
+    }
+
+    #[test]
+    fn duplicate_name_appends_bak_then_counter() {
+        assert_eq!(duplicate_name("/etc/foo.conf", 0), "/etc/foo.conf.bak");
+        assert_eq!(duplicate_name("/etc/foo.conf", 1), "/etc/foo.conf.bak.1");
+        assert_eq!(duplicate_name("notes", 12), "notes.bak.12");
+    }
+
+    #[test]
+    fn init_command_input_ends_with_single_newline() {
+        assert_eq!(init_command_input("cd /srv"), "cd /srv\n");
+        assert_eq!(
+            init_command_input("cd /srv\nsource .env\n"),
+            "cd /srv\nsource .env\n"
+        );
+    }
+
+    /// Accepts at most `chunk` bytes per call after one `WouldBlock`, and
+    /// nothing once `capacity` is reached.
+    struct SlowWriter {
+        written: Vec<u8>,
+        chunk: usize,
+        capacity: usize,
+        blocked: bool,
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if !self.blocked {
+                self.blocked = true;
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let n = buf
+                .len()
+                .min(self.chunk)
+                .min(self.capacity - self.written.len());
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_all_retrying_finishes_short_writes() {
+        let mut writer = SlowWriter {
+            written: Vec::new(),
+            chunk: 3,
+            capacity: 100,
+            blocked: false,
+        };
+        write_all_retrying(&mut writer, b"authorized_keys").unwrap();
+        assert_eq!(writer.written, b"authorized_keys");
+    }
+
+    #[test]
+    fn write_all_retrying_fails_on_zero_length_write() {
+        let mut writer = SlowWriter {
+            written: Vec::new(),
+            chunk: 4,
+            capacity: 6,
+            blocked: false,
+        };
+        let err = write_all_retrying(&mut writer, b"0123456789").unwrap_err();
+        assert!(err.contains("6 of 10"), "{}", err);
+    }
+
+    fn stat(perm: u32, size: u64) -> ssh2::FileStat {
+        ssh2::FileStat {
+            size: Some(size),
+            uid: None,
+            gid: None,
+            perm: Some(perm),
+            atime: None,
+            mtime: None,
+        }
+    }
+
+    /// `/root` holding `a` (10 bytes), `sub/b` and `sub/c` (5 bytes each)
+    fn list_fixture(dir: &Path) -> Result<DirListing, String> {
+        let entries = match dir.to_str() {
+            Some("/root") => vec![
+                (".", stat(0o040755, 0)),
+                ("/root/a", stat(0o100644, 10)),
+                ("/root/sub", stat(0o040755, 0)),
+            ],
+            Some("/root/sub") => vec![
+                ("/root/sub/b", stat(0o100644, 5)),
+                ("/root/sub/c", stat(0o100644, 5)),
+            ],
+            _ => return Err(format!("no such directory: {}", dir.display())),
+        };
+        Ok(entries
+            .into_iter()
+            .map(|(path, stat)| (std::path::PathBuf::from(path), stat))
+            .collect())
+    }
+
+    #[test]
+    fn walk_tree_visits_directories_after_their_contents() {
+        let mut visited = Vec::new();
+        let finished = walk_tree(&mut list_fixture, Path::new("/root"), &mut |path, _| {
+            visited.push(path.to_string_lossy().to_string());
+            Ok(true)
+        })
+        .unwrap();
+        assert!(finished);
+        assert_eq!(
+            visited,
+            vec!["/root/a", "/root/sub/b", "/root/sub/c", "/root/sub"]
+        );
+    }
+
+    #[test]
+    fn count_tree_tallies_everything_without_a_limit() {
+        let counts = count_tree(&mut list_fixture, Path::new("/root"), None).unwrap();
+        assert_eq!(
+            (counts.file_count, counts.dir_count, counts.total_size),
+            (3, 1, 20)
+        );
+        assert!(!counts.truncated);
+        let exact = count_tree(&mut list_fixture, Path::new("/root"), Some(3)).unwrap();
+        assert!(!exact.truncated);
+    }
+
+    #[test]
+    fn count_tree_stops_past_the_limit() {
+        let counts = count_tree(&mut list_fixture, Path::new("/root"), Some(2)).unwrap();
+        assert!(counts.truncated);
+        assert_eq!(counts.file_count, 2);
+        assert_eq!(counts.total_size, 15);
+    }
+
+    #[test]
+    fn status_follows_the_probe() {
+        assert_eq!(
+            probe_status(SessionProbe::Keepalive),
+            ConnectionStatus::Connected
+        );
+        assert_eq!(
+            probe_status(SessionProbe::Channel),
+            ConnectionStatus::Degraded
+        );
+        assert_eq!(
+            probe_status(SessionProbe::Dead),
+            ConnectionStatus::Disconnected
+        );
+    }
+
+    #[test]
+    fn status_is_reported_only_on_change() {
+        use ConnectionStatus::*;
+        assert_eq!(
+            status_step(None, Connected, true),
+            StatusStep::Report(Connected)
+        );
+        assert_eq!(
+            status_step(Some(Connected), Connected, true),
+            StatusStep::Unchanged
+        );
+        assert_eq!(
+            status_step(Some(Connected), Degraded, true),
+            StatusStep::Report(Degraded)
+        );
+        assert_eq!(
+            status_step(Some(Degraded), Connected, false),
+            StatusStep::Report(Connected)
+        );
+    }
+
+    #[test]
+    fn dead_session_reconnects_once() {
+        use ConnectionStatus::*;
+        assert_eq!(
+            status_step(Some(Connected), Disconnected, true),
+            StatusStep::Reconnect
+        );
+        assert_eq!(
+            status_step(Some(Degraded), Disconnected, true),
+            StatusStep::Reconnect
+        );
+        assert_eq!(
+            status_step(Some(Disconnected), Disconnected, true),
+            StatusStep::Unchanged
+        );
+        assert_eq!(
+            status_step(Some(Connected), Disconnected, false),
+            StatusStep::Report(Disconnected)
+        );
+    }
+
+    #[test]
+    fn idle_backoff_doubles_up_to_cap() {
+        let mut backoff = IdleBackoff::default();
+        let waits: Vec<u64> = (0..8)
+            .map(|_| backoff.next(IDLE_WAIT_MAX).as_millis() as u64)
+            .collect();
+        assert_eq!(waits, vec![5, 10, 20, 40, 80, 160, 200, 200]);
+    }
+
+    #[test]
+    fn idle_backoff_respects_lower_cap_and_resets() {
+        let mut backoff = IdleBackoff::default();
+        for _ in 0..10 {
+            assert!(backoff.next(SHELL_IDLE_WAIT_MAX) <= SHELL_IDLE_WAIT_MAX);
+        }
+        backoff.reset();
+        assert_eq!(backoff.next(IDLE_WAIT_MAX), IDLE_WAIT_MIN);
+    }
+
+    /// Replays a script of read results, splitting chunks larger than the
+    /// caller's buffer across reads like a real channel would.
+    struct ScriptedChannel {
+        steps: std::collections::VecDeque<std::io::Result<Vec<u8>>>,
+    }
+
+    impl Read for ScriptedChannel {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.steps.pop_front() {
+                None => Ok(0),
+                Some(Err(e)) => Err(e),
+                Some(Ok(mut chunk)) => {
+                    let n = chunk.len().min(buf.len());
+                    buf[..n].copy_from_slice(&chunk[..n]);
+                    if n < chunk.len() {
+                        self.steps.push_front(Ok(chunk.split_off(n)));
+                    }
+                    Ok(n)
+                }
+            }
+        }
+    }
+
+    fn drain_shell(channel: &mut ScriptedChannel) -> (Vec<u8>, Vec<ShellPoll>) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut polls = Vec::new();
+        loop {
+            let poll = poll_shell_output(channel, &tx, "test");
+            let closed = poll == ShellPoll::Closed;
+            polls.push(poll);
+            if closed {
+                break;
+            }
+        }
+        drop(tx);
+        let mut data = Vec::new();
+        for msg in rx {
+            match msg {
+                ShellMsg::Data(d) => data.extend(d),
+                _ => panic!("unexpected message"),
+            }
+        }
+        (data, polls)
+    }
+
+    fn would_block() -> std::io::Result<Vec<u8>> {
+        Err(std::io::Error::new(ErrorKind::WouldBlock, "again"))
+    }
+
+    #[test]
+    fn shell_output_is_forwarded_once_in_order() {
+        let big: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let mut channel = ScriptedChannel {
+            steps: vec![
+                Ok(b"hello ".to_vec()),
+                would_block(),
+                Ok(big.clone()),
+                would_block(),
+                would_block(),
+                Ok(b"world".to_vec()),
+            ]
+            .into(),
+        };
+
+        let (data, polls) = drain_shell(&mut channel);
+
+        let mut expected = b"hello ".to_vec();
+        expected.extend(&big);
+        expected.extend(b"world");
+        assert_eq!(data, expected);
+        assert_eq!(polls.iter().filter(|p| **p == ShellPoll::Idle).count(), 3);
+        assert_eq!(polls.last(), Some(&ShellPoll::Closed));
+    }
+
+    #[test]
+    fn shell_read_error_closes_after_delivered_data() {
+        let mut channel = ScriptedChannel {
+            steps: vec![
+                Ok(b"partial".to_vec()),
+                Err(std::io::Error::new(ErrorKind::ConnectionReset, "reset")),
+                Ok(b"never read".to_vec()),
+            ]
+            .into(),
+        };
+
+        let (data, polls) = drain_shell(&mut channel);
+
+        assert_eq!(data, b"partial");
+        assert_eq!(polls, vec![ShellPoll::Data, ShellPoll::Closed]);
+    }
 }
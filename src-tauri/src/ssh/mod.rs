@@ -4,6 +4,9 @@ pub const DEFAULT_JUMP_HOST_TIMEOUT_SECS: u32 = 30;
 pub const DEFAULT_LOCAL_FORWARD_TIMEOUT_SECS: u32 = 10;
 pub const DEFAULT_COMMAND_TIMEOUT_SECS: u32 = 30;
 pub const DEFAULT_SFTP_OPERATION_TIMEOUT_SECS: u32 = 60;
+// Overall limit for `exec_command` runs, which may be long (builds, installs)
+pub const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 120;
+pub const DEFAULT_FORWARD_BIND_ADDRESS: &str = "127.0.0.1";
 
 use crate::models::ConnectionTimeoutSettings;
 use tauri::{AppHandle, Emitter};
@@ -52,6 +55,41 @@ pub fn get_sftp_operation_timeout(
     )
 }
 
+pub fn get_forward_bind_address(settings: Option<&ConnectionTimeoutSettings>) -> String {
+    settings
+        .map(|s| s.forward_bind_address.trim())
+        .filter(|address| !address.is_empty())
+        .unwrap_or(DEFAULT_FORWARD_BIND_ADDRESS)
+        .to_string()
+}
+
+/// Check that `address` is an IP the forward listeners can bind to. Binding to
+/// an unspecified address exposes forwarded ports to the network, so that is
+/// allowed but comes back as a warning.
+pub fn validate_forward_bind_address(address: &str) -> Result<Option<String>, String> {
+    let address = address.trim();
+    if address.is_empty() {
+        return Ok(None);
+    }
+    let ip: std::net::IpAddr = address
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .map_err(|_| {
+            format!(
+                "Invalid forward bind address '{}': expected an IP address",
+                address
+            )
+        })?;
+    if ip.is_unspecified() {
+        return Ok(Some(format!(
+            "Forwarded ports bound to {} are reachable from other machines on the network",
+            address
+        )));
+    }
+    Ok(None)
+}
+
 #[derive(Clone)]
 pub struct ExecStreamContext {
     pub event_name: String,
@@ -86,7 +124,16 @@ pub fn emit_command_output(
 #[derive(Debug, Clone)]
 pub enum ShellMsg {
     Data(Vec<u8>),
-    Resize { rows: u16, cols: u16 },
+    Resize {
+        rows: u16,
+        cols: u16,
+    },
+    /// Close the shell's input (SSH channel EOF)
+    Eof,
+    /// Send a serial-style break to the PTY
+    Break,
+    /// The remote shell ended with this exit status (-1 when unknown)
+    Exited(i32),
     Exit,
 }
 
@@ -97,24 +144,50 @@ pub struct ProgressPayload {
     pub total: u64,
 }
 
+#[derive(Clone, serde::Serialize)]
+pub struct BatchProgressPayload {
+    pub id: String,
+    pub completed: usize,
+    pub total: usize,
+}
+
+pub mod archive;
+pub mod authorized_keys;
+pub mod benchmark;
+pub mod checksum;
 pub mod client;
 pub mod command;
 pub mod connection;
+pub mod connectivity;
+pub mod dir_watch;
+pub mod docker;
+pub mod editor;
 pub mod error_classifier;
 pub mod events;
+pub mod file_handles;
 pub mod file_ops;
+pub mod forwards;
 pub mod health_check;
 pub mod heartbeat;
 pub mod keys;
+pub mod known_hosts;
 pub mod manager;
 pub mod network_monitor;
+pub mod proxy_command;
 pub mod reconnect;
+pub mod scp;
+pub mod service;
+pub mod ssh_config;
+pub mod sync;
 pub mod system;
 pub mod terminal;
 pub mod transfer;
+pub mod transfer_queue;
+pub mod trash;
 pub mod tunnel;
 pub mod utils;
 pub mod wsl;
+pub mod x11;
 
 // Re-export main types and functions for backward compatibility
 pub use client::AppState;
@@ -0,0 +1,277 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// A running `ProxyCommand`. libssh2 only drives sockets, so the child's
+/// stdin/stdout are relayed over a loopback TCP pair whose other end goes to
+/// `Session::set_tcp_stream`. Dropping this kills the child.
+pub struct ProxyCommand {
+    child: Child,
+}
+
+impl Drop for ProxyCommand {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Substitute ssh_config(5) tokens: `%h` host, `%p` port, `%r` remote user
+/// and `%%` a literal percent sign. Unknown tokens are left as they are.
+pub(crate) fn expand_tokens(command: &str, host: &str, port: u16, user: &str) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => expanded.push_str(host),
+            Some('p') => expanded.push_str(&port.to_string()),
+            Some('r') => expanded.push_str(user),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+fn shell_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command).creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        // `exec` so killing the child kills the proxy itself, not just the shell
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(format!("exec {}", command));
+        cmd
+    }
+}
+
+/// A connected loopback pair. The listener only lives long enough to accept
+/// our own connection; anything else that races in is dropped.
+fn loopback_pair() -> Result<(TcpStream, TcpStream), String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to bind proxy relay: {}", e))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to bind proxy relay: {}", e))?;
+    let ssh_side =
+        TcpStream::connect(addr).map_err(|e| format!("Failed to connect proxy relay: {}", e))?;
+    let expected_peer = ssh_side
+        .local_addr()
+        .map_err(|e| format!("Failed to connect proxy relay: {}", e))?;
+    loop {
+        let (relay_side, peer) = listener
+            .accept()
+            .map_err(|e| format!("Failed to accept proxy relay: {}", e))?;
+        if peer == expected_peer {
+            let _ = ssh_side.set_nodelay(true);
+            let _ = relay_side.set_nodelay(true);
+            return Ok((ssh_side, relay_side));
+        }
+    }
+}
+
+/// Start `command` (tokens already expanded) through the platform shell and
+/// return the stream to hand to libssh2 along with the child's handle.
+pub(crate) fn spawn_proxy_command(command: &str) -> Result<(TcpStream, ProxyCommand), String> {
+    let child = shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start ProxyCommand '{}': {}", command, e))?;
+    // Killed by `ProxyCommand::drop` on any early return below
+    let mut proxy = ProxyCommand { child };
+    let mut stdin = proxy
+        .child
+        .stdin
+        .take()
+        .ok_or("ProxyCommand stdin unavailable")?;
+    let mut stdout = proxy
+        .child
+        .stdout
+        .take()
+        .ok_or("ProxyCommand stdout unavailable")?;
+    let stderr = proxy.child.stderr.take();
+
+    let (ssh_side, relay_side) = loopback_pair()?;
+    let mut to_child = relay_side
+        .try_clone()
+        .map_err(|e| format!("Failed to clone proxy relay: {}", e))?;
+    let mut from_child = relay_side;
+
+    // Session -> child. Ends when the session closes its socket; dropping
+    // stdin then tells the proxy there is nothing more to send.
+    thread::spawn(move || {
+        let mut buf = [0u8; 32 * 1024];
+        loop {
+            match to_child.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if stdin
+                        .write_all(&buf[..n])
+                        .and_then(|_| stdin.flush())
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = to_child.shutdown(Shutdown::Read);
+    });
+
+    // Child -> session. When the proxy exits the session sees EOF.
+    thread::spawn(move || {
+        let _ = std::io::copy(&mut stdout, &mut from_child);
+        let _ = from_child.shutdown(Shutdown::Both);
+    });
+
+    // Surface the proxy's diagnostics, e.g. authentication prompts it failed on
+    if let Some(stderr) = stderr {
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                eprintln!("ProxyCommand: {}", line);
+            }
+        });
+    }
+
+    Ok((ssh_side, proxy))
+}
+
+/// Whether `path` names a Windows named pipe (`\\.\pipe\name`, or with
+/// forward slashes) rather than a Unix domain socket.
+pub(crate) fn is_named_pipe_path(path: &str) -> bool {
+    path.replace('/', "\\")
+        .to_ascii_lowercase()
+        .starts_with(r"\\.\pipe\")
+}
+
+/// Connect to sshd behind a Windows named pipe and return the stream to hand
+/// to libssh2. The pipe is relayed over a loopback pair like a `ProxyCommand`;
+/// the relay needs overlapped I/O to read and write the pipe at once, so it
+/// runs Tokio's pipe client on a thread of its own.
+#[cfg(target_os = "windows")]
+pub(crate) fn connect_named_pipe(path: &str, timeout: Duration) -> Result<TcpStream, String> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+    // All instances of the pipe are connected to other clients
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    let (ssh_side, relay_side) = loopback_pair()?;
+    relay_side
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set up pipe relay: {}", e))?;
+    let (opened_tx, opened_rx) = std::sync::mpsc::channel::<Result<(), String>>();
+    let path = path.to_string();
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                let _ = opened_tx.send(Err(format!("Failed to start pipe relay: {}", e)));
+                return;
+            }
+        };
+        runtime.block_on(async move {
+            let deadline = tokio::time::Instant::now() + timeout;
+            let mut pipe = loop {
+                match ClientOptions::new().open(&path) {
+                    Ok(pipe) => break pipe,
+                    Err(e)
+                        if e.raw_os_error() == Some(ERROR_PIPE_BUSY)
+                            && tokio::time::Instant::now() < deadline =>
+                    {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                    Err(e) => {
+                        let _ =
+                            opened_tx.send(Err(format!("Failed to open pipe '{}': {}", path, e)));
+                        return;
+                    }
+                }
+            };
+            let mut relay = match tokio::net::TcpStream::from_std(relay_side) {
+                Ok(relay) => relay,
+                Err(e) => {
+                    let _ = opened_tx.send(Err(format!("Failed to set up pipe relay: {}", e)));
+                    return;
+                }
+            };
+            let _ = opened_tx.send(Ok(()));
+            // Ends once the session closes its socket or sshd closes the pipe
+            let _ = tokio::io::copy_bidirectional(&mut pipe, &mut relay).await;
+        });
+    });
+    opened_rx
+        .recv()
+        .map_err(|_| "Pipe relay stopped unexpectedly".to_string())??;
+    Ok(ssh_side)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn connect_named_pipe(path: &str, _timeout: Duration) -> Result<TcpStream, String> {
+    Err(format!(
+        "'{}' is a Windows named pipe, which is only available on Windows",
+        path
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_named_pipe_paths() {
+        assert!(is_named_pipe_path(r"\\.\pipe\openssh-ssh-agent"));
+        assert!(is_named_pipe_path("//./pipe/sshd"));
+        assert!(is_named_pipe_path(r"\\.\PIPE\sshd"));
+        assert!(!is_named_pipe_path("/run/sshd.sock"));
+        assert!(!is_named_pipe_path(r"C:\Users\me\sshd.sock"));
+    }
+
+    #[test]
+    fn expands_host_port_and_user_tokens() {
+        assert_eq!(
+            expand_tokens("corkscrew proxy 8080 %h %p", "example.com", 22, "root"),
+            "corkscrew proxy 8080 example.com 22"
+        );
+        assert_eq!(
+            expand_tokens(
+                "cloudflared access ssh --hostname %h --id %r",
+                "ssh.example.com",
+                2222,
+                "deploy"
+            ),
+            "cloudflared access ssh --hostname ssh.example.com --id deploy"
+        );
+    }
+
+    #[test]
+    fn keeps_literal_and_unknown_percent_signs() {
+        assert_eq!(
+            expand_tokens("echo 100%% %x %", "h", 1, "u"),
+            "echo 100% %x %"
+        );
+    }
+}
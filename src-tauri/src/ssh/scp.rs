@@ -0,0 +1,328 @@
+//! SCP / exec fallbacks for servers that have the SFTP subsystem disabled.
+//!
+//! Single files move over `scp_recv`/`scp_send`; small reads and writes used
+//! by the file browser go through `cat`/`tee` on an exec channel.
+
+use super::file_ops::escape_shell_arg;
+use ssh2::{Channel, Session};
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Whether an SFTP init error means the server refused the subsystem (as
+/// opposed to a slow or broken session, which is worth retrying over SFTP).
+pub fn is_sftp_unavailable(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    lower.contains("unable to request sftp subsystem") || lower.contains("subsystem request failed")
+}
+
+/// Switches the session to blocking mode and restores the previous mode on drop.
+struct BlockingGuard<'a> {
+    session: &'a Session,
+    was_blocking: bool,
+}
+
+impl<'a> BlockingGuard<'a> {
+    fn new(session: &'a Session) -> Self {
+        let was_blocking = session.is_blocking();
+        if !was_blocking {
+            session.set_blocking(true);
+        }
+        Self {
+            session,
+            was_blocking,
+        }
+    }
+}
+
+impl Drop for BlockingGuard<'_> {
+    fn drop(&mut self) {
+        if !self.was_blocking {
+            self.session.set_blocking(false);
+        }
+    }
+}
+
+fn finish_channel(channel: &mut Channel) {
+    let _ = channel.send_eof();
+    let _ = channel.wait_eof();
+    let _ = channel.close();
+    let _ = channel.wait_close();
+}
+
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    loop {
+        match reader.read(buf) {
+            Err(e) if e.kind() == ErrorKind::Interrupted || e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(5));
+            }
+            other => return other,
+        }
+    }
+}
+
+/// Download one file with SCP. `on_progress(transferred, total)` is called
+/// after every chunk.
+pub fn scp_download(
+    session: &Session,
+    remote_path: &str,
+    local_path: &str,
+    buffer_size: usize,
+    cancel_flag: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let _blocking = BlockingGuard::new(session);
+    let (mut channel, stat) = session
+        .scp_recv(Path::new(remote_path))
+        .map_err(|e| format!("SCP download failed: {}", e))?;
+    let total = stat.size();
+
+    let mut local = std::fs::File::create(local_path).map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; buffer_size];
+    let mut transferred = 0u64;
+
+    // SCP appends a status byte after the file data, so stop at the
+    // announced size instead of reading to EOF.
+    while transferred < total {
+        if cancel_flag.load(Ordering::Relaxed) {
+            finish_channel(&mut channel);
+            return Err("Cancelled".to_string());
+        }
+        let want = (total - transferred).min(buf.len() as u64) as usize;
+        let n = read_fully(&mut channel, &mut buf[..want]).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err(format!(
+                "SCP download ended early at {} of {} bytes",
+                transferred, total
+            ));
+        }
+        local.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+        transferred += n as u64;
+        on_progress(transferred, total);
+    }
+
+    finish_channel(&mut channel);
+    Ok(())
+}
+
+/// Upload one file with SCP, creating or replacing `remote_path`.
+pub fn scp_upload(
+    session: &Session,
+    local_path: &str,
+    remote_path: &str,
+    buffer_size: usize,
+    cancel_flag: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let _blocking = BlockingGuard::new(session);
+    let mut local = std::fs::File::open(local_path).map_err(|e| e.to_string())?;
+    let total = local.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut channel = session
+        .scp_send(Path::new(remote_path), 0o644, total, None)
+        .map_err(|e| format!("SCP upload failed: {}", e))?;
+    let mut buf = vec![0u8; buffer_size];
+    let mut transferred = 0u64;
+
+    loop {
+        if cancel_flag.load(Ordering::Relaxed) {
+            finish_channel(&mut channel);
+            return Err("Cancelled".to_string());
+        }
+        let n = local.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        channel
+            .write_all(&buf[..n])
+            .map_err(|e| format!("SCP upload failed: {}", e))?;
+        transferred += n as u64;
+        on_progress(transferred, total);
+    }
+
+    finish_channel(&mut channel);
+    Ok(())
+}
+
+fn exit_error(channel: &mut Channel, command: &str) -> Result<(), String> {
+    let status = channel.exit_status().map_err(|e| e.to_string())?;
+    if status == 0 {
+        return Ok(());
+    }
+    let mut stderr = String::new();
+    let _ = channel.stderr().read_to_string(&mut stderr);
+    Err(format!(
+        "{} exited with code {}: {}",
+        command,
+        status,
+        stderr.trim()
+    ))
+}
+
+/// Largest file moved through `cat`/`tee`. These paths buffer the whole file
+/// in memory and are meant for config-sized files, not transfers.
+pub const EXEC_FILE_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Read a file with `cat`, stopping after `max_len` bytes when given. Files
+/// over `EXEC_FILE_MAX_BYTES` are refused unless `max_len` truncates them
+/// below it.
+pub fn exec_read_file(
+    session: &Session,
+    path: &str,
+    max_len: Option<usize>,
+) -> Result<Vec<u8>, String> {
+    let (max_len, capped) = match max_len {
+        Some(max) if max <= EXEC_FILE_MAX_BYTES => (Some(max), false),
+        _ => (Some(EXEC_FILE_MAX_BYTES + 1), true),
+    };
+    let _blocking = BlockingGuard::new(session);
+    let mut channel = session
+        .channel_session()
+        .map_err(|e| format!("Failed to create channel: {}", e))?;
+    channel
+        .exec(&format!("cat -- '{}'", escape_shell_arg(path)))
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let mut content = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = read_fully(&mut channel, &mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        content.extend_from_slice(&buf[..n]);
+        if let Some(max) = max_len {
+            if content.len() >= max {
+                let _ = channel.close();
+                let _ = channel.wait_close();
+                if capped {
+                    return Err(format!(
+                        "{} is larger than {} bytes, too large to read without SFTP",
+                        path, EXEC_FILE_MAX_BYTES
+                    ));
+                }
+                content.truncate(max);
+                return Ok(content);
+            }
+        }
+    }
+
+    let _ = channel.wait_close();
+    exit_error(&mut channel, "cat")?;
+    Ok(content)
+}
+
+fn check_exec_write_size(content: &[u8]) -> Result<(), String> {
+    if content.len() > EXEC_FILE_MAX_BYTES {
+        return Err(format!(
+            "Content is larger than {} bytes, too large to write without SFTP",
+            EXEC_FILE_MAX_BYTES
+        ));
+    }
+    Ok(())
+}
+
+/// Write (or with `append`, extend) a file by piping `content` into `tee`.
+pub fn exec_write_file(
+    session: &Session,
+    path: &str,
+    content: &[u8],
+    append: bool,
+) -> Result<(), String> {
+    check_exec_write_size(content)?;
+    let _blocking = BlockingGuard::new(session);
+    let mut channel = session
+        .channel_session()
+        .map_err(|e| format!("Failed to create channel: {}", e))?;
+    let flag = if append { "-a " } else { "" };
+    channel
+        .exec(&format!(
+            "tee {}-- '{}' >/dev/null",
+            flag,
+            escape_shell_arg(path)
+        ))
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    channel
+        .write_all(content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    let _ = channel.send_eof();
+    let _ = channel.wait_eof();
+    let _ = channel.wait_close();
+    exit_error(&mut channel, "tee")
+}
+
+/// Rename `from` over `to` with `mv -f`, which replaces an existing target
+/// atomically where SFTPv3 rename refuses to.
+pub fn exec_rename(session: &Session, from: &str, to: &str) -> Result<(), String> {
+    let _blocking = BlockingGuard::new(session);
+    let mut channel = session
+        .channel_session()
+        .map_err(|e| format!("Failed to create channel: {}", e))?;
+    channel
+        .exec(&format!(
+            "mv -f -- '{}' '{}'",
+            escape_shell_arg(from),
+            escape_shell_arg(to)
+        ))
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    finish_channel(&mut channel);
+    exit_error(&mut channel, "mv")
+}
+
+/// `exec_write_file` counterpart of the atomic save: write to a temp file next
+/// to `path`, copy the original's mode onto it and `mv` it into place.
+pub fn exec_write_file_atomic(session: &Session, path: &str, content: &[u8]) -> Result<(), String> {
+    check_exec_write_size(content)?;
+    let _blocking = BlockingGuard::new(session);
+    let mut channel = session
+        .channel_session()
+        .map_err(|e| format!("Failed to create channel: {}", e))?;
+    let target = escape_shell_arg(path);
+    let script = format!(
+        "t='{target}.tmp.{id}'; cat > \"$t\" && {{ [ ! -e '{target}' ] || chmod \"$(stat -c %a '{target}' 2>/dev/null || stat -f %Lp '{target}')\" \"$t\"; }} && mv -f \"$t\" '{target}' || {{ rm -f \"$t\"; exit 1; }}",
+        target = target,
+        id = uuid::Uuid::new_v4()
+    );
+    channel
+        .exec(&script)
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    channel
+        .write_all(content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    let _ = channel.send_eof();
+    let _ = channel.wait_eof();
+    let _ = channel.wait_close();
+    exit_error(&mut channel, "atomic save")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sftp_unavailable_matches_refused_subsystem() {
+        assert!(is_sftp_unavailable(
+            "[Session(-31)] Unable to request SFTP subsystem"
+        ));
+        assert!(is_sftp_unavailable(
+            "Failed to init SFTP: [Session(-31)] Unable to request SFTP subsystem"
+        ));
+        assert!(is_sftp_unavailable("subsystem request failed on channel 0"));
+    }
+
+    #[test]
+    fn sftp_unavailable_ignores_transient_errors() {
+        assert!(!is_sftp_unavailable("[Session(-37)] Would block"));
+        assert!(!is_sftp_unavailable(
+            "[Session(-9)] Timed out waiting on socket"
+        ));
+        assert!(!is_sftp_unavailable(
+            "[Session(-7)] Unable to send FXP_OPEN*"
+        ));
+        assert!(!is_sftp_unavailable(""));
+    }
+}
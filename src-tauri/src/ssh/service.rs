@@ -0,0 +1,193 @@
+use super::client::AppState;
+use super::command::{register_cancel_flag, unregister_cancel_flag};
+use super::system::run_client_command_with_status;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+/// Error returned when the host is not managed by systemd, so the UI can
+/// hide the services panel instead of showing a raw shell error.
+pub const SYSTEMD_UNAVAILABLE: &str = "SYSTEMD_UNAVAILABLE";
+
+/// Wrap a systemctl invocation so non-systemd hosts print the marker with status 127.
+/// `(exit 127)` sets `$?` without ending the shell, keeping the exit-code trailer intact.
+fn systemd_script(systemctl_args: &str) -> String {
+    format!(
+        "export LC_ALL=C; if command -v systemctl >/dev/null 2>&1 && [ -d /run/systemd/system ]; then systemctl {} 2>&1; else echo {}; (exit 127); fi",
+        systemctl_args, SYSTEMD_UNAVAILABLE
+    )
+}
+
+const SERVICE_ACTIONS: &[&str] = &["start", "stop", "restart", "status"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceUnit {
+    pub unit: String,
+    pub load: String,
+    pub active: String,
+    pub sub: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceActionResult {
+    pub exit_code: i32,
+    pub output: String,
+}
+
+/// Parse `systemctl list-units --plain --no-legend` rows: UNIT LOAD ACTIVE SUB DESCRIPTION
+fn parse_service_units(raw: &str) -> Vec<ServiceUnit> {
+    raw.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let unit = parts.next()?;
+            if !unit.ends_with(".service") {
+                return None;
+            }
+            let load = parts.next()?;
+            let active = parts.next()?;
+            let sub = parts.next()?;
+            Some(ServiceUnit {
+                unit: unit.to_string(),
+                load: load.to_string(),
+                active: active.to_string(),
+                sub: sub.to_string(),
+                description: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+fn is_valid_unit_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '_' | '-' | ':' | '\\'))
+}
+
+fn check_systemd_output(output: String, exit_code: i32) -> Result<(String, i32), String> {
+    if exit_code == 127 && output.trim() == SYSTEMD_UNAVAILABLE {
+        Err(SYSTEMD_UNAVAILABLE.to_string())
+    } else {
+        Ok((output, exit_code))
+    }
+}
+
+/// List all systemd service units on the host.
+#[command]
+pub async fn list_services(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<ServiceUnit>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let script = systemd_script("list-units --type=service --all --no-pager --plain --no-legend");
+
+    let (output, exit_code) =
+        run_client_command_with_status(&client.client_type, script, None).await?;
+
+    let (output, exit_code) = check_systemd_output(output, exit_code)?;
+    if exit_code != 0 {
+        return Err(format!(
+            "systemctl exited with code {}: {}",
+            exit_code, output
+        ));
+    }
+    Ok(parse_service_units(&output))
+}
+
+/// Run `systemctl start|stop|restart|status <name>`. A `command_id` registers
+/// the call with `cancel_command_execution`, like `exec_command` does.
+#[command]
+pub async fn control_service(
+    state: State<'_, AppState>,
+    id: String,
+    name: String,
+    action: String,
+    command_id: Option<String>,
+) -> Result<ServiceActionResult, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    if !SERVICE_ACTIONS.contains(&action.as_str()) {
+        return Err(format!("Unsupported service action: {}", action));
+    }
+    if !is_valid_unit_name(&name) {
+        return Err(format!("Invalid service name: {}", name));
+    }
+
+    let cancel_flag = register_cancel_flag(&state, command_id.as_deref())?;
+
+    let script = systemd_script(&format!("{} --no-pager '{}'", action, name));
+
+    let result = run_client_command_with_status(&client.client_type, script, cancel_flag).await;
+    unregister_cancel_flag(&state, command_id.as_deref());
+
+    let (output, exit_code) = result?;
+    let (output, exit_code) = check_systemd_output(output, exit_code)?;
+    Ok(ServiceActionResult { exit_code, output })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_service_units_reads_rows() {
+        let raw = "\
+ssh.service                 loaded active running OpenBSD Secure Shell server
+cron.service                loaded active running Regular background program processing daemon
+systemd-tmpfiles-clean.timer loaded active waiting Daily Cleanup of Temporary Directories
+nginx.service               not-found inactive dead nginx.service
+broken.service loaded
+";
+        let units = parse_service_units(raw);
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[0].unit, "ssh.service");
+        assert_eq!(units[0].load, "loaded");
+        assert_eq!(units[0].active, "active");
+        assert_eq!(units[0].sub, "running");
+        assert_eq!(units[0].description, "OpenBSD Secure Shell server");
+        assert_eq!(units[2].load, "not-found");
+        assert_eq!(units[2].description, "nginx.service");
+    }
+
+    #[test]
+    fn unit_names_allow_systemd_escapes() {
+        for name in [
+            "nginx.service",
+            "getty@tty1.service",
+            "systemd-fsck@dev-disk-by\\x2duuid-1234.service",
+            "user-runtime-dir@1000",
+        ] {
+            assert!(is_valid_unit_name(name), "{:?} rejected", name);
+        }
+    }
+
+    #[test]
+    fn unit_names_reject_shell_syntax() {
+        for name in [
+            "",
+            "nginx;reboot",
+            "nginx service",
+            "nginx'",
+            "nginx\"",
+            "$(reboot)",
+            "`reboot`",
+            "a|b",
+            "a&b",
+            "a\nb",
+            "-H",
+            "--host=evil",
+        ] {
+            assert!(!is_valid_unit_name(name), "{:?} accepted", name);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use crate::models::{Connection as SshConnConfig, ConnectionImportSummary, JumpHop, SshKey};
+use std::path::PathBuf;
+use tauri::{command, AppHandle};
+
+/// Settings read from one `Host` block. Only the directives we can map onto
+/// a `Connection` are kept.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SshConfigEntry {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+    pub proxy_command: Option<String>,
+}
+
+impl SshConfigEntry {
+    /// Fill unset fields from `defaults`; values set on the host itself win.
+    fn merge_defaults(&mut self, defaults: &SshConfigEntry) {
+        if self.host_name.is_none() {
+            self.host_name = defaults.host_name.clone();
+        }
+        if self.user.is_none() {
+            self.user = defaults.user.clone();
+        }
+        if self.port.is_none() {
+            self.port = defaults.port;
+        }
+        if self.identity_file.is_none() {
+            self.identity_file = defaults.identity_file.clone();
+        }
+        if self.proxy_jump.is_none() {
+            self.proxy_jump = defaults.proxy_jump.clone();
+        }
+        if self.proxy_command.is_none() {
+            self.proxy_command = defaults.proxy_command.clone();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub entry: SshConfigEntry,
+}
+
+struct HostBlock {
+    patterns: Vec<String>,
+    entry: SshConfigEntry,
+}
+
+fn is_pattern(alias: &str) -> bool {
+    alias.contains(['*', '?', '!'])
+}
+
+/// ssh_config(5) style glob with `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p.split_first() {
+            None => t.is_empty(),
+            Some(('*', rest)) => (0..=t.len()).any(|i| matches(rest, &t[i..])),
+            Some(('?', rest)) => !t.is_empty() && matches(rest, &t[1..]),
+            Some((c, rest)) => t.first() == Some(c) && matches(rest, &t[1..]),
+        }
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    matches(&p, &t)
+}
+
+fn block_matches(patterns: &[String], alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_match(negated, alias) {
+                return false;
+            }
+        } else if glob_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Split `Key Value` / `Key=Value`, dropping surrounding quotes from the value.
+fn split_directive(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    let split_at = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let key = line[..split_at].to_lowercase();
+    let value = line[split_at..]
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+        .trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    if value.is_empty() {
+        None
+    } else {
+        Some((key, value.to_string()))
+    }
+}
+
+/// Parse an OpenSSH client config into concrete hosts. Wildcard blocks (and
+/// directives before the first `Host`) are applied as defaults to every host
+/// they match instead of becoming hosts themselves. `Match` blocks are ignored.
+pub fn parse_ssh_config(content: &str) -> Vec<SshConfigHost> {
+    let mut blocks = vec![HostBlock {
+        patterns: vec!["*".to_string()],
+        entry: SshConfigEntry::default(),
+    }];
+    let mut in_match = false;
+
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = split_directive(line) else {
+            continue;
+        };
+
+        match key.as_str() {
+            "host" => {
+                in_match = false;
+                blocks.push(HostBlock {
+                    patterns: value.split_whitespace().map(str::to_string).collect(),
+                    entry: SshConfigEntry::default(),
+                });
+                continue;
+            }
+            "match" => {
+                in_match = true;
+                continue;
+            }
+            _ if in_match => continue,
+            _ => {}
+        }
+
+        // Like ssh itself, the first value given for a directive wins.
+        let entry = &mut blocks.last_mut().expect("global block").entry;
+        match key.as_str() {
+            "hostname" if entry.host_name.is_none() => entry.host_name = Some(value),
+            "user" if entry.user.is_none() => entry.user = Some(value),
+            "port" if entry.port.is_none() => entry.port = value.parse().ok(),
+            "identityfile" if entry.identity_file.is_none() => entry.identity_file = Some(value),
+            "proxyjump" if entry.proxy_jump.is_none() => entry.proxy_jump = Some(value),
+            "proxycommand" if entry.proxy_command.is_none() => entry.proxy_command = Some(value),
+            _ => {}
+        }
+    }
+
+    let mut hosts: Vec<SshConfigHost> = Vec::new();
+    for block in &blocks {
+        for alias in block.patterns.iter().filter(|p| !is_pattern(p)) {
+            match hosts.iter_mut().find(|host| &host.alias == alias) {
+                Some(host) => host.entry.merge_defaults(&block.entry),
+                None => hosts.push(SshConfigHost {
+                    alias: alias.clone(),
+                    entry: block.entry.clone(),
+                }),
+            }
+        }
+    }
+
+    for host in &mut hosts {
+        for block in blocks
+            .iter()
+            .filter(|b| b.patterns.iter().any(|p| is_pattern(p)))
+        {
+            if block_matches(&block.patterns, &host.alias) {
+                host.entry.merge_defaults(&block.entry);
+            }
+        }
+    }
+
+    hosts
+}
+
+/// A `ProxyJump` hop as (user, host, port).
+type JumpTarget = (Option<String>, String, Option<u16>);
+
+/// Every hop of a `ProxyJump` value, outermost first. `none` disables it.
+fn parse_proxy_jump(value: &str) -> Vec<JumpTarget> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(parse_jump_hop)
+        .collect()
+}
+
+fn parse_jump_hop(hop: &str) -> JumpTarget {
+    let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+    let (user, host_port) = match hop.rsplit_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, hop),
+    };
+    let (host, port) = match host_port.strip_prefix('[') {
+        // Bracketed IPv6 literal, optionally followed by `:port`
+        Some(rest) => match rest.split_once(']') {
+            Some((host, tail)) => (host, tail.strip_prefix(':').and_then(|p| p.parse().ok())),
+            None => (rest, None),
+        },
+        None => match host_port.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (host, port.parse().ok()),
+            _ => (host_port, None),
+        },
+    };
+    (user, host.to_string(), port)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Store the private key behind `IdentityFile`, reusing an existing key with
+/// the same content. Returns `None` when the file cannot be read.
+fn import_identity_file(
+    app_handle: &AppHandle,
+    identity_file: &str,
+    known_keys: &mut Vec<SshKey>,
+) -> Result<Option<i64>, String> {
+    let path = expand_home(identity_file);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    if let Some(existing) = known_keys.iter().find(|key| key.content == content) {
+        return Ok(existing.id);
+    }
+
+    let key = SshKey {
+        id: None,
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| identity_file.to_string()),
+        content,
+        passphrase: None,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    let id = crate::db::insert_ssh_key(app_handle, &key)?;
+    known_keys.push(SshKey {
+        id: Some(id),
+        ..key
+    });
+    Ok(Some(id))
+}
+
+/// Create connections from an OpenSSH config file (default `~/.ssh/config`).
+/// Hosts whose name already exists, or that resolve to no address, are skipped.
+#[command]
+pub fn import_ssh_config(
+    app_handle: AppHandle,
+    path: Option<String>,
+) -> Result<ConnectionImportSummary, String> {
+    let path = match path {
+        Some(path) => expand_home(&path),
+        None => dirs::home_dir()
+            .ok_or("Cannot determine home directory")?
+            .join(".ssh")
+            .join("config"),
+    };
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let hosts = parse_ssh_config(&content);
+
+    let existing_names: Vec<String> = crate::db::get_connections(app_handle.clone())?
+        .into_iter()
+        .map(|conn| conn.name)
+        .collect();
+    let mut known_keys = crate::db::get_ssh_keys(app_handle.clone())?;
+    let mut summary = ConnectionImportSummary::default();
+
+    for host in &hosts {
+        let entry = &host.entry;
+        let address = entry
+            .host_name
+            .clone()
+            .unwrap_or_else(|| host.alias.clone());
+        if existing_names.contains(&host.alias) || address.is_empty() {
+            summary.skipped += 1;
+            summary.skipped_hosts.push(host.alias.clone());
+            continue;
+        }
+
+        let ssh_key_id = match &entry.identity_file {
+            Some(identity_file) => {
+                import_identity_file(&app_handle, identity_file, &mut known_keys)?
+            }
+            None => None,
+        };
+
+        // A jump target may itself be an alias from the same file.
+        let jumps: Vec<JumpTarget> = entry
+            .proxy_jump
+            .as_deref()
+            .map(parse_proxy_jump)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(user, jump_host, port)| {
+                match hosts.iter().find(|candidate| candidate.alias == jump_host) {
+                    Some(target) => (
+                        user.or_else(|| target.entry.user.clone()),
+                        target.entry.host_name.clone().unwrap_or(jump_host),
+                        port.or(target.entry.port),
+                    ),
+                    None => (user, jump_host, port),
+                }
+            })
+            .collect();
+        // The legacy single-hop fields mirror the first hop for display;
+        // `jump_hosts` holds the whole chain and takes precedence.
+        let jump = jumps.first();
+
+        let conn = SshConnConfig {
+            id: None,
+            name: host.alias.clone(),
+            host: address,
+            port: entry.port.unwrap_or(22),
+            username: entry.user.clone().unwrap_or_else(|| "root".to_string()),
+            password: None,
+            auth_type: Some(
+                if ssh_key_id.is_some() {
+                    "key"
+                } else {
+                    "password"
+                }
+                .to_string(),
+            ),
+            ssh_key_id,
+            jump_host: jump.map(|(_, host, _)| host.clone()),
+            jump_port: jump.map(|(_, _, port)| port.unwrap_or(22)),
+            jump_username: jump.and_then(|(user, _, _)| user.clone()),
+            jump_password: None,
+            group_id: None,
+            os_type: None,
+            key_content: None,
+            key_passphrase: None,
+            tags: Vec::new(),
+            last_connected: None,
+            last_latency_ms: None,
+            force_scp: false,
+            max_background_sessions: None,
+            jump_hosts: jumps
+                .iter()
+                .map(|(user, host, port)| JumpHop {
+                    host: host.clone(),
+                    port: Some(port.unwrap_or(22)),
+                    username: user.clone().unwrap_or_default(),
+                    auth_type: Some("password".to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            jump_auth_type: None,
+            jump_key_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            is_favorite: false,
+            agent_forwarding: false,
+            x11_forwarding: false,
+            low_latency: false,
+            idle_timeout_minutes: 0,
+            kex_algorithms: None,
+            ciphers: None,
+            mac_algorithms: None,
+            legacy_mode: false,
+            init_command: None,
+            remote_rc: None,
+            host_key_policy: None,
+            socket_path: None,
+            proxy_command: entry
+                .proxy_command
+                .clone()
+                .filter(|command| !command.eq_ignore_ascii_case("none")),
+            sftp_handle_cache: false,
+            notes: None,
+        };
+        crate::db::create_connection(app_handle.clone(), conn)?;
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_blocks_become_defaults() {
+        let config = r#"
+Host web db
+    HostName 10.0.0.5
+    Port 2222
+
+Host db
+    HostName 10.0.0.6
+    User postgres
+
+Host *
+    User deploy
+    IdentityFile ~/.ssh/id_ed25519
+"#;
+        let hosts = parse_ssh_config(config);
+        assert_eq!(hosts.len(), 2);
+
+        assert_eq!(hosts[0].alias, "web");
+        assert_eq!(hosts[0].entry.host_name.as_deref(), Some("10.0.0.5"));
+        assert_eq!(hosts[0].entry.user.as_deref(), Some("deploy"));
+        assert_eq!(hosts[0].entry.port, Some(2222));
+
+        // The first block listing `db` wins, the later one only fills gaps.
+        assert_eq!(hosts[1].alias, "db");
+        assert_eq!(hosts[1].entry.host_name.as_deref(), Some("10.0.0.5"));
+        assert_eq!(hosts[1].entry.user.as_deref(), Some("postgres"));
+        assert_eq!(
+            hosts[1].entry.identity_file.as_deref(),
+            Some("~/.ssh/id_ed25519")
+        );
+    }
+
+    #[test]
+    fn skips_match_blocks_and_negated_patterns() {
+        let config = r#"
+Host=prod
+    HostName="prod.example.com"
+Match user root
+    Port 2200
+Host * !prod
+    Port 2022
+"#;
+        let hosts = parse_ssh_config(config);
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(
+            hosts[0].entry.host_name.as_deref(),
+            Some("prod.example.com")
+        );
+        assert_eq!(hosts[0].entry.port, None);
+    }
+
+    #[test]
+    fn keeps_whole_proxy_command() {
+        let config = r#"
+Host internal
+    ProxyCommand cloudflared access ssh --hostname %h
+"#;
+        let hosts = parse_ssh_config(config);
+        assert_eq!(
+            hosts[0].entry.proxy_command.as_deref(),
+            Some("cloudflared access ssh --hostname %h")
+        );
+    }
+
+    #[test]
+    fn parses_every_proxy_jump_hop() {
+        assert_eq!(
+            parse_proxy_jump("admin@bastion:2200, ssh://inner"),
+            vec![
+                (Some("admin".to_string()), "bastion".to_string(), Some(2200)),
+                (None, "inner".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            parse_proxy_jump("[fe80::1]:22"),
+            vec![(None, "fe80::1".to_string(), Some(22))]
+        );
+        assert!(parse_proxy_jump("none").is_empty());
+        assert!(parse_proxy_jump(" , ").is_empty());
+    }
+}
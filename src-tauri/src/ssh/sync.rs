@@ -0,0 +1,786 @@
+use super::client::{emit_transfers_updated, AppState, ClientType, TransferState};
+use super::file_ops::{
+    list_wsl_entries, recv_transfer_result, remote_file_hash, remote_file_stat, wsl_download_file,
+    wsl_upload_file, ErrorPayload,
+};
+use super::manager::SshCommand;
+use super::transfer_queue::acquire_transfer_slot;
+use super::wsl;
+use crate::models::{FileEntry, Transfer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, State};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SyncDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SyncMode {
+    /// Only add and update files at the destination
+    Additive,
+    /// Also delete destination entries the source does not have
+    Mirror,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct SyncEntry {
+    is_dir: bool,
+    size: u64,
+    /// Unix seconds
+    mtime: i64,
+}
+
+/// Everything under a synced directory, keyed by relative path with `/`
+/// separators. Sorted order puts every directory before its contents.
+type SyncTree = BTreeMap<String, SyncEntry>;
+
+/// One step of a sync, in the order it runs.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    /// "delete", "mkdir" or "copy"
+    pub action: &'static str,
+    /// Relative to the synced directories, with `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    /// Bytes to copy; 0 for deletes and mkdirs
+    pub size: u64,
+    /// "new", "changed" or "extraneous"
+    pub reason: &'static str,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    /// Transfer tracking the run; `None` for a dry run
+    pub transfer_id: Option<String>,
+    pub actions: Vec<SyncAction>,
+}
+
+/// Emitted as `sync-item` after each action of a running sync.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncItemPayload {
+    id: String,
+    action: &'static str,
+    path: String,
+    error: Option<String>,
+}
+
+fn is_inside(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn sync_action(
+    action: &'static str,
+    path: &str,
+    entry: &SyncEntry,
+    reason: &'static str,
+) -> SyncAction {
+    SyncAction {
+        action,
+        path: path.to_string(),
+        is_dir: entry.is_dir,
+        size: if action == "copy" { entry.size } else { 0 },
+        reason,
+    }
+}
+
+/// Work out what turns `dest` into a copy of `source`: deletes first (mirror
+/// mode only), then directories to create, then files to copy. A file counts
+/// as changed when its size or mtime differs; with `by_checksum` same-size
+/// files are instead returned separately so the caller can compare hashes.
+/// Additive mode leaves entries whose type differs between the sides alone.
+fn plan_sync(
+    source: &SyncTree,
+    dest: &SyncTree,
+    mode: SyncMode,
+    by_checksum: bool,
+) -> (Vec<SyncAction>, Vec<String>) {
+    let mut deletes = Vec::new();
+    let mut mkdirs = Vec::new();
+    let mut copies = Vec::new();
+    let mut to_hash = Vec::new();
+
+    if mode == SyncMode::Mirror {
+        let mut deleted_dirs: Vec<&str> = Vec::new();
+        for (path, entry) in dest {
+            if deleted_dirs.iter().any(|dir| is_inside(path, dir)) {
+                continue;
+            }
+            let kept = source
+                .get(path)
+                .is_some_and(|source_entry| source_entry.is_dir == entry.is_dir);
+            if !kept {
+                deletes.push(sync_action("delete", path, entry, "extraneous"));
+                if entry.is_dir {
+                    deleted_dirs.push(path);
+                }
+            }
+        }
+    }
+
+    let mut skipped_dirs: Vec<&str> = Vec::new();
+    for (path, entry) in source {
+        if skipped_dirs.iter().any(|dir| is_inside(path, dir)) {
+            continue;
+        }
+        match dest.get(path) {
+            Some(existing) if existing.is_dir == entry.is_dir => {
+                if entry.is_dir {
+                    continue;
+                }
+                if existing.size != entry.size || (!by_checksum && existing.mtime != entry.mtime) {
+                    copies.push(sync_action("copy", path, entry, "changed"));
+                } else if by_checksum {
+                    to_hash.push(path.clone());
+                }
+            }
+            Some(_) if mode == SyncMode::Additive => {
+                if entry.is_dir {
+                    skipped_dirs.push(path);
+                }
+            }
+            _ if entry.is_dir => mkdirs.push(sync_action("mkdir", path, entry, "new")),
+            _ => copies.push(sync_action("copy", path, entry, "new")),
+        }
+    }
+
+    deletes.extend(mkdirs);
+    deletes.extend(copies);
+    (deletes, to_hash)
+}
+
+fn local_join(root: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+fn remote_join(root: &str, relative: &str) -> String {
+    format!("{}/{}", root.trim_end_matches('/'), relative)
+}
+
+fn unix_mtime(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Walk a local directory. Symlinked directories are skipped so a link cycle
+/// cannot recurse forever.
+fn walk_local_tree(root: &Path) -> Result<SyncTree, String> {
+    let mut tree = SyncTree::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, relative)) = pending.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let child = if relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative, name)
+            };
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            let Ok(metadata) = std::fs::metadata(entry.path()) else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push((entry.path(), child.clone()));
+            } else if !metadata.is_file() {
+                continue;
+            }
+            tree.insert(
+                child,
+                SyncEntry {
+                    is_dir: file_type.is_dir(),
+                    size: if metadata.is_file() {
+                        metadata.len()
+                    } else {
+                        0
+                    },
+                    mtime: unix_mtime(&metadata),
+                },
+            );
+        }
+    }
+    Ok(tree)
+}
+
+/// Send a command to the SSH manager and block until it answers.
+fn request<T>(
+    sender: &Sender<SshCommand>,
+    command: impl FnOnce(Sender<Result<T, String>>) -> SshCommand,
+) -> Result<T, String> {
+    let (tx, rx) = channel();
+    sender
+        .send(command(tx))
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+    rx.recv()
+        .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+}
+
+fn list_remote_dir(client_type: &ClientType, path: &str) -> Result<Vec<FileEntry>, String> {
+    match client_type {
+        ClientType::Ssh(senders) => request(&senders.ops, |listener| SshCommand::SftpLs {
+            path: path.to_string(),
+            listener,
+        }),
+        ClientType::Wsl(distro) => list_wsl_entries(distro, path),
+    }
+}
+
+fn walk_remote_tree(client_type: &ClientType, root: &str) -> Result<SyncTree, String> {
+    let mut tree = SyncTree::new();
+    let mut pending = vec![String::new()];
+    while let Some(relative) = pending.pop() {
+        let dir = if relative.is_empty() {
+            root.to_string()
+        } else {
+            remote_join(root, &relative)
+        };
+        for entry in list_remote_dir(client_type, &dir)? {
+            let child = if relative.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", relative, entry.name)
+            };
+            if entry.is_dir {
+                pending.push(child.clone());
+            }
+            tree.insert(
+                child,
+                SyncEntry {
+                    is_dir: entry.is_dir,
+                    size: if entry.is_dir { 0 } else { entry.size },
+                    mtime: entry.mtime,
+                },
+            );
+        }
+    }
+    Ok(tree)
+}
+
+/// Whether two same-size files have identical content. A file that cannot
+/// be hashed on either side counts as different, so it gets copied.
+fn same_content(client_type: &ClientType, local_path: &Path, remote_path: &str) -> bool {
+    remote_file_hash(client_type, remote_path, None)
+        .and_then(|remote| {
+            let local = crate::ssh::utils::compute_local_file_hash_like(local_path, &remote)?;
+            Ok(local == remote)
+        })
+        .unwrap_or(false)
+}
+
+fn remote_mkdir(client_type: &ClientType, path: &str) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => request(&senders.ops, |listener| SshCommand::SftpMkdirAll {
+            path: path.to_string(),
+            listener,
+        })
+        .map(|_| ()),
+        ClientType::Wsl(distro) => {
+            wsl::run_bash_text(distro, r#"mkdir -p -- "$1""#, &[path.to_string()]).map(|_| ())
+        }
+    }
+}
+
+fn remote_delete(client_type: &ClientType, path: &str, is_dir: bool) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => request(&senders.ops, |listener| SshCommand::SftpDelete {
+            path: path.to_string(),
+            is_dir,
+            listener,
+        }),
+        ClientType::Wsl(distro) => {
+            wsl::run_bash_text(distro, r#"rm -rf -- "$1""#, &[path.to_string()]).map(|_| ())
+        }
+    }
+}
+
+/// Give the remote copy the source's mtime, so the next sync sees it as
+/// unchanged.
+fn set_remote_mtime(client_type: &ClientType, path: &str, mtime: i64) -> Result<(), String> {
+    match client_type {
+        ClientType::Ssh(senders) => request(&senders.ops, |listener| SshCommand::SftpTouch {
+            path: path.to_string(),
+            time: mtime.max(0) as u64,
+            listener,
+        }),
+        ClientType::Wsl(distro) => wsl::run_bash_text(
+            distro,
+            r#"touch -m -d "@$2" -- "$1""#,
+            &[path.to_string(), mtime.to_string()],
+        )
+        .map(|_| ()),
+    }
+}
+
+fn set_local_mtime(path: &Path, mtime: i64) -> Result<(), String> {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(mtime.max(0) as u64);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(time))
+        .map_err(|e| format!("Failed to set mtime of {}: {}", path.display(), e))
+}
+
+struct SyncJob {
+    app: AppHandle,
+    client_type: ClientType,
+    direction: SyncDirection,
+    local_root: PathBuf,
+    remote_root: String,
+    source: SyncTree,
+    transfer: Arc<TransferState>,
+    transfer_id: String,
+    rate_limit: Option<u64>,
+}
+
+impl SyncJob {
+    /// Copy one file with the regular transfer primitives, then carry over
+    /// its mtime.
+    fn copy(&self, local_path: &Path, remote_path: &str, mtime: i64) -> Result<(), String> {
+        let local = local_path.to_string_lossy().to_string();
+        match (&self.client_type, self.direction) {
+            (ClientType::Ssh(senders), SyncDirection::Upload) => {
+                let (tx, rx) = channel();
+                senders
+                    .ops
+                    .send(SshCommand::SftpUpload {
+                        local_path: local,
+                        remote_path: remote_path.to_string(),
+                        transfer_id: self.transfer_id.clone(),
+                        app_handle: self.app.clone(),
+                        listener: tx,
+                        cancel_flag: self.transfer.cancel_flag.clone(),
+                        pause_flag: self.transfer.pause_flag.clone(),
+                        skip_space_check: false,
+                        rate_limit: self.rate_limit,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+                recv_transfer_result(rx)
+                    .unwrap_or_else(|| Err("Upload ended without a result".to_string()))?;
+            }
+            (ClientType::Ssh(senders), SyncDirection::Download) => {
+                let (tx, rx) = channel();
+                senders
+                    .ops
+                    .send(SshCommand::SftpDownload {
+                        remote_path: remote_path.to_string(),
+                        local_path: local,
+                        transfer_id: self.transfer_id.clone(),
+                        app_handle: self.app.clone(),
+                        listener: tx,
+                        cancel_flag: self.transfer.cancel_flag.clone(),
+                        pause_flag: self.transfer.pause_flag.clone(),
+                        rate_limit: self.rate_limit,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+                recv_transfer_result(rx)
+                    .unwrap_or_else(|| Err("Download ended without a result".to_string()))?;
+            }
+            (ClientType::Wsl(distro), SyncDirection::Upload) => {
+                wsl_upload_file(
+                    distro,
+                    &local,
+                    remote_path,
+                    &self.transfer,
+                    &self.app,
+                    &self.transfer_id,
+                )?;
+            }
+            (ClientType::Wsl(distro), SyncDirection::Download) => {
+                wsl_download_file(
+                    distro,
+                    remote_path,
+                    &local,
+                    &self.transfer,
+                    &self.app,
+                    &self.transfer_id,
+                )?;
+            }
+        }
+        match self.direction {
+            SyncDirection::Upload => set_remote_mtime(&self.client_type, remote_path, mtime),
+            SyncDirection::Download => set_local_mtime(local_path, mtime),
+        }
+    }
+
+    fn apply(&self, action: &SyncAction) -> Result<(), String> {
+        let local_path = local_join(&self.local_root, &action.path);
+        let remote_path = remote_join(&self.remote_root, &action.path);
+        match (action.action, self.direction) {
+            ("delete", SyncDirection::Upload) => {
+                remote_delete(&self.client_type, &remote_path, action.is_dir)
+            }
+            ("delete", SyncDirection::Download) => if action.is_dir {
+                std::fs::remove_dir_all(&local_path)
+            } else {
+                std::fs::remove_file(&local_path)
+            }
+            .map_err(|e| format!("Failed to delete {}: {}", local_path.display(), e)),
+            ("mkdir", SyncDirection::Upload) => remote_mkdir(&self.client_type, &remote_path),
+            ("mkdir", SyncDirection::Download) => std::fs::create_dir_all(&local_path)
+                .map_err(|e| format!("Failed to create {}: {}", local_path.display(), e)),
+            _ => {
+                let mtime = self
+                    .source
+                    .get(&action.path)
+                    .map(|entry| entry.mtime)
+                    .unwrap_or(0);
+                self.copy(&local_path, &remote_path, mtime)
+            }
+        }
+    }
+
+    /// Run the plan in order under the job's transfer, emitting `sync-item`
+    /// per action. Stops at the first failure or on cancel.
+    fn run(&self, actions: &[SyncAction]) -> Result<(), String> {
+        match self.direction {
+            SyncDirection::Upload => remote_mkdir(&self.client_type, &self.remote_root)?,
+            SyncDirection::Download => std::fs::create_dir_all(&self.local_root)
+                .map_err(|e| format!("Failed to create {}: {}", self.local_root.display(), e))?,
+        }
+
+        let mut done = 0u64;
+        for action in actions {
+            if self.transfer.cancel_flag.load(Ordering::Relaxed) {
+                return Err("Sync cancelled".to_string());
+            }
+            self.transfer.progress_offset.store(done, Ordering::Relaxed);
+            let result = self.apply(action);
+            let _ = self.app.emit(
+                "sync-item",
+                SyncItemPayload {
+                    id: self.transfer_id.clone(),
+                    action: action.action,
+                    path: action.path.clone(),
+                    error: result.as_ref().err().cloned(),
+                },
+            );
+            result?;
+            done += action.size;
+        }
+        Ok(())
+    }
+}
+
+/// One-way sync between `local_path` and `remote_path`, like `rsync -rt`.
+/// `direction` is "upload" or "download"; `mode` is "additive" (the default)
+/// or "mirror", which also deletes destination entries missing from the
+/// source. `compare` is "mtime" (the default: size and mtime) or "checksum".
+///
+/// With `dry_run` only the planned actions are returned. Otherwise they run
+/// in the background as a single transfer under `transfer_id`, reporting each
+/// finished action as a `sync-item` event; cancel it like any transfer.
+#[tauri::command]
+pub async fn sync_directory(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    transfer_id: String,
+    local_path: String,
+    remote_path: String,
+    direction: String,
+    mode: Option<String>,
+    compare: Option<String>,
+    dry_run: Option<bool>,
+    rate_limit: Option<u64>,
+) -> Result<SyncPlan, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let direction = match direction.as_str() {
+        "upload" => SyncDirection::Upload,
+        "download" => SyncDirection::Download,
+        other => return Err(format!("Unknown sync direction: {}", other)),
+    };
+    let mode = match mode.as_deref().unwrap_or("additive") {
+        "additive" => SyncMode::Additive,
+        "mirror" => SyncMode::Mirror,
+        other => return Err(format!("Unknown sync mode: {}", other)),
+    };
+    let by_checksum = match compare.as_deref().unwrap_or("mtime") {
+        "mtime" => false,
+        "checksum" => true,
+        other => return Err(format!("Unknown sync comparison: {}", other)),
+    };
+
+    let local_root = PathBuf::from(&local_path);
+    let remote_exists = remote_file_stat(&client.client_type, remote_path.clone())
+        .await?
+        .is_some();
+    match direction {
+        SyncDirection::Upload if !local_root.is_dir() => {
+            return Err(format!("{} is not a directory", local_path))
+        }
+        SyncDirection::Download if !remote_exists => {
+            return Err(format!("Directory not found: {}", remote_path))
+        }
+        _ => {}
+    }
+
+    let (source, actions) = {
+        let client_type = client.client_type.clone();
+        let local_root = local_root.clone();
+        let remote_root = remote_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let local_tree = if local_root.exists() {
+                walk_local_tree(&local_root)?
+            } else {
+                SyncTree::new()
+            };
+            let remote_tree = if remote_exists {
+                walk_remote_tree(&client_type, &remote_root)?
+            } else {
+                SyncTree::new()
+            };
+            let (source, dest) = match direction {
+                SyncDirection::Upload => (local_tree, remote_tree),
+                SyncDirection::Download => (remote_tree, local_tree),
+            };
+
+            let (mut actions, to_hash) = plan_sync(&source, &dest, mode, by_checksum);
+            for path in to_hash {
+                let local = local_join(&local_root, &path);
+                if !same_content(&client_type, &local, &remote_join(&remote_root, &path)) {
+                    actions.push(sync_action("copy", &path, &source[&path], "changed"));
+                }
+            }
+            Ok::<_, String>((source, actions))
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??
+    };
+
+    if dry_run.unwrap_or(false) {
+        return Ok(SyncPlan {
+            transfer_id: None,
+            actions,
+        });
+    }
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let name = match direction {
+        SyncDirection::Upload => local_root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        SyncDirection::Download => Path::new(&remote_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+    }
+    .unwrap_or_default();
+
+    let transfer = Transfer {
+        id: transfer_id.clone(),
+        session_id: id.clone(),
+        name,
+        local_path: local_path.clone(),
+        remote_path: remote_path.clone(),
+        transfer_type: match direction {
+            SyncDirection::Upload => "upload",
+            SyncDirection::Download => "download",
+        }
+        .to_string(),
+        status: "pending".to_string(),
+        total_size: actions.iter().map(|action| action.size).sum(),
+        transferred: 0,
+        created_at: now,
+        error: None,
+        local_hash: None,
+        remote_hash: None,
+    };
+
+    let transfer_state = Arc::new(TransferState {
+        data: Mutex::new(transfer),
+        cancel_flag: Arc::new(AtomicBool::new(false)),
+        pause_flag: Arc::new(AtomicBool::new(false)),
+        progress_offset: AtomicU64::new(0),
+    });
+
+    {
+        let mut transfers = state.transfers.lock().map_err(|e| e.to_string())?;
+        transfers.insert(transfer_id.clone(), transfer_state.clone());
+    }
+    state.transfer_queue.enqueue(&transfer_id);
+    emit_transfers_updated(&app);
+
+    let job = SyncJob {
+        app: app.clone(),
+        client_type: client.client_type.clone(),
+        direction,
+        local_root,
+        remote_root: remote_path,
+        source,
+        transfer: transfer_state.clone(),
+        transfer_id: transfer_id.clone(),
+        rate_limit,
+    };
+    let planned = actions.clone();
+    tokio::spawn(async move {
+        let Some(_slot) = acquire_transfer_slot(&app, &transfer_state, &job.transfer_id).await
+        else {
+            return;
+        };
+
+        let transfer_id = job.transfer_id.clone();
+        let result = tokio::task::spawn_blocking(move || job.run(&planned))
+            .await
+            .unwrap_or_else(|e| Err(format!("Task join error: {}", e)));
+
+        match result {
+            Ok(()) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "completed".to_string();
+                    data.transferred = data.total_size;
+                });
+            }
+            Err(_) if transfer_state.cancel_flag.load(Ordering::Relaxed) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "cancelled".to_string();
+                });
+            }
+            Err(e) => {
+                transfer_state.update(&app, |data| {
+                    data.status = "error".to_string();
+                    data.error = Some(e.clone());
+                });
+                let _ = app.emit(
+                    "transfer-error",
+                    ErrorPayload {
+                        id: transfer_id,
+                        error: e,
+                    },
+                );
+            }
+        }
+    });
+
+    Ok(SyncPlan {
+        transfer_id: Some(transfer_id),
+        actions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64, mtime: i64) -> SyncEntry {
+        SyncEntry {
+            is_dir: false,
+            size,
+            mtime,
+        }
+    }
+
+    fn dir() -> SyncEntry {
+        SyncEntry {
+            is_dir: true,
+            size: 0,
+            mtime: 0,
+        }
+    }
+
+    fn tree(entries: &[(&str, SyncEntry)]) -> SyncTree {
+        entries
+            .iter()
+            .map(|(path, entry)| (path.to_string(), *entry))
+            .collect()
+    }
+
+    fn summary(actions: &[SyncAction]) -> Vec<(&'static str, &str, &'static str)> {
+        actions
+            .iter()
+            .map(|action| (action.action, action.path.as_str(), action.reason))
+            .collect()
+    }
+
+    #[test]
+    fn plan_copies_new_and_changed_files_only() {
+        let source = tree(&[
+            ("a.txt", file(3, 100)),
+            ("b.txt", file(5, 100)),
+            ("c.txt", file(5, 200)),
+            ("sub", dir()),
+            ("sub/d.txt", file(1, 100)),
+        ]);
+        let dest = tree(&[
+            ("a.txt", file(3, 100)),
+            ("b.txt", file(4, 100)),
+            ("c.txt", file(5, 100)),
+            ("extra.txt", file(1, 1)),
+        ]);
+        let (actions, to_hash) = plan_sync(&source, &dest, SyncMode::Additive, false);
+        assert_eq!(
+            summary(&actions),
+            vec![
+                ("mkdir", "sub", "new"),
+                ("copy", "b.txt", "changed"),
+                ("copy", "c.txt", "changed"),
+                ("copy", "sub/d.txt", "new"),
+            ]
+        );
+        assert!(to_hash.is_empty());
+        assert_eq!(actions[1].size, 5);
+    }
+
+    #[test]
+    fn checksum_mode_defers_same_size_files() {
+        let source = tree(&[("a.txt", file(3, 100)), ("b.txt", file(5, 100))]);
+        let dest = tree(&[("a.txt", file(3, 999)), ("b.txt", file(4, 100))]);
+        let (actions, to_hash) = plan_sync(&source, &dest, SyncMode::Additive, true);
+        assert_eq!(summary(&actions), vec![("copy", "b.txt", "changed")]);
+        assert_eq!(to_hash, vec!["a.txt".to_string()]);
+    }
+
+    #[test]
+    fn mirror_deletes_extraneous_entries_once_per_tree() {
+        let source = tree(&[("keep.txt", file(1, 1)), ("swap", file(2, 2))]);
+        let dest = tree(&[
+            ("keep.txt", file(1, 1)),
+            ("old", dir()),
+            ("old/nested", dir()),
+            ("old/nested/x.txt", file(1, 1)),
+            ("old y.txt", file(1, 1)),
+            ("swap", dir()),
+            ("swap/z.txt", file(1, 1)),
+        ]);
+        let (actions, _) = plan_sync(&source, &dest, SyncMode::Mirror, false);
+        assert_eq!(
+            summary(&actions),
+            vec![
+                ("delete", "old", "extraneous"),
+                ("delete", "old y.txt", "extraneous"),
+                ("delete", "swap", "extraneous"),
+                ("copy", "swap", "new"),
+            ]
+        );
+    }
+
+    #[test]
+    fn additive_mode_skips_type_conflicts() {
+        let source = tree(&[("conf", dir()), ("conf/a.txt", file(1, 1))]);
+        let dest = tree(&[("conf", file(9, 9))]);
+        let (actions, _) = plan_sync(&source, &dest, SyncMode::Additive, false);
+        assert!(actions.is_empty());
+    }
+}
@@ -1,10 +1,13 @@
 use super::client::{AppState, ClientType};
-use crate::models::{DiskUsage, ServerStatus};
-use crate::ssh::{execute_ssh_operation, ExecTarget, SshCommand};
+use crate::models::{DiskUsage, PathFreeSpace, ServerStatus};
+use crate::ssh::{execute_ssh_operation, wsl, ExecTarget, SshCommand};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{command, AppHandle, State};
 
 #[cfg(target_os = "windows")]
@@ -51,6 +54,49 @@ pub struct MemoryInfo {
     pub top_processes: Vec<ProcessInfo>,
 }
 
+/// A single row of `ps aux`, used by the process manager panel
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub user: String,
+    pub cpu: f32,
+    pub mem: f32,
+    pub state: String,
+    pub command: String,
+}
+
+/// What the remote host reports about itself, from `/etc/os-release`,
+/// `lsb_release -a` and `uname`. Fields are `None` when no source had them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteOsInfo {
+    /// Lowercase distro ID such as "ubuntu" or "alpine", for picking an icon
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub pretty_name: Option<String>,
+    pub kernel: Option<String>,
+    pub architecture: Option<String>,
+    /// Raw `uname -a`
+    pub uname: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KillProcessResult {
+    pub exit_code: i32,
+    pub output: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInterfaceInfo {
+    pub name: String,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionStats {
@@ -60,11 +106,14 @@ pub struct SessionStats {
     pub ip: String,
     pub cpu: Option<CpuInfo>,
     pub memory: Option<MemoryInfo>,
+    pub network: Option<Vec<NetworkInterfaceInfo>>,
+    pub load_avg: (f64, f64, f64),
+    pub users_online: u32,
 }
 
 // Helper to run command on SSH session
 // Helper to run command on SSH session
-fn run_ssh_command(sender: &Sender<SshCommand>, cmd: &str) -> Result<String, String> {
+pub(crate) fn run_ssh_command(sender: &Sender<SshCommand>, cmd: &str) -> Result<String, String> {
     let (tx, rx) = std::sync::mpsc::channel();
     sender
         .send(SshCommand::Exec {
@@ -73,6 +122,7 @@ fn run_ssh_command(sender: &Sender<SshCommand>, cmd: &str) -> Result<String, Str
             cancel_flag: None,
             target: ExecTarget::Status,
             stream: None,
+            timeout: None,
         })
         .map_err(|e| format!("Failed to send command: {}", e))?;
 
@@ -80,8 +130,64 @@ fn run_ssh_command(sender: &Sender<SshCommand>, cmd: &str) -> Result<String, Str
         .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
 }
 
+// Helper to run command on SSH session and capture the channel's exit status.
+pub(crate) fn run_ssh_command_with_status(
+    sender: &Sender<SshCommand>,
+    cmd: &str,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> Result<(String, i32), String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    sender
+        .send(SshCommand::ExecWithStatus {
+            command: cmd.to_string(),
+            listener: tx,
+            cancel_flag,
+            target: ExecTarget::Status,
+        })
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+    let (output, code) = rx
+        .recv()
+        .map_err(|_| "Failed to receive response from SSH Manager".to_string())??;
+    Ok((output.trim().to_string(), code))
+}
+
+// Helper to run a script on either client type and capture its exit code.
+// Cancellation is honoured during SSH execution and before a WSL process starts.
+pub(crate) async fn run_client_command_with_status(
+    client_type: &ClientType,
+    script: String,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> Result<(String, i32), String> {
+    match client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                run_ssh_command_with_status(&sender, &script, cancel_flag)
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Some(ref flag) = cancel_flag {
+                    if flag.load(Ordering::Relaxed) {
+                        return Err("Command cancelled by user".to_string());
+                    }
+                }
+                let output = wsl::run_bash_output(&distro, &script, &[])?;
+                Ok((
+                    wsl::decode_wsl_output(&output.stdout).trim().to_string(),
+                    output.status.code().unwrap_or(-1),
+                ))
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
 // Helper to run command on WSL
-fn run_wsl_command(distro: &str, cmd: &str) -> Result<String, String> {
+pub(crate) fn run_wsl_command(distro: &str, cmd: &str) -> Result<String, String> {
     let mut command = std::process::Command::new("wsl");
     #[cfg(target_os = "windows")]
     command.creation_flags(CREATE_NO_WINDOW);
@@ -98,7 +204,7 @@ fn run_wsl_command(distro: &str, cmd: &str) -> Result<String, String> {
     // We treat stderr as potential non-fatal or just mix it, but for stats we usually want clean output.
     // However, some commands might output to stderr on non-error (unlikely for these standard tools).
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        Ok(wsl::decode_wsl_output(&output.stdout).trim().to_string())
     } else {
         // If failed, return empty or error message?
         // Return empty string to allow fallback handling or partial stats
@@ -122,6 +228,151 @@ where
         .collect()
 }
 
+/// Parse the last line of `df -Pk <path>` (1K blocks) into a `PathFreeSpace`.
+pub(crate) fn parse_df_free_space(path: &str, output: &str) -> Result<PathFreeSpace, String> {
+    let line = output
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .ok_or_else(|| format!("No df output for path: {}", path))?;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 6 {
+        return Err(format!("Invalid df output for path: {}", path));
+    }
+
+    let parse_kb = |value: &str, field: &str| -> Result<u64, String> {
+        value
+            .parse::<u64>()
+            .map(|kb| kb.saturating_mul(1024))
+            .map_err(|_| format!("Failed to parse {}", field))
+    };
+    let total = parse_kb(parts[1], "total")?;
+    let used = parse_kb(parts[2], "used")?;
+    let available = parse_kb(parts[3], "available")?;
+
+    Ok(PathFreeSpace {
+        path: path.to_string(),
+        total,
+        free: total.saturating_sub(used),
+        available,
+        block_size: 1024,
+    })
+}
+
+/// Parse `/proc/net/dev` into cumulative (rx_bytes, tx_bytes) per interface, skipping loopback.
+fn parse_net_dev(raw: &str) -> HashMap<String, (u64, u64)> {
+    raw.lines()
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let name = name.trim();
+            if name.is_empty() || name == "lo" {
+                return None;
+            }
+            let fields: Vec<&str> = counters.split_whitespace().collect();
+            // Receive: bytes packets errs drop fifo frame compressed multicast, then transmit bytes
+            let rx = fields.first()?.parse::<u64>().ok()?;
+            let tx = fields.get(8)?.parse::<u64>().ok()?;
+            Some((name.to_string(), (rx, tx)))
+        })
+        .collect()
+}
+
+fn compute_network_rates(
+    sample1: &str,
+    sample2: &str,
+    elapsed: Duration,
+) -> Option<Vec<NetworkInterfaceInfo>> {
+    let before = parse_net_dev(sample1);
+    let after = parse_net_dev(sample2);
+    if before.is_empty() || after.is_empty() {
+        return None;
+    }
+    let secs = elapsed.as_secs_f64().max(0.001);
+
+    let mut interfaces: Vec<NetworkInterfaceInfo> = after
+        .iter()
+        .filter_map(|(name, (rx2, tx2))| {
+            let (rx1, tx1) = before.get(name)?;
+            Some(NetworkInterfaceInfo {
+                name: name.clone(),
+                rx_bytes_per_sec: rx2.saturating_sub(*rx1) as f64 / secs,
+                tx_bytes_per_sec: tx2.saturating_sub(*tx1) as f64 / secs,
+            })
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(interfaces)
+}
+
+// Load averages come from /proc/loadavg, or from `uptime` on BSD/macOS where /proc is absent.
+const LOAD_USERS_CMD: &str = r#"export LC_ALL=C; if [ -r /proc/loadavg ]; then load=$(cut -d' ' -f1-3 /proc/loadavg); else load=$(uptime 2>/dev/null | sed -e 's/.*load average[s]*: *//' -e 's/,/ /g'); fi; users=$(who 2>/dev/null | wc -l); echo "$load|$users""#;
+
+/// Parse the `load1 load5 load15|users` line produced by `LOAD_USERS_CMD`.
+fn parse_load_and_users(raw: &str) -> ((f64, f64, f64), u32) {
+    let (load_part, users_part) = raw.trim().split_once('|').unwrap_or((raw.trim(), ""));
+    let loads: Vec<f64> = load_part
+        .split_whitespace()
+        .filter_map(|value| value.parse::<f64>().ok())
+        .collect();
+    let load_avg = match loads.as_slice() {
+        [one, five, fifteen, ..] => (*one, *five, *fifteen),
+        _ => (0.0, 0.0, 0.0),
+    };
+    let users_online = users_part.trim().parse::<u32>().unwrap_or(0);
+    (load_avg, users_online)
+}
+
+const PS_AUX_CMD: &str = "export LC_ALL=C; ps aux 2>/dev/null | tail -n +2";
+
+/// Parse `ps aux` rows: USER PID %CPU %MEM VSZ RSS TTY STAT START TIME COMMAND
+fn parse_ps_aux(raw: &str) -> Vec<ProcessEntry> {
+    raw.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 11 {
+                return None;
+            }
+            Some(ProcessEntry {
+                pid: parts[1].parse().ok()?,
+                user: parts[0].to_string(),
+                cpu: parts[2].parse().unwrap_or(0.0),
+                mem: parts[3].parse().unwrap_or(0.0),
+                state: parts[7].to_string(),
+                command: parts[10..].join(" "),
+            })
+        })
+        .collect()
+}
+
+fn sort_processes(processes: &mut [ProcessEntry], sort_by: &str, descending: bool) {
+    match sort_by {
+        "pid" => processes.sort_by_key(|p| p.pid),
+        "user" => processes.sort_by(|a, b| a.user.cmp(&b.user)),
+        "command" => processes.sort_by(|a, b| a.command.cmp(&b.command)),
+        "mem" | "memory" => processes.sort_by(|a, b| a.mem.total_cmp(&b.mem)),
+        _ => processes.sort_by(|a, b| a.cpu.total_cmp(&b.cpu)),
+    }
+    if descending {
+        processes.reverse();
+    }
+}
+
+/// Turn a user-supplied signal ("TERM", "SIGKILL", "9") into `kill` options:
+/// `-s NAME` for names, `-N` for numbers, which `kill -s` does not accept.
+fn normalize_signal(signal: Option<String>) -> Result<Vec<String>, String> {
+    let raw = signal.unwrap_or_else(|| "TERM".to_string());
+    let trimmed = raw.trim().to_uppercase();
+    let name = trimmed.strip_prefix("SIG").unwrap_or(&trimmed).to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid signal: {}", raw));
+    }
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        Ok(vec![format!("-{}", name)])
+    } else {
+        Ok(vec!["-s".to_string(), name])
+    }
+}
+
 fn parse_cpu_stats(line: &str) -> Option<(u64, u64)> {
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.len() < 5 || parts[0] != "cpu" {
@@ -163,11 +414,20 @@ pub async fn get_remote_system_status(
     };
 
     // Execute commands in steps
-    let (uptime_str, mounts_str, ip_str, cpu_str, memory_str, proc_cpu_str, proc_mem_str) =
-        match &client.client_type {
-            ClientType::Ssh(senders) => {
-                let sender = senders.ops.clone();
-                execute_ssh_operation(move || {
+    let (
+        uptime_str,
+        mounts_str,
+        ip_str,
+        cpu_str,
+        memory_str,
+        proc_cpu_str,
+        proc_mem_str,
+        (net_str1, net_str2, net_elapsed),
+        load_users_str,
+    ) = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
                 // 1. Uptime
                 let uptime = run_ssh_command(
                     &sender,
@@ -186,7 +446,9 @@ pub async fn get_remote_system_status(
                     "export LC_ALL=C; (hostname -I 2>/dev/null || echo 'n/a')",
                 )?;
 
-                // 4. CPU
+                // 4. CPU (network counters are sampled around the same delay window)
+                let net1 = run_ssh_command(&sender, "cat /proc/net/dev 2>/dev/null").unwrap_or_default();
+                let net_start = Instant::now();
                 let cpu_stat1 = run_ssh_command(&sender, "cat /proc/stat | grep '^cpu '").ok();
 
                 let cpu = if let Some(stat1) = cpu_stat1 {
@@ -208,6 +470,8 @@ pub async fn get_remote_system_status(
                      let top_cmd = "top -bn1 2>/dev/null | grep \"Cpu(s)\" | awk '{print $2}' | sed 's/%us,//' | sed 's/%id,.*//'";
                      run_ssh_command(&sender, top_cmd).unwrap_or_else(|_| "0".to_string())
                 };
+                let net_elapsed = net_start.elapsed();
+                let net2 = run_ssh_command(&sender, "cat /proc/net/dev 2>/dev/null").unwrap_or_default();
 
                 // 5. Memory
                 let mem_cmd = r#"export LC_ALL=C; awk '/MemTotal:/ {total=$2} /MemAvailable:/ {avail=$2} END {if(total>0){used=total-avail; printf "%.1f%%|%.1fGB|%.1fGB|%.1fGB", (used/total)*100, total/1024/1024, used/1024/1024, avail/1024/1024} else {print "0%|0|0|0"}}' /proc/meminfo 2>/dev/null"#;
@@ -221,12 +485,15 @@ pub async fn get_remote_system_status(
                 let proc_mem_cmd = r#"export LC_ALL=C; ps aux --sort=-%mem --no-headers 2>/dev/null | head -5 | awk '{printf "%s|%s|%s|%s|%.1fMB\n", $2, $11, $3"%", $4"%", $6/1024}'"#;
                 let proc_mem = run_ssh_command(&sender, proc_mem_cmd)?;
 
-                Ok((uptime, mounts, ip, cpu, memory, proc_cpu, proc_mem))
+                // 8. Load average & logged-in users
+                let load_users = run_ssh_command(&sender, LOAD_USERS_CMD).unwrap_or_default();
+
+                Ok((uptime, mounts, ip, cpu, memory, proc_cpu, proc_mem, (net1, net2, net_elapsed), load_users))
             }).await?
-            }
-            ClientType::Wsl(distro) => {
-                let distro = distro.clone();
-                tokio::task::spawn_blocking(move || {
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
                 // 1. Uptime
                 let uptime = run_wsl_command(&distro, "export LC_ALL=C; (uptime -p 2>/dev/null || uptime 2>/dev/null)")?;
 
@@ -236,7 +503,9 @@ pub async fn get_remote_system_status(
                 // 3. IP
                 let ip = run_wsl_command(&distro, "export LC_ALL=C; (hostname -I 2>/dev/null || echo 'n/a')")?;
 
-                // 4. CPU
+                // 4. CPU (network counters are sampled around the same delay window)
+                let net1 = run_wsl_command(&distro, "cat /proc/net/dev 2>/dev/null").unwrap_or_default();
+                let net_start = Instant::now();
                 let cpu_stat1 = run_wsl_command(&distro, "cat /proc/stat | grep '^cpu '").ok();
                 let cpu = if let Some(stat1) = cpu_stat1 {
                     if stat1.is_empty() { "0".to_string() } else {
@@ -254,6 +523,8 @@ pub async fn get_remote_system_status(
                         } else { "0".to_string() }
                     }
                 } else { "0".to_string() };
+                let net_elapsed = net_start.elapsed();
+                let net2 = run_wsl_command(&distro, "cat /proc/net/dev 2>/dev/null").unwrap_or_default();
 
                 // 5. Memory
                 let mem_cmd = r#"export LC_ALL=C; awk '/MemTotal:/ {total=$2} /MemAvailable:/ {avail=$2} END {if(total>0){used=total-avail; printf "%.1f%%|%.1fGB|%.1fGB|%.1fGB", (used/total)*100, total/1024/1024, used/1024/1024, avail/1024/1024} else {print "0%|0|0|0"}}' /proc/meminfo 2>/dev/null"#;
@@ -266,10 +537,13 @@ pub async fn get_remote_system_status(
                 let proc_mem_cmd = r#"export LC_ALL=C; ps aux --sort=-%mem --no-headers 2>/dev/null | head -5 | awk '{printf "%s|%s|%s|%s|%.1fMB\n", $2, $11, $3"%", $4"%", $6/1024}'"#;
                 let proc_mem = run_wsl_command(&distro, proc_mem_cmd)?;
 
-                Ok::<_, String>((uptime, mounts, ip, cpu, memory, proc_cpu, proc_mem))
+                // 7. Load average & logged-in users
+                let load_users = run_wsl_command(&distro, LOAD_USERS_CMD).unwrap_or_default();
+
+                Ok::<_, String>((uptime, mounts, ip, cpu, memory, proc_cpu, proc_mem, (net1, net2, net_elapsed), load_users))
             }).await.map_err(|e| format!("Task join error: {}", e))??
-            }
-        };
+        }
+    };
 
     // --- Parsing ---
 
@@ -344,6 +618,12 @@ pub async fn get_remote_system_status(
         top_processes: cpu_top_processes,
     });
 
+    // Network
+    let network = compute_network_rates(&net_str1, &net_str2, net_elapsed);
+
+    // Load average & users
+    let (load_avg, users_online) = parse_load_and_users(&load_users_str);
+
     Ok(SessionStats {
         uptime: if uptime_str.is_empty() {
             "N/A".to_string()
@@ -355,6 +635,9 @@ pub async fn get_remote_system_status(
         ip,
         cpu: final_cpu,
         memory: final_memory,
+        network,
+        load_avg,
+        users_online,
     })
 }
 
@@ -441,3 +724,380 @@ pub async fn get_disk_usage(
         }
     }
 }
+
+/// Get free space of the filesystem that backs `path`
+#[command]
+pub async fn get_path_free_space(
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+) -> Result<PathFreeSpace, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::GetPathFreeSpace {
+                        path: path.clone(),
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                let cmd = format!(
+                    "export LC_ALL=C; df -Pk '{}' 2>/dev/null | tail -1",
+                    crate::ssh::file_ops::escape_shell_arg(&path)
+                );
+                let output = run_wsl_command(&distro, &cmd)?;
+                parse_df_free_space(&path, &output)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+        }
+    }
+}
+
+/// List all processes on the remote host (`ps aux`), sorted by `sort_by`
+/// ("cpu" default, "mem", "pid", "user", "command").
+#[command]
+pub async fn list_processes(
+    state: State<'_, AppState>,
+    id: String,
+    sort_by: Option<String>,
+    descending: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<ProcessEntry>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+
+    let raw = match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || run_ssh_command(&sender, PS_AUX_CMD)).await?
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || run_wsl_command(&distro, PS_AUX_CMD))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))??
+        }
+    };
+
+    let sort_by = sort_by.unwrap_or_else(|| "cpu".to_string());
+    let descending = descending.unwrap_or(sort_by == "cpu" || sort_by == "mem");
+    let mut processes = parse_ps_aux(&raw);
+    sort_processes(&mut processes, &sort_by, descending);
+    if let Some(limit) = limit {
+        processes.truncate(limit);
+    }
+    Ok(processes)
+}
+
+/// Send a signal (default TERM) to a remote process. A non-zero `exit_code`
+/// usually means the process belongs to another user or no longer exists.
+#[command]
+pub async fn kill_process(
+    state: State<'_, AppState>,
+    id: String,
+    pid: u32,
+    signal: Option<String>,
+) -> Result<KillProcessResult, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    let signal = normalize_signal(signal)?;
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            let cmd = format!("kill {} {} 2>&1", signal.join(" "), pid);
+            let (output, exit_code) =
+                execute_ssh_operation(move || run_ssh_command_with_status(&sender, &cmd, None))
+                    .await?;
+            Ok(KillProcessResult { exit_code, output })
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut args = signal;
+                args.push(pid.to_string());
+                let output = wsl::run_bash_output(&distro, "kill \"$@\" 2>&1", &args)?;
+                Ok(KillProcessResult {
+                    exit_code: output.status.code().unwrap_or(-1),
+                    output: wsl::decode_wsl_output(&output.stdout).trim().to_string(),
+                })
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+const OS_SECTION_MARKER: &str = "__SSH_ASSISTANT_OS_SECTION__:";
+
+fn os_probe_script() -> String {
+    [
+        ("os-release", "cat /etc/os-release 2>/dev/null"),
+        ("uname", "uname -a 2>/dev/null"),
+        ("uname-srm", "uname -srm 2>/dev/null"),
+        ("lsb", "lsb_release -a 2>/dev/null"),
+    ]
+    .iter()
+    .map(|(name, cmd)| format!("echo '{}{}'; {}", OS_SECTION_MARKER, name, cmd))
+    .collect::<Vec<_>>()
+    .join("; ")
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('"').trim_matches('\'').trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn parse_remote_os(output: &str) -> RemoteOsInfo {
+    let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut current = "";
+    for line in output.lines() {
+        match line.strip_prefix(OS_SECTION_MARKER) {
+            Some(name) => current = name.trim(),
+            None => sections.entry(current).or_default().push(line),
+        }
+    }
+    let section = |name: &str| sections.get(name).cloned().unwrap_or_default();
+
+    let mut info = RemoteOsInfo::default();
+    for line in section("os-release") {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "ID" => info.id = non_empty(value).map(|id| id.to_lowercase()),
+            "NAME" => info.name = non_empty(value),
+            "VERSION_ID" => info.version = non_empty(value),
+            "PRETTY_NAME" => info.pretty_name = non_empty(value),
+            _ => {}
+        }
+    }
+
+    // Older systems without /etc/os-release often still ship lsb_release.
+    for line in section("lsb") {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "Distributor ID" => {
+                if info.id.is_none() {
+                    info.id = non_empty(value).map(|id| id.to_lowercase());
+                }
+                if info.name.is_none() {
+                    info.name = non_empty(value);
+                }
+            }
+            "Release" if info.version.is_none() => info.version = non_empty(value),
+            "Description" if info.pretty_name.is_none() => info.pretty_name = non_empty(value),
+            _ => {}
+        }
+    }
+
+    info.uname = section("uname").first().and_then(|line| non_empty(line));
+    if let Some(line) = section("uname-srm").first() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 3 {
+            if info.name.is_none() {
+                info.name = Some(fields[0].to_string());
+            }
+            info.kernel = Some(fields[1].to_string());
+            info.architecture = Some(fields[fields.len() - 1].to_string());
+        }
+    }
+    info
+}
+
+/// Probe the remote distro, version, kernel and architecture. The result is
+/// cached on the session; pass `refresh` to probe again.
+#[command]
+pub async fn detect_remote_os(
+    state: State<'_, AppState>,
+    id: String,
+    refresh: Option<bool>,
+) -> Result<RemoteOsInfo, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(&id).ok_or("Session not found")?.clone()
+    };
+    if !refresh.unwrap_or(false) {
+        if let Some(info) = client.remote_os {
+            return Ok(info);
+        }
+    }
+
+    let (output, _) =
+        run_client_command_with_status(&client.client_type, os_probe_script(), None).await?;
+    let info = parse_remote_os(&output);
+
+    let mut clients = state.clients.lock().map_err(|e| e.to_string())?;
+    if let Some(client) = clients.get_mut(&id) {
+        client.remote_os = Some(info.clone());
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ps_aux_reads_rows_and_keeps_command_spaces() {
+        let raw = "\
+root         1  0.0  0.1 167744 11784 ?        Ss   Jan01   0:12 /sbin/init splash
+alice     4242 12.5  3.2 912345 65432 pts/0    Sl+  10:00   1:02 python3 -m http.server 8000
+";
+        let processes = parse_ps_aux(raw);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].pid, 1);
+        assert_eq!(processes[0].user, "root");
+        assert_eq!(processes[0].command, "/sbin/init splash");
+        assert_eq!(processes[1].pid, 4242);
+        assert_eq!(processes[1].cpu, 12.5);
+        assert_eq!(processes[1].mem, 3.2);
+        assert_eq!(processes[1].state, "Sl+");
+        assert_eq!(processes[1].command, "python3 -m http.server 8000");
+    }
+
+    #[test]
+    fn parse_ps_aux_skips_short_and_malformed_rows() {
+        let raw = "\
+USER PID %CPU %MEM VSZ RSS TTY STAT START TIME COMMAND
+too few columns
+bob notapid 0.0 0.0 1 1 ? S 10:00 0:00 sleep 1
+";
+        assert!(parse_ps_aux(raw).is_empty());
+    }
+
+    #[test]
+    fn parse_load_and_users_reads_loadavg_and_uptime_output() {
+        assert_eq!(
+            parse_load_and_users("0.52 0.58 0.59|3\n"),
+            ((0.52, 0.58, 0.59), 3)
+        );
+        // `uptime` fallback after the commas were turned into spaces
+        assert_eq!(
+            parse_load_and_users("1.23  1.45  1.67|1"),
+            ((1.23, 1.45, 1.67), 1)
+        );
+    }
+
+    #[test]
+    fn parse_load_and_users_defaults_missing_parts_to_zero() {
+        assert_eq!(
+            parse_load_and_users("0.10 0.20 0.30|"),
+            ((0.1, 0.2, 0.3), 0)
+        );
+        assert_eq!(parse_load_and_users("|2"), ((0.0, 0.0, 0.0), 2));
+        assert_eq!(parse_load_and_users(""), ((0.0, 0.0, 0.0), 0));
+    }
+
+    const NET_DEV_HEADER: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+";
+
+    fn net_dev(rows: &str) -> String {
+        format!("{}{}", NET_DEV_HEADER, rows)
+    }
+
+    #[test]
+    fn parse_net_dev_skips_header_and_loopback() {
+        let raw = net_dev(
+            "    lo: 1000000    1000    0    0    0     0          0         0  1000000    1000    0    0    0     0       0          0
+  eth0: 5000000    4000    0    0    0     0          0         0  2000000    3000    0    0    0     0       0          0
+",
+        );
+        let counters = parse_net_dev(&raw);
+        assert_eq!(counters.len(), 1);
+        assert_eq!(counters.get("eth0"), Some(&(5_000_000, 2_000_000)));
+    }
+
+    #[test]
+    fn network_rates_cover_the_sample_window() {
+        let before = net_dev(
+            "    lo: 1000000    1000    0    0    0     0          0         0  1000000    1000    0    0    0     0       0          0
+  eth0: 5000000    4000    0    0    0     0          0         0  2000000    3000    0    0    0     0       0          0
+ wlan0: 4000000000  9000    0    0    0     0          0         0  7000    30    0    0    0     0       0          0
+",
+        );
+        let after = net_dev(
+            "    lo: 9000000    9000    0    0    0     0          0         0  9000000    9000    0    0    0     0       0          0
+  eth0: 7000000    5000    0    0    0     0          0         0  2500000    3500    0    0    0     0       0          0
+ wlan0: 1000    9001    0    0    0     0          0         0  9000    31    0    0    0     0       0          0
+",
+        );
+        let rates = compute_network_rates(&before, &after, Duration::from_secs(2)).unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].name, "eth0");
+        assert_eq!(rates[0].rx_bytes_per_sec, 1_000_000.0);
+        assert_eq!(rates[0].tx_bytes_per_sec, 250_000.0);
+        // A counter that wrapped reports no traffic rather than a huge rate
+        assert_eq!(rates[1].name, "wlan0");
+        assert_eq!(rates[1].rx_bytes_per_sec, 0.0);
+        assert_eq!(rates[1].tx_bytes_per_sec, 1_000.0);
+    }
+
+    #[test]
+    fn network_rates_need_both_samples() {
+        // `cat /proc/net/dev 2>/dev/null` prints nothing where the file is missing
+        let sample = net_dev(
+            "  eth0: 5000000    4000    0    0    0     0          0         0  2000000    3000    0    0    0     0       0          0
+",
+        );
+        assert!(compute_network_rates("", "", Duration::from_secs(1)).is_none());
+        assert!(compute_network_rates(&sample, "", Duration::from_secs(1)).is_none());
+        assert!(
+            compute_network_rates(NET_DEV_HEADER, NET_DEV_HEADER, Duration::from_secs(1)).is_none()
+        );
+    }
+
+    #[test]
+    fn normalize_signal_uses_s_for_names() {
+        assert_eq!(normalize_signal(None).unwrap(), vec!["-s", "TERM"]);
+        assert_eq!(
+            normalize_signal(Some(" sigkill ".to_string())).unwrap(),
+            vec!["-s", "KILL"]
+        );
+        assert_eq!(
+            normalize_signal(Some("hup".to_string())).unwrap(),
+            vec!["-s", "HUP"]
+        );
+    }
+
+    #[test]
+    fn normalize_signal_passes_numbers_as_options() {
+        assert_eq!(normalize_signal(Some("9".to_string())).unwrap(), vec!["-9"]);
+        assert_eq!(
+            normalize_signal(Some("15".to_string())).unwrap(),
+            vec!["-15"]
+        );
+    }
+
+    #[test]
+    fn normalize_signal_rejects_shell_syntax() {
+        assert!(normalize_signal(Some("TERM; rm -rf /".to_string())).is_err());
+        assert!(normalize_signal(Some("-9".to_string())).is_err());
+        assert!(normalize_signal(Some("SIG".to_string())).is_err());
+    }
+}
@@ -1,12 +1,209 @@
-use super::client::{AppState, SshClient};
+use super::client::{AppState, ClientType, SshClient};
 use super::manager::SshCommand;
 use crate::ssh::ShellMsg;
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use tauri::{AppHandle, Emitter, State};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+pub const DEFAULT_TERM_TYPE: &str = "xterm-256color";
+
+/// Payload of `term-exit:{id}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermExitPayload {
+    /// Shell exit status, or -1 when it is not known
+    pub exit_code: i32,
+}
+
+fn emit_term_exit(app: &AppHandle, shell_id: &str, exit_code: i32) {
+    if let Ok(mut cwds) = app.state::<AppState>().shell_cwds.lock() {
+        cwds.remove(shell_id);
+    }
+    let _ = app.emit(
+        &format!("term-exit:{}", shell_id),
+        TermExitPayload { exit_code },
+    );
+}
+
+const OSC7_PREFIX: &[u8] = b"\x1b]7;";
+/// Longer unterminated sequences are treated as garbage and dropped.
+const OSC7_MAX_LEN: usize = 4096;
+
+/// Finds `OSC 7 ; file://host/path ST` working-directory reports in shell
+/// output. Sequences split across reads are carried over to the next chunk.
+#[derive(Default)]
+pub struct Osc7Parser {
+    pending: Vec<u8>,
+}
+
+impl Osc7Parser {
+    /// Scan a chunk of output and return the directories it reports.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<String> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(data);
+
+        let mut found = Vec::new();
+        let mut pos = 0;
+        while let Some(start) = find_bytes(&input[pos..], OSC7_PREFIX).map(|i| pos + i) {
+            let body = start + OSC7_PREFIX.len();
+            match find_st(&input[body..]) {
+                Some((end, st_len)) => {
+                    if let Some(path) = parse_file_uri(&input[body..body + end]) {
+                        found.push(path);
+                    }
+                    pos = body + end + st_len;
+                }
+                None => {
+                    if input.len() - start <= OSC7_MAX_LEN {
+                        self.pending = input[start..].to_vec();
+                    }
+                    return found;
+                }
+            }
+        }
+
+        // Keep a trailing partial prefix, e.g. a lone ESC at the end
+        let tail = &input[pos..];
+        let keep = (1..OSC7_PREFIX.len().min(tail.len() + 1))
+            .rev()
+            .find(|&n| tail.ends_with(&OSC7_PREFIX[..n]))
+            .unwrap_or(0);
+        self.pending = tail[tail.len() - keep..].to_vec();
+        found
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Offset and length of the string terminator: BEL or `ESC \`.
+fn find_st(data: &[u8]) -> Option<(usize, usize)> {
+    data.iter().enumerate().find_map(|(i, &b)| match b {
+        0x07 => Some((i, 1)),
+        0x1b if data.get(i + 1) == Some(&b'\\') => Some((i, 2)),
+        _ => None,
+    })
+}
+
+/// Path of a `file://host/path` URI, percent-decoded.
+fn parse_file_uri(uri: &[u8]) -> Option<String> {
+    let rest = uri.strip_prefix(b"file://")?;
+    let path = &rest[rest.iter().position(|&b| b == b'/')?..];
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (path[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&decoded).to_string())
+}
+
+/// Whether shells should be scanned for OSC 7 (`track_cwd` setting).
+fn track_cwd_enabled(app: &AppHandle) -> bool {
+    crate::db::get_settings(app.clone())
+        .map(|settings| settings.terminal_appearance.track_cwd)
+        .unwrap_or(false)
+}
+
+/// Record the directories reported in `data` and emit `cwd-changed:{id}`
+/// for the last one.
+fn report_cwd(app: &AppHandle, shell_id: &str, parser: &mut Osc7Parser, data: &[u8]) {
+    let Some(path) = parser.feed(data).pop() else {
+        return;
+    };
+    if let Ok(mut cwds) = app.state::<AppState>().shell_cwds.lock() {
+        if cwds.get(shell_id) == Some(&path) {
+            return;
+        }
+        cwds.insert(shell_id.to_string(), path.clone());
+    }
+    let _ = app.emit(&format!("cwd-changed:{}", shell_id), path);
+}
+
+/// PTY parameters for the shell opened by `connect`. The frontend passes the
+/// xterm.js size it is about to render at so the first prompt is laid out
+/// correctly before any resize arrives.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShellOptions {
+    pub term_type: String,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            term_type: DEFAULT_TERM_TYPE.to_string(),
+            cols: 80,
+            rows: 24,
+        }
+    }
+}
+
+impl ShellOptions {
+    /// Replace an empty terminal type and zero dimensions with the defaults.
+    fn normalized(self) -> Self {
+        let defaults = Self::default();
+        let term_type = self.term_type.trim();
+        Self {
+            term_type: if term_type.is_empty() {
+                defaults.term_type
+            } else {
+                term_type.to_string()
+            },
+            cols: if self.cols == 0 {
+                defaults.cols
+            } else {
+                self.cols
+            },
+            rows: if self.rows == 0 {
+                defaults.rows
+            } else {
+                self.rows
+            },
+        }
+    }
+}
+
+/// Resolve a terminal id to its owning client and input channel. The id is
+/// either a session id (its primary shell) or a shell id from `open_shell`.
+fn find_shell<'a>(
+    clients: &'a HashMap<String, SshClient>,
+    id: &str,
+) -> Result<(&'a SshClient, Option<&'a Sender<ShellMsg>>), String> {
+    if let Some(client) = clients.get(id) {
+        return Ok((client, client.shell_tx.as_ref()));
+    }
+    clients
+        .values()
+        .find_map(|client| client.shells.get(id).map(|tx| (client, Some(tx))))
+        .ok_or_else(|| "Session not found".to_string())
+}
 
 #[tauri::command]
 pub async fn write_to_pty(
@@ -15,8 +212,7 @@ pub async fn write_to_pty(
     data: String, // xterm sends string usually
 ) -> Result<(), String> {
     let clients = state.clients.lock().map_err(|e| e.to_string())?;
-    let client = clients.get(&id).ok_or("Session not found")?;
-    if let Some(tx) = &client.shell_tx {
+    if let (_, Some(tx)) = find_shell(&clients, &id)? {
         let _ = tx.send(ShellMsg::Data(data.into_bytes()));
     }
     Ok(())
@@ -29,13 +225,70 @@ pub async fn write_binary_to_pty(
     data: Vec<u8>,
 ) -> Result<(), String> {
     let clients = state.clients.lock().map_err(|e| e.to_string())?;
-    let client = clients.get(&id).ok_or("Session not found")?;
-    if let Some(tx) = &client.shell_tx {
+    if let (_, Some(tx)) = find_shell(&clients, &id)? {
         let _ = tx.send(ShellMsg::Data(data));
     }
     Ok(())
 }
 
+/// Signals `send_signal_to_shell` accepts. INT, QUIT and TSTP are written as
+/// their control characters so the remote line discipline delivers them to
+/// the foreground process group, exactly like the keypress would.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShellSignal {
+    Int,
+    Quit,
+    Tstp,
+    Eof,
+    Break,
+}
+
+impl ShellSignal {
+    fn parse(name: &str) -> Result<Self, String> {
+        let upper = name.trim().to_ascii_uppercase();
+        match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "INT" => Ok(Self::Int),
+            "QUIT" => Ok(Self::Quit),
+            "TSTP" => Ok(Self::Tstp),
+            "EOF" => Ok(Self::Eof),
+            "BREAK" => Ok(Self::Break),
+            _ => Err(format!(
+                "Unsupported signal '{}': expected INT, QUIT, TSTP, EOF or BREAK",
+                name
+            )),
+        }
+    }
+
+    fn message(self) -> ShellMsg {
+        match self {
+            Self::Int => ShellMsg::Data(vec![0x03]),
+            Self::Quit => ShellMsg::Data(vec![0x1c]),
+            Self::Tstp => ShellMsg::Data(vec![0x1a]),
+            Self::Eof => ShellMsg::Eof,
+            Self::Break => ShellMsg::Break,
+        }
+    }
+}
+
+/// Interrupt, quit or suspend the shell's foreground process, close its input
+/// (`EOF`) or send a break (`BREAK`, SSH only).
+#[tauri::command]
+pub async fn send_signal_to_shell(
+    state: State<'_, AppState>,
+    id: String,
+    signal: String,
+) -> Result<(), String> {
+    let signal = ShellSignal::parse(&signal)?;
+    let clients = state.clients.lock().map_err(|e| e.to_string())?;
+    let (client, tx) = find_shell(&clients, &id)?;
+    if signal == ShellSignal::Break && matches!(client.client_type, ClientType::Wsl(_)) {
+        return Err("Break is only supported on SSH sessions".to_string());
+    }
+    let tx = tx.ok_or("No shell is open")?;
+    tx.send(signal.message())
+        .map_err(|_| "Shell is no longer running".to_string())
+}
+
 #[tauri::command]
 pub async fn resize_pty(
     state: State<'_, AppState>,
@@ -44,39 +297,112 @@ pub async fn resize_pty(
     cols: u16,
 ) -> Result<(), String> {
     let clients = state.clients.lock().map_err(|e| e.to_string())?;
-    let client = clients.get(&id).ok_or("Session not found")?;
-    if let Some(tx) = &client.shell_tx {
+    if let (_, Some(tx)) = find_shell(&clients, &id)? {
         let _ = tx.send(ShellMsg::Resize { rows, cols });
     }
     Ok(())
 }
 
+/// Open another terminal on an existing connection and return its shell id.
+/// Output is emitted as `term-data:{shell_id}` / `term-exit:{shell_id}`; the
+/// shell id is accepted by the `*_pty` and `send_signal_to_shell` commands.
+/// SSH shells share the connection's main session as extra channels.
+#[tauri::command]
+pub async fn open_shell(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    options: Option<ShellOptions>,
+) -> Result<String, String> {
+    let mut client = state
+        .clients
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .cloned()
+        .ok_or("Session not found")?;
+
+    let shell_id = format!("{}:{}", id, Uuid::new_v4());
+    let tx = start_shell_thread(
+        app,
+        &mut client,
+        shell_id.clone(),
+        options.unwrap_or_default(),
+    )?;
+
+    let mut clients = state.clients.lock().map_err(|e| e.to_string())?;
+    match clients.get_mut(&id) {
+        Some(client) => {
+            client.shells.insert(shell_id.clone(), tx);
+            Ok(shell_id)
+        }
+        None => {
+            // Disconnected while the shell was starting
+            let _ = tx.send(ShellMsg::Exit);
+            Err("Session not found".to_string())
+        }
+    }
+}
+
+/// Close a shell opened with `open_shell`. The connection and its primary
+/// shell stay up.
+#[tauri::command]
+pub async fn close_shell(
+    state: State<'_, AppState>,
+    id: String,
+    shell_id: String,
+) -> Result<(), String> {
+    let mut clients = state.clients.lock().map_err(|e| e.to_string())?;
+    let client = clients.get_mut(&id).ok_or("Session not found")?;
+    let tx = client.shells.remove(&shell_id).ok_or("Shell not found")?;
+    let _ = tx.send(ShellMsg::Exit);
+    Ok(())
+}
+
 pub fn start_shell_thread(
     app: AppHandle,
     client: &mut SshClient,
     id: String,
+    options: ShellOptions,
 ) -> Result<Sender<ShellMsg>, String> {
+    let options = options.normalized();
     // Determine connection type
     match &client.client_type {
-        crate::ssh::client::ClientType::Ssh(senders) => {
+        ClientType::Ssh(senders) => {
             let ssh_sender = senders.shell.clone();
             let shell_id = id.clone();
 
             // 1. Create callback channel for data FROM SSH to UI
             let (callback_tx, callback_rx): (Sender<ShellMsg>, Receiver<ShellMsg>) = channel();
 
+            // Set once the remote side has closed; the manager has already
+            // dropped the channel by then, so later input is discarded here.
+            let shell_closed = Arc::new(AtomicBool::new(false));
+
             // 2. Spawn thread to pump data from callback to UI
             let app_clone = app.clone();
             let shell_id_clone = shell_id.clone();
+            let shell_closed_pump = shell_closed.clone();
+            let mut osc7 = track_cwd_enabled(&app).then(Osc7Parser::default);
             thread::spawn(move || {
                 while let Ok(msg) = callback_rx.recv() {
                     match msg {
                         ShellMsg::Data(d) => {
+                            if let Some(parser) = osc7.as_mut() {
+                                report_cwd(&app_clone, &shell_id_clone, parser, &d);
+                            }
                             let _ = app_clone.emit(&format!("term-data:{}", shell_id_clone), d);
                         }
                         ShellMsg::Resize { .. } => {} // Incoming resize? Usually not relevant
+                        ShellMsg::Eof | ShellMsg::Break => {}
+                        ShellMsg::Exited(code) => {
+                            shell_closed_pump.store(true, Ordering::Relaxed);
+                            emit_term_exit(&app_clone, &shell_id_clone, code);
+                            break;
+                        }
                         ShellMsg::Exit => {
-                            let _ = app_clone.emit(&format!("term-exit:{}", shell_id_clone), ());
+                            shell_closed_pump.store(true, Ordering::Relaxed);
+                            emit_term_exit(&app_clone, &shell_id_clone, -1);
                             break;
                         }
                     }
@@ -84,10 +410,11 @@ pub fn start_shell_thread(
             });
 
             // 3. Send ShellOpen command
-            // xterm default size
             let _ = ssh_sender.send(SshCommand::ShellOpen {
-                cols: 80,
-                rows: 24,
+                shell_id: shell_id.clone(),
+                term_type: options.term_type,
+                cols: options.cols,
+                rows: options.rows,
                 sender: callback_tx,
             });
 
@@ -97,15 +424,36 @@ pub fn start_shell_thread(
             // 5. Spawn adapter thread
             thread::spawn(move || {
                 while let Ok(msg) = ui_rx.recv() {
+                    if shell_closed.load(Ordering::Relaxed) {
+                        break;
+                    }
                     match msg {
                         ShellMsg::Data(d) => {
-                            let _ = ssh_sender.send(SshCommand::ShellWrite(d));
+                            let _ = ssh_sender.send(SshCommand::ShellWrite {
+                                shell_id: shell_id.clone(),
+                                data: d,
+                            });
                         }
                         ShellMsg::Resize { rows, cols } => {
-                            let _ = ssh_sender.send(SshCommand::ShellResize { rows, cols });
+                            let _ = ssh_sender.send(SshCommand::ShellResize {
+                                shell_id: shell_id.clone(),
+                                rows,
+                                cols,
+                            });
+                        }
+                        ShellMsg::Eof => {
+                            let _ = ssh_sender.send(SshCommand::ShellEof {
+                                shell_id: shell_id.clone(),
+                            });
+                        }
+                        ShellMsg::Break => {
+                            let _ = ssh_sender.send(SshCommand::ShellBreak {
+                                shell_id: shell_id.clone(),
+                            });
                         }
+                        ShellMsg::Exited(_) => {}
                         ShellMsg::Exit => {
-                            let _ = ssh_sender.send(SshCommand::ShellClose);
+                            let _ = ssh_sender.send(SshCommand::ShellClose { shell_id });
                             break;
                         }
                     }
@@ -114,7 +462,7 @@ pub fn start_shell_thread(
 
             Ok(ui_tx)
         }
-        crate::ssh::client::ClientType::Wsl(distro) => {
+        ClientType::Wsl(distro) => {
             use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
 
             let (tx, rx): (Sender<ShellMsg>, Receiver<ShellMsg>) = channel();
@@ -124,8 +472,8 @@ pub fn start_shell_thread(
             let pty_system = NativePtySystem::default();
             let pair = pty_system
                 .openpty(PtySize {
-                    rows: 24,
-                    cols: 80,
+                    rows: options.rows,
+                    cols: options.cols,
                     pixel_width: 0,
                     pixel_height: 0,
                 })
@@ -135,10 +483,13 @@ pub fn start_shell_thread(
             let mut cmd = CommandBuilder::new("wsl");
             cmd.arg("-d");
             cmd.arg(distro);
+            // WSLENV forwards TERM from the Windows side into the distro.
+            cmd.env("TERM", &options.term_type);
+            cmd.env("WSLENV", "TERM/u");
 
             // Need to drop slave to close it in this process effectively?
             // portable-pty documentation suggests spawn_command takes generic command.
-            let _child = pair
+            let mut child = pair
                 .slave
                 .spawn_command(cmd)
                 .map_err(|e| format!("Failed to spawn WSL: {}", e))?;
@@ -150,6 +501,7 @@ pub fn start_shell_thread(
                 .map_err(|e| format!("Failed to clone reader: {}", e))?;
             let app_clone = app.clone();
             let shell_id_read = shell_id.clone();
+            let mut osc7 = track_cwd_enabled(&app).then(Osc7Parser::default);
 
             thread::spawn(move || {
                 let mut buf = [0u8; 4096];
@@ -160,6 +512,9 @@ pub fn start_shell_thread(
                     match reader.read(&mut buf) {
                         Ok(n) if n > 0 => {
                             last_activity = std::time::Instant::now();
+                            if let Some(parser) = osc7.as_mut() {
+                                report_cwd(&app_clone, &shell_id_read, parser, &buf[0..n]);
+                            }
                             let _ = app_clone
                                 .emit(&format!("term-data:{}", shell_id_read), buf[0..n].to_vec());
                         }
@@ -181,7 +536,13 @@ pub fn start_shell_thread(
                         break;
                     }
                 }
-                let _ = app_clone.emit(&format!("term-exit:{}", shell_id_read), ());
+                // The shell has usually exited by the time its PTY hits EOF;
+                // don't block on it if it hasn't (read timeout).
+                let exit_code = match child.try_wait() {
+                    Ok(Some(status)) => status.exit_code() as i32,
+                    _ => -1,
+                };
+                emit_term_exit(&app_clone, &shell_id_read, exit_code);
             });
 
             // Writer thread (handle rx)
@@ -210,6 +571,12 @@ pub fn start_shell_thread(
                                 eprintln!("WSL Resize Error: {}", e);
                             }
                         }
+                        // A local PTY has no channel to half-close; Ctrl-D is
+                        // what the line discipline treats as end of input.
+                        ShellMsg::Eof => {
+                            let _ = writer.write_all(&[0x04]);
+                        }
+                        ShellMsg::Break | ShellMsg::Exited(_) => {}
                         ShellMsg::Exit => {
                             break;
                         }
@@ -221,3 +588,39 @@ pub fn start_shell_thread(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc7_parser_reads_bel_and_st_terminated_sequences() {
+        let mut parser = Osc7Parser::default();
+        assert_eq!(
+            parser.feed(b"prompt\x1b]7;file://host/home/me\x07$ "),
+            vec!["/home/me"]
+        );
+        assert_eq!(
+            parser.feed(b"\x1b]7;file:///tmp/a%20b\x1b\\\x1b]7;file://h/srv\x07"),
+            vec!["/tmp/a b", "/srv"]
+        );
+        assert!(parser.feed(b"plain output\x1b]0;title\x07").is_empty());
+    }
+
+    #[test]
+    fn osc7_parser_joins_sequences_split_across_chunks() {
+        let mut parser = Osc7Parser::default();
+        assert!(parser.feed(b"ls\r\n\x1b").is_empty());
+        assert!(parser.feed(b"]7;file://host/var/").is_empty());
+        assert_eq!(parser.feed(b"log\x1b"), Vec::<String>::new());
+        assert_eq!(parser.feed(b"\\rest"), vec!["/var/log"]);
+    }
+
+    #[test]
+    fn parse_file_uri_requires_file_scheme_and_path() {
+        assert_eq!(parse_file_uri(b"file://host/"), Some("/".to_string()));
+        assert_eq!(parse_file_uri(b"file://host"), None);
+        assert_eq!(parse_file_uri(b"http://host/x"), None);
+        assert_eq!(parse_file_uri(b"file:///100%"), Some("/100%".to_string()));
+    }
+}
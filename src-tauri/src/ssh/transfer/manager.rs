@@ -704,6 +704,33 @@ mod tests {
             os_type: None,
             key_content: None,
             key_passphrase: None,
+            tags: Vec::new(),
+            last_connected: None,
+            last_latency_ms: None,
+            force_scp: false,
+            max_background_sessions: None,
+            jump_hosts: Vec::new(),
+            jump_auth_type: None,
+            jump_key_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            is_favorite: false,
+            agent_forwarding: false,
+            x11_forwarding: false,
+            low_latency: false,
+            idle_timeout_minutes: 0,
+            kex_algorithms: None,
+            ciphers: None,
+            mac_algorithms: None,
+            legacy_mode: false,
+            init_command: None,
+            remote_rc: None,
+            host_key_policy: None,
+            socket_path: None,
+            proxy_command: None,
+            sftp_handle_cache: false,
+            notes: None,
         };
 
         let settings = TransferSettings::default();
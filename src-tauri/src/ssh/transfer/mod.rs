@@ -17,6 +17,7 @@
 //! - **async_sftp**: Async SFTP wrappers with timeout and retry logic
 //! - **checkpoint**: Checkpoint management for resume support
 //! - **manager**: Main TransferManager orchestrating all operations
+//! - **throttle**: Token-bucket bandwidth limiter shared across transfers
 //!
 //! # Usage Example
 //!
@@ -47,6 +48,7 @@ pub mod pool;
 pub mod prompt;
 pub mod retry;
 pub mod state;
+pub mod throttle;
 pub mod types;
 
 // Re-export commonly used types
@@ -60,3 +62,4 @@ pub use checkpoint::{CheckpointManager, TransferCheckpoint};
 pub use manager::TransferManager;
 pub use pool::{PoolStats, TransferConnection, TransferPool};
 pub use state::{TransferState, TransferStateHandle};
+pub use throttle::RateLimiter;
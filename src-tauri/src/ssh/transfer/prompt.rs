@@ -4,6 +4,7 @@
 //! including confirmations, warnings, error messages, and progress feedback.
 
 use crate::ssh::transfer::types::{TransferError, TransferOperation, TransferStatus};
+use crate::ssh::utils::format_bytes;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
@@ -173,7 +174,7 @@ impl PromptManager {
                 "Local: {}\nRemote: {}\nSize: {}",
                 local_path,
                 remote_path,
-                Self::format_bytes(file_size)
+                format_bytes(file_size)
             )),
             transfer_id: Some(transfer_id.to_string()),
             operation: Some(operation),
@@ -366,7 +367,7 @@ impl PromptManager {
                     "The transfer encountered a network error. This is usually temporary.\n\
                      Transferred: {}\n\
                      Suggestion: Retry the transfer. If the problem persists, check your network connection.",
-                    Self::format_bytes(transferred_bytes)
+                    format_bytes(transferred_bytes)
                 ),
             ),
             TransferError::Timeout(msg) => (
@@ -376,7 +377,7 @@ impl PromptManager {
                     "The operation took too long to complete.\n\
                      Transferred: {}\n\
                      Suggestion: Try again with a larger timeout or check network conditions.",
-                    Self::format_bytes(transferred_bytes)
+                    format_bytes(transferred_bytes)
                 ),
             ),
             TransferError::PermissionDenied(msg) => (
@@ -410,7 +411,7 @@ impl PromptManager {
                     "The SSH connection was interrupted.\n\
                      Transferred: {}\n\
                      Suggestion: The transfer can be resumed automatically.",
-                    Self::format_bytes(transferred_bytes)
+                    format_bytes(transferred_bytes)
                 ),
             ),
             TransferError::Cancelled => (
@@ -419,7 +420,7 @@ impl PromptManager {
                 format!(
                     "You cancelled the transfer.\n\
                      Transferred: {}",
-                    Self::format_bytes(transferred_bytes)
+                    format_bytes(transferred_bytes)
                 ),
             ),
             TransferError::CheckpointMismatch(msg) => (
@@ -453,31 +454,13 @@ impl PromptManager {
                     "An unexpected error occurred.\n\
                      Transferred: {}\n\
                      Suggestion: Try again or contact support if the problem persists.",
-                    Self::format_bytes(transferred_bytes)
+                    format_bytes(transferred_bytes)
                 ),
             ),
         };
 
         (title, message, suggestion)
     }
-
-    /// Format bytes in human-readable format
-    fn format_bytes(bytes: u64) -> String {
-        const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-        let mut size = bytes as f64;
-        let mut unit_index = 0;
-
-        while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-            size /= 1024.0;
-            unit_index += 1;
-        }
-
-        if unit_index == 0 {
-            format!("{} {}", bytes, UNITS[unit_index])
-        } else {
-            format!("{:.2} {}", size, UNITS[unit_index])
-        }
-    }
 }
 
 /// Helper functions for creating common prompts
@@ -505,8 +488,8 @@ impl PromptManager {
             ),
             description: Some(format!(
                 "Existing file: {}\nNew file: {}\nPath: {}",
-                Self::format_bytes(existing_size),
-                Self::format_bytes(new_size),
+                format_bytes(existing_size),
+                format_bytes(new_size),
                 file_path
             )),
             transfer_id: Some(transfer_id.to_string()),
@@ -564,14 +547,11 @@ impl PromptManager {
             prompt_type: PromptType::Warning,
             severity: PromptSeverity::Medium,
             title: "Large File Transfer".to_string(),
-            message: format!(
-                "This is a large file transfer: {}",
-                Self::format_bytes(file_size)
-            ),
+            message: format!("This is a large file transfer: {}", format_bytes(file_size)),
             description: Some(format!(
                 "File: {}\nSize: {}\nThis may take a long time to transfer.",
                 file_path,
-                Self::format_bytes(file_size)
+                format_bytes(file_size)
             )),
             transfer_id: Some(transfer_id.to_string()),
             operation: Some(operation),
@@ -609,10 +589,10 @@ mod tests {
 
     #[test]
     fn test_format_bytes() {
-        assert_eq!(PromptManager::format_bytes(512), "512 B");
-        assert_eq!(PromptManager::format_bytes(1024), "1.00 KB");
-        assert_eq!(PromptManager::format_bytes(1536), "1.50 KB");
-        assert_eq!(PromptManager::format_bytes(1048576), "1.00 MB");
-        assert_eq!(PromptManager::format_bytes(1073741824), "1.00 GB");
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1024), "1.00 KB");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+        assert_eq!(format_bytes(1048576), "1.00 MB");
+        assert_eq!(format_bytes(1073741824), "1.00 GB");
     }
 }
@@ -0,0 +1,133 @@
+//! Token-bucket bandwidth limiter for file transfers
+//!
+//! One `RateLimiter` lives in `AppState` and is shared by every running
+//! transfer, so the configured rate caps their combined throughput. A
+//! transfer with an explicit rate override gets its own limiter instead.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest single sleep, so cancellation stays responsive while throttled.
+const MAX_SLEEP_SLICE: Duration = Duration::from_millis(100);
+/// Smallest chunk handed out while throttled, to keep per-call overhead low.
+const MIN_CHUNK: usize = 4 * 1024;
+
+struct Bucket {
+    rate: u64,
+    /// May go negative: callers take what they need and sleep off the debt.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// `rate` in bytes per second; 0 disables limiting.
+    pub fn new(rate: u64) -> Self {
+        Self {
+            bucket: Mutex::new(Bucket {
+                rate,
+                tokens: rate as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.bucket.lock().map(|bucket| bucket.rate).unwrap_or(0)
+    }
+
+    /// Change the rate, keeping at most one second of burst.
+    pub fn set_rate(&self, rate: u64) {
+        if let Ok(mut bucket) = self.bucket.lock() {
+            if bucket.rate != rate {
+                bucket.rate = rate;
+                bucket.tokens = bucket.tokens.min(rate as f64);
+            }
+        }
+    }
+
+    /// Buffer size to use per read/write so one chunk is roughly a tenth of a
+    /// second of allowance.
+    pub fn chunk_size(&self, max: usize) -> usize {
+        match self.rate() {
+            0 => max,
+            rate => ((rate / 10) as usize).max(MIN_CHUNK).min(max),
+        }
+    }
+
+    /// Take `bytes` from the bucket and return how long the caller must wait
+    /// before sending them.
+    fn reserve(&self, bytes: u64, now: Instant) -> Duration {
+        let Ok(mut bucket) = self.bucket.lock() else {
+            return Duration::ZERO;
+        };
+        if bucket.rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let rate = bucket.rate as f64;
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(rate);
+        bucket.last_refill = now;
+        bucket.tokens -= bytes as f64;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+
+    /// Block until `bytes` fit within the rate and return how long that took,
+    /// so callers can keep throttled time out of their timeouts. Returns early
+    /// once `cancel_flag` is set.
+    pub fn throttle(&self, bytes: u64, cancel_flag: &AtomicBool) -> Duration {
+        let started = Instant::now();
+        let mut wait = self.reserve(bytes, started);
+        while !wait.is_zero() && !cancel_flag.load(Ordering::Relaxed) {
+            let slice = wait.min(MAX_SLEEP_SLICE);
+            thread::sleep(slice);
+            wait -= slice;
+        }
+        started.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_never_waits() {
+        let limiter = RateLimiter::new(0);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(10 * 1024 * 1024, now), Duration::ZERO);
+        assert_eq!(limiter.chunk_size(512 * 1024), 512 * 1024);
+    }
+
+    #[test]
+    fn test_waits_once_burst_is_spent() {
+        let limiter = RateLimiter::new(1000);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(1000, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(500, now), Duration::from_millis(500));
+        // Half a second later the debt is paid off but nothing has accrued.
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.reserve(250, later), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_set_rate_caps_burst() {
+        let limiter = RateLimiter::new(10_000);
+        limiter.set_rate(100);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(100, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(50, now), Duration::from_millis(500));
+        assert_eq!(limiter.chunk_size(512 * 1024), MIN_CHUNK);
+    }
+}
@@ -0,0 +1,294 @@
+use super::client::{emit_transfers_updated, AppState, TransferState};
+use crate::models::DEFAULT_MAX_CONCURRENT_TRANSFERS;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Notify;
+
+/// Limits how many `download_file`/`upload_file` transfers run at once.
+/// New transfers wait as "pending" in queue order; a slot is handed to the
+/// first waiting transfer that is not paused whenever one frees up.
+#[derive(Default)]
+pub struct TransferQueue {
+    inner: Mutex<QueueState>,
+    changed: Notify,
+    /// `max_concurrent_transfers` from the settings, 0 until first read
+    limit: AtomicUsize,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Queued transfer IDs in the order they will start
+    waiting: Vec<String>,
+    running: HashSet<String>,
+}
+
+/// Held by a transfer while it runs; frees the slot when dropped.
+pub struct TransferSlot {
+    app: AppHandle,
+    transfer_id: String,
+}
+
+impl Drop for TransferSlot {
+    fn drop(&mut self) {
+        self.app
+            .state::<AppState>()
+            .transfer_queue
+            .remove(&self.transfer_id);
+    }
+}
+
+impl TransferQueue {
+    pub fn enqueue(&self, transfer_id: &str) {
+        if let Ok(mut queue) = self.inner.lock() {
+            queue.waiting.retain(|id| id != transfer_id);
+            queue.waiting.push(transfer_id.to_string());
+        }
+        self.wake();
+    }
+
+    /// Whether the transfer is still waiting for a slot.
+    pub fn is_waiting(&self, transfer_id: &str) -> bool {
+        self.inner
+            .lock()
+            .map(|queue| queue.waiting.iter().any(|id| id == transfer_id))
+            .unwrap_or(false)
+    }
+
+    /// Re-check waiting transfers, e.g. after a status change or a slot freed.
+    pub fn wake(&self) {
+        self.changed.notify_waiters();
+    }
+
+    /// Re-read the concurrency limit from the settings after they were saved.
+    pub fn reload_limit(&self) {
+        self.limit.store(0, Ordering::Relaxed);
+        self.wake();
+    }
+
+    fn limit(&self, app: &AppHandle) -> usize {
+        match self.limit.load(Ordering::Relaxed) {
+            0 => {
+                let limit = max_concurrent_transfers(app);
+                self.limit.store(limit, Ordering::Relaxed);
+                limit
+            }
+            limit => limit,
+        }
+    }
+
+    fn remove(&self, transfer_id: &str) {
+        if let Ok(mut queue) = self.inner.lock() {
+            queue.waiting.retain(|id| id != transfer_id);
+            queue.running.remove(transfer_id);
+        }
+        self.wake();
+    }
+
+    fn waiting(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .inner
+            .lock()
+            .map_err(|e| e.to_string())?
+            .waiting
+            .clone())
+    }
+
+    fn reorder(&self, order: &[String]) -> Result<Vec<String>, String> {
+        let waiting = {
+            let mut queue = self.inner.lock().map_err(|e| e.to_string())?;
+            queue.waiting = reordered(&queue.waiting, order);
+            queue.waiting.clone()
+        };
+        self.wake();
+        Ok(waiting)
+    }
+}
+
+/// `waiting` with the IDs from `order` moved to the front in that order.
+/// IDs that are not queued are ignored; the rest keep their relative order.
+fn reordered(waiting: &[String], order: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::with_capacity(waiting.len());
+    for id in order {
+        if waiting.contains(id) && !result.contains(id) {
+            result.push(id.clone());
+        }
+    }
+    for id in waiting {
+        if !result.contains(id) {
+            result.push(id.clone());
+        }
+    }
+    result
+}
+
+fn max_concurrent_transfers(app: &AppHandle) -> usize {
+    crate::db::get_settings(app.clone())
+        .map(|settings| settings.file_manager.max_concurrent_transfers)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_TRANSFERS)
+        .max(1) as usize
+}
+
+/// The transfer that gets the next free slot: the first waiting one that is
+/// pending (paused ones keep their place but do not block the queue), if a
+/// slot is free.
+fn next_to_start<'a>(
+    waiting: &'a [String],
+    pending: &HashSet<String>,
+    running: usize,
+    limit: usize,
+) -> Option<&'a String> {
+    if running >= limit {
+        return None;
+    }
+    waiting.iter().find(|id| pending.contains(*id))
+}
+
+/// Status of a tracked transfer, or `None` once it was removed.
+fn transfer_status(state: &AppState, transfer_id: &str) -> Option<String> {
+    let transfer = state.transfers.lock().ok()?.get(transfer_id).cloned()?;
+    let status = transfer.data.lock().ok()?.status.clone();
+    Some(status)
+}
+
+/// Wait until the transfer may start, then mark it "running". Returns `None`
+/// if it was cancelled or removed while queued.
+pub async fn acquire_transfer_slot(
+    app: &AppHandle,
+    transfer: &TransferState,
+    transfer_id: &str,
+) -> Option<TransferSlot> {
+    let state = app.state::<AppState>();
+    let queue = &state.transfer_queue;
+    loop {
+        // Register before checking so a wake between the check and the
+        // await is not lost
+        let notified = queue.changed.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        if transfer.cancel_flag.load(Ordering::Relaxed)
+            || transfer_status(&state, transfer_id).is_none()
+        {
+            queue.remove(transfer_id);
+            return None;
+        }
+
+        let limit = queue.limit(app);
+        // Statuses are read without holding the queue lock, which the
+        // transfers map must never be locked under. A status change after
+        // this snapshot wakes the queue, so it is re-checked.
+        let pending: HashSet<String> = queue
+            .waiting()
+            .ok()?
+            .into_iter()
+            .filter(|id| transfer_status(&state, id).as_deref() == Some("pending"))
+            .collect();
+        let acquired = {
+            let mut inner = queue.inner.lock().ok()?;
+            let next = next_to_start(&inner.waiting, &pending, inner.running.len(), limit);
+            if next.map(String::as_str) == Some(transfer_id) {
+                inner.waiting.retain(|id| id != transfer_id);
+                inner.running.insert(transfer_id.to_string());
+                true
+            } else {
+                false
+            }
+        };
+
+        if acquired {
+            let slot = TransferSlot {
+                app: app.clone(),
+                transfer_id: transfer_id.to_string(),
+            };
+            transfer.update(app, |data| {
+                if data.status == "pending" {
+                    data.status = "running".to_string();
+                }
+            });
+            return Some(slot);
+        }
+        notified.await;
+    }
+}
+
+/// IDs of queued transfers in the order they will start.
+#[tauri::command]
+pub async fn get_transfer_queue(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.transfer_queue.waiting()
+}
+
+/// Move the given queued transfers to the front in the given order. Returns
+/// the new queue order.
+#[tauri::command]
+pub async fn reorder_queue(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transfer_ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let waiting = state.transfer_queue.reorder(&transfer_ids)?;
+    emit_transfers_updated(&app);
+    Ok(waiting)
+}
+
+/// Start a queued transfer before every other waiting one.
+#[tauri::command]
+pub async fn prioritize_transfer(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<Vec<String>, String> {
+    if !state.transfer_queue.is_waiting(&transfer_id) {
+        return Err("Transfer is not queued".to_string());
+    }
+    reorder_queue(app, state, vec![transfer_id]).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn reordered_moves_listed_ids_to_front() {
+        let waiting = ids(&["a", "b", "c", "d"]);
+        assert_eq!(
+            reordered(&waiting, &ids(&["c"])),
+            ids(&["c", "a", "b", "d"])
+        );
+        assert_eq!(
+            reordered(&waiting, &ids(&["d", "b"])),
+            ids(&["d", "b", "a", "c"])
+        );
+    }
+
+    #[test]
+    fn next_to_start_skips_transfers_that_are_not_pending() {
+        let waiting = ids(&["a", "b", "c"]);
+        let pending: HashSet<String> = ids(&["b", "c"]).into_iter().collect();
+        assert_eq!(next_to_start(&waiting, &pending, 0, 2), Some(&waiting[1]));
+        assert_eq!(next_to_start(&waiting, &HashSet::new(), 0, 2), None);
+    }
+
+    #[test]
+    fn next_to_start_waits_for_a_free_slot() {
+        let waiting = ids(&["a"]);
+        let pending: HashSet<String> = waiting.iter().cloned().collect();
+        assert_eq!(next_to_start(&waiting, &pending, 2, 2), None);
+        assert_eq!(next_to_start(&waiting, &pending, 1, 2), Some(&waiting[0]));
+    }
+
+    #[test]
+    fn reordered_ignores_unknown_and_duplicate_ids() {
+        let waiting = ids(&["a", "b"]);
+        assert_eq!(
+            reordered(&waiting, &ids(&["x", "b", "b"])),
+            ids(&["b", "a"])
+        );
+        assert_eq!(reordered(&waiting, &[]), waiting);
+    }
+}
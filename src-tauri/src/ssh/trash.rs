@@ -0,0 +1,535 @@
+use super::client::{AppState, ClientType};
+use super::file_ops::append_file_audit_event;
+use super::manager::{write_all_retrying, SshCommand, SshManager};
+use super::wsl;
+use crate::models::TrashEntry;
+use crate::ssh::execute_ssh_operation;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, State};
+use uuid::Uuid;
+
+/// Trash root, relative to the remote user's home directory. Each trashed
+/// item gets its own `<timestamp>` directory holding a manifest and an
+/// `item/` subdirectory with the item, so no item name can clash with the
+/// manifest.
+const TRASH_DIR: &str = ".ssh-assistant-trash";
+const MANIFEST_NAME: &str = ".manifest.json";
+const ITEM_DIR: &str = "item";
+
+/// SFTP status code for a generic failure. Servers report a rename across
+/// filesystems this way, but also a full disk or a busy file.
+const SFTP_FX_FAILURE: i32 = 4;
+
+/// Prefix of the error returned when an item cannot be moved into the trash
+/// and the caller did not allow deleting it instead, followed by the reason
+/// (e.g. `TRASH_UNAVAILABLE:Failure`).
+pub const TRASH_UNAVAILABLE: &str = "TRASH_UNAVAILABLE";
+
+/// What to do with the trash. Every action answers with the entries it
+/// touched.
+pub enum TrashAction {
+    /// `permanent_fallback` allows a real delete when the move into the trash
+    /// fails with a generic failure, as it does across filesystems
+    Trash {
+        path: String,
+        is_dir: bool,
+        permanent_fallback: bool,
+    },
+    List,
+    Restore {
+        entry_id: String,
+    },
+    Empty,
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Unique even for items deleted in the same millisecond, and still sorts
+/// by deletion time.
+fn new_entry_id(deleted_at: i64) -> String {
+    format!("{}-{}", deleted_at, Uuid::new_v4().simple())
+}
+
+fn file_name(path: &str) -> Result<String, String> {
+    Path::new(path.trim_end_matches('/'))
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Cannot move {} to the trash", path))
+}
+
+/// Entry ids are directory names we generated; reject anything else so a
+/// crafted id cannot point outside the trash.
+fn validate_entry_id(entry_id: &str) -> Result<(), String> {
+    if !entry_id.is_empty()
+        && entry_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        Ok(())
+    } else {
+        Err(format!("Invalid trash entry: {}", entry_id))
+    }
+}
+
+/// Where an entry keeps its item.
+fn item_path(entry_dir: &Path, name: &str) -> PathBuf {
+    entry_dir.join(ITEM_DIR).join(name)
+}
+
+fn sftp_exists(sftp: &ssh2::Sftp, path: &Path) -> bool {
+    crate::ssh::utils::ssh2_retry(|| sftp.lstat(path)).is_ok()
+}
+
+fn sftp_trash_root(sftp: &ssh2::Sftp) -> Result<PathBuf, String> {
+    let home = crate::ssh::utils::ssh2_retry(|| sftp.realpath(Path::new(".")))
+        .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+    Ok(home.join(TRASH_DIR))
+}
+
+fn sftp_read_manifest(sftp: &ssh2::Sftp, entry_dir: &Path) -> Result<TrashEntry, String> {
+    let manifest_path = entry_dir.join(MANIFEST_NAME);
+    let mut file =
+        crate::ssh::utils::ssh2_retry(|| sftp.open(&manifest_path)).map_err(|e| e.to_string())?;
+    let content = SshManager::read_sftp_file(&mut file, 0, None)?;
+    serde_json::from_slice(&content).map_err(|e| format!("Corrupt trash manifest: {}", e))
+}
+
+fn sftp_write_manifest(
+    sftp: &ssh2::Sftp,
+    entry_dir: &Path,
+    entry: &TrashEntry,
+) -> Result<(), String> {
+    let manifest = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+    let manifest_path = entry_dir.join(MANIFEST_NAME);
+    let mut file =
+        crate::ssh::utils::ssh2_retry(|| sftp.create(&manifest_path)).map_err(|e| e.to_string())?;
+    write_all_retrying(&mut file, &manifest)
+}
+
+/// Remove an entry directory whose item has left (or never arrived).
+fn sftp_discard_entry(sftp: &ssh2::Sftp, entry_dir: &Path) {
+    let _ = sftp.unlink(&entry_dir.join(MANIFEST_NAME));
+    let _ = sftp.rmdir(&entry_dir.join(ITEM_DIR));
+    let _ = sftp.rmdir(entry_dir);
+}
+
+fn sftp_trash(
+    sftp: &ssh2::Sftp,
+    path: &str,
+    is_dir: bool,
+    permanent_fallback: bool,
+) -> Result<TrashEntry, String> {
+    let name = file_name(path)?;
+    let root = sftp_trash_root(sftp)?;
+    if !sftp_exists(sftp, &root) {
+        crate::ssh::utils::ssh2_retry(|| sftp.mkdir(&root, 0o700))
+            .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+    }
+
+    let deleted_at = now_millis();
+    let entry_id = new_entry_id(deleted_at);
+    let entry_dir = root.join(&entry_id);
+    crate::ssh::utils::ssh2_retry(|| sftp.mkdir(&entry_dir, 0o700))
+        .and_then(|_| {
+            crate::ssh::utils::ssh2_retry(|| sftp.mkdir(&entry_dir.join(ITEM_DIR), 0o700))
+        })
+        .map_err(|e| {
+            let _ = sftp.rmdir(&entry_dir);
+            format!("Failed to create trash entry: {}", e)
+        })?;
+
+    let mut entry = TrashEntry {
+        id: entry_id,
+        original_path: path.to_string(),
+        name: name.clone(),
+        is_dir,
+        deleted_at,
+        permanent: false,
+    };
+
+    // Manifest first: an item in the trash without one could not be listed
+    // or restored
+    if let Err(e) = sftp_write_manifest(sftp, &entry_dir, &entry) {
+        sftp_discard_entry(sftp, &entry_dir);
+        return Err(format!("Failed to write trash manifest: {}", e));
+    }
+
+    let renamed = crate::ssh::utils::ssh2_retry(|| {
+        sftp.rename(Path::new(path), &item_path(&entry_dir, &name), None)
+    });
+    let Err(e) = renamed else {
+        return Ok(entry);
+    };
+    sftp_discard_entry(sftp, &entry_dir);
+    if e.code() != ssh2::ErrorCode::SFTP(SFTP_FX_FAILURE) {
+        return Err(format!("Failed to move {} to the trash: {}", path, e));
+    }
+    // Possibly another filesystem, possibly a full disk or a busy file; only
+    // delete outright when the caller confirmed that
+    if !permanent_fallback {
+        return Err(format!("{}:{}", TRASH_UNAVAILABLE, e));
+    }
+    if is_dir {
+        SshManager::rm_recursive_internal(sftp, Path::new(path))?;
+    } else {
+        crate::ssh::utils::ssh2_retry(|| sftp.unlink(Path::new(path)))
+            .map_err(|e| e.to_string())?;
+    }
+    entry.permanent = true;
+    Ok(entry)
+}
+
+fn sftp_list(sftp: &ssh2::Sftp) -> Result<Vec<TrashEntry>, String> {
+    let root = sftp_trash_root(sftp)?;
+    if !sftp_exists(sftp, &root) {
+        return Ok(Vec::new());
+    }
+    let dirs = crate::ssh::utils::ssh2_retry(|| sftp.readdir(&root)).map_err(|e| e.to_string())?;
+    let mut entries: Vec<TrashEntry> = dirs
+        .into_iter()
+        .filter(|(_, stat)| stat.is_dir())
+        .filter_map(|(dir, _)| match sftp_read_manifest(sftp, &dir) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                eprintln!("Skipping trash entry {}: {}", dir.display(), e);
+                None
+            }
+        })
+        .collect();
+    entries.sort_by_key(|entry| Reverse(entry.deleted_at));
+    Ok(entries)
+}
+
+fn sftp_restore(sftp: &ssh2::Sftp, entry_id: &str) -> Result<TrashEntry, String> {
+    validate_entry_id(entry_id)?;
+    let entry_dir = sftp_trash_root(sftp)?.join(entry_id);
+    let entry = sftp_read_manifest(sftp, &entry_dir)?;
+    let original = Path::new(&entry.original_path);
+    if sftp_exists(sftp, original) {
+        return Err(format!("{} already exists", entry.original_path));
+    }
+    // Entries trashed before the `item/` subdirectory keep the item beside
+    // the manifest
+    let mut item = item_path(&entry_dir, &entry.name);
+    if !sftp_exists(sftp, &item) {
+        item = entry_dir.join(&entry.name);
+    }
+    crate::ssh::utils::ssh2_retry(|| sftp.rename(&item, original, None))
+        .map_err(|e| format!("Failed to restore {}: {}", entry.original_path, e))?;
+    sftp_discard_entry(sftp, &entry_dir);
+    Ok(entry)
+}
+
+fn sftp_empty(sftp: &ssh2::Sftp) -> Result<Vec<TrashEntry>, String> {
+    let entries = sftp_list(sftp)?;
+    let root = sftp_trash_root(sftp)?;
+    if sftp_exists(sftp, &root) {
+        SshManager::rm_recursive_internal(sftp, &root)?;
+    }
+    Ok(entries)
+}
+
+/// Run a trash action on an SFTP session (called from the manager).
+pub(crate) fn run_sftp(sftp: &ssh2::Sftp, action: &TrashAction) -> Result<Vec<TrashEntry>, String> {
+    match action {
+        TrashAction::Trash {
+            path,
+            is_dir,
+            permanent_fallback,
+        } => sftp_trash(sftp, path, *is_dir, *permanent_fallback).map(|e| vec![e]),
+        TrashAction::List => sftp_list(sftp),
+        TrashAction::Restore { entry_id } => sftp_restore(sftp, entry_id).map(|e| vec![e]),
+        TrashAction::Empty => sftp_empty(sftp),
+    }
+}
+
+fn wsl_list(distro: &str) -> Result<Vec<TrashEntry>, String> {
+    let output = wsl::run_bash_text(
+        distro,
+        &format!(
+            "for f in ~/{}/*/{}; do [ -f \"$f\" ] && cat \"$f\" && echo; done; true",
+            TRASH_DIR, MANIFEST_NAME
+        ),
+        &[],
+    )?;
+    let mut entries: Vec<TrashEntry> = output
+        .lines()
+        .filter_map(|line| serde_json::from_str(line.trim()).ok())
+        .collect();
+    entries.sort_by_key(|entry| Reverse(entry.deleted_at));
+    Ok(entries)
+}
+
+/// Script and arguments that move an item into a new WSL trash entry. The
+/// path and manifest are passed as positional arguments.
+fn wsl_trash_command(entry: &TrashEntry, manifest: String) -> (String, Vec<String>) {
+    let script = format!(
+        "set -e; dir=~/{root}/{id}; mkdir -p \"$dir/{item}\"; printf '%s' \"$2\" > \"$dir/{manifest_name}\"; mv -- \"$1\" \"$dir/{item}/\" || {{ rm -rf \"$dir\"; exit 1; }}",
+        root = TRASH_DIR,
+        id = entry.id,
+        item = ITEM_DIR,
+        manifest_name = MANIFEST_NAME
+    );
+    (script, vec![entry.original_path.clone(), manifest])
+}
+
+/// Script and arguments that move a WSL trash entry back. The entry name and
+/// original path come from a manifest on the remote side, so they are passed
+/// as positional arguments rather than spliced into the script. Entries
+/// trashed before the `item/` subdirectory keep the item beside the manifest.
+fn wsl_restore_command(entry: &TrashEntry) -> (String, Vec<String>) {
+    let script = format!(
+        "set -e; dir=~/{root}/{id}; [ ! -e \"$2\" ] || {{ printf '%s already exists\\n' \"$2\" >&2; exit 1; }}; src=\"$dir/{item}/$1\"; [ -e \"$src\" ] || [ -L \"$src\" ] || src=\"$dir/$1\"; mv -- \"$src\" \"$2\"; rm -rf \"$dir\"",
+        root = TRASH_DIR,
+        id = entry.id,
+        item = ITEM_DIR
+    );
+    (
+        script,
+        vec![entry.name.clone(), entry.original_path.clone()],
+    )
+}
+
+/// WSL has no SFTP session, so the same layout is managed with `mv`, which
+/// also copes with crossing filesystems.
+fn run_wsl(distro: &str, action: &TrashAction) -> Result<Vec<TrashEntry>, String> {
+    match action {
+        // `mv` copies across filesystems, so there is no fallback to allow
+        TrashAction::Trash { path, is_dir, .. } => {
+            let deleted_at = now_millis();
+            let entry = TrashEntry {
+                id: new_entry_id(deleted_at),
+                original_path: path.clone(),
+                name: file_name(path)?,
+                is_dir: *is_dir,
+                deleted_at,
+                permanent: false,
+            };
+            let manifest = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+            let (script, args) = wsl_trash_command(&entry, manifest);
+            wsl::run_bash_text(distro, &script, &args)?;
+            Ok(vec![entry])
+        }
+        TrashAction::List => wsl_list(distro),
+        TrashAction::Restore { entry_id } => {
+            validate_entry_id(entry_id)?;
+            let entry = wsl_list(distro)?
+                .into_iter()
+                .find(|entry| &entry.id == entry_id)
+                .ok_or_else(|| format!("Trash entry {} not found", entry_id))?;
+            let (script, args) = wsl_restore_command(&entry);
+            wsl::run_bash_text(distro, &script, &args)?;
+            Ok(vec![entry])
+        }
+        TrashAction::Empty => {
+            let entries = wsl_list(distro)?;
+            wsl::run_bash_text(distro, &format!("rm -rf ~/{}", TRASH_DIR), &[])?;
+            Ok(entries)
+        }
+    }
+}
+
+async fn run_trash_action(
+    state: &State<'_, AppState>,
+    id: &str,
+    action: TrashAction,
+) -> Result<Vec<TrashEntry>, String> {
+    let client = {
+        let clients = state.clients.lock().map_err(|e| e.to_string())?;
+        clients.get(id).ok_or("Session not found")?.clone()
+    };
+
+    match &client.client_type {
+        ClientType::Ssh(senders) => {
+            let sender = senders.ops.clone();
+            execute_ssh_operation(move || {
+                let (tx, rx) = std::sync::mpsc::channel();
+                sender
+                    .send(SshCommand::SftpTrash {
+                        action,
+                        listener: tx,
+                    })
+                    .map_err(|e| format!("Failed to send command: {}", e))?;
+
+                rx.recv()
+                    .map_err(|_| "Failed to receive response from SSH Manager".to_string())?
+            })
+            .await
+        }
+        ClientType::Wsl(distro) => {
+            let distro = distro.clone();
+            tokio::task::spawn_blocking(move || run_wsl(&distro, &action))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+        }
+    }
+}
+
+fn single(entries: Vec<TrashEntry>) -> Result<TrashEntry, String> {
+    entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Trash operation returned no entry".to_string())
+}
+
+/// Move a remote file or directory into the trash. When the move fails in a
+/// way that may just mean the trash is on another filesystem, the error
+/// starts with `TRASH_UNAVAILABLE`; calling again with `permanent_fallback`
+/// deletes the item instead and the returned entry has `permanent` set.
+#[command]
+pub async fn trash_item(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    path: String,
+    is_dir: bool,
+    permanent_fallback: Option<bool>,
+) -> Result<TrashEntry, String> {
+    let action = TrashAction::Trash {
+        path: path.clone(),
+        is_dir,
+        permanent_fallback: permanent_fallback.unwrap_or(false),
+    };
+    let entry = single(run_trash_action(&state, &id, action).await?)?;
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        if entry.permanent {
+            if is_dir {
+                "file.directoryDeleted"
+            } else {
+                "file.deleted"
+            }
+        } else {
+            "file.trashed"
+        },
+        if entry.permanent {
+            "Deleted remote item (trash unavailable)"
+        } else {
+            "Moved remote item to trash"
+        },
+        Some(path.as_str()),
+        "warning",
+    );
+    Ok(entry)
+}
+
+/// Items currently in the trash, most recently deleted first.
+#[command]
+pub async fn list_trash(state: State<'_, AppState>, id: String) -> Result<Vec<TrashEntry>, String> {
+    run_trash_action(&state, &id, TrashAction::List).await
+}
+
+/// Move a trashed item back to its original path.
+#[command]
+pub async fn restore_from_trash(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    entry_id: String,
+) -> Result<TrashEntry, String> {
+    let entry = single(run_trash_action(&state, &id, TrashAction::Restore { entry_id }).await?)?;
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.restored",
+        "Restored remote item from trash",
+        Some(entry.original_path.as_str()),
+        "info",
+    );
+    Ok(entry)
+}
+
+/// Permanently delete everything in the trash. Returns the number of items
+/// removed.
+#[command]
+pub async fn empty_trash(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<usize, String> {
+    let removed = run_trash_action(&state, &id, TrashAction::Empty).await?;
+    append_file_audit_event(
+        &app_handle,
+        &state,
+        &id,
+        "file.trashEmptied",
+        "Emptied remote trash",
+        None,
+        "warning",
+    );
+    Ok(removed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_ignores_trailing_slashes() {
+        assert_eq!(file_name("/srv/app/logs/").unwrap(), "logs");
+        assert_eq!(file_name("notes.txt").unwrap(), "notes.txt");
+        assert!(file_name("/").is_err());
+        assert!(file_name("").is_err());
+    }
+
+    #[test]
+    fn entry_ids_are_unique_and_pass_validation() {
+        let first = new_entry_id(1_700_000_000_000);
+        let second = new_entry_id(1_700_000_000_000);
+        assert_ne!(first, second);
+        assert!(first.starts_with("1700000000000-"));
+        assert!(validate_entry_id(&first).is_ok());
+    }
+
+    #[test]
+    fn wsl_restore_passes_names_as_arguments() {
+        let entry = TrashEntry {
+            id: new_entry_id(1_700_000_000_000),
+            original_path: "/srv/it's $(touch pwned)".to_string(),
+            name: "it's $(touch pwned)".to_string(),
+            is_dir: false,
+            deleted_at: 1_700_000_000_000,
+            permanent: false,
+        };
+        let (script, args) = wsl_restore_command(&entry);
+        assert!(!script.contains("pwned"));
+        assert!(script.contains(r#"src="$dir/item/$1""#));
+        assert!(script.contains(r#"mv -- "$src" "$2""#));
+        assert_eq!(args, vec![entry.name.clone(), entry.original_path.clone()]);
+    }
+
+    #[test]
+    fn items_named_like_the_manifest_do_not_clash() {
+        let entry_dir = Path::new("/home/u/.ssh-assistant-trash/1-abc");
+        assert_ne!(
+            item_path(entry_dir, MANIFEST_NAME),
+            entry_dir.join(MANIFEST_NAME)
+        );
+
+        let entry = TrashEntry {
+            id: "1-abc".to_string(),
+            original_path: format!("/srv/{}", MANIFEST_NAME),
+            name: MANIFEST_NAME.to_string(),
+            is_dir: false,
+            deleted_at: 1,
+            permanent: false,
+        };
+        let (script, args) = wsl_trash_command(&entry, "{}".to_string());
+        assert!(script.contains(r#"> "$dir/.manifest.json""#));
+        assert!(script.contains(r#"mv -- "$1" "$dir/item/""#));
+        assert_eq!(args, vec![entry.original_path.clone(), "{}".to_string()]);
+    }
+
+    #[test]
+    fn entry_ids_cannot_leave_the_trash() {
+        for id in ["", "..", "../1", "1/2", "1\\2", "~", "1 2"] {
+            assert!(validate_entry_id(id).is_err(), "{:?} accepted", id);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use hex;
 use sha2::{Digest, Sha256};
 use ssh2::Session;
 use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use std::thread;
 use std::time::{Duration, Instant};
@@ -86,6 +87,16 @@ pub fn open_sftp_with_timeout(
     ssh2_retry_with_timeout(|| session.sftp(), timeout)
 }
 
+/// Block while a transfer is paused, waking early on cancel. Returns how long
+/// it waited so callers can exclude the pause from their timeouts.
+pub fn wait_while_paused(pause_flag: &AtomicBool, cancel_flag: &AtomicBool) -> Duration {
+    let started = Instant::now();
+    while pause_flag.load(Ordering::Relaxed) && !cancel_flag.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    started.elapsed()
+}
+
 // 异步执行SSH操作，避免阻塞主线程
 pub async fn execute_ssh_operation<F, T>(operation: F) -> Result<T, String>
 where
@@ -100,59 +111,184 @@ where
         })?
 }
 
-// Get SFTP buffer size from settings
+/// SFTP status code for a path that does not exist (SSH_FX_NO_SUCH_FILE).
+pub const SFTP_NO_SUCH_FILE: i32 = 2;
+
+/// Allowed range for the transfer buffer settings, in KB.
+pub const MIN_SFTP_BUFFER_KB: i32 = 4;
+pub const MAX_SFTP_BUFFER_KB: i32 = 4096;
+pub const DEFAULT_SFTP_BUFFER_KB: i32 = 512;
+
+/// Reject a buffer size (in KB) outside 4KB–4MB.
+pub fn validate_sftp_buffer_size(kb: i32, name: &str) -> Result<(), String> {
+    if (MIN_SFTP_BUFFER_KB..=MAX_SFTP_BUFFER_KB).contains(&kb) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} must be between {}KB and {}KB, got {}KB",
+            name, MIN_SFTP_BUFFER_KB, MAX_SFTP_BUFFER_KB, kb
+        ))
+    }
+}
+
+fn sftp_buffer_bytes(kb: i32) -> usize {
+    kb.clamp(MIN_SFTP_BUFFER_KB, MAX_SFTP_BUFFER_KB) as usize * 1024
+}
+
+// Write (upload) buffer size from settings. Larger buffers keep more data in
+// flight per round trip, which mostly helps on high-latency links.
 pub fn get_sftp_buffer_size(app: Option<&AppHandle>) -> usize {
     if let Some(app_handle) = app {
         if let Ok(settings) = crate::db::get_settings(app_handle.clone()) {
-            return (settings.file_manager.sftp_buffer_size * 1024) as usize; // Convert KB to bytes
+            return sftp_buffer_bytes(settings.file_manager.sftp_buffer_size);
         }
     }
-    // Default to 512KB if settings not available
-    512 * 1024
+    sftp_buffer_bytes(DEFAULT_SFTP_BUFFER_KB)
 }
 
-pub fn get_remote_file_hash(sess: &Session, path: &str) -> Result<Option<String>, String> {
-    let mut channel = ssh2_retry(|| sess.channel_session())
-        .map_err(|e| format!("Failed to create channel: {}", e))?;
-    // Try sha256sum first
-    let cmd = format!("sha256sum '{}'", path);
-    ssh2_retry(|| channel.exec(&cmd)).map_err(|e| format!("Failed to execute command: {}", e))?;
+// Read (download) buffer size from settings
+pub fn get_sftp_read_buffer_size(app: Option<&AppHandle>) -> usize {
+    if let Some(app_handle) = app {
+        if let Ok(settings) = crate::db::get_settings(app_handle.clone()) {
+            return sftp_buffer_bytes(settings.file_manager.sftp_read_buffer_size);
+        }
+    }
+    sftp_buffer_bytes(DEFAULT_SFTP_BUFFER_KB)
+}
 
-    let mut s = String::new();
-    let mut buf = [0u8; 1024];
-    let start_time = std::time::Instant::now();
-    let timeout = Duration::from_secs(10);
+/// Marker printed by the remote hash script when no suitable tool exists.
+const HASH_TOOL_MISSING: &str = "__SSH_ASSISTANT_NO_CHECKSUM_TOOL__";
 
-    loop {
-        if start_time.elapsed() > timeout {
-            return Err("Command timeout".to_string());
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Md5,
+    Sha1,
+}
+
+impl HashAlgorithm {
+    pub fn parse(algorithm: Option<&str>) -> Result<Self, String> {
+        match algorithm.unwrap_or("sha256").to_lowercase().as_str() {
+            "sha256" => Ok(Self::Sha256),
+            "md5" => Ok(Self::Md5),
+            "sha1" => Ok(Self::Sha1),
+            other => Err(format!("Unsupported checksum algorithm: {}", other)),
         }
+    }
 
-        match channel.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => s.push_str(&String::from_utf8_lossy(&buf[..n])),
-            Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                thread::sleep(Duration::from_millis(10));
-            }
-            Err(e) => return Err(e.to_string()),
+    /// Candidate commands in order of preference: the GNU `*sum` tool, then
+    /// the BSD/macOS equivalent. Each takes the quoted path as last argument.
+    fn commands(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Sha256 => &[
+                ("sha256sum", "sha256sum --"),
+                ("shasum", "shasum -a 256 --"),
+            ],
+            Self::Md5 => &[("md5sum", "md5sum --"), ("md5", "md5 -r")],
+            Self::Sha1 => &[("sha1sum", "sha1sum --"), ("shasum", "shasum -a 1 --")],
         }
     }
-    ssh2_retry(|| channel.wait_close())
-        .map_err(|e| format!("Failed to wait for channel close: {}", e))?;
 
-    if channel.exit_status().unwrap_or(-1) == 0 {
-        let parts: Vec<&str> = s.split_whitespace().collect();
-        if let Some(hash) = parts.get(0) {
-            return Ok(Some(hash.to_string()));
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
         }
     }
 
-    // Fallback to md5sum
+    fn hex_len(self) -> usize {
+        match self {
+            Self::Sha256 => 64,
+            Self::Md5 => 32,
+            Self::Sha1 => 40,
+        }
+    }
+}
+
+/// Algorithms tried for `algorithm`: just that one, or sha256 then md5.
+pub fn hash_candidates(algorithm: Option<HashAlgorithm>) -> Vec<HashAlgorithm> {
+    match algorithm {
+        Some(algorithm) => vec![algorithm],
+        None => vec![HashAlgorithm::Sha256, HashAlgorithm::Md5],
+    }
+}
+
+/// Script that hashes `path` with the first installed candidate tool, or
+/// prints the missing-tool marker with status 127 when none is installed.
+pub fn remote_hash_script(algorithms: &[HashAlgorithm], path: &str) -> String {
+    let quoted = format!("'{}'", crate::ssh::file_ops::escape_shell_arg(path));
+    let branches: Vec<String> = algorithms
+        .iter()
+        .flat_map(|algorithm| algorithm.commands())
+        .map(|(tool, cmd)| {
+            format!(
+                "command -v {} >/dev/null 2>&1; then {} {} 2>&1",
+                tool, cmd, quoted
+            )
+        })
+        .collect();
+    format!(
+        "export LC_ALL=C; if {}; else echo {}; (exit 127); fi",
+        branches.join("; elif "),
+        HASH_TOOL_MISSING
+    )
+}
+
+/// Lowercase hex digest from the output of `remote_hash_script`.
+pub fn parse_remote_hash(
+    algorithms: &[HashAlgorithm],
+    path: &str,
+    output: &str,
+    exit_code: i32,
+) -> Result<String, String> {
+    let output = output.trim();
+    if exit_code == 127 && output == HASH_TOOL_MISSING {
+        let names: Vec<&str> = algorithms.iter().map(|a| a.name()).collect();
+        let tools: Vec<&str> = algorithms
+            .iter()
+            .flat_map(|a| a.commands())
+            .map(|(tool, _)| *tool)
+            .collect();
+        return Err(format!(
+            "No {} checksum tool available on the remote host (tried {})",
+            names.join(" or "),
+            tools.join(", ")
+        ));
+    }
+    if exit_code != 0 {
+        return Err(format!(
+            "Checksum of {} failed with code {}: {}",
+            path, exit_code, output
+        ));
+    }
+
+    output
+        .split_whitespace()
+        .next()
+        // GNU tools prefix the digest with `\` when the name needs escaping
+        .map(|hash| hash.trim_start_matches('\\'))
+        .filter(|hash| {
+            algorithms.iter().any(|a| hash.len() == a.hex_len())
+                && hash.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .map(|hash| hash.to_lowercase())
+        .ok_or_else(|| format!("Unexpected checksum output: {}", output))
+}
+
+/// Hash a remote file with `algorithm`, or with sha256 falling back to md5
+/// when `None`.
+pub fn get_remote_file_hash(
+    sess: &Session,
+    path: &str,
+    algorithm: Option<HashAlgorithm>,
+    timeout: Duration,
+) -> Result<String, String> {
+    let algorithms = hash_candidates(algorithm);
+    let cmd = remote_hash_script(&algorithms, path);
     let mut channel = ssh2_retry(|| sess.channel_session())
-        .map_err(|e| format!("Failed to create channel for md5sum: {}", e))?;
-    let cmd = format!("md5sum '{}'", path);
-    ssh2_retry(|| channel.exec(&cmd))
-        .map_err(|e| format!("Failed to execute md5sum command: {}", e))?;
+        .map_err(|e| format!("Failed to create channel: {}", e))?;
+    ssh2_retry(|| channel.exec(&cmd)).map_err(|e| format!("Failed to execute command: {}", e))?;
 
     let mut s = String::new();
     let mut buf = [0u8; 1024];
@@ -175,14 +311,7 @@ pub fn get_remote_file_hash(sess: &Session, path: &str) -> Result<Option<String>
     ssh2_retry(|| channel.wait_close())
         .map_err(|e| format!("Failed to wait for channel close: {}", e))?;
 
-    if channel.exit_status().unwrap_or(-1) == 0 {
-        let parts: Vec<&str> = s.split_whitespace().collect();
-        if let Some(hash) = parts.get(0) {
-            return Ok(Some(hash.to_string()));
-        }
-    }
-
-    Ok(None)
+    parse_remote_hash(&algorithms, path, &s, channel.exit_status().unwrap_or(-1))
 }
 
 pub fn compute_local_file_hash(path: &std::path::Path, limit: u64) -> Result<String, String> {
@@ -214,6 +343,28 @@ pub fn compute_local_file_hash(path: &std::path::Path, limit: u64) -> Result<Str
     Ok(hex::encode(hasher.finalize()))
 }
 
+/// Hash a whole local file with the algorithm `get_remote_file_hash` used
+/// for `reference`: md5 for a 32-character digest, sha256 otherwise.
+pub fn compute_local_file_hash_like(
+    path: &std::path::Path,
+    reference: &str,
+) -> Result<String, String> {
+    if reference.len() != 32 {
+        return compute_local_file_hash(path, u64::MAX);
+    }
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = md5::Md5::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
 pub fn get_dir_size(path: &std::path::Path) -> u64 {
     let mut size = 0;
     if let Ok(entries) = std::fs::read_dir(path) {
@@ -229,3 +380,63 @@ pub fn get_dir_size(path: &std::path::Path) -> u64 {
     }
     size
 }
+
+/// Format a byte count for user-facing messages (e.g. "1.50 GB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{} {}", bytes, UNITS[unit_index])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit_index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_hash_script_chains_every_candidate() {
+        let script = remote_hash_script(&hash_candidates(None), "/srv/it's");
+        assert_eq!(
+            script,
+            "export LC_ALL=C; \
+             if command -v sha256sum >/dev/null 2>&1; then sha256sum -- '/srv/it'\"'\"'s' 2>&1; \
+             elif command -v shasum >/dev/null 2>&1; then shasum -a 256 -- '/srv/it'\"'\"'s' 2>&1; \
+             elif command -v md5sum >/dev/null 2>&1; then md5sum -- '/srv/it'\"'\"'s' 2>&1; \
+             elif command -v md5 >/dev/null 2>&1; then md5 -r '/srv/it'\"'\"'s' 2>&1; \
+             else echo __SSH_ASSISTANT_NO_CHECKSUM_TOOL__; (exit 127); fi"
+        );
+    }
+
+    #[test]
+    fn parse_remote_hash_checks_status_and_digest() {
+        let sha256 = [HashAlgorithm::Sha256];
+        let digest = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        assert_eq!(
+            parse_remote_hash(&sha256, "/f", &format!("\\{}  /f\n", digest), 0).unwrap(),
+            digest.to_lowercase()
+        );
+        assert!(
+            parse_remote_hash(&sha256, "/f", "d41d8cd98f00b204e9800998ecf8427e  /f", 0).is_err()
+        );
+        let fallback = hash_candidates(None);
+        assert!(
+            parse_remote_hash(&fallback, "/f", "d41d8cd98f00b204e9800998ecf8427e  /f", 0).is_ok()
+        );
+
+        let missing = parse_remote_hash(&sha256, "/f", HASH_TOOL_MISSING, 127).unwrap_err();
+        assert!(missing.starts_with("No sha256 checksum tool"));
+        let failed =
+            parse_remote_hash(&sha256, "/f", "sha256sum: /f: No such file", 1).unwrap_err();
+        assert!(failed.contains("failed with code 1"));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::db;
 use crate::models::{Connection, ConnectionGroup};
+use serde::Serialize;
 use std::process::{Child, Command, Output, Stdio};
 use tauri::AppHandle;
 
@@ -9,54 +10,197 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WslDistro {
+    pub name: String,
+    /// As reported by `wsl -l -v`, e.g. "Running", "Stopped", "Installing"
+    pub state: String,
+    pub version: u8,
+    pub is_default: bool,
+}
+
+/// `wsl.exe` writes its own messages (distro lists, "distribution not found"
+/// errors) as UTF-16LE, while commands run inside a distro write whatever they
+/// like (usually UTF-8). Use this for any text read from a `wsl` process; never
+/// for file contents.
+pub fn decode_wsl_output(raw_bytes: &[u8]) -> String {
+    if let Some(rest) = raw_bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return decode_utf16le(rest);
+    }
+    if let Some(rest) = raw_bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if looks_like_utf16le(raw_bytes) {
+        return decode_utf16le(raw_bytes);
+    }
+    String::from_utf8_lossy(raw_bytes).into_owned()
+}
+
+/// UTF-8 text never contains NUL, while mostly-ASCII UTF-16LE has a zero in
+/// nearly every high byte.
+fn looks_like_utf16le(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(256)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return false;
+    }
+    let high_zeros = sample.chunks_exact(2).filter(|pair| pair[1] == 0).count();
+    let low_zeros = sample.chunks_exact(2).filter(|pair| pair[0] == 0).count();
+    high_zeros * 2 >= pairs && low_zeros < high_zeros
+}
+
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(target_os = "windows")]
+fn wsl_list(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("wsl")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to execute wsl command: {}", e))?;
+
+    if !output.status.success() {
+        return Err("WSL list command failed".to_string());
+    }
+    Ok(decode_wsl_output(&output.stdout))
+}
+
 pub fn get_distributions() -> Result<Vec<String>, String> {
     #[cfg(target_os = "windows")]
     {
-        let output = Command::new("wsl")
-            .arg("--list")
-            .arg("--quiet")
-            .creation_flags(CREATE_NO_WINDOW)
-            .output()
-            .map_err(|e| format!("Failed to execute wsl command: {}", e))?;
-
-        if !output.status.success() {
-            return Err("WSL list command failed".to_string());
-        }
+        Ok(wsl_list(&["--list", "--quiet"])?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
 
-        let raw_bytes = output.stdout;
-        let is_utf16 = raw_bytes.len() >= 2 && raw_bytes[1] == 0;
-        let mut distros = Vec::new();
-
-        if is_utf16 {
-            // Basic UTF-16 LE conversion
-            let u16_vec: Vec<u16> = raw_bytes
-                .chunks_exact(2)
-                .map(|c| u16::from_le_bytes([c[0], c[1]]))
-                .collect();
-            let s = String::from_utf16_lossy(&u16_vec);
-            for line in s.lines() {
-                let trimmed = line.trim();
-                if !trimmed.is_empty() {
-                    distros.push(trimmed.to_string());
-                }
-            }
-        } else {
-            let stdout = String::from_utf8_lossy(&raw_bytes);
-            for line in stdout.lines() {
-                let trimmed = line.trim();
-                if !trimmed.is_empty() {
-                    distros.push(trimmed.to_string());
-                }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+/// Parse `wsl -l -v`. The header is localized, so skip the first line rather
+/// than matching it, and read columns from the right since names may contain
+/// spaces.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_verbose_list(output: &str) -> Vec<WslDistro> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim();
+            let (is_default, line) = match line.strip_prefix('*') {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, line),
+            };
+            let mut columns: Vec<&str> = line.split_whitespace().collect();
+            let version = columns.pop()?.parse().ok()?;
+            let state = columns.pop()?.to_string();
+            if columns.is_empty() {
+                return None;
             }
-        }
+            Some(WslDistro {
+                name: columns.join(" "),
+                state,
+                version,
+                is_default,
+            })
+        })
+        .collect()
+}
 
-        Ok(distros)
+/// List installed distros with their state and WSL version, for refreshing
+/// WSL targets after installing a new distro.
+#[tauri::command]
+pub async fn list_wsl_distros() -> Result<Vec<WslDistro>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        tokio::task::spawn_blocking(|| wsl_list(&["--list", "--verbose"]))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))
+            .and_then(|output| output)
+            .map(|output| parse_verbose_list(&output))
     }
 
     #[cfg(not(target_os = "windows"))]
     {
         Ok(Vec::new())
     }
+}
+
+/// Map a Windows path to the form a distro sees it under: drive paths go to
+/// `/mnt/<drive>/...` (the default automount root) and `\\wsl$\<distro>\...`
+/// or `\\wsl.localhost\<distro>\...` paths to the path inside the distro.
+pub fn to_linux_path(path: &str) -> Result<String, String> {
+    let normalized = path.trim().replace('\\', "/");
+
+    for prefix in ["//wsl$/", "//wsl.localhost/"] {
+        if normalized.len() >= prefix.len()
+            && normalized[..prefix.len()].eq_ignore_ascii_case(prefix)
+        {
+            let rest = &normalized[prefix.len()..];
+            let inner = rest.find('/').map(|idx| &rest[idx..]).unwrap_or("/");
+            return Ok(inner.to_string());
+        }
+    }
+
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        let drive = (bytes[0] as char).to_ascii_lowercase();
+        let rest = normalized[2..].trim_start_matches('/');
+        return Ok(if rest.is_empty() {
+            format!("/mnt/{}", drive)
+        } else {
+            format!("/mnt/{}/{}", drive, rest)
+        });
+    }
+
+    Err(format!("Not a Windows drive or WSL path: {}", path))
+}
+
+/// Reverse of `to_linux_path`: `/mnt/<drive>/...` becomes a drive path and
+/// anything else a `\\wsl$\<distro>\...` UNC path.
+pub fn to_windows_path(distro: &str, path: &str) -> Result<String, String> {
+    if !path.starts_with('/') {
+        return Err(format!("Not an absolute WSL path: {}", path));
+    }
+
+    let mut parts = path.trim_start_matches('/').splitn(3, '/');
+    if let (Some("mnt"), Some(drive)) = (parts.next(), parts.next()) {
+        if drive.len() == 1 && drive.as_bytes()[0].is_ascii_alphabetic() {
+            let rest = parts.next().unwrap_or("").replace('/', "\\");
+            return Ok(format!("{}:\\{}", drive.to_ascii_uppercase(), rest));
+        }
+    }
+
+    Ok(format!(
+        "\\\\wsl$\\{}{}",
+        distro,
+        path.trim_end_matches('/').replace('/', "\\")
+    ))
+}
+
+/// Translate a dropped Windows path for uploading into a WSL file manager.
+#[tauri::command]
+pub fn windows_to_wsl_path(path: String) -> Result<String, String> {
+    to_linux_path(&path)
+}
+
+/// Translate a WSL path for opening from Windows (Explorer, local editors).
+#[tauri::command]
+pub fn wsl_to_windows_path(distro: String, path: String) -> Result<String, String> {
+    to_windows_path(&distro, &path)
 }
 
 pub fn bash_command(distro: &str, script: &str, args: &[String]) -> Command {
@@ -89,9 +233,9 @@ pub fn run_bash_output(distro: &str, script: &str, args: &[String]) -> Result<Ou
 pub fn run_bash_text(distro: &str, script: &str, args: &[String]) -> Result<String, String> {
     let output = run_bash_output(distro, script, args)?;
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        Ok(decode_wsl_output(&output.stdout).trim().to_string())
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stderr = decode_wsl_output(&output.stderr).trim().to_string();
         if stderr.is_empty() {
             Err(format!("WSL command failed with status {}", output.status))
         } else {
@@ -100,6 +244,35 @@ pub fn run_bash_text(distro: &str, script: &str, args: &[String]) -> Result<Stri
     }
 }
 
+/// Error text for a failed file read that says whether the distro or the
+/// file was the problem. `wsl.exe` reports distro failures itself (not found,
+/// failed to start) before bash ever runs.
+pub fn read_error(distro: &str, path: &str, output: &Output) -> String {
+    let stderr = decode_wsl_output(&output.stderr);
+    classify_read_error(distro, path, stderr.trim())
+        .unwrap_or_else(|| format!("WSL command failed with status {}", output.status))
+}
+
+fn classify_read_error(distro: &str, path: &str, stderr: &str) -> Option<String> {
+    let lower = stderr.to_lowercase();
+    if lower.contains("wsl_e_distro_not_found")
+        || lower.contains("no distribution with the supplied name")
+    {
+        Some(format!("WSL distribution '{}' is not installed", distro))
+    } else if lower.contains("wsl/service") || lower.contains("could not be started") {
+        Some(format!(
+            "WSL distribution '{}' is not running and could not be started: {}",
+            distro, stderr
+        ))
+    } else if lower.contains("no such file or directory") {
+        Some(format!("File not found: {}", path))
+    } else if stderr.is_empty() {
+        None
+    } else {
+        Some(stderr.to_string())
+    }
+}
+
 pub fn spawn_bash(
     distro: &str,
     script: &str,
@@ -154,6 +327,8 @@ pub fn import_wsl_to_db(app: &AppHandle) -> Result<(), String> {
                 id: None,
                 name: "WSL".to_string(),
                 parent_id: None,
+                color: None,
+                icon: None,
             },
         )?;
         // Retrieve it back to get ID
@@ -193,6 +368,33 @@ pub fn import_wsl_to_db(app: &AppHandle) -> Result<(), String> {
             ssh_key_id: None,
             key_content: None,
             key_passphrase: None,
+            tags: Vec::new(),
+            last_connected: None,
+            last_latency_ms: None,
+            force_scp: false,
+            max_background_sessions: None,
+            jump_hosts: Vec::new(),
+            jump_auth_type: None,
+            jump_key_id: None,
+            color: None,
+            icon: None,
+            sort_order: 0,
+            is_favorite: false,
+            agent_forwarding: false,
+            x11_forwarding: false,
+            low_latency: false,
+            idle_timeout_minutes: 0,
+            kex_algorithms: None,
+            ciphers: None,
+            mac_algorithms: None,
+            legacy_mode: false,
+            init_command: None,
+            remote_rc: None,
+            host_key_policy: None,
+            socket_path: None,
+            proxy_command: None,
+            sftp_handle_cache: false,
+            notes: None,
         };
 
         db::create_connection(app.clone(), new_conn)?;
@@ -200,3 +402,129 @@ pub fn import_wsl_to_db(app: &AppHandle) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `wsl -l -v` output as written by wsl.exe (UTF-16LE, no BOM).
+    const WSL_LIST_VERBOSE_UTF16: &[u8] = &[
+        0x20, 0x00, 0x20, 0x00, 0x4e, 0x00, 0x41, 0x00, 0x4d, 0x00, 0x45, 0x00, 0x20, 0x00, 0x20,
+        0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00,
+        0x20, 0x00, 0x53, 0x00, 0x54, 0x00, 0x41, 0x00, 0x54, 0x00, 0x45, 0x00, 0x20, 0x00, 0x20,
+        0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x56, 0x00, 0x45, 0x00,
+        0x52, 0x00, 0x53, 0x00, 0x49, 0x00, 0x4f, 0x00, 0x4e, 0x00, 0x0d, 0x00, 0x0a, 0x00, 0x2a,
+        0x00, 0x20, 0x00, 0x55, 0x00, 0x62, 0x00, 0x75, 0x00, 0x6e, 0x00, 0x74, 0x00, 0x75, 0x00,
+        0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x52, 0x00, 0x75,
+        0x00, 0x6e, 0x00, 0x6e, 0x00, 0x69, 0x00, 0x6e, 0x00, 0x67, 0x00, 0x20, 0x00, 0x20, 0x00,
+        0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x32, 0x00, 0x0d, 0x00, 0x0a, 0x00, 0x20, 0x00, 0x20,
+        0x00, 0x44, 0x00, 0x65, 0x00, 0x62, 0x00, 0x69, 0x00, 0x61, 0x00, 0x6e, 0x00, 0x20, 0x00,
+        0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00, 0x53, 0x00, 0x74, 0x00, 0x6f,
+        0x00, 0x70, 0x00, 0x70, 0x00, 0x65, 0x00, 0x64, 0x00, 0x20, 0x00, 0x20, 0x00, 0x20, 0x00,
+        0x20, 0x00, 0x20, 0x00, 0x31, 0x00, 0x0d, 0x00, 0x0a, 0x00,
+    ];
+
+    #[test]
+    fn test_decode_utf16le_without_bom() {
+        let text = decode_wsl_output(WSL_LIST_VERBOSE_UTF16);
+        assert!(!text.contains('\0'));
+        assert!(text.contains("* Ubuntu"));
+
+        let distros = parse_verbose_list(&text);
+        assert_eq!(distros.len(), 2);
+        assert_eq!(distros[0].name, "Ubuntu");
+        assert_eq!(distros[0].state, "Running");
+        assert_eq!(distros[0].version, 2);
+        assert!(distros[0].is_default);
+        assert_eq!(distros[1].name, "Debian");
+        assert_eq!(distros[1].state, "Stopped");
+        assert_eq!(distros[1].version, 1);
+        assert!(!distros[1].is_default);
+    }
+
+    #[test]
+    fn test_decode_utf16le_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(
+            "There is no distribution with the supplied name."
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        assert_eq!(
+            decode_wsl_output(&bytes),
+            "There is no distribution with the supplied name."
+        );
+    }
+
+    #[test]
+    fn test_decode_utf8_passthrough() {
+        assert_eq!(decode_wsl_output("/home/用户\n".as_bytes()), "/home/用户\n");
+        assert_eq!(decode_wsl_output(b"\xEF\xBB\xBFok"), "ok");
+        assert_eq!(decode_wsl_output(b""), "");
+    }
+
+    #[test]
+    fn test_to_linux_path() {
+        assert_eq!(
+            to_linux_path(r"C:\Users\me\notes.txt").unwrap(),
+            "/mnt/c/Users/me/notes.txt"
+        );
+        assert_eq!(to_linux_path("D:/").unwrap(), "/mnt/d");
+        assert_eq!(to_linux_path(r"\\wsl$\Ubuntu\home\me").unwrap(), "/home/me");
+        assert_eq!(
+            to_linux_path(r"\\WSL.localhost\Ubuntu-22.04\etc\hosts").unwrap(),
+            "/etc/hosts"
+        );
+        assert_eq!(to_linux_path(r"\\wsl$\Ubuntu").unwrap(), "/");
+        assert!(to_linux_path(r"\\server\share\file").is_err());
+        assert!(to_linux_path("relative/path").is_err());
+    }
+
+    #[test]
+    fn test_to_windows_path() {
+        assert_eq!(
+            to_windows_path("Ubuntu", "/mnt/c/Users/me").unwrap(),
+            r"C:\Users\me"
+        );
+        assert_eq!(to_windows_path("Ubuntu", "/mnt/d").unwrap(), r"D:\");
+        assert_eq!(
+            to_windows_path("Ubuntu", "/home/me/").unwrap(),
+            r"\\wsl$\Ubuntu\home\me"
+        );
+        assert_eq!(
+            to_windows_path("Ubuntu", "/mnt/wslg").unwrap(),
+            r"\\wsl$\Ubuntu\mnt\wslg"
+        );
+        assert!(to_windows_path("Ubuntu", "home/me").is_err());
+    }
+
+    #[test]
+    fn test_classify_read_error() {
+        assert_eq!(
+            classify_read_error(
+                "Debian",
+                "/etc/x",
+                "There is no distribution with the supplied name.\r\nError code: Wsl/Service/WSL_E_DISTRO_NOT_FOUND"
+            )
+            .unwrap(),
+            "WSL distribution 'Debian' is not installed"
+        );
+        assert_eq!(
+            classify_read_error(
+                "Ubuntu",
+                "/etc/x",
+                "head: cannot open '/etc/x' for reading: No such file or directory"
+            )
+            .unwrap(),
+            "File not found: /etc/x"
+        );
+        assert!(classify_read_error(
+            "Ubuntu",
+            "/etc/x",
+            "The virtual machine could not be started because a required feature is not installed."
+        )
+        .unwrap()
+        .starts_with("WSL distribution 'Ubuntu' is not running"));
+        assert_eq!(classify_read_error("Ubuntu", "/etc/x", ""), None);
+    }
+}
@@ -0,0 +1,777 @@
+//! X11 forwarding for shell channels.
+//!
+//! ssh2 exposes neither the `x11-req` channel request nor libssh2's callback
+//! for server-initiated `x11` channels, so shells that forward X11 are opened
+//! on a raw libssh2 channel instead. The server's X11 connections are handed
+//! over by the callback, picked up by the manager with [`accept`] and each
+//! relayed to the local display on a thread of its own.
+//!
+//! Like OpenSSH, the server is given a random fake cookie. The first packet of
+//! every X11 connection must carry it; it is swapped for the local display's
+//! real cookie (from `xauth`) before anything reaches the X server.
+
+use super::manager::{write_all_retrying, ShellIo};
+use libssh2_sys as raw;
+use ssh_key::rand_core::{OsRng, RngCore};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_uint, c_void};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+// Declared by libssh2 but not by libssh2-sys 0.3. `callback_set2` only exists
+// from libssh2 1.11.1 and libssh2-sys links a system or vcpkg copy when one is
+// found, so the older `callback_set`, deprecated but still exported by every
+// release, registers the X11 callback.
+extern "C" {
+    fn libssh2_channel_x11_req_ex(
+        channel: *mut raw::LIBSSH2_CHANNEL,
+        single_connection: c_int,
+        auth_proto: *const c_char,
+        auth_cookie: *const c_char,
+        screen_number: c_int,
+    ) -> c_int;
+    fn libssh2_session_callback_set(
+        session: *mut raw::LIBSSH2_SESSION,
+        cbtype: c_int,
+        callback: *mut c_void,
+    ) -> *mut c_void;
+}
+
+const LIBSSH2_CALLBACK_X11: c_int = 4;
+const AUTH_PROTO: &str = "MIT-MAGIC-COOKIE-1";
+const COOKIE_LEN: usize = 16;
+/// Display `:N` listens on TCP port 6000 + N
+const X11_BASE_PORT: u16 = 6000;
+
+type X11OpenFn = unsafe extern "C" fn(
+    *mut raw::LIBSSH2_SESSION,
+    *mut raw::LIBSSH2_CHANNEL,
+    *const c_char,
+    c_int,
+    *mut *mut c_void,
+);
+
+/// A libssh2 channel driven without ssh2's wrapper. Every call holds the
+/// session lock, as ssh2's own channels do.
+pub(crate) struct RawChannel {
+    session: ssh2::Session,
+    raw: *mut raw::LIBSSH2_CHANNEL,
+}
+
+// The pointer is only dereferenced by libssh2 with the session locked
+unsafe impl Send for RawChannel {}
+
+impl RawChannel {
+    /// Open a `session` channel, like `Session::channel_session`.
+    pub(crate) fn open_session(session: &ssh2::Session) -> Result<Self, ssh2::Error> {
+        const CHANNEL_TYPE: &str = "session";
+        let mut sess = session.raw();
+        let sess: *mut raw::LIBSSH2_SESSION = &mut *sess;
+        let channel = unsafe {
+            raw::libssh2_channel_open_ex(
+                sess,
+                CHANNEL_TYPE.as_ptr() as *const c_char,
+                CHANNEL_TYPE.len() as c_uint,
+                raw::LIBSSH2_CHANNEL_WINDOW_DEFAULT,
+                raw::LIBSSH2_CHANNEL_PACKET_DEFAULT,
+                std::ptr::null(),
+                0,
+            )
+        };
+        if channel.is_null() {
+            return Err(
+                ssh2::Error::last_session_error_raw(sess).unwrap_or_else(ssh2::Error::unknown)
+            );
+        }
+        Ok(Self {
+            session: session.clone(),
+            raw: channel,
+        })
+    }
+
+    /// Ask the server to forward X11 connections from this channel's
+    /// programs, authenticated with `cookie`.
+    pub(crate) fn request_x11(&mut self, cookie: &[u8], screen: u32) -> Result<(), ssh2::Error> {
+        let proto = format!("{}\0", AUTH_PROTO);
+        let cookie = format!("{}\0", hex::encode(cookie));
+        self.call(|channel| unsafe {
+            libssh2_channel_x11_req_ex(
+                channel,
+                0,
+                proto.as_ptr() as *const c_char,
+                cookie.as_ptr() as *const c_char,
+                screen as c_int,
+            )
+        })
+        .map(drop)
+    }
+
+    /// Run `op` on the channel with the session locked, turning a negative
+    /// return code into the session's error.
+    fn call<F>(&self, op: F) -> Result<c_int, ssh2::Error>
+    where
+        F: FnOnce(*mut raw::LIBSSH2_CHANNEL) -> c_int,
+    {
+        let mut sess = self.session.raw();
+        let rc = op(self.raw);
+        if rc < 0 {
+            Err(ssh2::Error::from_session_error_raw(&mut *sess, rc))
+        } else {
+            Ok(rc)
+        }
+    }
+
+    fn call_io<F>(&mut self, op: F) -> io::Result<usize>
+    where
+        F: FnOnce(*mut raw::LIBSSH2_CHANNEL) -> isize,
+    {
+        let mut sess = self.session.raw();
+        let rc = op(self.raw);
+        if rc < 0 {
+            Err(ssh2::Error::from_session_error_raw(&mut *sess, rc as c_int).into())
+        } else {
+            Ok(rc as usize)
+        }
+    }
+}
+
+impl Read for RawChannel {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.call_io(|channel| unsafe {
+            raw::libssh2_channel_read_ex(channel, 0, buf.as_mut_ptr() as *mut c_char, buf.len())
+        })
+    }
+}
+
+impl Write for RawChannel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.call_io(|channel| unsafe {
+            raw::libssh2_channel_write_ex(channel, 0, buf.as_ptr() as *const c_char, buf.len())
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ShellIo for RawChannel {
+    fn request_pty(&mut self, term: &str, cols: u32, rows: u32) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe {
+            raw::libssh2_channel_request_pty_ex(
+                channel,
+                term.as_ptr() as *const c_char,
+                term.len() as c_uint,
+                std::ptr::null(),
+                0,
+                cols as c_int,
+                rows as c_int,
+                0,
+                0,
+            )
+        })
+        .map(drop)
+    }
+
+    fn request_pty_size(&mut self, cols: u32, rows: u32) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe {
+            raw::libssh2_channel_request_pty_size_ex(channel, cols as c_int, rows as c_int, 0, 0)
+        })
+        .map(drop)
+    }
+
+    fn request_auth_agent_forwarding(&mut self) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe { raw::libssh2_channel_request_auth_agent(channel) })
+            .map(drop)
+    }
+
+    fn process_startup(&mut self, request: &str, message: Option<&str>) -> Result<(), ssh2::Error> {
+        // As in ssh2, `Some("")` still sends an empty string and `None` nothing
+        let message_ptr = message.map_or(std::ptr::null(), |m| m.as_ptr() as *const c_char);
+        let message_len = message.map_or(0, str::len);
+        self.call(|channel| unsafe {
+            raw::libssh2_channel_process_startup(
+                channel,
+                request.as_ptr() as *const c_char,
+                request.len() as c_uint,
+                message_ptr,
+                message_len as c_uint,
+            )
+        })
+        .map(drop)
+    }
+
+    fn send_eof(&mut self) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe { raw::libssh2_channel_send_eof(channel) })
+            .map(drop)
+    }
+
+    fn close(&mut self) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe { raw::libssh2_channel_close(channel) })
+            .map(drop)
+    }
+
+    fn wait_close(&mut self) -> Result<(), ssh2::Error> {
+        self.call(|channel| unsafe { raw::libssh2_channel_wait_closed(channel) })
+            .map(drop)
+    }
+
+    fn exit_status(&self) -> Result<i32, ssh2::Error> {
+        self.call(|channel| unsafe { raw::libssh2_channel_get_exit_status(channel) })
+    }
+}
+
+impl Drop for RawChannel {
+    fn drop(&mut self) {
+        let _sess = self.session.raw();
+        unsafe {
+            raw::libssh2_channel_free(self.raw);
+        }
+    }
+}
+
+/// X11 channels opened by the server and not yet picked up, keyed by the
+/// session they arrived on. Only sessions passed to `listen` have an entry.
+fn inbox() -> &'static Mutex<HashMap<usize, Vec<usize>>> {
+    static INBOX: OnceLock<Mutex<HashMap<usize, Vec<usize>>>> = OnceLock::new();
+    INBOX.get_or_init(Default::default)
+}
+
+fn session_key(session: &ssh2::Session) -> usize {
+    let mut sess = session.raw();
+    &mut *sess as *mut raw::LIBSSH2_SESSION as usize
+}
+
+/// Called by libssh2, with the session locked, once it has accepted an X11
+/// channel. Channels of sessions nobody listens on stay with the session and
+/// are freed along with it.
+unsafe extern "C" fn on_x11_open(
+    session: *mut raw::LIBSSH2_SESSION,
+    channel: *mut raw::LIBSSH2_CHANNEL,
+    _host: *const c_char,
+    _port: c_int,
+    _abstract: *mut *mut c_void,
+) {
+    if let Ok(mut inbox) = inbox().lock() {
+        if let Some(pending) = inbox.get_mut(&(session as usize)) {
+            pending.push(channel as usize);
+        }
+    }
+}
+
+/// Accept the X11 channels the server opens on `session` from now on, for
+/// `accept` to pick up. Calling it again is harmless.
+pub(crate) fn listen(session: &ssh2::Session) {
+    let mut sess = session.raw();
+    let sess: *mut raw::LIBSSH2_SESSION = &mut *sess;
+    if let Ok(mut inbox) = inbox().lock() {
+        inbox.entry(sess as usize).or_default();
+    }
+    unsafe {
+        libssh2_session_callback_set(
+            sess,
+            LIBSSH2_CALLBACK_X11,
+            on_x11_open as X11OpenFn as *mut c_void,
+        );
+    }
+}
+
+/// Take the X11 channels that arrived on `session` since the last call.
+pub(crate) fn accept(session: &ssh2::Session) -> Vec<RawChannel> {
+    let key = session_key(session);
+    // Taken out before the channels lock the session, which the callback
+    // holds while waiting for the inbox
+    let pending = match inbox().lock() {
+        Ok(mut inbox) => inbox.get_mut(&key).map(std::mem::take).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    pending
+        .into_iter()
+        .map(|channel| RawChannel {
+            session: session.clone(),
+            raw: channel as *mut raw::LIBSSH2_CHANNEL,
+        })
+        .collect()
+}
+
+/// Stop listening on `session`, freeing channels nobody picked up.
+pub(crate) fn forget(session: &ssh2::Session) {
+    let key = session_key(session);
+    let pending = match inbox().lock() {
+        Ok(mut inbox) => inbox.remove(&key).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    for channel in pending {
+        drop(RawChannel {
+            session: session.clone(),
+            raw: channel as *mut raw::LIBSSH2_CHANNEL,
+        });
+    }
+}
+
+/// How to reach a local X server, parsed from `$DISPLAY`.
+#[derive(Debug, PartialEq)]
+struct LocalDisplay {
+    /// Unix socket, tried first
+    socket: Option<PathBuf>,
+    /// TCP address, tried when there is no socket or it cannot be reached
+    tcp: Option<(String, u16)>,
+    screen: u32,
+}
+
+/// Parse `$DISPLAY`: `:N[.S]` and `unix:N` use the socket in
+/// `/tmp/.X11-unix` (`:N` falls back to TCP on localhost), `host:N` is TCP
+/// port 6000 + N, and an absolute path (XQuartz) is the socket itself.
+fn parse_display(display: &str) -> Option<LocalDisplay> {
+    let (host, number) = display.rsplit_once(':')?;
+    if host.starts_with('/') {
+        return Some(LocalDisplay {
+            socket: Some(PathBuf::from(display)),
+            tcp: None,
+            screen: 0,
+        });
+    }
+    let (number, screen) = match number.split_once('.') {
+        Some((number, screen)) => (number, screen.parse().ok()?),
+        None => (number, 0),
+    };
+    let number: u16 = number.parse().ok()?;
+    let port = X11_BASE_PORT.checked_add(number)?;
+    let socket = PathBuf::from(format!("/tmp/.X11-unix/X{}", number));
+    Some(match host {
+        "" => LocalDisplay {
+            socket: Some(socket),
+            tcp: Some(("127.0.0.1".to_string(), port)),
+            screen,
+        },
+        "unix" => LocalDisplay {
+            socket: Some(socket),
+            tcp: None,
+            screen,
+        },
+        host => LocalDisplay {
+            socket: None,
+            tcp: Some((host.to_string(), port)),
+            screen,
+        },
+    })
+}
+
+/// The first `MIT-MAGIC-COOKIE-1` in `xauth list` output, decoded.
+fn parse_xauth_list(output: &str) -> Option<Vec<u8>> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let _display = fields.next()?;
+        if fields.next()? != AUTH_PROTO {
+            return None;
+        }
+        hex::decode(fields.next()?).ok()
+    })
+}
+
+/// The real cookie for `display`, or `None` when the X server takes
+/// connections without one (or `xauth` is missing).
+#[cfg(not(windows))]
+fn local_cookie(display: &str) -> Option<Vec<u8>> {
+    // GUI apps on macOS do not get XQuartz's bin directory on their PATH
+    ["xauth", "/opt/X11/bin/xauth"].iter().find_map(|xauth| {
+        let output = std::process::Command::new(xauth)
+            .args(["list", display])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        parse_xauth_list(&String::from_utf8_lossy(&output.stdout))
+    })
+}
+
+/// Windows X servers (VcXsrv, Xming) normally run without access control.
+#[cfg(windows)]
+fn local_cookie(_display: &str) -> Option<Vec<u8>> {
+    None
+}
+
+/// Outcome of checking the connection setup an X11 client sent.
+#[derive(Debug, PartialEq)]
+enum SetupRewrite {
+    /// More bytes are needed
+    Incomplete,
+    /// Not the fake cookie; the connection is dropped
+    Rejected,
+    /// `setup` replaces the first `consumed` bytes
+    Ready { setup: Vec<u8>, consumed: usize },
+}
+
+fn pad4(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+/// Check the connection setup at the start of `buf` for `fake_cookie` and
+/// rebuild it with `real_cookie`, or with no authorization at all.
+fn rewrite_setup(buf: &[u8], fake_cookie: &[u8], real_cookie: Option<&[u8]>) -> SetupRewrite {
+    if buf.len() < 12 {
+        return SetupRewrite::Incomplete;
+    }
+    let big_endian = match buf[0] {
+        b'B' => true,
+        b'l' => false,
+        _ => return SetupRewrite::Rejected,
+    };
+    let read_u16 = |at: usize| {
+        let bytes = [buf[at], buf[at + 1]];
+        usize::from(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let write_u16 = |setup: &mut Vec<u8>, value: usize| {
+        let value = value as u16;
+        setup.extend_from_slice(&if big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        });
+    };
+
+    let name_len = read_u16(6);
+    let data_len = read_u16(8);
+    let data_start = 12 + name_len + pad4(name_len);
+    let consumed = data_start + data_len + pad4(data_len);
+    if buf.len() < consumed {
+        return SetupRewrite::Incomplete;
+    }
+    if &buf[12..12 + name_len] != AUTH_PROTO.as_bytes()
+        || &buf[data_start..data_start + data_len] != fake_cookie
+    {
+        return SetupRewrite::Rejected;
+    }
+
+    let (name, data) = match real_cookie {
+        Some(cookie) => (AUTH_PROTO.as_bytes(), cookie),
+        None => (&[][..], &[][..]),
+    };
+    let mut setup = buf[..6].to_vec();
+    write_u16(&mut setup, name.len());
+    write_u16(&mut setup, data.len());
+    setup.extend_from_slice(&buf[10..12]);
+    setup.extend_from_slice(name);
+    setup.resize(setup.len() + pad4(name.len()), 0);
+    setup.extend_from_slice(data);
+    setup.resize(setup.len() + pad4(data.len()), 0);
+    SetupRewrite::Ready { setup, consumed }
+}
+
+/// The local display X11 connections are relayed to, and the cookies swapped
+/// on the way.
+pub(crate) struct X11Display {
+    display: LocalDisplay,
+    fake_cookie: [u8; COOKIE_LEN],
+    real_cookie: Option<Vec<u8>>,
+}
+
+impl X11Display {
+    /// Resolve `$DISPLAY` (`:0` on Windows when unset) and its cookie.
+    pub(crate) fn from_env() -> Result<Self, String> {
+        let name = match std::env::var("DISPLAY") {
+            Ok(name) if !name.is_empty() => name,
+            _ if cfg!(windows) => ":0".to_string(),
+            _ => return Err("DISPLAY is not set".to_string()),
+        };
+        let display =
+            parse_display(&name).ok_or_else(|| format!("Unsupported DISPLAY: {}", name))?;
+        let mut fake_cookie = [0u8; COOKIE_LEN];
+        OsRng.fill_bytes(&mut fake_cookie);
+        Ok(Self {
+            display,
+            fake_cookie,
+            real_cookie: local_cookie(&name),
+        })
+    }
+
+    /// Request X11 forwarding on a shell channel that is about to start.
+    pub(crate) fn request(&self, channel: &mut RawChannel) -> Result<(), ssh2::Error> {
+        crate::ssh::utils::ssh2_retry(|| {
+            channel.request_x11(&self.fake_cookie, self.display.screen)
+        })
+    }
+
+    fn connect(&self) -> io::Result<DisplayStream> {
+        #[cfg(unix)]
+        if let Some(path) = &self.display.socket {
+            match UnixStream::connect(path) {
+                Ok(stream) => return Ok(DisplayStream::Unix(stream)),
+                Err(e) if self.display.tcp.is_none() => return Err(e),
+                Err(_) => {}
+            }
+        }
+        match &self.display.tcp {
+            Some((host, port)) => {
+                TcpStream::connect((host.as_str(), *port)).map(DisplayStream::Tcp)
+            }
+            None => Err(io::Error::new(
+                ErrorKind::NotFound,
+                "display has no TCP address",
+            )),
+        }
+    }
+}
+
+/// A connection to the local X server.
+enum DisplayStream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl DisplayStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self {
+            DisplayStream::Tcp(stream) => stream.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            DisplayStream::Unix(stream) => stream.set_nonblocking(nonblocking),
+        }
+    }
+}
+
+impl Read for DisplayStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            DisplayStream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            DisplayStream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for DisplayStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            DisplayStream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            DisplayStream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            DisplayStream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            DisplayStream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Relay one X11 channel to the local display until either side closes or
+/// `shutdown` is set. Nothing reaches the X server before the client's
+/// connection setup has been checked for the fake cookie and rewritten.
+pub(crate) fn relay(mut channel: RawChannel, display: Arc<X11Display>, shutdown: Arc<AtomicBool>) {
+    let local = display
+        .connect()
+        .and_then(|local| local.set_nonblocking(true).map(|_| local));
+    let mut local = match local {
+        Ok(local) => local,
+        Err(e) => {
+            eprintln!("[X11] Failed to connect to the local display: {}", e);
+            let _ = crate::ssh::utils::ssh2_retry(|| channel.close());
+            return;
+        }
+    };
+
+    // Client bytes held back until the setup is complete
+    let mut pending_setup = Some(Vec::new());
+    let mut buf = [0u8; 32768];
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let mut has_data = false;
+
+        // Remote client -> local X server
+        match channel.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                has_data = true;
+                let forwarded = match pending_setup.as_mut() {
+                    None => write_all_retrying(&mut local, &buf[..n]),
+                    Some(pending) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        match rewrite_setup(
+                            pending,
+                            &display.fake_cookie,
+                            display.real_cookie.as_deref(),
+                        ) {
+                            SetupRewrite::Incomplete => Ok(()),
+                            SetupRewrite::Rejected => {
+                                Err("connection rejected: wrong authentication".to_string())
+                            }
+                            SetupRewrite::Ready { setup, consumed } => {
+                                let rest = pending.split_off(consumed);
+                                pending_setup = None;
+                                write_all_retrying(&mut local, &setup)
+                                    .and_then(|_| write_all_retrying(&mut local, &rest))
+                            }
+                        }
+                    }
+                };
+                if let Err(e) = forwarded {
+                    eprintln!("[X11] {}", e);
+                    break;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        // Local X server -> remote client
+        match local.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                has_data = true;
+                if write_all_retrying(&mut channel, &buf[..n]).is_err() {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+
+        if !has_data {
+            thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    let _ = crate::ssh::utils::ssh2_retry(|| channel.send_eof());
+    let _ = crate::ssh::utils::ssh2_retry(|| channel.close());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_packet(order: u8, name: &[u8], data: &[u8]) -> Vec<u8> {
+        let u16_bytes = |value: usize| {
+            let value = value as u16;
+            if order == b'B' {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        };
+        let mut packet = vec![order, 0];
+        packet.extend_from_slice(&u16_bytes(11));
+        packet.extend_from_slice(&u16_bytes(0));
+        packet.extend_from_slice(&u16_bytes(name.len()));
+        packet.extend_from_slice(&u16_bytes(data.len()));
+        packet.extend_from_slice(&[0, 0]);
+        packet.extend_from_slice(name);
+        packet.resize(packet.len() + pad4(name.len()), 0);
+        packet.extend_from_slice(data);
+        packet.resize(packet.len() + pad4(data.len()), 0);
+        packet
+    }
+
+    #[test]
+    fn parse_display_handles_local_remote_and_socket_forms() {
+        assert_eq!(
+            parse_display(":0"),
+            Some(LocalDisplay {
+                socket: Some(PathBuf::from("/tmp/.X11-unix/X0")),
+                tcp: Some(("127.0.0.1".to_string(), 6000)),
+                screen: 0,
+            })
+        );
+        assert_eq!(
+            parse_display("unix:1.2"),
+            Some(LocalDisplay {
+                socket: Some(PathBuf::from("/tmp/.X11-unix/X1")),
+                tcp: None,
+                screen: 2,
+            })
+        );
+        assert_eq!(
+            parse_display("localhost:10.0"),
+            Some(LocalDisplay {
+                socket: None,
+                tcp: Some(("localhost".to_string(), 6010)),
+                screen: 0,
+            })
+        );
+        assert_eq!(
+            parse_display("/private/tmp/com.apple.launchd.abc/org.xquartz:0"),
+            Some(LocalDisplay {
+                socket: Some(PathBuf::from(
+                    "/private/tmp/com.apple.launchd.abc/org.xquartz:0"
+                )),
+                tcp: None,
+                screen: 0,
+            })
+        );
+        assert_eq!(parse_display("localhost"), None);
+        assert_eq!(parse_display(":x"), None);
+    }
+
+    #[test]
+    fn parse_xauth_list_picks_the_magic_cookie() {
+        let output = "host/unix:0  XDM-AUTHORIZATION-1  00ff\n\
+                      host/unix:0  MIT-MAGIC-COOKIE-1  0a0b0c0d\n";
+        assert_eq!(parse_xauth_list(output), Some(vec![0x0a, 0x0b, 0x0c, 0x0d]));
+        assert_eq!(parse_xauth_list(""), None);
+    }
+
+    #[test]
+    fn rewrite_setup_swaps_the_fake_cookie_for_the_real_one() {
+        let fake = [7u8; COOKIE_LEN];
+        let real = [9u8; COOKIE_LEN];
+        for order in [b'B', b'l'] {
+            let packet = setup_packet(order, AUTH_PROTO.as_bytes(), &fake);
+            assert_eq!(
+                rewrite_setup(&packet, &fake, Some(&real)),
+                SetupRewrite::Ready {
+                    setup: setup_packet(order, AUTH_PROTO.as_bytes(), &real),
+                    consumed: packet.len(),
+                }
+            );
+            assert_eq!(
+                rewrite_setup(&packet, &fake, None),
+                SetupRewrite::Ready {
+                    setup: setup_packet(order, b"", b""),
+                    consumed: packet.len(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn rewrite_setup_waits_for_the_whole_setup() {
+        let fake = [7u8; COOKIE_LEN];
+        let packet = setup_packet(b'l', AUTH_PROTO.as_bytes(), &fake);
+        assert_eq!(
+            rewrite_setup(&packet[..8], &fake, None),
+            SetupRewrite::Incomplete
+        );
+        assert_eq!(
+            rewrite_setup(&packet[..packet.len() - 1], &fake, None),
+            SetupRewrite::Incomplete
+        );
+    }
+
+    #[test]
+    fn rewrite_setup_rejects_other_credentials() {
+        let fake = [7u8; COOKIE_LEN];
+        let wrong = setup_packet(b'l', AUTH_PROTO.as_bytes(), &[8u8; COOKIE_LEN]);
+        assert_eq!(rewrite_setup(&wrong, &fake, None), SetupRewrite::Rejected);
+        let none = setup_packet(b'B', b"", b"");
+        assert_eq!(rewrite_setup(&none, &fake, None), SetupRewrite::Rejected);
+        let mut bad_order = setup_packet(b'l', AUTH_PROTO.as_bytes(), &fake);
+        bad_order[0] = b'x';
+        assert_eq!(
+            rewrite_setup(&bad_order, &fake, None),
+            SetupRewrite::Rejected
+        );
+    }
+}
@@ -26,6 +26,7 @@ export const opsService = {
     assetId?: number,
     riskLevel?: string,
     source?: string,
+    toolCallId?: string,
   ) =>
     invoke<JobRun>("ops_execute_job", {
       sessionId,
@@ -33,6 +34,7 @@ export const opsService = {
       commandText,
       riskLevel,
       source,
+      toolCallId,
     }),
   previewJobBatch: (request: JobBatchRequest) =>
     invoke<JobBatchPreview>("ops_preview_job_batch", { request }),
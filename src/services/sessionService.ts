@@ -4,6 +4,7 @@ import type {
   Connection,
   ConnectionHistorySource,
   OpsSession,
+  ShellOptions,
 } from "../types";
 
 export const sessionService = {
@@ -12,12 +13,14 @@ export const sessionService = {
     accessEndpointId?: number | null,
     existingSessionId?: string | null,
     source?: ConnectionHistorySource,
+    shell?: ShellOptions,
   ) =>
     invoke<AssetSessionConnectResult>("session_connect_asset", {
       assetId,
       accessEndpointId,
       existingSessionId,
       source,
+      shell,
     }),
   disconnectAsset: (sessionId: string, assetId?: number | null) =>
     invoke("session_disconnect_asset", { sessionId, assetId }),
@@ -25,4 +28,6 @@ export const sessionService = {
     invoke<OpsSession[]>("session_get_ops_sessions"),
   testConnection: (config: Connection) =>
     invoke<string>("test_connection", { config }),
+  listAuthMethods: (config: Connection) =>
+    invoke<string[]>("list_auth_methods", { config }),
 };
@@ -619,6 +619,7 @@ export const useAssetStore = defineStore("assets", {
       assetId?: number,
       riskLevel?: string,
       source?: string,
+      toolCallId?: string,
     ) {
       const run = await opsService.executeJob(
         sessionId,
@@ -626,6 +627,7 @@ export const useAssetStore = defineStore("assets", {
         assetId,
         riskLevel,
         source,
+        toolCallId,
       );
       await this.refreshOpsData(assetId);
       return run;
@@ -1,6 +1,7 @@
 import { defineStore } from 'pinia';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { confirm } from '@tauri-apps/plugin-dialog';
 import { useNotificationStore } from './notifications';
 import type {
   OpsSession,
@@ -8,9 +9,25 @@ import type {
   ConnectionHistorySource,
   ConnectionStatusEvent,
   ReconnectEvent,
+  HostKeyPromptEvent,
+  ShellOptions,
 } from '../types';
 import { useAssetStore } from './assets';
 import { sessionService } from '../services';
+import { useSettingsStore } from './settings';
+
+// Approximate the xterm.js grid the terminal pane will fit to, so the remote
+// PTY starts at roughly the right size before the first resize_pty arrives.
+function initialShellOptions(): ShellOptions {
+  const { fontSize, lineHeight } = useSettingsStore().terminalAppearance;
+  const cellWidth = Math.max(fontSize * 0.6, 1);
+  const cellHeight = Math.max(Math.ceil(fontSize * 1.2) * lineHeight, 1);
+  return {
+    termType: 'xterm-256color',
+    cols: Math.max(Math.floor(window.innerWidth / cellWidth), 20),
+    rows: Math.max(Math.floor(window.innerHeight / cellHeight), 5),
+  };
+}
 
 export const useSessionStore = defineStore('sessions', {
   state: () => ({
@@ -129,6 +146,7 @@ export const useSessionStore = defineStore('sessions', {
           authorizedAsset.accessEndpointId ?? null,
           null,
           source,
+          initialShellOptions(),
         );
         const session: OpsSession = {
           id: connectionResult.sessionId,
@@ -223,6 +241,7 @@ export const useSessionStore = defineStore('sessions', {
           session.accessEndpointId ?? asset.accessEndpointId ?? null,
           session.id,
           'history',
+          initialShellOptions(),
         );
         session.status = 'connected';
         session.connectedAt = Date.now();
@@ -331,8 +350,24 @@ export const useSessionStore = defineStore('sessions', {
         );
       });
 
+      // Listen for unknown host keys under the "ask" host key policy
+      const unlistenHostKey = await listen<HostKeyPromptEvent>('host-key-prompt', async (event) => {
+        const { promptId, host, port, keyType, fingerprint } = event.payload;
+        const accept = await confirm(
+          `The authenticity of host ${host}:${port} can't be established.\n` +
+            `${keyType} key fingerprint is ${fingerprint}.\n\n` +
+            'Trust this key and continue connecting?',
+          { title: 'Unknown host key', kind: 'warning' }
+        );
+        try {
+          await invoke('confirm_host_key', { promptId, accept });
+        } catch (error) {
+          useNotificationStore().error(`Host key confirmation failed: ${error}`);
+        }
+      });
+
       // Store unlisten functions for cleanup
-      this._unlistenFns = [unlistenStatus, unlistenError, unlistenReconnect];
+      this._unlistenFns = [unlistenStatus, unlistenError, unlistenReconnect, unlistenHostKey];
     },
 
     // Cleanup event listeners
@@ -156,6 +156,7 @@ function sanitizeCloudSettingsPatch(parsed: Partial<Settings>): Partial<Settings
     heartbeat: parsed.heartbeat,
     poolHealth: parsed.poolHealth,
     networkAdaptive: parsed.networkAdaptive,
+    commandSafety: parsed.commandSafety,
   };
 }
 
@@ -190,24 +191,34 @@ export const useSettingsStore = defineStore('settings', {
       fontSize: 14,
       fontFamily: 'Menlo, Monaco, "Courier New", monospace',
       cursorStyle: 'block',
-      lineHeight: 1.0
+      lineHeight: 1.0,
+      trackCwd: false
     },
     fileManager: {
       viewMode: 'flat',
       layout: 'bottom',
-      sftpBufferSize: 512
+      sftpBufferSize: 512,
+      sftpReadBufferSize: 512,
+      defaultEditor: null,
+      maxTransferRateBytesPerSec: 0,
+      softDelete: true,
+      requireConfirmThreshold: 1000,
+      maxConcurrentTransfers: 3
     },
     sshPool: {
       maxBackgroundSessions: 6,
       enableAutoCleanup: true,
-      cleanupIntervalMinutes: 5
+      cleanupIntervalMinutes: 5,
+      idleSessionTimeoutMinutes: 10
     },
     connectionTimeout: {
       connectionTimeoutSecs: 15,
       jumpHostTimeoutSecs: 30,
       localForwardTimeoutSecs: 10,
       commandTimeoutSecs: 30,
-      sftpOperationTimeoutSecs: 60
+      sftpOperationTimeoutSecs: 60,
+      forwardBindAddress: '127.0.0.1',
+      hostKeyPolicy: 'ask'
     },
     reconnect: {
       maxReconnectAttempts: 5,
@@ -221,7 +232,8 @@ export const useSettingsStore = defineStore('settings', {
       sshKeepaliveIntervalSecs: 15,
       appHeartbeatIntervalSecs: 30,
       heartbeatTimeoutSecs: 5,
-      failedHeartbeatsBeforeAction: 3
+      failedHeartbeatsBeforeAction: 3,
+      shellKeepaliveIntervalSecs: 30
     },
     poolHealth: {
       healthCheckIntervalSecs: 60,
@@ -234,6 +246,13 @@ export const useSettingsStore = defineStore('settings', {
       latencyCheckIntervalSecs: 30,
       highLatencyThresholdMs: 300,
       lowBandwidthThresholdKbps: 100
+    },
+    commandSafety: {
+      dangerousCommandPatterns: [
+        'rm -rf', 'rm -fr', 'rm -r -f', 'mkfs', 'dd if=', ':(){ :|:& };:', '> /dev/sd',
+        'wipefs', 'shred', 'chmod -R 777 /', 'chown -R', 'shutdown', 'reboot', 'halt',
+        'poweroff', 'init 0', 'kill -9 -1'
+      ]
     }
   }),
   actions: {
@@ -5,7 +5,7 @@ import { listen } from '@tauri-apps/api/event';
 import { useNotificationStore } from './notifications';
 import { i18n } from '../i18n';
 
-export type TransferStatus = 'pending' | 'running' | 'paused' | 'completed' | 'error' | 'cancelled';
+export type TransferStatus = 'pending' | 'running' | 'paused' | 'completed' | 'error' | 'cancelled' | 'verify-failed';
 
 export interface TransferItem {
     id: string;
@@ -21,8 +21,14 @@ export interface TransferItem {
     speed?: string;
     sessionId: string;
     isDirectory?: boolean;
+    // Upload of a whole local directory, sent by the backend as one transfer
+    localDirectory?: boolean;
     childFiles?: number;
     completedFiles?: number;
+    // Compare local and remote checksums once the transfer completes
+    verify?: boolean;
+    localHash?: string;
+    remoteHash?: string;
     // Helper to track if this item is syncing with backend (temp ID)
     isTemp?: boolean;
 }
@@ -30,7 +36,8 @@ export interface TransferItem {
 export const useTransferStore = defineStore('transfers', () => {
     const items = ref<TransferItem[]>([]);
     const active = ref(false);
-    const maxConcurrent = 3;
+    // IDs handed to the backend, which queues them and limits concurrency
+    const submitted = new Set<string>();
     const translate = computed(() => i18n?.global?.t?.bind(i18n.global));
 
     function t(key: string, params?: Record<string, unknown>) {
@@ -112,6 +119,10 @@ export const useTransferStore = defineStore('transfers', () => {
                     }
                 });
 
+                const unlistenTransfers = await listen('transfers-updated', (event: any) => {
+                    applyBackendTransfers(event.payload as any[]);
+                });
+
                 const unlistenError = await listen('transfer-error', (event: any) => {
                     const payload = event.payload as { id: string, error: string };
                     const item = items.value.find(i => i.id === payload.id);
@@ -124,6 +135,7 @@ export const useTransferStore = defineStore('transfers', () => {
                 unlisten = () => {
                     unlistenProgress();
                     unlistenError();
+                    unlistenTransfers();
                     // Clean up progress update timer
                     if (progressUpdateTimer !== null) {
                         clearTimeout(progressUpdateTimer);
@@ -141,49 +153,7 @@ export const useTransferStore = defineStore('transfers', () => {
 
     async function syncWithBackend() {
         try {
-            const transfers = await invoke<any[]>('get_transfers');
-
-            // 获取后端 ID 集合
-            const backendIds = new Set(transfers.map(t => t.id));
-
-            // 保留 pending 和本地临时状态的项（这些可能还未同步到后端）
-            const localOnlyItems = items.value.filter(i =>
-                i.status === 'pending' ||
-                (i.isTemp && !backendIds.has(i.id))
-            );
-
-            // 映射后端数据
-            const mappedItems: TransferItem[] = transfers.map(t => ({
-                id: t.id,
-                type: t.transfer_type as 'upload' | 'download',
-                name: t.name,
-                localPath: t.local_path,
-                remotePath: t.remote_path,
-                size: t.total_size,
-                transferred: t.transferred,
-                progress: t.total_size > 0 ? Math.round((t.transferred / t.total_size) * 100) : 0,
-                status: t.status as TransferStatus,
-                error: t.error || undefined,
-                sessionId: t.session_id,
-                isDirectory: t.is_directory,
-                childFiles: t.child_files,
-                completedFiles: t.completed_files,
-            }));
-
-            // 合并：后端状态优先，但保留本地独有项
-            items.value = [...mappedItems, ...localOnlyItems];
-
-            // 清理 directoryProgress 中已完成的项
-            const validIds = new Set(items.value.map(i => i.id));
-            for (const id of directoryProgress.keys()) {
-                if (!validIds.has(id)) {
-                    directoryProgress.delete(id);
-                }
-            }
-
-            // 验证状态一致性
-            validateStateConsistency();
-
+            applyBackendTransfers(await invoke<any[]>('get_transfers'));
         } catch (e) {
             console.error("Failed to sync transfers:", e);
             const notificationStore = useNotificationStore();
@@ -191,6 +161,53 @@ export const useTransferStore = defineStore('transfers', () => {
         }
     }
 
+    function applyBackendTransfers(transfers: any[]) {
+        // 获取后端 ID 集合
+        const backendIds = new Set(transfers.map(t => t.id));
+
+        // 保留 pending 和本地临时状态的项（这些可能还未同步到后端）
+        const localOnlyItems = items.value.filter(i =>
+            !backendIds.has(i.id) && (i.status === 'pending' || i.isTemp)
+        );
+
+        const localDirectoryIds = new Set(items.value.filter(i => i.localDirectory).map(i => i.id));
+
+        // 映射后端数据
+        const mappedItems: TransferItem[] = transfers.map(t => ({
+            id: t.id,
+            type: t.transfer_type as 'upload' | 'download',
+            name: t.name,
+            localPath: t.local_path,
+            remotePath: t.remote_path,
+            size: t.total_size,
+            transferred: t.transferred,
+            progress: t.total_size > 0 ? Math.round((t.transferred / t.total_size) * 100) : 0,
+            status: t.status as TransferStatus,
+            error: t.error || undefined,
+            sessionId: t.session_id,
+            isDirectory: t.is_directory,
+            localDirectory: localDirectoryIds.has(t.id) || undefined,
+            childFiles: t.child_files,
+            completedFiles: t.completed_files,
+            localHash: t.local_hash || undefined,
+            remoteHash: t.remote_hash || undefined,
+        }));
+
+        // 合并：后端状态优先，但保留本地独有项
+        items.value = [...mappedItems, ...localOnlyItems];
+
+        // 清理 directoryProgress 中已完成的项
+        const validIds = new Set(items.value.map(i => i.id));
+        for (const id of directoryProgress.keys()) {
+            if (!validIds.has(id)) {
+                directoryProgress.delete(id);
+            }
+        }
+
+        // 验证状态一致性
+        validateStateConsistency();
+    }
+
     function updateDirectoryProgress(fileTransferId: string, transferred: number, _total: number) {
         const directoryItem = items.value.find(item =>
             item.isDirectory && item.remotePath && fileTransferId.startsWith(item.remotePath)
@@ -207,15 +224,18 @@ export const useTransferStore = defineStore('transfers', () => {
     }
 
     function processQueue() {
-        const runningCount = items.value.filter(i => i.status === 'running').length;
-        if (runningCount >= maxConcurrent) return;
-
-        const nextItem = items.value.find(i => i.status === 'pending');
-        if (nextItem) {
-            void startTransfer(nextItem.id);
+        const nextItems = items.value.filter(i =>
+            i.status === 'pending' && !i.isDirectory && !submitted.has(i.id)
+        );
+        for (const item of nextItems) {
+            void startTransfer(item.id);
         }
     }
 
+    async function prioritizeTransfer(id: string) {
+        await invoke('prioritize_transfer', { transferId: id });
+    }
+
     function addTransfer(item: TransferItem) {
         // Mark as temp so we know to replace ID later
         item.isTemp = true;
@@ -309,6 +329,7 @@ export const useTransferStore = defineStore('transfers', () => {
 
         if (item.status === 'completed') return;
 
+        submitted.add(item.id);
         item.status = 'running';
         item.error = undefined;
         active.value = true;
@@ -316,19 +337,28 @@ export const useTransferStore = defineStore('transfers', () => {
         try {
             // let realId: string; // Not needed
 
-            if (item.type === 'upload') {
+            if (item.type === 'upload' && item.localDirectory) {
+                await invoke('upload_directory', {
+                    id: item.sessionId,
+                    localPath: item.localPath,
+                    remotePath: item.remotePath,
+                    transferId: item.id
+                });
+            } else if (item.type === 'upload') {
                 await invoke('upload_file', {
                     id: item.sessionId,
                     localPath: item.localPath,
                     remotePath: item.remotePath,
-                    transferId: item.id // Pass generated ID
+                    transferId: item.id, // Pass generated ID
+                    verify: item.verify ?? false
                 });
             } else {
                 await invoke('download_file', {
                     id: item.sessionId,
                     remotePath: item.remotePath,
                     localPath: item.localPath,
-                    transferId: item.id // Pass generated ID
+                    transferId: item.id, // Pass generated ID
+                    verify: item.verify ?? false
                 });
             }
 
@@ -416,6 +446,7 @@ export const useTransferStore = defineStore('transfers', () => {
                     // We need to queue them again.
                     const child = items.value.find(c => c.id === childFileId);
                     if (child) {
+                        submitted.delete(child.id);
                         child.status = 'pending';
                     }
                 }
@@ -429,6 +460,7 @@ export const useTransferStore = defineStore('transfers', () => {
         // Create new transfer actually, as backend doesn't support resume state persistence fully
         // But we want to reuse the item?
         // We can set status to pending and processQueue will pick it up?
+        submitted.delete(item.id);
         item.status = 'pending';
         processQueue();
     }
@@ -482,7 +514,7 @@ export const useTransferStore = defineStore('transfers', () => {
     function batchResume(sessionId?: string) {
         const pausedItems = items.value.filter(i => {
             if (sessionId && i.sessionId !== sessionId) return false;
-            return ['paused', 'error', 'cancelled'].includes(i.status);
+            return ['paused', 'error', 'cancelled', 'verify-failed'].includes(i.status);
         });
         pausedItems.forEach(item => resumeTransfer(item.id));
     }
@@ -498,7 +530,7 @@ export const useTransferStore = defineStore('transfers', () => {
     async function batchDelete(sessionId?: string) {
         const deletableItems = items.value.filter(i => {
             if (sessionId && i.sessionId !== sessionId) return false;
-            return ['completed', 'cancelled', 'error', 'paused'].includes(i.status);
+            return ['completed', 'cancelled', 'error', 'paused', 'verify-failed'].includes(i.status);
         });
         await Promise.all(deletableItems.map(item => removeTransfer(item.id)));
     }
@@ -523,6 +555,7 @@ export const useTransferStore = defineStore('transfers', () => {
 
     function clearLocalState() {
         items.value = [];
+        submitted.clear();
         directoryProgress.clear();
         active.value = false;
         if (progressUpdateTimer !== null) {
@@ -546,6 +579,7 @@ export const useTransferStore = defineStore('transfers', () => {
         incrementDirectoryCompleted,
         pauseTransfer,
         resumeTransfer,
+        prioritizeTransfer,
         cancelTransfer,
         clearHistory,
         batchPause,
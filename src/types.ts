@@ -10,6 +10,7 @@ export type HostPlatform = "Linux" | "Windows" | "macOS";
 export type AssetCriticality = "low" | "medium" | "high" | "critical";
 export type AccessAuthType = "password" | "key";
 export type CredentialKind = "password" | "sshKey" | "token";
+export type HostKeyPolicy = "strict" | "ask" | "accept-new";
 
 export interface HostAsset {
   id?: number;
@@ -29,9 +30,33 @@ export interface HostAsset {
   healthSummary?: string | null;
   lastAccessedAt?: number | null;
   isFavorite?: boolean;
+  agentForwarding?: boolean;
+  x11Forwarding?: boolean;
+  lowLatency?: boolean;
+  idleTimeoutMinutes?: number;
+  kexAlgorithms?: string | null;
+  ciphers?: string | null;
+  macAlgorithms?: string | null;
+  legacyMode?: boolean;
+  initCommand?: string;
+  remoteRc?: string;
+  hostKeyPolicy?: HostKeyPolicy | null;
+  socketPath?: string | null;
+  proxyCommand?: string | null;
+  sftpHandleCache?: boolean;
+  notes?: string | null;
   groupId?: number | null;
 }
 
+export interface JumpHop {
+  host: string;
+  port?: number | null;
+  username: string;
+  authType?: AccessAuthType;
+  password?: string | null;
+  sshKeyId?: number | null;
+}
+
 export interface Connection {
   id?: number;
   name: string;
@@ -49,6 +74,17 @@ export interface Connection {
   osType?: HostPlatform;
   keyContent?: string | null;
   keyPassphrase?: string | null;
+  tags?: string[];
+  lastConnected?: number | null;
+  lastLatencyMs?: number | null;
+  forceScp?: boolean;
+  maxBackgroundSessions?: number | null;
+  jumpHosts?: JumpHop[];
+  jumpAuthType?: AccessAuthType | null;
+  jumpKeyId?: number | null;
+  color?: string | null;
+  icon?: string | null;
+  sortOrder?: number;
   platform?: HostPlatform;
   folderId?: number | null;
   envId?: number | null;
@@ -88,11 +124,26 @@ export interface AssetFolder {
   name: string;
   parentId?: number | null;
   color?: string | null;
+  icon?: string | null;
   children?: (AssetFolder | HostAsset)[];
 }
 
 export type ConnectionGroup = AssetFolder;
 
+export interface KeyPublicExport {
+  publicKey: string;
+  installSnippet: string;
+}
+
+export interface GroupDefaults {
+  username?: string | null;
+  authType?: string | null;
+  sshKeyId?: number | null;
+  jumpHosts?: JumpHop[];
+  hostKeyPolicy?: HostKeyPolicy | null;
+  proxyCommand?: string | null;
+}
+
 export interface Environment {
   id?: number;
   name: string;
@@ -147,6 +198,12 @@ export interface CloudAssetRecord {
   defaultCredentialRef?: CredentialRef | null;
 }
 
+export interface ShellOptions {
+  termType?: string;
+  cols?: number;
+  rows?: number;
+}
+
 export interface AssetSessionConnectResult {
   sessionId: string;
   assetId: number;
@@ -393,12 +450,89 @@ export interface Tunnel {
   createdAt?: number;
 }
 
+export interface CommandSnippet {
+  id?: number;
+  name: string;
+  command: string;
+  description?: string | null;
+  tags?: string[];
+  createdAt?: number;
+}
+
+/** Saved or recently visited remote directory for one connection. */
+export interface PathBookmark {
+  id?: number;
+  connectionId: number;
+  path: string;
+  name?: string | null;
+  createdAt?: number;
+}
+
 export interface TunnelStatus {
   id: number;
   active: boolean;
   pid?: number;
 }
 
+/** One line of `~/.ssh/known_hosts`, as returned by `list_known_hosts`. */
+export interface KnownHostEntry {
+  line: number;
+  /** Patterns as written; hashed entries show the `|1|...` hash */
+  hosts: string[];
+  hashed: boolean;
+  marker: string | null;
+  keyType: string;
+  fingerprint: string;
+  comment: string;
+}
+
+export interface AuthorizedKey {
+  line: number;
+  keyType: string;
+  fingerprint: string;
+  comment: string;
+  /** Leading options such as `from="..."`, if any */
+  options: string | null;
+}
+
+export interface RemoteDirWatch {
+  watchId: string;
+  /** `inotify` watches recursively; `poll` only the directory's own entries */
+  method: 'inotify' | 'poll';
+}
+
+/** Payload of `dir-changed:{sessionId}` */
+export interface DirChangeEvent {
+  watchId: string;
+  path: string;
+  kind: 'created' | 'deleted' | 'modified' | 'moved_from' | 'moved_to' | 'attrib' | 'stopped';
+  isDir: boolean;
+}
+
+/** Result of `benchmark_transfer`; rates are MiB/s */
+export interface TransferBenchmark {
+  bytes: number;
+  uploadMs: number;
+  downloadMs: number;
+  uploadMbPerSec: number;
+  downloadMbPerSec: number;
+  roundTripMbPerSec: number;
+}
+
+export interface ForwardInfo {
+  id: string;
+  kind: 'jump' | 'local';
+  localPort: number;
+  remoteTarget: string;
+  via: string;
+  sessionId?: string;
+  rxBytes: number;
+  txBytes: number;
+  rxBytesPerSecond: number;
+  txBytesPerSecond: number;
+  startedAt: number;
+}
+
 export interface FileEntry {
   name: string;
   isDir: boolean;
@@ -415,6 +549,59 @@ export interface FilePageResponse {
   hasMore: boolean;
 }
 
+export interface BinaryFileInfo {
+  isBinary: true;
+  mimeHint: string;
+  size: number;
+}
+
+/** Modification time (Unix seconds) and size of a remote file. */
+export interface RemoteFileStat {
+  mtime: number;
+  size: number;
+}
+
+/** `read_remote_file` returns plain text, or a BinaryFileInfo for binary files. */
+export type RemoteFileContent = string | BinaryFileInfo;
+
+/** `read_remote_file` result when called with `withStat: true`. */
+export interface RemoteFileContentWithStat extends RemoteFileStat {
+  content: RemoteFileContent;
+}
+
+/** `get_file_preview` result: inlined image/PDF, an oversized one, or a text snippet. */
+export type FilePreview =
+  | { kind: "media"; mime: string; dataUrl: string; size: number }
+  | { kind: "tooLarge"; mime: string; size: number; limit: number }
+  | { kind: "snippet"; content: RemoteFileContent; size: number; truncated: boolean };
+
+export interface SyncAction {
+  action: "delete" | "mkdir" | "copy";
+  /** Relative to the synced directories, with `/` separators */
+  path: string;
+  isDir: boolean;
+  size: number;
+  reason: "new" | "changed" | "extraneous";
+}
+
+/** `sync_directory` result; `transferId` is null for a dry run. */
+export interface SyncPlan {
+  transferId: string | null;
+  actions: SyncAction[];
+}
+
+export interface AggregateTransferProgress {
+  transferred: number;
+  total: number;
+  bytesPerSecond: number;
+  etaSeconds: number | null;
+  running: number;
+  queued: number;
+  paused: number;
+  completed: number;
+  failed: number;
+}
+
 export type ColumnKey = "name" | "size" | "date" | "owner";
 export type AccountMode = "personal" | "enterpriseSubAccount" | "local";
 export type AISubscriptionPlan =
@@ -631,6 +818,7 @@ export interface TerminalAppearanceSettings {
   fontFamily: string;
   cursorStyle: TerminalCursorStyle;
   lineHeight: number;
+  trackCwd?: boolean;
 }
 
 export type FileManagerViewMode = "flat" | "tree";
@@ -640,12 +828,19 @@ export interface FileManagerSettings {
   viewMode: FileManagerViewMode;
   layout: FileManagerLayout;
   sftpBufferSize: number;
+  sftpReadBufferSize?: number;
+  defaultEditor?: string | null;
+  maxTransferRateBytesPerSec?: number;
+  softDelete?: boolean;
+  requireConfirmThreshold?: number;
+  maxConcurrentTransfers?: number;
 }
 
 export interface SshPoolSettings {
   maxBackgroundSessions: number;
   enableAutoCleanup: boolean;
   cleanupIntervalMinutes: number;
+  idleSessionTimeoutMinutes?: number;
 }
 
 export interface ConnectionTimeoutSettings {
@@ -654,6 +849,8 @@ export interface ConnectionTimeoutSettings {
   localForwardTimeoutSecs: number;
   commandTimeoutSecs: number;
   sftpOperationTimeoutSecs: number;
+  forwardBindAddress: string;
+  hostKeyPolicy: HostKeyPolicy;
 }
 
 export interface ReconnectSettings {
@@ -670,6 +867,8 @@ export interface HeartbeatSettings {
   appHeartbeatIntervalSecs: number;
   heartbeatTimeoutSecs: number;
   failedHeartbeatsBeforeAction: number;
+  /** Keepalive interval while a terminal is open; 0 disables */
+  shellKeepaliveIntervalSecs: number;
 }
 
 export interface PoolHealthSettings {
@@ -688,6 +887,11 @@ export interface NetworkAdaptiveSettings {
   lowBandwidthThresholdKbps: number;
 }
 
+export interface CommandSafetySettings {
+  /** AI-issued commands containing one of these wait for confirmation. */
+  dangerousCommandPatterns: string[];
+}
+
 export interface NetworkStatus {
   latencyMs: number;
   bandwidthKbps?: number;
@@ -716,6 +920,7 @@ export interface Settings {
   heartbeat: HeartbeatSettings;
   poolHealth: PoolHealthSettings;
   networkAdaptive: NetworkAdaptiveSettings;
+  commandSafety: CommandSafetySettings;
 }
 
 export interface Workspace {
@@ -782,3 +987,11 @@ export interface ReconnectEvent {
   maxAttempts: number;
   delayMs: number;
 }
+
+export interface HostKeyPromptEvent {
+  promptId: string;
+  host: string;
+  port: number;
+  keyType: string;
+  fingerprint: string;
+}